@@ -0,0 +1,384 @@
+//! Folder-level access control lists.
+//!
+//! Rules map users and groups to path globs (`Lens Edu/Course 1/**`) and an
+//! authorization level. They narrow what a token grants and never widen it: a
+//! token for a user is capped at the highest level the rules give that user on
+//! the document's path. Paths that no rule mentions are left to the token
+//! alone, and once any rule mentions a path, users with no rule of their own
+//! there are denied. Tokens that carry no user (server tokens, anonymous doc
+//! tokens) are not subject to the list. API keys and configured MCP keys are
+//! named in rules as `key:<name>` and held to them on every transport, on top
+//! of their own folder scopes.
+//!
+//! The list is stored at `.config/acl.json` alongside the webhook config and
+//! can be replaced or re-read while the server runs.
+
+use glob_match::glob_match;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use y_sweet_core::api_key::{is_api_key, API_KEY_SUBJECT_PREFIX};
+use y_sweet_core::api_types::Authorization;
+use y_sweet_core::store::Store;
+
+pub const ACL_CONFIG_KEY: &str = ".config/acl.json";
+
+/// Subject that matches every user.
+const EVERYONE: &str = "*";
+
+/// Prefix marking a rule subject as a group name rather than a user.
+const GROUP_PREFIX: &str = "group:";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AclDocument {
+    /// Group name to member user names.
+    #[serde(default)]
+    pub groups: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub rules: Vec<AclRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AclRule {
+    /// A user name, `group:<name>`, or `*` for everyone.
+    pub subject: String,
+    /// Glob over document paths, e.g. `Lens Edu/Course 1/**`. `*` stays within
+    /// one path segment and `**` spans any number.
+    pub path: String,
+    pub permission: Authorization,
+}
+
+/// Who the list is asked about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AclPrincipal<'a> {
+    /// The user a token was issued to.
+    User(&'a str),
+    /// An API key or configured MCP key, by name.
+    Key(&'a str),
+}
+
+impl<'a> AclPrincipal<'a> {
+    /// The principal behind `token`, verified as issued to `user`. Only an API
+    /// key is taken as a key; any other token naming a `key:` user stays a
+    /// user.
+    pub fn of_token(token: &str, user: &'a str) -> Self {
+        match user.strip_prefix(API_KEY_SUBJECT_PREFIX) {
+            Some(name) if is_api_key(token) => Self::Key(name),
+            _ => Self::User(user),
+        }
+    }
+}
+
+/// What the list says about one user and one document path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AclDecision {
+    /// No rule mentions the path.
+    Unrestricted,
+    /// The highest level granted by the user's rules for the path.
+    Granted(Authorization),
+    /// Rules mention the path, but none of them apply to the user.
+    Denied,
+}
+
+impl AclDocument {
+    pub fn validate(&self) -> Result<(), String> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if rule.subject.is_empty() {
+                return Err(format!("rule {} has an empty subject", i));
+            }
+            if rule.path.is_empty() {
+                return Err(format!("rule {} has an empty path", i));
+            }
+            if let Some(group) = rule.subject.strip_prefix(GROUP_PREFIX) {
+                if !self.groups.contains_key(group) {
+                    return Err(format!("rule {} names unknown group '{}'", i, group));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The decision for a token issued to `user`.
+    pub fn decide(&self, user: &str, path: &str) -> AclDecision {
+        self.decide_for(AclPrincipal::User(user), path)
+    }
+
+    /// The decision for `principal`. Keys match the rules for `key:<name>`. A
+    /// user named like a key, a group or everyone matches no rule, so is
+    /// denied wherever the list applies.
+    pub fn decide_for(&self, principal: AclPrincipal, path: &str) -> AclDecision {
+        let (subject, reserved) = match principal {
+            AclPrincipal::User(user) => (Cow::Borrowed(user), is_reserved_user(user)),
+            AclPrincipal::Key(name) => (
+                Cow::Owned(format!("{}{}", API_KEY_SUBJECT_PREFIX, name)),
+                false,
+            ),
+        };
+        let mut covered = false;
+        let mut granted: Option<Authorization> = None;
+        for rule in &self.rules {
+            if !glob_match(&rule.path, path) {
+                continue;
+            }
+            covered = true;
+            if !reserved
+                && self.applies_to(rule, &subject)
+                && granted.map_or(true, |g| rank(rule.permission) > rank(g))
+            {
+                granted = Some(rule.permission);
+            }
+        }
+        match (covered, granted) {
+            (false, _) => AclDecision::Unrestricted,
+            (true, Some(authorization)) => AclDecision::Granted(authorization),
+            (true, None) => AclDecision::Denied,
+        }
+    }
+
+    /// Group rules apply only through membership, never by name.
+    fn applies_to(&self, rule: &AclRule, user: &str) -> bool {
+        if rule.subject == EVERYONE {
+            return true;
+        }
+        match rule.subject.strip_prefix(GROUP_PREFIX) {
+            Some(group) => self
+                .groups
+                .get(group)
+                .is_some_and(|members| members.iter().any(|member| member == user)),
+            None => rule.subject == user,
+        }
+    }
+}
+
+/// Whether `user` is a name the list keeps for keys, groups or everyone,
+/// which a token's user can't take on.
+pub fn is_reserved_user(user: &str) -> bool {
+    user == EVERYONE || user.starts_with(GROUP_PREFIX) || user.starts_with(API_KEY_SUBJECT_PREFIX)
+}
+
+/// Cap a token's `authorization` by the list's decision. `None` means denied.
+pub fn narrow(authorization: Authorization, decision: AclDecision) -> Option<Authorization> {
+    match decision {
+        AclDecision::Unrestricted => Some(authorization),
+        AclDecision::Granted(granted) if rank(granted) < rank(authorization) => Some(granted),
+        AclDecision::Granted(_) => Some(authorization),
+        AclDecision::Denied => None,
+    }
+}
+
+/// Orders authorization levels from `ReadOnly` (lowest) to `Full`.
+pub(crate) fn rank(authorization: Authorization) -> u8 {
+    match authorization {
+        Authorization::ReadOnly => 0,
+        Authorization::Comment => 1,
+        Authorization::Suggest => 2,
+        Authorization::Full => 3,
+    }
+}
+
+/// The list in force, swapped out whole when reloaded.
+#[derive(Default)]
+pub struct Acl {
+    document: RwLock<AclDocument>,
+}
+
+impl Acl {
+    pub fn replace(&self, document: AclDocument) {
+        *self.document.write().unwrap_or_else(|e| e.into_inner()) = document;
+    }
+
+    pub fn document(&self) -> AclDocument {
+        self.document
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn decide(&self, user: &str, path: &str) -> AclDecision {
+        self.decide_for(AclPrincipal::User(user), path)
+    }
+
+    pub fn decide_for(&self, principal: AclPrincipal, path: &str) -> AclDecision {
+        self.document
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .decide_for(principal, path)
+    }
+
+    /// Groups the list names `user` a member of.
+    pub fn groups_of(&self, user: &str) -> Vec<String> {
+        let document = self.document.read().unwrap_or_else(|e| e.into_inner());
+        let mut groups: Vec<String> = document
+            .groups
+            .iter()
+            .filter(|(_, members)| members.iter().any(|member| member == user))
+            .map(|(group, _)| group.clone())
+            .collect();
+        groups.sort();
+        groups
+    }
+}
+
+/// Load the access control list from the store. `None` if none has been set.
+pub async fn load_acl_from_store(
+    store: Arc<Box<dyn Store>>,
+) -> Result<Option<AclDocument>, Box<dyn std::error::Error>> {
+    let Some(data) = store.get(ACL_CONFIG_KEY).await? else {
+        return Ok(None);
+    };
+    let document: AclDocument = serde_json::from_slice(&data)?;
+    document
+        .validate()
+        .map_err(|e| format!("Invalid access control list: {}", e))?;
+    Ok(Some(document))
+}
+
+/// Save the access control list to the store.
+pub async fn set_acl_in_store(
+    store: Arc<Box<dyn Store>>,
+    document: &AclDocument,
+) -> Result<(), Box<dyn std::error::Error>> {
+    document
+        .validate()
+        .map_err(|e| format!("Invalid access control list: {}", e))?;
+    store
+        .set(ACL_CONFIG_KEY, serde_json::to_vec(document)?)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn course_acl() -> AclDocument {
+        serde_json::from_value(serde_json::json!({
+            "groups": { "Editors": ["alice"] },
+            "rules": [
+                { "subject": "group:Editors", "path": "Lens Edu/Course 1/**", "permission": "full" },
+                { "subject": "*", "path": "Lens Edu/Course 1/**", "permission": "read-only" },
+                { "subject": "bob", "path": "Lens Edu/Course 1/Drafts/*", "permission": "suggest" },
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn highest_matching_rule_wins() {
+        let acl = course_acl();
+        assert_eq!(
+            acl.decide("alice", "Lens Edu/Course 1/Week 1.md"),
+            AclDecision::Granted(Authorization::Full)
+        );
+        assert_eq!(
+            acl.decide("bob", "Lens Edu/Course 1/Week 1.md"),
+            AclDecision::Granted(Authorization::ReadOnly)
+        );
+        assert_eq!(
+            acl.decide("bob", "Lens Edu/Course 1/Drafts/Week 2.md"),
+            AclDecision::Granted(Authorization::Suggest)
+        );
+    }
+
+    #[test]
+    fn paths_without_rules_are_unrestricted() {
+        let acl = course_acl();
+        assert_eq!(
+            acl.decide("bob", "Lens/Notes.md"),
+            AclDecision::Unrestricted
+        );
+        assert_eq!(
+            acl.decide("bob", "Lens Edu/Course 2/Week 1.md"),
+            AclDecision::Unrestricted
+        );
+    }
+
+    #[test]
+    fn covered_paths_deny_users_without_a_rule() {
+        let acl: AclDocument = serde_json::from_value(serde_json::json!({
+            "rules": [{ "subject": "alice", "path": "Private/**", "permission": "full" }]
+        }))
+        .unwrap();
+        assert_eq!(acl.decide("bob", "Private/Plans.md"), AclDecision::Denied);
+    }
+
+    #[test]
+    fn users_named_like_groups_or_everyone_match_nothing() {
+        let acl = course_acl();
+        for user in ["group:Editors", "*"] {
+            assert_eq!(
+                acl.decide(user, "Lens Edu/Course 1/Week 1.md"),
+                AclDecision::Denied
+            );
+            assert_eq!(acl.decide(user, "Lens/Notes.md"), AclDecision::Unrestricted);
+        }
+    }
+
+    #[test]
+    fn keys_match_only_their_own_rules() {
+        let acl: AclDocument = serde_json::from_value(serde_json::json!({
+            "rules": [
+                { "subject": "key:git-sync", "path": "Lens Edu/**", "permission": "read-only" },
+                { "subject": "alice", "path": "Lens Edu/**", "permission": "full" },
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            acl.decide_for(AclPrincipal::Key("git-sync"), "Lens Edu/Intro.md"),
+            AclDecision::Granted(Authorization::ReadOnly)
+        );
+        assert_eq!(
+            acl.decide_for(AclPrincipal::Key("alice"), "Lens Edu/Intro.md"),
+            AclDecision::Denied
+        );
+        assert_eq!(
+            acl.decide("key:git-sync", "Lens Edu/Intro.md"),
+            AclDecision::Denied
+        );
+
+        let (secret, key) = y_sweet_core::api_key::ApiKey::generate(
+            "git-sync",
+            Vec::new(),
+            Authorization::Full,
+            0,
+            None,
+        );
+        assert_eq!(
+            AclPrincipal::of_token(&secret, &key.subject()),
+            AclPrincipal::Key("git-sync")
+        );
+        assert_eq!(
+            AclPrincipal::of_token("not-a-key", &key.subject()),
+            AclPrincipal::User("key:git-sync")
+        );
+    }
+
+    #[test]
+    fn narrow_caps_but_never_widens() {
+        let granted = AclDecision::Granted(Authorization::Comment);
+        assert_eq!(
+            narrow(Authorization::Full, granted),
+            Some(Authorization::Comment)
+        );
+        assert_eq!(
+            narrow(Authorization::ReadOnly, granted),
+            Some(Authorization::ReadOnly)
+        );
+        assert_eq!(
+            narrow(Authorization::Full, AclDecision::Unrestricted),
+            Some(Authorization::Full)
+        );
+        assert_eq!(narrow(Authorization::Full, AclDecision::Denied), None);
+    }
+
+    #[test]
+    fn unknown_groups_are_rejected() {
+        let acl: AclDocument = serde_json::from_value(serde_json::json!({
+            "rules": [{ "subject": "group:Nobody", "path": "**", "permission": "full" }]
+        }))
+        .unwrap();
+        assert!(acl.validate().unwrap_err().contains("Nobody"));
+        assert!(course_acl().validate().is_ok());
+    }
+}
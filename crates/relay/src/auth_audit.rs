@@ -0,0 +1,111 @@
+//! Audit trail of authorization decisions.
+//!
+//! Every HTTP request that presents a token, or is refused with 401 or 403,
+//! is recorded with the principal, token kind and key ID read from the token,
+//! the path and route it was for, and whether it was allowed and if not, why.
+//! The record describes the token as the request's handler verified it, so
+//! it can't disagree with the decision; a token the handler never got to is
+//! verified for the record alone. Tokens refreshed on an open websocket are
+//! recorded as they are presented.
+//! Entries are kept in a [`RotatingLog`] under `auth-audit/` and served by
+//! `GET /auth-audit`.
+
+use crate::rotating_log::{LogEntry, RotatingLog};
+use crate::server::Server;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::Arc;
+use y_sweet_core::auth::{AuthError, Permission};
+use y_sweet_core::auth_audit::AuthDecision;
+
+pub const AUTH_AUDIT_PREFIX: &str = "auth-audit";
+
+pub type AuthAuditLog = RotatingLog<AuthDecision>;
+
+impl LogEntry for AuthDecision {
+    fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
+tokio::task_local! {
+    /// What the token of the request being handled was verified to.
+    static VERIFIED: RefCell<Option<Result<Permission, AuthError>>>;
+}
+
+/// Note that the token of the request being handled was verified to
+/// `verified`, for its audit record. Outside a request this does nothing.
+pub(crate) fn note_verification(verified: Result<&Permission, &AuthError>) {
+    let verified = verified.cloned().map_err(AuthError::clone);
+    let _ = VERIFIED.try_with(|slot| *slot.borrow_mut() = Some(verified));
+}
+
+/// Handle a request with `handling`, returning what it verified the
+/// request's token to, if it did.
+pub(crate) async fn noting_verification<F: Future>(
+    handling: F,
+) -> (F::Output, Option<Result<Permission, AuthError>>) {
+    VERIFIED
+        .scope(RefCell::new(None), async move {
+            let output = handling.await;
+            (output, VERIFIED.with(RefCell::take))
+        })
+        .await
+}
+
+/// Why a request failed, attached to error responses so the audit trail
+/// can give the reason for a denial.
+#[derive(Debug, Clone)]
+pub struct DenialReason(pub String);
+
+/// Record the decision on each request that presents a token, as a bearer
+/// header or a `token` query parameter, or is refused with 401 or 403.
+pub async fn record_auth_decisions(
+    State(server): State<Arc<Server>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let token = request_token(&request);
+    let resource = request.uri().path().to_string();
+    let action = format!(
+        "{} {}",
+        request.method(),
+        request
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or(resource.as_str(), |path| path.as_str())
+    );
+
+    let (response, verified) = noting_verification(next.run(request)).await;
+    let status = response.status();
+    let denied = status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN;
+    if token.is_none() && !denied {
+        return response;
+    }
+    let denial = denied.then(|| {
+        response
+            .extensions()
+            .get::<DenialReason>()
+            .map_or_else(|| status.to_string(), |reason| reason.0.clone())
+    });
+    server.record_auth_decision(token.as_deref(), verified, &resource, &action, denial);
+    response
+}
+
+fn request_token(request: &Request) -> Option<String> {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+    bearer.or_else(|| {
+        url::form_urlencoded::parse(request.uri().query()?.as_bytes())
+            .find(|(name, _)| name == "token")
+            .map(|(_, token)| token.into_owned())
+    })
+}
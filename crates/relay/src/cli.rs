@@ -4,6 +4,7 @@ use tokio::io::AsyncReadExt;
 use url::Url;
 use y_sweet_core::api_types::Authorization;
 use y_sweet_core::auth::{AuthError, Authenticator};
+use y_sweet_core::revocation::RevocationKind;
 
 pub fn print_auth_message(auth: &Authenticator) {
     match auth.key_material() {
@@ -110,8 +111,13 @@ pub async fn sign_stdin(auth: &Authenticator) -> anyhow::Result<()> {
 
     let authorization = match input.authorization.as_deref() {
         Some("read") => Authorization::ReadOnly,
+        Some("comment") => Authorization::Comment,
+        Some("suggest") => Authorization::Suggest,
         Some("full") => Authorization::Full,
-        Some(other) => anyhow::bail!("Invalid authorization: {}. Must be 'read' or 'full'", other),
+        Some(other) => anyhow::bail!(
+            "Invalid authorization: {}. Must be 'read', 'comment', 'suggest' or 'full'",
+            other
+        ),
         None => Authorization::Full,
     };
 
@@ -260,6 +266,8 @@ pub async fn verify_stdin(auth: &Authenticator, id: Option<&str>) -> anyhow::Res
                         // Successfully verified as file hash
                         let auth_str = match authorization {
                             Authorization::ReadOnly => "read",
+                            Authorization::Comment => "comment",
+                            Authorization::Suggest => "suggest",
                             Authorization::Full => "full",
                         };
 
@@ -275,6 +283,8 @@ pub async fn verify_stdin(auth: &Authenticator, id: Option<&str>) -> anyhow::Res
                         // Successfully verified as doc_id
                         let auth_str = match authorization {
                             Authorization::ReadOnly => "read",
+                            Authorization::Comment => "comment",
+                            Authorization::Suggest => "suggest",
                             Authorization::Full => "full",
                         };
 
@@ -306,6 +316,8 @@ pub async fn verify_stdin(auth: &Authenticator, id: Option<&str>) -> anyhow::Res
                         Ok(authorization) => {
                             let auth_str = match authorization {
                                 Authorization::ReadOnly => "read",
+                                Authorization::Comment => "comment",
+                                Authorization::Suggest => "suggest",
                                 Authorization::Full => "full",
                             };
 
@@ -360,3 +372,149 @@ pub async fn verify_stdin(auth: &Authenticator, id: Option<&str>) -> anyhow::Res
     println!("{}", serde_json::to_string_pretty(&output)?);
     Ok(())
 }
+
+/// Send a request to one of a running relay's admin endpoints and print the
+/// response. Requires the relay's server token.
+async fn admin_request(
+    url: &str,
+    server_token: &str,
+    method: reqwest::Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> anyhow::Result<()> {
+    let endpoint = Url::parse(url)?.join(path)?;
+    let mut request = reqwest::Client::new()
+        .request(method, endpoint)
+        .bearer_auth(server_token);
+    if let Some(body) = body {
+        request = request
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(&body)?);
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        anyhow::bail!("Relay returned {}: {}", status, text);
+    }
+    match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(value) => println!("{}", serde_json::to_string_pretty(&value)?),
+        Err(_) => println!("{}", text),
+    }
+    Ok(())
+}
+
+pub async fn list_revocations(url: &str, server_token: &str) -> anyhow::Result<()> {
+    admin_request(url, server_token, reqwest::Method::GET, "revocations", None).await
+}
+
+/// Revoke tokens on a running relay. For `RevocationKind::Token` without a
+/// value, the token is read from stdin and only its ID is sent.
+pub async fn add_revocation(
+    url: &str,
+    server_token: &str,
+    kind: RevocationKind,
+    value: Option<&str>,
+    reason: Option<&str>,
+) -> anyhow::Result<()> {
+    let value = match (kind, value) {
+        (_, Some(value)) => value.to_string(),
+        (RevocationKind::Token, None) => {
+            let mut token = String::new();
+            tokio::io::stdin().read_to_string(&mut token).await?;
+            y_sweet_core::auth::token_id(token.trim())
+        }
+        (kind, None) => anyhow::bail!("A value is required to revoke by {}", kind),
+    };
+    let body = serde_json::json!({ "kind": kind, "value": value, "reason": reason });
+    admin_request(
+        url,
+        server_token,
+        reqwest::Method::POST,
+        "revocations",
+        Some(body),
+    )
+    .await
+}
+
+pub async fn remove_revocation(
+    url: &str,
+    server_token: &str,
+    kind: RevocationKind,
+    value: &str,
+) -> anyhow::Result<()> {
+    let body = serde_json::json!({ "kind": kind, "value": value });
+    admin_request(
+        url,
+        server_token,
+        reqwest::Method::DELETE,
+        "revocations",
+        Some(body),
+    )
+    .await
+}
+
+pub async fn list_api_keys(url: &str, server_token: &str) -> anyhow::Result<()> {
+    admin_request(url, server_token, reqwest::Method::GET, "api-keys", None).await
+}
+
+/// Create an API key on a running relay. The response, printed, holds the
+/// key's secret; it cannot be shown again.
+pub async fn create_api_key(
+    url: &str,
+    server_token: &str,
+    name: &str,
+    scopes: &[String],
+    permission: Authorization,
+    valid_for_seconds: Option<u64>,
+) -> anyhow::Result<()> {
+    let body = serde_json::json!({
+        "name": name,
+        "scopes": scopes,
+        "permission": permission,
+        "validForSeconds": valid_for_seconds,
+    });
+    admin_request(
+        url,
+        server_token,
+        reqwest::Method::POST,
+        "api-keys",
+        Some(body),
+    )
+    .await
+}
+
+pub async fn delete_api_key(url: &str, server_token: &str, name: &str) -> anyhow::Result<()> {
+    let path = format!("api-keys/{}", urlencoding::encode(name));
+    admin_request(url, server_token, reqwest::Method::DELETE, &path, None).await
+}
+
+/// Print the keys a running relay verifies tokens with. No token required.
+pub async fn list_keys(url: &str) -> anyhow::Result<()> {
+    let endpoint = Url::parse(url)?.join(".well-known/relay-keys")?;
+    let keys: serde_json::Value = reqwest::get(endpoint)
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    println!("{}", serde_json::to_string_pretty(&keys)?);
+    Ok(())
+}
+
+/// Start signing tokens on a running relay with a fresh key. The keys it
+/// replaces keep verifying tokens for `grace_period_seconds`.
+pub async fn rotate_keys(
+    url: &str,
+    server_token: &str,
+    grace_period_seconds: u64,
+) -> anyhow::Result<()> {
+    let body = serde_json::json!({ "gracePeriodSeconds": grace_period_seconds });
+    admin_request(
+        url,
+        server_token,
+        reqwest::Method::POST,
+        "keys/rotate",
+        Some(body),
+    )
+    .await
+}
@@ -0,0 +1,516 @@
+use crate::mcp::tools::critic_diff::smart_critic_markup;
+use crate::mcp::tools::critic_markup::{self, MergeResult, Span};
+
+/// Render pending suggestions as a zero-context unified diff of `base_view` vs `accepted_view`.
+///
+/// Each suggestion becomes its own hunk, with the suggestion's author after the
+/// closing `@@`. Suggestions that share a line are emitted as a single hunk
+/// (listing every author), since hunks in a unified diff cannot overlap.
+///
+/// The output is equivalent to `diff -U0`; apply it with `patch` or
+/// `git apply --unidiff-zero`. Returns an empty string if there are no suggestions.
+pub fn render_unified_diff(raw: &str, path: &str) -> String {
+    let spans = critic_markup::parse(raw);
+    let base = critic_markup::base_view(&spans);
+    let accepted = critic_markup::accepted_view(&spans);
+
+    let groups = group_changes(&spans, &base);
+    if groups.is_empty() {
+        return String::new();
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path, path);
+    for group in groups {
+        let (base_region, accepted_region) = hunk_regions(&base, &accepted, &group);
+
+        let mut authors: Vec<&str> = Vec::new();
+        for change in &group {
+            if !authors.contains(&change.author) {
+                authors.push(change.author);
+            }
+        }
+
+        out.push_str(&format!(
+            "@@ -{} +{} @@ {}\n",
+            hunk_range(&base, base_region),
+            hunk_range(&accepted, accepted_region),
+            authors.join(", ")
+        ));
+        push_lines(&mut out, '-', &base[base_region.0..base_region.1]);
+        push_lines(
+            &mut out,
+            '+',
+            &accepted[accepted_region.0..accepted_region.1],
+        );
+    }
+    out
+}
+
+/// Convert a unified diff into CriticMarkup suggestions against `raw`.
+///
+/// Hunks are matched against the accepted view. The hunk's line number picks
+/// between repeated occurrences of the same text. Returns one `MergeResult` per
+/// hunk that changes something. Each result is relative to the document after
+/// the previous results have been applied, so callers must apply them in order.
+pub fn apply_unified_diff(
+    raw: &str,
+    patch: &str,
+    author: &str,
+    timestamp: u64,
+) -> Result<Vec<MergeResult>, String> {
+    critic_markup::reject_if_contains_markup(author, "author")?;
+    if author.contains("@@") {
+        return Err("Error: author must not contain '@@'".into());
+    }
+    let hunks = parse_hunks(patch)?;
+    if hunks.is_empty() {
+        return Err("Error: patch contains no hunks".into());
+    }
+
+    let mut current = raw.to_string();
+    let mut line_delta: isize = 0;
+    let mut results = Vec::new();
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        critic_markup::reject_if_contains_markup(&hunk.new_text, "patch")?;
+
+        let spans = critic_markup::parse(&current);
+        let accepted = critic_markup::accepted_view(&spans);
+
+        let expected_line = (hunk.old_start as isize + line_delta).max(0) as usize;
+        let merge = if accepted.is_empty() {
+            let meta = critic_markup::meta_prefix(author, timestamp);
+            MergeResult {
+                raw_offset: current.len(),
+                raw_len: 0,
+                replacement: smart_critic_markup("", &hunk.new_text, Some(&meta)),
+            }
+        } else {
+            let start = if hunk.old_text.is_empty() {
+                // Pure insertion: `old_start` is the line after which to insert.
+                nth_line_start(&accepted, expected_line)
+            } else {
+                locate_block(&accepted, &hunk.old_text, expected_line.saturating_sub(1))
+                    .ok_or_else(|| {
+                        format!(
+                            "Error: hunk {} does not match the document near line {}",
+                            index + 1,
+                            hunk.old_start
+                        )
+                    })?
+            };
+            let (old, new) = widen_until_unique(
+                &accepted,
+                start,
+                start + hunk.old_text.len(),
+                &hunk.new_text,
+            );
+            critic_markup::merge_edit(&current, &old, &new, author, timestamp)
+                .map_err(|e| format!("Error: hunk {}: {}", index + 1, e))?
+        };
+
+        line_delta += hunk.new_text.matches('\n').count() as isize
+            - hunk.old_text.matches('\n').count() as isize;
+
+        if merge.raw_len == 0 && merge.replacement.is_empty() {
+            continue;
+        }
+        current.replace_range(
+            merge.raw_offset..merge.raw_offset + merge.raw_len,
+            &merge.replacement,
+        );
+        results.push(merge);
+    }
+
+    Ok(results)
+}
+
+struct Change<'a> {
+    base_start: usize,
+    base_end: usize,
+    accepted_start: usize,
+    accepted_end: usize,
+    author: &'a str,
+}
+
+/// Collect suggestion positions in both views, grouped so no two groups share a line.
+fn group_changes<'a>(spans: &'a [Span], base: &str) -> Vec<Vec<Change<'a>>> {
+    let mut groups: Vec<Vec<Change>> = Vec::new();
+    let mut base_pos = 0;
+    let mut accepted_pos = 0;
+
+    for span in spans {
+        match span {
+            Span::Plain(text) => {
+                base_pos += text.len();
+                accepted_pos += text.len();
+            }
+            Span::Suggestion {
+                deleted,
+                inserted,
+                author,
+                ..
+            } => {
+                let change = Change {
+                    base_start: base_pos,
+                    base_end: base_pos + deleted.len(),
+                    accepted_start: accepted_pos,
+                    accepted_end: accepted_pos + inserted.len(),
+                    author: author.as_str(),
+                };
+                base_pos += deleted.len();
+                accepted_pos += inserted.len();
+
+                let shares_line = groups.last().is_some_and(|group| {
+                    let prev = &group[group.len() - 1];
+                    line_region(base, prev.base_start, prev.base_end).1
+                        > line_region(base, change.base_start, change.base_end).0
+                });
+                match groups.last_mut() {
+                    Some(group) if shares_line => group.push(change),
+                    _ => groups.push(vec![change]),
+                }
+            }
+        }
+    }
+    groups
+}
+
+/// Line-aligned byte ranges a group covers in the base and accepted views.
+fn hunk_regions(base: &str, accepted: &str, group: &[Change]) -> ((usize, usize), (usize, usize)) {
+    let first = &group[0];
+    let last = &group[group.len() - 1];
+    let base_change = &base[first.base_start..last.base_end];
+    let accepted_change = &accepted[first.accepted_start..last.accepted_end];
+    let at_line_start = |text: &str, pos: usize| pos == 0 || text.as_bytes()[pos - 1] == b'\n';
+
+    // Whole-line insertions and deletions leave the neighbouring line alone on the other side
+    if base_change.is_empty()
+        && accepted_change.ends_with('\n')
+        && at_line_start(base, first.base_start)
+    {
+        return (
+            (first.base_start, first.base_start),
+            (first.accepted_start, last.accepted_end),
+        );
+    }
+    if accepted_change.is_empty()
+        && base_change.ends_with('\n')
+        && at_line_start(accepted, first.accepted_start)
+    {
+        return (
+            (first.base_start, last.base_end),
+            (first.accepted_start, first.accepted_start),
+        );
+    }
+
+    (
+        line_region(base, first.base_start, last.base_end),
+        line_region(accepted, first.accepted_start, last.accepted_end),
+    )
+}
+
+/// Expand `[start, end)` to whole lines (including the trailing newline).
+fn line_region(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let line_start = text[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = if end > start && text[..end].ends_with('\n') {
+        end
+    } else {
+        text[end..]
+            .find('\n')
+            .map(|i| end + i + 1)
+            .unwrap_or(text.len())
+    };
+    (line_start, line_end)
+}
+
+/// Format `start,count` for a hunk header. An empty range names the line before it.
+fn hunk_range(text: &str, (start, end): (usize, usize)) -> String {
+    let preceding_lines = text[..start].matches('\n').count();
+    let region = &text[start..end];
+    let count =
+        region.matches('\n').count() + usize::from(!region.ends_with('\n') && !region.is_empty());
+    if count == 0 {
+        format!("{},0", preceding_lines)
+    } else {
+        format!("{},{}", preceding_lines + 1, count)
+    }
+}
+
+fn push_lines(out: &mut String, marker: char, region: &str) {
+    for line in region.split_inclusive('\n') {
+        out.push(marker);
+        out.push_str(line);
+        if !line.ends_with('\n') {
+            out.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+struct Hunk {
+    /// Line number from the `-start,count` header (1-based; 0-based "after" line for pure insertions).
+    old_start: usize,
+    old_text: String,
+    new_text: String,
+}
+
+fn parse_hunks(patch: &str) -> Result<Vec<Hunk>, String> {
+    let mut hunks = Vec::new();
+    let mut lines = patch.split_inclusive('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        if !line.starts_with("@@ ") {
+            continue;
+        }
+        let (old_start, old_count, new_count) = parse_hunk_header(line)?;
+
+        let mut hunk = Hunk {
+            old_start,
+            old_text: String::new(),
+            new_text: String::new(),
+        };
+        let (mut old_seen, mut new_seen) = (0, 0);
+
+        while old_seen < old_count || new_seen < new_count {
+            let Some(body) = lines.next() else {
+                return Err("Error: patch ends in the middle of a hunk".into());
+            };
+            let (marker, content) = match body.chars().next() {
+                Some(c @ (' ' | '-' | '+')) => (c, &body[1..]),
+                // Some tools strip the trailing space from empty context lines
+                Some('\n') => (' ', body),
+                Some('\\') => continue,
+                _ => {
+                    return Err(format!(
+                        "Error: unexpected line in hunk: {}",
+                        body.trim_end()
+                    ))
+                }
+            };
+            if matches!(marker, ' ' | '-') {
+                hunk.old_text.push_str(content);
+                old_seen += 1;
+            }
+            if matches!(marker, ' ' | '+') {
+                hunk.new_text.push_str(content);
+                new_seen += 1;
+            }
+            // "\ No newline at end of file" applies to the line just read
+            if lines.peek().is_some_and(|next| next.starts_with('\\')) {
+                lines.next();
+                if matches!(marker, ' ' | '-') && hunk.old_text.ends_with('\n') {
+                    hunk.old_text.pop();
+                }
+                if matches!(marker, ' ' | '+') && hunk.new_text.ends_with('\n') {
+                    hunk.new_text.pop();
+                }
+            }
+        }
+        hunks.push(hunk);
+    }
+    Ok(hunks)
+}
+
+/// Parse `@@ -a[,b] +c[,d] @@` into `(a, b, d)`.
+fn parse_hunk_header(line: &str) -> Result<(usize, usize, usize), String> {
+    let invalid = || format!("Error: invalid hunk header: {}", line.trim_end());
+    let mut parts = line.split_whitespace().skip(1);
+    let old = parts
+        .next()
+        .and_then(|p| p.strip_prefix('-'))
+        .ok_or_else(invalid)?;
+    let new = parts
+        .next()
+        .and_then(|p| p.strip_prefix('+'))
+        .ok_or_else(invalid)?;
+
+    let parse_range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = parse_range(old).ok_or_else(invalid)?;
+    let (_, new_count) = parse_range(new).ok_or_else(invalid)?;
+    Ok((old_start, old_count, new_count))
+}
+
+/// Byte offset of the start of the 0-based `line`, or the end of text if past the last line.
+fn nth_line_start(text: &str, line: usize) -> usize {
+    if line == 0 {
+        return 0;
+    }
+    text.match_indices('\n')
+        .nth(line - 1)
+        .map(|(i, _)| i + 1)
+        .unwrap_or(text.len())
+}
+
+/// Find `block` starting at a line boundary, preferring the occurrence closest to `expected_line`.
+fn locate_block(text: &str, block: &str, expected_line: usize) -> Option<usize> {
+    text.match_indices(block)
+        .map(|(i, _)| i)
+        .filter(|&i| i == 0 || text.as_bytes()[i - 1] == b'\n')
+        .min_by_key(|&i| text[..i].matches('\n').count().abs_diff(expected_line))
+}
+
+/// Grow `[start, end)` by whole lines until its text is unique, so `merge_edit` can find it.
+fn widen_until_unique(text: &str, start: usize, end: usize, new_text: &str) -> (String, String) {
+    let (mut s, mut e) = (start, end);
+    while (s == e || text.matches(&text[s..e]).count() > 1) && (s > 0 || e < text.len()) {
+        if s > 0 {
+            s = text[..s - 1].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        }
+        if e < text.len() {
+            e = text[e..]
+                .find('\n')
+                .map(|i| e + i + 1)
+                .unwrap_or(text.len());
+        }
+    }
+    (
+        text[s..e].to_string(),
+        format!("{}{}{}", &text[s..start], new_text, &text[end..e]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply_all(raw: &str, results: &[MergeResult]) -> String {
+        let mut out = raw.to_string();
+        for r in results {
+            out.replace_range(r.raw_offset..r.raw_offset + r.raw_len, &r.replacement);
+        }
+        out
+    }
+
+    fn accepted(raw: &str) -> String {
+        critic_markup::accepted_view(&critic_markup::parse(raw))
+    }
+
+    #[test]
+    fn render_single_substitution() {
+        let raw = "one\n{--{\"author\":\"AI\",\"timestamp\":1}@@two--}{++{\"author\":\"AI\",\"timestamp\":1}@@2++}\nthree\n";
+        assert_eq!(
+            render_unified_diff(raw, "Lens/Doc.md"),
+            "--- a/Lens/Doc.md\n+++ b/Lens/Doc.md\n@@ -2,1 +2,1 @@ AI\n-two\n+2\n"
+        );
+    }
+
+    #[test]
+    fn render_one_hunk_per_suggestion() {
+        let raw = "a {--{\"author\":\"AI\",\"timestamp\":1}@@b--}\nc\nd {++{\"author\":\"Bob\",\"timestamp\":2}@@e++}\n";
+        let patch = render_unified_diff(raw, "x.md");
+        assert!(
+            patch.contains("@@ -1,1 +1,1 @@ AI\n-a b\n+a \n"),
+            "{}",
+            patch
+        );
+        assert!(
+            patch.contains("@@ -3,1 +3,1 @@ Bob\n-d \n+d e\n"),
+            "{}",
+            patch
+        );
+    }
+
+    #[test]
+    fn render_merges_suggestions_on_same_line() {
+        let raw = "{++{\"author\":\"AI\",\"timestamp\":1}@@x++} and {++{\"author\":\"Bob\",\"timestamp\":2}@@y++}\n";
+        let patch = render_unified_diff(raw, "x.md");
+        assert_eq!(patch.matches("\n@@").count(), 1, "{}", patch);
+        assert!(patch.contains("@@ AI, Bob\n"), "{}", patch);
+    }
+
+    #[test]
+    fn render_whole_line_insertion_and_missing_newline() {
+        let raw = "a\n{++{\"author\":\"AI\",\"timestamp\":1}@@b\n++}c";
+        let patch = render_unified_diff(raw, "x.md");
+        assert!(patch.contains("@@ -1,0 +2,1 @@ AI\n+b\n"), "{}", patch);
+
+        let raw = "a\nb{++{\"author\":\"AI\",\"timestamp\":1}@@!++}";
+        let patch = render_unified_diff(raw, "x.md");
+        assert!(
+            patch
+                .ends_with("-b\n\\ No newline at end of file\n+b!\n\\ No newline at end of file\n"),
+            "{}",
+            patch
+        );
+    }
+
+    #[test]
+    fn render_without_suggestions_is_empty() {
+        assert_eq!(render_unified_diff("plain text\n", "x.md"), "");
+    }
+
+    #[test]
+    fn import_creates_suggestions() {
+        let raw = "one\ntwo\nthree\n";
+        let patch = "--- a/x.md\n+++ b/x.md\n@@ -2,1 +2,1 @@\n-two\n+2\n";
+        let results = apply_unified_diff(raw, patch, "Reviewer", 5).unwrap();
+        let out = apply_all(raw, &results);
+        assert_eq!(accepted(&out), "one\n2\nthree\n");
+        assert!(
+            out.contains(r#"{--{"author":"Reviewer","timestamp":5}@@two--}"#),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn import_escapes_author() {
+        let raw = "one\ntwo\n";
+        let patch = "@@ -2,1 +2,1 @@\n-two\n+2\n";
+        let author = r#"Eve","timestamp":0}"#;
+        let out = apply_all(raw, &apply_unified_diff(raw, patch, author, 5).unwrap());
+        assert_eq!(accepted(&out), "one\n2\n");
+        let spans = critic_markup::parse(&out);
+        assert!(
+            spans.iter().any(|s| matches!(
+                s,
+                Span::Suggestion { author: a, timestamp: Some(5), .. } if a == author
+            )),
+            "{}",
+            out
+        );
+
+        assert!(apply_unified_diff(raw, patch, "a@@b", 5).is_err());
+        assert!(apply_unified_diff(raw, patch, "a++}b", 5).is_err());
+    }
+
+    #[test]
+    fn import_uses_line_numbers_for_repeated_text() {
+        let raw = "x\ny\nx\ny\n";
+        let patch = "@@ -3,1 +3,1 @@\n-x\n+z\n";
+        let out = apply_all(raw, &apply_unified_diff(raw, patch, "AI", 1).unwrap());
+        assert_eq!(accepted(&out), "x\ny\nz\ny\n");
+        assert!(out.starts_with("x\ny\n"), "{}", out);
+    }
+
+    #[test]
+    fn import_multiple_hunks_tracks_line_shift() {
+        let raw = "a\nb\nc\nd\n";
+        let patch = "@@ -0,0 +1,2 @@\n+new1\n+new2\n@@ -4,1 +6,1 @@\n-d\n+D\n";
+        let out = apply_all(raw, &apply_unified_diff(raw, patch, "AI", 1).unwrap());
+        assert_eq!(accepted(&out), "new1\nnew2\na\nb\nc\nD\n");
+    }
+
+    #[test]
+    fn import_rejects_mismatched_hunk() {
+        let raw = "one\ntwo\n";
+        let patch = "@@ -1,1 +1,1 @@\n-missing\n+found\n";
+        let err = apply_unified_diff(raw, patch, "AI", 1).unwrap_err();
+        assert!(err.contains("does not match"), "{}", err);
+    }
+
+    #[test]
+    fn export_then_import_round_trips() {
+        let raw = "Intro\n{--{\"author\":\"AI\",\"timestamp\":1}@@old line--}{++{\"author\":\"AI\",\"timestamp\":1}@@new line++}\nOutro {++{\"author\":\"AI\",\"timestamp\":1}@@added++}\n";
+        let patch = render_unified_diff(raw, "x.md");
+        let base = critic_markup::base_view(&critic_markup::parse(raw));
+        let out = apply_all(&base, &apply_unified_diff(&base, &patch, "AI", 1).unwrap());
+        assert_eq!(accepted(&out), accepted(raw));
+        assert_eq!(critic_markup::base_view(&critic_markup::parse(&out)), base);
+    }
+}
@@ -1,8 +1,14 @@
 #![doc = include_str!("../README.md")]
 
+pub mod acl;
+pub mod auth_audit;
 pub mod cli;
 pub mod convert;
+pub mod critic_patch;
 pub mod mcp;
+pub mod oidc;
+pub mod rotating_log;
 pub mod server;
 pub mod stores;
+pub mod suggestion_anchors;
 pub mod webhook;
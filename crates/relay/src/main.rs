@@ -2,7 +2,10 @@ use anyhow::Context;
 use anyhow::Result;
 use axum::middleware;
 use clap::{Parser, Subcommand, ValueEnum};
-use relay::cli::{print_auth_message, sign_stdin, verify_stdin};
+use relay::cli::{
+    add_revocation, create_api_key, delete_api_key, list_api_keys, list_keys, list_revocations,
+    print_auth_message, remove_revocation, rotate_keys, sign_stdin, verify_stdin,
+};
 use relay::server::AllowedHost;
 use relay::stores::filesystem::FileSystemStore;
 use serde_json::json;
@@ -16,11 +19,15 @@ use tokio::io::AsyncReadExt;
 use tokio::net::TcpListener;
 use tokio_util::sync::CancellationToken;
 use tracing::metadata::LevelFilter;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{
+    fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter,
+};
 use url::Url;
 use y_sweet_core::{
+    api_types::Authorization,
     auth::Authenticator,
     config::Config,
+    revocation::RevocationKind,
     store::{
         s3::{S3Config, S3Store},
         Store,
@@ -67,6 +74,45 @@ enum KeyType {
     EdDsa,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum RevocationKindArg {
+    /// One token, by ID
+    Token,
+    /// Every token issued to a user
+    Subject,
+    /// Every token signed with a key, by key ID
+    Key,
+}
+
+impl From<RevocationKindArg> for RevocationKind {
+    fn from(kind: RevocationKindArg) -> Self {
+        match kind {
+            RevocationKindArg::Token => RevocationKind::Token,
+            RevocationKindArg::Subject => RevocationKind::Subject,
+            RevocationKindArg::Key => RevocationKind::Key,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PermissionArg {
+    ReadOnly,
+    Comment,
+    Suggest,
+    Full,
+}
+
+impl From<PermissionArg> for Authorization {
+    fn from(permission: PermissionArg) -> Self {
+        match permission {
+            PermissionArg::ReadOnly => Authorization::ReadOnly,
+            PermissionArg::Comment => Authorization::Comment,
+            PermissionArg::Suggest => Authorization::Suggest,
+            PermissionArg::Full => Authorization::Full,
+        }
+    }
+}
+
 #[derive(Parser)]
 struct Opts {
     #[clap(subcommand)]
@@ -141,6 +187,28 @@ enum ServSubcommand {
         checkpoint_freq_seconds: Option<u64>,
     },
 
+    /// Serve MCP over stdin/stdout for assistants that launch tools as subprocesses.
+    /// Runs an embedded relay on the configured store, or proxies to a running
+    /// relay with --remote. Use --remote rather than pointing the embedded relay
+    /// at a store that a running server also writes to.
+    McpStdio {
+        /// Path to configuration file
+        #[clap(short = 'c', long = "config")]
+        config: Option<PathBuf>,
+
+        /// Store path or s3:// URL, overriding the configuration file
+        #[clap()]
+        store: Option<String>,
+
+        /// URL of a running relay to forward MCP messages to
+        #[clap(long, conflicts_with_all = ["config", "store"])]
+        remote: Option<String>,
+
+        /// Bearer token for --remote: an MCP key, MCP_API_KEY or server token
+        #[clap(long, env = "RELAY_MCP_TOKEN", requires = "remote")]
+        token: Option<String>,
+    },
+
     Sign {
         #[clap(long)]
         auth: String,
@@ -156,6 +224,115 @@ enum ServSubcommand {
         #[clap(long)]
         file_hash: Option<String>,
     },
+
+    /// Manage revoked tokens on a running relay
+    Revocations {
+        /// URL of the running relay
+        #[clap(long, env = "RELAY_URL")]
+        url: String,
+
+        /// The relay's server token
+        #[clap(long, env = "RELAY_SERVER_TOKEN")]
+        token: String,
+
+        #[clap(subcommand)]
+        cmd: RevocationsSubcommand,
+    },
+
+    /// Manage service-account API keys on a running relay
+    ApiKeys {
+        /// URL of the running relay
+        #[clap(long, env = "RELAY_URL")]
+        url: String,
+
+        /// The relay's server token
+        #[clap(long, env = "RELAY_SERVER_TOKEN")]
+        token: String,
+
+        #[clap(subcommand)]
+        cmd: ApiKeysSubcommand,
+    },
+
+    /// List and rotate the keys a running relay signs tokens with
+    Keys {
+        /// URL of the running relay
+        #[clap(long, env = "RELAY_URL")]
+        url: String,
+
+        #[clap(subcommand)]
+        cmd: KeysSubcommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeysSubcommand {
+    /// Print the published verification keys
+    List,
+
+    /// Sign new tokens with a fresh key, retiring the current ones after a
+    /// grace period
+    Rotate {
+        /// The relay's server token
+        #[clap(long, env = "RELAY_SERVER_TOKEN")]
+        token: String,
+
+        /// Hours the replaced keys keep verifying tokens
+        #[clap(long, default_value_t = 24)]
+        grace_hours: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum ApiKeysSubcommand {
+    /// List API keys
+    List,
+
+    /// Create an API key and print its secret, which is shown only once
+    Create {
+        name: String,
+
+        /// Folder the key may reach; repeat for several. Leave out for every
+        /// folder.
+        #[clap(long = "scope")]
+        scopes: Vec<String>,
+
+        #[clap(long, value_enum)]
+        permission: PermissionArg,
+
+        /// Days until the key expires. Leave out for a key that does not.
+        #[clap(long)]
+        expires_in_days: Option<u64>,
+    },
+
+    /// Revoke an API key and close the connections using it
+    Revoke { name: String },
+}
+
+#[derive(Subcommand)]
+enum RevocationsSubcommand {
+    /// List revocations in force
+    List,
+
+    /// Revoke tokens and close the connections using them
+    Add {
+        #[clap(value_enum)]
+        kind: RevocationKindArg,
+
+        /// Token ID, user or key ID. To revoke a token by ID without knowing
+        /// it, leave this out and pass the token on stdin.
+        value: Option<String>,
+
+        #[clap(long)]
+        reason: Option<String>,
+    },
+
+    /// Lift a revocation
+    Remove {
+        #[clap(value_enum)]
+        kind: RevocationKindArg,
+
+        value: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -449,8 +626,14 @@ async fn main() -> Result<()> {
     let filter = EnvFilter::builder()
         .with_default_directive(LevelFilter::INFO.into())
         .from_env_lossy();
+    // stdout carries the protocol for mcp-stdio, so logs go to stderr there
+    let log_writer = if matches!(opts.subcmd, ServSubcommand::McpStdio { .. }) {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(log_writer))
         .with(filter)
         .init();
 
@@ -590,12 +773,45 @@ async fn main() -> Result<()> {
             .await?;
 
             let redact_errors = config.server.redact_errors;
-            let server = Arc::new(server);
+            let server = Arc::new(
+                server
+                    .with_mcp_keys(&config.mcp_keys)
+                    .with_mcp_limits(&config.mcp_limits, &config.mcp_keys)
+                    .with_oidc(config.oidc.as_ref())?,
+            );
 
             if let Err(e) = server.startup_reindex(&config.folders).await {
                 tracing::warn!("Startup reindex failed: {:?}", e);
             }
 
+            if let Err(e) = server.restore_mcp_sessions().await {
+                tracing::warn!("Failed to restore MCP sessions: {:?}", e);
+            }
+
+            match server.reload_acl().await {
+                Ok(0) => {}
+                Ok(rules) => tracing::info!("Loaded {} access control rules", rules),
+                Err(e) => tracing::warn!("Failed to load access control list: {:?}", e),
+            }
+
+            match server.reload_revocations().await {
+                Ok(0) => {}
+                Ok(revocations) => tracing::info!("Loaded {} token revocations", revocations),
+                Err(e) => tracing::warn!("Failed to load token revocations: {:?}", e),
+            }
+
+            match server.reload_api_keys().await {
+                Ok(0) => {}
+                Ok(keys) => tracing::info!("Loaded {} API keys", keys),
+                Err(e) => tracing::warn!("Failed to load API keys: {:?}", e),
+            }
+
+            match server.reload_key_ring().await {
+                Ok(0) => {}
+                Ok(keys) => tracing::info!("Loaded {} rotated signing keys", keys),
+                Err(e) => tracing::warn!("Failed to load rotated signing keys: {:?}", e),
+            }
+
             // Spawn workers AFTER startup_reindex to avoid race conditions
             server.spawn_workers(worker_receivers);
 
@@ -910,10 +1126,120 @@ async fn main() -> Result<()> {
                 }
             }
         }
+        ServSubcommand::McpStdio {
+            config,
+            store,
+            remote,
+            token,
+        } => {
+            let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+            let stdout = tokio::io::stdout();
+
+            if let Some(remote) = remote {
+                let token = token.as_deref().ok_or_else(|| {
+                    anyhow::anyhow!("--token or RELAY_MCP_TOKEN is required with --remote")
+                })?;
+                Url::parse(remote).with_context(|| format!("Invalid relay URL: {}", remote))?;
+                tracing::info!("Forwarding MCP over stdio to {}", remote);
+                relay::mcp::stdio::serve_remote(remote, token, stdin, stdout).await?;
+            } else {
+                let config = load_config_for_serve_args(
+                    config.as_ref(),
+                    store,
+                    &None,
+                    &None,
+                    &None,
+                    &None,
+                    &None,
+                    &None,
+                    &None,
+                )?;
+
+                let store = if let Some(store) = get_store_from_config(&config.store)? {
+                    store.init().await?;
+                    Some(store)
+                } else {
+                    tracing::warn!("No store set. Documents will be stored in memory only.");
+                    None
+                };
+
+                let token = CancellationToken::new();
+                let (server, worker_receivers) = relay::server::Server::new(
+                    store,
+                    std::time::Duration::from_secs(config.server.checkpoint_freq_seconds),
+                    None,
+                    None,
+                    Vec::new(),
+                    token.clone(),
+                    config.server.doc_gc,
+                    None,
+                )
+                .await?;
+                let server = Arc::new(server.with_mcp_limits(&config.mcp_limits, &[]));
+
+                if let Err(e) = server.startup_reindex(&config.folders).await {
+                    tracing::warn!("Startup reindex failed: {:?}", e);
+                }
+                server.spawn_workers(worker_receivers);
+
+                tracing::info!("Serving MCP over stdio");
+                relay::mcp::stdio::serve_local(server.clone(), stdin, stdout).await?;
+
+                // Let the doc workers save before exiting
+                token.cancel();
+                server.wait_for_doc_workers().await;
+            }
+        }
         ServSubcommand::Sign { auth } => {
             let authenticator = Authenticator::new(auth)?;
             sign_stdin(&authenticator).await?;
         }
+        ServSubcommand::Revocations { url, token, cmd } => match cmd {
+            RevocationsSubcommand::List => list_revocations(url, token).await?,
+            RevocationsSubcommand::Add {
+                kind,
+                value,
+                reason,
+            } => {
+                add_revocation(
+                    url,
+                    token,
+                    (*kind).into(),
+                    value.as_deref(),
+                    reason.as_deref(),
+                )
+                .await?
+            }
+            RevocationsSubcommand::Remove { kind, value } => {
+                remove_revocation(url, token, (*kind).into(), value).await?
+            }
+        },
+        ServSubcommand::ApiKeys { url, token, cmd } => match cmd {
+            ApiKeysSubcommand::List => list_api_keys(url, token).await?,
+            ApiKeysSubcommand::Create {
+                name,
+                scopes,
+                permission,
+                expires_in_days,
+            } => {
+                create_api_key(
+                    url,
+                    token,
+                    name,
+                    scopes,
+                    (*permission).into(),
+                    expires_in_days.map(|days| days * 24 * 60 * 60),
+                )
+                .await?
+            }
+            ApiKeysSubcommand::Revoke { name } => delete_api_key(url, token, name).await?,
+        },
+        ServSubcommand::Keys { url, cmd } => match cmd {
+            KeysSubcommand::List => list_keys(url).await?,
+            KeysSubcommand::Rotate { token, grace_hours } => {
+                rotate_keys(url, token, grace_hours * 60 * 60).await?
+            }
+        },
         ServSubcommand::Verify {
             auth,
             doc_id,
@@ -0,0 +1,232 @@
+//! Audit trail of MCP `tools/call` requests.
+//!
+//! Entries are kept in a [`RotatingLog`] under `mcp-audit/`.
+
+use crate::rotating_log::{LogEntry, RotatingLog};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use y_sweet_core::store::Store;
+
+pub const AUDIT_PREFIX: &str = "mcp-audit";
+
+/// Longer string arguments (e.g. `write` content) are cut to this many bytes.
+const MAX_ARGUMENT_LEN: usize = 2048;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Ok,
+    Error,
+    Timeout,
+    Cancelled,
+}
+
+impl AuditOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditOutcome::Ok => "ok",
+            AuditOutcome::Error => "error",
+            AuditOutcome::Timeout => "timeout",
+            AuditOutcome::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Milliseconds since the epoch when the call started.
+    pub timestamp: u64,
+    pub session_id: String,
+    pub principal: String,
+    pub client_info: Option<Value>,
+    pub tool: String,
+    pub arguments: Value,
+    /// Documents named by the call's path arguments.
+    pub doc_ids: Vec<String>,
+    pub outcome: AuditOutcome,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+impl LogEntry for AuditEntry {
+    fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+}
+
+pub struct AuditLog {
+    log: Arc<RotatingLog<AuditEntry>>,
+}
+
+impl AuditLog {
+    pub fn new(store: Option<Arc<Box<dyn Store>>>) -> Self {
+        Self {
+            log: Arc::new(RotatingLog::new(AUDIT_PREFIX, store)),
+        }
+    }
+
+    /// Queue an entry, with long string arguments truncated.
+    pub fn record(&self, mut entry: AuditEntry) {
+        truncate_strings(&mut entry.arguments);
+        self.log.record(entry);
+    }
+
+    pub async fn flush(&self) {
+        self.log.flush().await;
+    }
+
+    /// Entries with `from_ms <= timestamp <= to_ms`, oldest first, optionally
+    /// for one session only. Unflushed entries are included.
+    pub async fn query(
+        &self,
+        from_ms: u64,
+        to_ms: u64,
+        session_id: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<AuditEntry>> {
+        self.log
+            .query(
+                from_ms,
+                to_ms,
+                |e| session_id.map_or(true, |sid| e.session_id == sid),
+                limit,
+            )
+            .await
+    }
+}
+
+fn truncate_strings(value: &mut Value) {
+    match value {
+        Value::String(s) if s.len() > MAX_ARGUMENT_LEN => {
+            let mut end = MAX_ARGUMENT_LEN;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            let total = s.len();
+            s.truncate(end);
+            s.push_str(&format!("... [{} bytes total]", total));
+        }
+        Value::Array(items) => items.iter_mut().for_each(truncate_strings),
+        Value::Object(map) => map.values_mut().for_each(truncate_strings),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use dashmap::DashMap;
+    use serde_json::json;
+    use y_sweet_core::store::{FileInfo, Result as StoreResult};
+
+    #[derive(Default, Clone)]
+    struct MemoryStore {
+        data: Arc<DashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl Store for MemoryStore {
+        async fn init(&self) -> StoreResult<()> {
+            Ok(())
+        }
+        async fn get(&self, key: &str) -> StoreResult<Option<Vec<u8>>> {
+            Ok(self.data.get(key).map(|v| v.clone()))
+        }
+        async fn set(&self, key: &str, value: Vec<u8>) -> StoreResult<()> {
+            self.data.insert(key.to_owned(), value);
+            Ok(())
+        }
+        async fn remove(&self, key: &str) -> StoreResult<()> {
+            self.data.remove(key);
+            Ok(())
+        }
+        async fn exists(&self, key: &str) -> StoreResult<bool> {
+            Ok(self.data.contains_key(key))
+        }
+        async fn list(&self, prefix: &str) -> StoreResult<Vec<FileInfo>> {
+            let dir = format!("{}/", prefix);
+            Ok(self
+                .data
+                .iter()
+                .filter_map(|e| {
+                    e.key().strip_prefix(&dir).map(|name| FileInfo {
+                        key: name.to_string(),
+                        size: e.value().len() as u64,
+                        last_modified: 0,
+                    })
+                })
+                .collect())
+        }
+    }
+
+    // 2026-10-18T00:00:00Z
+    const DAY: u64 = 1_792_281_600_000;
+
+    fn entry(session: &str, timestamp: u64) -> AuditEntry {
+        AuditEntry {
+            timestamp,
+            session_id: session.to_string(),
+            principal: "mcp-api-key".to_string(),
+            client_info: Some(json!({"name": "test-client"})),
+            tool: "read".to_string(),
+            arguments: json!({"file_path": "Lens/Doc.md"}),
+            doc_ids: vec!["relay-doc".to_string()],
+            outcome: AuditOutcome::Ok,
+            error: None,
+            duration_ms: 3,
+        }
+    }
+
+    #[tokio::test]
+    async fn flush_rotates_by_date_and_query_reads_back() {
+        let store = MemoryStore::default();
+        let log = Arc::new(AuditLog::new(Some(Arc::new(Box::new(store.clone())))));
+
+        log.record(entry("s1", DAY - 1_000));
+        log.record(entry("s2", DAY + 1_000));
+        log.record(entry("s1", DAY + 2_000));
+        log.flush().await;
+
+        let mut keys: Vec<String> = store.data.iter().map(|e| e.key().clone()).collect();
+        keys.sort();
+        assert_eq!(keys.len(), 2);
+        assert!(keys[0].starts_with("mcp-audit/2026-10-17/"), "{:?}", keys);
+        assert!(keys[1].starts_with("mcp-audit/2026-10-18/"), "{:?}", keys);
+
+        let all = log
+            .query(DAY - 10_000, DAY + 10_000, None, 100)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+
+        let s1 = log
+            .query(DAY - 10_000, DAY + 10_000, Some("s1"), 100)
+            .await
+            .unwrap();
+        assert_eq!(s1.len(), 2);
+
+        let today = log.query(DAY, DAY + 10_000, None, 100).await.unwrap();
+        assert_eq!(today.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn unflushed_entries_are_queryable() {
+        let log = Arc::new(AuditLog::new(None));
+        log.record(entry("s1", DAY));
+        let found = log.query(DAY, DAY, Some("s1"), 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+    }
+
+    #[test]
+    fn long_arguments_are_truncated() {
+        let mut args = json!({"content": "x".repeat(5000), "file_path": "Lens/Doc.md"});
+        truncate_strings(&mut args);
+        let content = args["content"].as_str().unwrap();
+        assert!(content.len() < 2100);
+        assert!(content.ends_with("[5000 bytes total]"));
+        assert_eq!(args["file_path"], "Lens/Doc.md");
+    }
+}
@@ -0,0 +1,435 @@
+use crate::acl::{Acl, AclDecision, AclPrincipal};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use y_sweet_core::api_key::ApiKey;
+use y_sweet_core::api_types::Authorization;
+use y_sweet_core::auth::Permission;
+use y_sweet_core::config::{McpKeyConfig, McpMode};
+use y_sweet_core::doc_resolver::DocumentResolver;
+
+/// Which documents a credential can reach.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum McpScope {
+    /// Every folder (shared MCP_API_KEY, server tokens, unrestricted keys).
+    All,
+    /// Folder paths such as "Lens" or "Lens Edu/Biology".
+    Folders(Vec<String>),
+    /// A single document, from a CWT doc token.
+    Doc(String),
+    /// Every doc whose ID starts with the prefix, from a CWT prefix token.
+    DocPrefix(String),
+}
+
+/// Who an MCP credential belongs to. Kept apart from the `user` claim of a
+/// token so that no user name can pass for the shared key, a server token or
+/// a configured key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "name", rename_all = "snake_case")]
+pub enum McpPrincipal {
+    /// The shared MCP_API_KEY.
+    SharedKey,
+    /// A server token.
+    ServerToken,
+    /// A doc or prefix token issued without a user, labelled `doc:<id>` or
+    /// `prefix:<prefix>`.
+    Anonymous(String),
+    /// The user a doc or prefix token was issued to.
+    User(String),
+    /// A separately issued key, by name.
+    Key(String),
+}
+
+impl fmt::Display for McpPrincipal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SharedKey => f.write_str("mcp-api-key"),
+            Self::ServerToken => f.write_str("server-token"),
+            Self::Anonymous(label) | Self::User(label) => f.write_str(label),
+            Self::Key(name) => write!(f, "key:{}", name),
+        }
+    }
+}
+
+impl McpPrincipal {
+    /// The name rate limits are counted under. Unlike the display form, users
+    /// are kept apart from keys so a user can't share or take a key's limits.
+    pub fn limit_id(&self) -> String {
+        match self {
+            Self::User(user) => format!("user:{}", user),
+            principal => principal.to_string(),
+        }
+    }
+}
+
+/// The identity and permissions behind an MCP bearer token or path key.
+/// Bound to the MCP session at initialize time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpCredential {
+    pub principal: McpPrincipal,
+    pub scope: McpScope,
+    pub mode: McpMode,
+}
+
+impl McpCredential {
+    /// The credential behind the shared MCP_API_KEY: full access everywhere.
+    pub fn shared_key() -> Self {
+        Self {
+            principal: McpPrincipal::SharedKey,
+            scope: McpScope::All,
+            mode: McpMode::Full,
+        }
+    }
+
+    pub fn from_key_config(config: &McpKeyConfig) -> Self {
+        Self {
+            principal: McpPrincipal::Key(config.name.clone()),
+            scope: folder_scope(&config.folders),
+            mode: config.mode,
+        }
+    }
+
+    /// The credential behind a service-account API key. The key verifies to a
+    /// prefix permission over every document, so its folders are taken from
+    /// the key itself rather than from the verified permission.
+    pub fn from_api_key(key: &ApiKey) -> Self {
+        Self {
+            principal: McpPrincipal::Key(key.name.clone()),
+            scope: folder_scope(&key.scopes),
+            mode: mode_for(key.permission),
+        }
+    }
+
+    /// Map a verified CWT/legacy token to an MCP credential. File tokens are
+    /// not accepted.
+    pub fn from_permission(permission: Permission) -> Option<Self> {
+        match permission {
+            Permission::Server => Some(Self {
+                principal: McpPrincipal::ServerToken,
+                scope: McpScope::All,
+                mode: McpMode::Full,
+            }),
+            Permission::Doc(doc) => Some(Self {
+                principal: match doc.user {
+                    Some(user) => McpPrincipal::User(user),
+                    None => McpPrincipal::Anonymous(format!("doc:{}", doc.doc_id)),
+                },
+                scope: McpScope::Doc(doc.doc_id),
+                mode: mode_for(doc.authorization),
+            }),
+            Permission::Prefix(prefix) => Some(Self {
+                principal: match prefix.user {
+                    Some(user) => McpPrincipal::User(user),
+                    None => McpPrincipal::Anonymous(format!("prefix:{}", prefix.prefix)),
+                },
+                scope: McpScope::DocPrefix(prefix.prefix),
+                mode: mode_for(prefix.authorization),
+            }),
+            Permission::File(_) => None,
+        }
+    }
+
+    /// Whether the document at `path` (with ID `doc_id`, if it exists yet) is
+    /// inside this credential's scope.
+    pub fn covers(&self, path: &str, doc_id: Option<&str>) -> bool {
+        match &self.scope {
+            McpScope::All => true,
+            McpScope::Folders(folders) => folders.iter().any(|folder| {
+                path == folder
+                    || (path.starts_with(folder.as_str()) && path[folder.len()..].starts_with('/'))
+            }),
+            McpScope::Doc(id) => doc_id == Some(id.as_str()),
+            McpScope::DocPrefix(prefix) => doc_id.is_some_and(|id| id.starts_with(prefix.as_str())),
+        }
+    }
+
+    /// Like `covers`, looking up the doc ID only when the scope needs it, and
+    /// also requiring that the access control list doesn't deny the path.
+    pub fn can_see(&self, resolver: &DocumentResolver, acl: &Acl, path: &str) -> bool {
+        let covered = match &self.scope {
+            McpScope::All | McpScope::Folders(_) => self.covers(path, None),
+            McpScope::Doc(_) | McpScope::DocPrefix(_) => {
+                let doc_id = resolver.resolve_path(path).map(|info| info.doc_id);
+                self.covers(path, doc_id.as_deref())
+            }
+        };
+        covered && self.acl_decision(acl, path) != AclDecision::Denied
+    }
+
+    /// The name the access control list knows this credential by: the user a
+    /// token was issued to, or `key:<name>` for a separately issued key. The
+    /// shared key, server tokens and tokens without a user aren't subject to it.
+    pub fn acl_subject(&self) -> Option<String> {
+        match &self.principal {
+            McpPrincipal::SharedKey | McpPrincipal::ServerToken | McpPrincipal::Anonymous(_) => {
+                None
+            }
+            McpPrincipal::User(user) => Some(user.clone()),
+            McpPrincipal::Key(_) => Some(self.principal.to_string()),
+        }
+    }
+
+    /// What the access control list says about this credential at `path`.
+    /// Keys are held to the rules for `key:<name>`, as they are over HTTP.
+    pub fn acl_decision(&self, acl: &Acl, path: &str) -> AclDecision {
+        let principal = match &self.principal {
+            McpPrincipal::SharedKey | McpPrincipal::ServerToken | McpPrincipal::Anonymous(_) => {
+                return AclDecision::Unrestricted
+            }
+            McpPrincipal::User(user) => AclPrincipal::User(user),
+            McpPrincipal::Key(name) => AclPrincipal::Key(name),
+        };
+        acl.decide_for(principal, path)
+    }
+
+    /// Author recorded on CriticMarkup suggestions made through this credential.
+    /// The shared key keeps the historical "AI" author.
+    pub fn suggestion_author(&self) -> String {
+        match &self.principal {
+            McpPrincipal::SharedKey => "AI".to_string(),
+            principal => principal.to_string(),
+        }
+    }
+
+    /// Check that the credential's mode allows `tool`.
+    pub fn require_mode(&self, required: McpMode, tool: &str) -> Result<(), String> {
+        if self.mode >= required {
+            return Ok(());
+        }
+        let mode = match self.mode {
+            McpMode::ReadOnly => "read-only",
+            McpMode::SuggestOnly => "suggest-only",
+            McpMode::Full => "full",
+        };
+        Err(format!(
+            "Error: The {} tool is not permitted for this {} credential",
+            tool, mode
+        ))
+    }
+}
+
+/// Folder scope for a key's folder list. An empty list means every folder.
+fn folder_scope(folders: &[String]) -> McpScope {
+    if folders.is_empty() {
+        McpScope::All
+    } else {
+        McpScope::Folders(
+            folders
+                .iter()
+                .map(|f| f.trim_matches('/').to_string())
+                .collect(),
+        )
+    }
+}
+
+/// The MCP mode matching a token's authorization level.
+pub fn mode_for(authorization: Authorization) -> McpMode {
+    match authorization {
+        // MCP has no comment-only mode; commenters get read access
+        Authorization::ReadOnly | Authorization::Comment => McpMode::ReadOnly,
+        Authorization::Suggest => McpMode::SuggestOnly,
+        Authorization::Full => McpMode::Full,
+    }
+}
+
+/// Separately issued MCP keys, held by SHA-256 digest so the raw keys are not
+/// kept around after startup.
+#[derive(Default)]
+pub struct McpKeyring {
+    keys: HashMap<[u8; 32], McpCredential>,
+}
+
+impl McpKeyring {
+    pub fn new(configs: &[McpKeyConfig]) -> Self {
+        let keys = configs
+            .iter()
+            .map(|config| (digest(&config.key), McpCredential::from_key_config(config)))
+            .collect();
+        Self { keys }
+    }
+
+    pub fn lookup(&self, key: &str) -> Option<McpCredential> {
+        self.keys.get(&digest(key)).cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+}
+
+fn digest(key: &str) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&Sha256::digest(key.as_bytes()));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use y_sweet_core::auth::{DocPermission, PrefixPermission};
+
+    fn folder_key(folders: &[&str], mode: McpMode) -> McpKeyConfig {
+        McpKeyConfig {
+            name: "assistant".to_string(),
+            key: "0123456789abcdef".to_string(),
+            folders: folders.iter().map(|f| f.to_string()).collect(),
+            mode,
+            limits: None,
+        }
+    }
+
+    #[test]
+    fn folder_scope_matches_whole_path_segments() {
+        let cred =
+            McpCredential::from_key_config(&folder_key(&["Lens Edu/Biology/"], McpMode::ReadOnly));
+        assert!(cred.covers("Lens Edu/Biology/Cells.md", None));
+        assert!(cred.covers("Lens Edu/Biology/Plants/Roots.md", None));
+        assert!(!cred.covers("Lens Edu/Biology2/Cells.md", None));
+        assert!(!cred.covers("Lens Edu/Chemistry.md", None));
+        assert!(!cred.covers("Lens/Photosynthesis.md", None));
+    }
+
+    #[test]
+    fn empty_folder_list_means_all_folders() {
+        let cred = McpCredential::from_key_config(&folder_key(&[], McpMode::Full));
+        assert_eq!(cred.scope, McpScope::All);
+        assert!(cred.covers("Anything/At/All.md", None));
+    }
+
+    #[test]
+    fn doc_token_is_scoped_to_its_doc() {
+        let cred = McpCredential::from_permission(Permission::Doc(DocPermission {
+            doc_id: "relay-doc1".to_string(),
+            authorization: Authorization::ReadOnly,
+            user: Some("alice".to_string()),
+        }))
+        .unwrap();
+        assert_eq!(cred.principal, McpPrincipal::User("alice".to_string()));
+        assert_eq!(cred.mode, McpMode::ReadOnly);
+        assert!(cred.covers("Lens/A.md", Some("relay-doc1")));
+        assert!(!cred.covers("Lens/B.md", Some("relay-doc2")));
+        assert!(!cred.covers("Lens/New.md", None));
+    }
+
+    #[test]
+    fn prefix_token_matches_doc_id_prefix() {
+        let cred = McpCredential::from_permission(Permission::Prefix(PrefixPermission {
+            prefix: "relay-".to_string(),
+            authorization: Authorization::Full,
+            user: None,
+        }))
+        .unwrap();
+        assert_eq!(
+            cred.principal,
+            McpPrincipal::Anonymous("prefix:relay-".to_string())
+        );
+        assert_eq!(cred.mode, McpMode::Full);
+        assert!(cred.covers("Lens/A.md", Some("relay-doc1")));
+        assert!(!cred.covers("Lens/A.md", Some("other-doc1")));
+    }
+
+    #[test]
+    fn user_names_cannot_pass_for_built_in_principals() {
+        let acl = Acl::default();
+        acl.replace(
+            serde_json::from_value(serde_json::json!({
+                "rules": [
+                    {"subject": "key:assistant", "path": "Lens/**", "permission": "full"}
+                ]
+            }))
+            .unwrap(),
+        );
+        let as_user = |user: &str| {
+            McpCredential::from_permission(Permission::Doc(DocPermission {
+                doc_id: "relay-doc1".to_string(),
+                authorization: Authorization::Full,
+                user: Some(user.to_string()),
+            }))
+            .unwrap()
+        };
+
+        for user in ["mcp-api-key", "server-token", "doc:relay-doc1", "prefix:"] {
+            let cred = as_user(user);
+            assert_eq!(cred.acl_subject().as_deref(), Some(user));
+            assert_eq!(cred.acl_decision(&acl, "Lens/A.md"), AclDecision::Denied);
+        }
+        assert_eq!(as_user("mcp-api-key").suggestion_author(), "mcp-api-key");
+        assert_eq!(McpCredential::shared_key().suggestion_author(), "AI");
+
+        let impostor = as_user("key:assistant");
+        assert_eq!(
+            impostor.acl_decision(&acl, "Lens/A.md"),
+            AclDecision::Denied
+        );
+        assert_eq!(
+            impostor.acl_decision(&acl, "Notes/A.md"),
+            AclDecision::Unrestricted
+        );
+        let key = McpCredential::from_key_config(&folder_key(&[], McpMode::Full));
+        assert_eq!(
+            key.acl_decision(&acl, "Lens/A.md"),
+            AclDecision::Granted(Authorization::Full)
+        );
+    }
+
+    #[test]
+    fn api_key_is_scoped_to_its_folders() {
+        let (_, key) = ApiKey::generate(
+            "ci",
+            vec!["Lens Edu/".to_string()],
+            Authorization::Suggest,
+            0,
+            None,
+        );
+        let cred = McpCredential::from_api_key(&key);
+        assert_eq!(cred.principal, McpPrincipal::Key("ci".to_string()));
+        assert_eq!(cred.mode, McpMode::SuggestOnly);
+        assert!(cred.covers("Lens Edu/Cells.md", Some("relay-doc1")));
+        assert!(!cred.covers("Lens/Cells.md", Some("relay-doc1")));
+        assert_eq!(
+            cred.acl_decision(&Acl::default(), "Lens/Cells.md"),
+            AclDecision::Unrestricted
+        );
+
+        // Rules for the key narrow it within its folders, as over HTTP
+        let acl = Acl::default();
+        acl.replace(
+            serde_json::from_value(serde_json::json!({
+                "rules": [
+                    {"subject": "key:ci", "path": "Lens Edu/**", "permission": "read-only"}
+                ]
+            }))
+            .unwrap(),
+        );
+        assert_eq!(
+            cred.acl_decision(&acl, "Lens Edu/Cells.md"),
+            AclDecision::Granted(Authorization::ReadOnly)
+        );
+    }
+
+    #[test]
+    fn require_mode_orders_read_suggest_full() {
+        let read_only = McpCredential::from_key_config(&folder_key(&[], McpMode::ReadOnly));
+        let suggest = McpCredential::from_key_config(&folder_key(&[], McpMode::SuggestOnly));
+        assert!(read_only.require_mode(McpMode::ReadOnly, "read").is_ok());
+        assert!(read_only
+            .require_mode(McpMode::SuggestOnly, "edit")
+            .unwrap_err()
+            .contains("read-only"));
+        assert!(suggest.require_mode(McpMode::SuggestOnly, "edit").is_ok());
+        assert!(suggest.require_mode(McpMode::Full, "create").is_err());
+    }
+
+    #[test]
+    fn keyring_looks_up_by_raw_key() {
+        let keyring = McpKeyring::new(&[folder_key(&["Lens"], McpMode::SuggestOnly)]);
+        let cred = keyring.lookup("0123456789abcdef").unwrap();
+        assert_eq!(cred.principal, McpPrincipal::Key("assistant".to_string()));
+        assert!(keyring.lookup("0123456789abcdeg").is_none());
+    }
+}
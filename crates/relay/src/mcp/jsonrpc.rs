@@ -8,6 +8,11 @@ pub const METHOD_NOT_FOUND: i64 = -32601;
 pub const INVALID_PARAMS: i64 = -32602;
 pub const INTERNAL_ERROR: i64 = -32603;
 
+// MCP-specific error codes
+pub const REQUEST_TIMEOUT: i64 = -32001;
+pub const RATE_LIMITED: i64 = -32003;
+pub const REQUEST_CANCELLED: i64 = -32800;
+
 #[derive(Debug, Deserialize)]
 pub struct JsonRpcRequest {
     pub jsonrpc: String,
@@ -111,6 +116,20 @@ pub fn error_response(id: Value, code: i64, message: impl Into<String>) -> JsonR
     }
 }
 
+/// Create an error response carrying structured `data`.
+pub fn error_response_with_data(
+    id: Value,
+    code: i64,
+    message: impl Into<String>,
+    data: Value,
+) -> JsonRpcResponse {
+    let mut resp = error_response(id, code, message);
+    if let Some(ref mut error) = resp.error {
+        error.data = Some(data);
+    }
+    resp
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,378 @@
+//! Per-session and per-credential caps on MCP tool usage.
+//!
+//! Each limit is counted in a fixed window (a minute for calls, an hour for
+//! edits and bytes) that starts with the first call after the previous window
+//! ran out. A call is rejected when any applicable counter is already at its
+//! cap, so a single large result can overshoot `bytes_per_hour` once.
+
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use y_sweet_core::config::{McpKeyConfig, McpLimitsConfig, McpQuota};
+
+/// Tools that change documents and count towards `edits_per_hour`.
+const EDIT_TOOLS: &[&str] = &["edit", "multi_edit", "write", "create", "move"];
+
+/// Expired windows are swept once the table grows past this many entries.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitKind {
+    Calls,
+    Edits,
+    Bytes,
+}
+
+impl LimitKind {
+    fn window(self) -> Duration {
+        match self {
+            LimitKind::Calls => Duration::from_secs(60),
+            LimitKind::Edits | LimitKind::Bytes => Duration::from_secs(3600),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LimitKind::Calls => "calls_per_minute",
+            LimitKind::Edits => "edits_per_hour",
+            LimitKind::Bytes => "bytes_per_hour",
+        }
+    }
+
+    fn max(self, quota: &McpQuota) -> Option<u64> {
+        match self {
+            LimitKind::Calls => quota.calls_per_minute.map(u64::from),
+            LimitKind::Edits => quota.edits_per_hour.map(u64::from),
+            LimitKind::Bytes => quota.bytes_per_hour,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitScope {
+    Session,
+    Key,
+}
+
+impl LimitScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LimitScope::Session => "session",
+            LimitScope::Key => "key",
+        }
+    }
+}
+
+/// A call refused because a limit is used up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimited {
+    pub scope: LimitScope,
+    pub limit: LimitKind,
+    pub max: u64,
+    pub retry_after: Duration,
+}
+
+impl RateLimited {
+    pub fn message(&self) -> String {
+        let what = match self.scope {
+            LimitScope::Session => "this session",
+            LimitScope::Key => "this credential",
+        };
+        format!(
+            "Rate limit exceeded: {} is limited to {} {}. Retry after {}s.",
+            what,
+            self.max,
+            self.limit.as_str().replace('_', " "),
+            self.retry_after.as_secs().max(1)
+        )
+    }
+}
+
+struct Window {
+    started: Instant,
+    used: u64,
+}
+
+#[derive(Default)]
+pub struct McpLimiter {
+    defaults: McpLimitsConfig,
+    /// Limits for configured keys, by principal ("key:{name}"), already merged
+    /// with the defaults.
+    per_key: HashMap<String, McpLimitsConfig>,
+    windows: DashMap<(LimitScope, String, LimitKind), Window>,
+}
+
+impl McpLimiter {
+    pub fn new(defaults: &McpLimitsConfig, keys: &[McpKeyConfig]) -> Self {
+        let per_key = keys
+            .iter()
+            .filter_map(|key| {
+                let limits = key.limits.as_ref()?;
+                Some((
+                    format!("key:{}", key.name),
+                    McpLimitsConfig {
+                        session: defaults.session.overridden_by(&limits.session),
+                        key: defaults.key.overridden_by(&limits.key),
+                    },
+                ))
+            })
+            .collect();
+        Self {
+            defaults: defaults.clone(),
+            per_key,
+            windows: DashMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.defaults == McpLimitsConfig::default()
+            && self
+                .per_key
+                .values()
+                .all(|limits| *limits == McpLimitsConfig::default())
+    }
+
+    /// Count a call to `tool`, or refuse it if a limit is used up. Nothing is
+    /// counted for a refused call.
+    pub fn check(&self, principal: &str, session_id: &str, tool: &str) -> Result<(), RateLimited> {
+        self.check_at(principal, session_id, tool, Instant::now())
+    }
+
+    /// Add the size of a returned result to the byte counters.
+    pub fn record_bytes(&self, principal: &str, session_id: &str, bytes: u64) {
+        let now = Instant::now();
+        let limits = self.limits_for(principal);
+        for (scope, id, quota) in scopes(limits, principal, session_id) {
+            if LimitKind::Bytes.max(quota).is_some() {
+                self.window(scope, id, LimitKind::Bytes, now).used += bytes;
+            }
+        }
+    }
+
+    fn check_at(
+        &self,
+        principal: &str,
+        session_id: &str,
+        tool: &str,
+        now: Instant,
+    ) -> Result<(), RateLimited> {
+        if self.windows.len() > PRUNE_THRESHOLD {
+            self.windows
+                .retain(|(_, _, kind), w| now.duration_since(w.started) < kind.window());
+        }
+
+        let limits = self.limits_for(principal);
+        let mut kinds = vec![LimitKind::Calls, LimitKind::Bytes];
+        if EDIT_TOOLS.contains(&tool) {
+            kinds.push(LimitKind::Edits);
+        }
+
+        let mut counted = Vec::new();
+        for (scope, id, quota) in scopes(limits, principal, session_id) {
+            for &kind in &kinds {
+                let Some(max) = kind.max(quota) else {
+                    continue;
+                };
+                let window = self.window(scope, id, kind, now);
+                if window.used >= max {
+                    return Err(RateLimited {
+                        scope,
+                        limit: kind,
+                        max,
+                        retry_after: kind.window() - now.duration_since(window.started),
+                    });
+                }
+                // Bytes are added once the result is known
+                if kind != LimitKind::Bytes {
+                    counted.push((scope, id, kind));
+                }
+            }
+        }
+
+        for (scope, id, kind) in counted {
+            self.window(scope, id, kind, now).used += 1;
+        }
+        Ok(())
+    }
+
+    fn limits_for(&self, principal: &str) -> &McpLimitsConfig {
+        self.per_key.get(principal).unwrap_or(&self.defaults)
+    }
+
+    /// The current window for a counter, starting a new one if it ran out.
+    fn window(
+        &self,
+        scope: LimitScope,
+        id: &str,
+        kind: LimitKind,
+        now: Instant,
+    ) -> dashmap::mapref::one::RefMut<'_, (LimitScope, String, LimitKind), Window> {
+        let mut window = self
+            .windows
+            .entry((scope, id.to_string(), kind))
+            .or_insert(Window {
+                started: now,
+                used: 0,
+            });
+        if now.duration_since(window.started) >= kind.window() {
+            *window = Window {
+                started: now,
+                used: 0,
+            };
+        }
+        window
+    }
+}
+
+fn scopes<'a>(
+    limits: &'a McpLimitsConfig,
+    principal: &'a str,
+    session_id: &'a str,
+) -> [(LimitScope, &'a str, &'a McpQuota); 2] {
+    [
+        (LimitScope::Session, session_id, &limits.session),
+        (LimitScope::Key, principal, &limits.key),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use y_sweet_core::config::McpMode;
+
+    fn limits(session: McpQuota, key: McpQuota) -> McpLimitsConfig {
+        McpLimitsConfig { session, key }
+    }
+
+    #[test]
+    fn calls_per_minute_resets_with_the_window() {
+        let limiter = McpLimiter::new(
+            &limits(
+                McpQuota {
+                    calls_per_minute: Some(2),
+                    ..Default::default()
+                },
+                McpQuota::default(),
+            ),
+            &[],
+        );
+        let start = Instant::now();
+        assert!(limiter.check_at("p", "s1", "grep", start).is_ok());
+        assert!(limiter.check_at("p", "s1", "grep", start).is_ok());
+
+        let err = limiter
+            .check_at("p", "s1", "grep", start + Duration::from_secs(20))
+            .unwrap_err();
+        assert_eq!(err.scope, LimitScope::Session);
+        assert_eq!(err.limit, LimitKind::Calls);
+        assert_eq!(err.retry_after, Duration::from_secs(40));
+
+        // Another session has its own counter
+        assert!(limiter.check_at("p", "s2", "grep", start).is_ok());
+        // And the first one recovers after a minute
+        assert!(limiter
+            .check_at("p", "s1", "grep", start + Duration::from_secs(60))
+            .is_ok());
+    }
+
+    #[test]
+    fn edits_are_counted_per_key_across_sessions() {
+        let limiter = McpLimiter::new(
+            &limits(
+                McpQuota::default(),
+                McpQuota {
+                    edits_per_hour: Some(1),
+                    ..Default::default()
+                },
+            ),
+            &[],
+        );
+        assert!(limiter.check("p", "s1", "edit").is_ok());
+        assert!(limiter.check("p", "s1", "read").is_ok());
+        let err = limiter.check("p", "s2", "write").unwrap_err();
+        assert_eq!(err.scope, LimitScope::Key);
+        assert_eq!(err.limit, LimitKind::Edits);
+        assert!(
+            err.message().contains("1 edits per hour"),
+            "{}",
+            err.message()
+        );
+
+        // Other credentials are unaffected
+        assert!(limiter.check("q", "s3", "edit").is_ok());
+    }
+
+    #[test]
+    fn bytes_block_the_next_call_once_used_up() {
+        let limiter = McpLimiter::new(
+            &limits(
+                McpQuota {
+                    bytes_per_hour: Some(1000),
+                    ..Default::default()
+                },
+                McpQuota::default(),
+            ),
+            &[],
+        );
+        assert!(limiter.check("p", "s1", "read").is_ok());
+        limiter.record_bytes("p", "s1", 1500);
+        let err = limiter.check("p", "s1", "read").unwrap_err();
+        assert_eq!(err.limit, LimitKind::Bytes);
+    }
+
+    #[test]
+    fn key_limits_override_defaults_field_by_field() {
+        let key = McpKeyConfig {
+            name: "importer".to_string(),
+            key: "0123456789abcdef".to_string(),
+            folders: Vec::new(),
+            mode: McpMode::Full,
+            limits: Some(limits(
+                McpQuota {
+                    calls_per_minute: Some(3),
+                    ..Default::default()
+                },
+                McpQuota::default(),
+            )),
+        };
+        let limiter = McpLimiter::new(
+            &limits(
+                McpQuota {
+                    calls_per_minute: Some(1),
+                    edits_per_hour: Some(1),
+                    ..Default::default()
+                },
+                McpQuota::default(),
+            ),
+            &[key],
+        );
+        assert!(!limiter.is_empty());
+
+        for _ in 0..3 {
+            assert!(limiter.check("key:importer", "s1", "read").is_ok());
+        }
+        assert!(limiter.check("key:importer", "s1", "read").is_err());
+        // The edit cap is still inherited from the defaults
+        assert!(limiter.check("key:importer", "s2", "edit").is_ok());
+        assert_eq!(
+            limiter
+                .check("key:importer", "s2", "edit")
+                .unwrap_err()
+                .limit,
+            LimitKind::Edits
+        );
+
+        assert!(limiter.check("mcp-api-key", "s3", "read").is_ok());
+        assert!(limiter.check("mcp-api-key", "s3", "read").is_err());
+    }
+
+    #[test]
+    fn no_limits_never_refuses() {
+        let limiter = McpLimiter::default();
+        assert!(limiter.is_empty());
+        for _ in 0..100 {
+            assert!(limiter.check("p", "s1", "edit").is_ok());
+        }
+        assert!(limiter.windows.is_empty());
+    }
+}
@@ -1,6 +1,12 @@
+pub mod audit;
+pub mod auth;
 pub mod jsonrpc;
+pub mod limits;
+pub mod presence;
+pub mod resources;
 pub mod router;
 pub mod session;
+pub mod stdio;
 pub mod tools;
 pub mod transport;
 
@@ -0,0 +1,195 @@
+//! Agent presence in documents.
+//!
+//! While an editing tool runs, the session publishes an awareness state into
+//! the document under a client ID of its own, in the shape lens-editor and the
+//! Obsidian plugin render for collaborators: a name and colour under `user`
+//! and a `cursor` at the edit. Tool calls finish in milliseconds, so the state
+//! is cleared a few seconds after the call rather than straight away; an agent
+//! making several edits in a row stays on screen instead of flickering.
+
+use dashmap::DashMap;
+use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use y_sweet_core::sync::awareness::Awareness;
+use yrs::block::ClientID;
+use yrs::{Assoc, IndexedSequence, TextRef, TransactionMut};
+
+use crate::server::Server;
+
+/// How long the state outlives the call that set it.
+pub const LINGER: Duration = Duration::from_secs(3);
+
+/// Name shown when the MCP client did not identify itself.
+const DEFAULT_NAME: &str = "AI agent";
+
+/// Cursor colours and their translucent selection variants.
+const COLORS: &[(&str, &str)] = &[
+    ("#30bced", "#30bced33"),
+    ("#6eeb83", "#6eeb8333"),
+    ("#ffbc42", "#ffbc4233"),
+    ("#ecd444", "#ecd44433"),
+    ("#ee6352", "#ee635233"),
+    ("#9ac2c9", "#9ac2c933"),
+    ("#8acb88", "#8acb8833"),
+    ("#1be7ff", "#1be7ff33"),
+];
+
+#[derive(Default)]
+pub struct AgentPresence {
+    /// The latest call shown per (client ID, doc ID). Only that call's guard
+    /// clears the state, so overlapping calls don't cut each other short.
+    shown: Arc<DashMap<(ClientID, String), u64>>,
+    next_call: AtomicU64,
+}
+
+impl AgentPresence {
+    /// Show `session_id` as present in `doc_id`, which must be loaded. The
+    /// state is cleared `LINGER` after the returned guard is dropped.
+    pub fn begin(&self, server: &Server, session_id: &str, doc_id: &str) -> PresenceGuard {
+        let client_id = client_id_for(session_id);
+        let name = server
+            .mcp_sessions
+            .get_session(session_id)
+            .and_then(|session| {
+                let info = session.client_info.as_ref()?;
+                let name = info.get("title").or_else(|| info.get("name"))?;
+                name.as_str().map(str::to_string)
+            })
+            .unwrap_or_else(|| DEFAULT_NAME.to_string());
+        let (color, color_light) = COLORS[client_id as usize % COLORS.len()];
+
+        let call = self.next_call.fetch_add(1, Ordering::Relaxed);
+        let key = (client_id, doc_id.to_string());
+        self.shown.insert(key.clone(), call);
+
+        let guard = PresenceGuard {
+            awareness: server.docs().get(doc_id).map(|doc| doc.awareness()),
+            shown: self.shown.clone(),
+            key,
+            call,
+            user: json!({"name": name, "color": color, "colorLight": color_light}),
+        };
+        guard.publish(None);
+        guard
+    }
+}
+
+pub struct PresenceGuard {
+    awareness: Option<Arc<RwLock<Awareness>>>,
+    shown: Arc<DashMap<(ClientID, String), u64>>,
+    key: (ClientID, String),
+    call: u64,
+    user: Value,
+}
+
+impl PresenceGuard {
+    /// Move the agent's cursor, given as built by [`cursor_at`].
+    pub fn set_cursor(&self, cursor: Option<Value>) {
+        self.publish(cursor);
+    }
+
+    fn publish(&self, cursor: Option<Value>) {
+        let Some(awareness) = &self.awareness else {
+            return;
+        };
+        let mut state = json!({ "user": self.user });
+        if let Some(position) = cursor {
+            state["cursor"] = json!({ "anchor": position, "head": position });
+        }
+        awareness
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .set_state(self.key.0, state.to_string());
+    }
+}
+
+impl Drop for PresenceGuard {
+    fn drop(&mut self) {
+        let Some(awareness) = self.awareness.take() else {
+            return;
+        };
+        let shown = self.shown.clone();
+        let key = self.key.clone();
+        let call = self.call;
+        let clear = move || {
+            // Hold the lock so a call starting now republishes after the removal
+            let mut awareness = awareness.write().unwrap_or_else(|e| e.into_inner());
+            if shown.remove_if(&key, |_, latest| *latest == call).is_some() {
+                awareness.remove_state(key.0);
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    tokio::time::sleep(LINGER).await;
+                    clear();
+                });
+            }
+            Err(_) => clear(),
+        }
+    }
+}
+
+/// A cursor position at byte `index` of `text`, as a Yjs relative position in
+/// JSON form. It sticks to the character before it, so it stays at the end of
+/// text the agent just inserted.
+pub fn cursor_at(text: &TextRef, txn: &mut TransactionMut, index: u32) -> Option<Value> {
+    let position = text.sticky_index(txn, index, Assoc::Before)?;
+    serde_json::to_value(&position).ok()
+}
+
+/// Awareness client ID for a session: stable for its lifetime and, like Yjs
+/// client IDs, a 32-bit number.
+fn client_id_for(session_id: &str) -> ClientID {
+    let mut hasher = DefaultHasher::new();
+    session_id.hash(&mut hasher);
+    hasher.finish() as u32 as ClientID
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tools::test_helpers::*;
+    use super::*;
+
+    fn agent_state(server: &Server, doc_id: &str, session_id: &str) -> Option<Value> {
+        let doc = server.docs().get(doc_id)?;
+        let awareness = doc.awareness();
+        let awareness = awareness.read().unwrap();
+        let state = awareness.clients().get(&client_id_for(session_id))?;
+        serde_json::from_str(state).ok()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn state_is_cleared_after_the_last_call_lingers() {
+        let server = build_test_server(&[("/Doc.md", "uuid-doc", "hello")]).await;
+        let doc_id = format!("{}-uuid-doc", RELAY_ID);
+        let sid = setup_session_no_reads(&server);
+
+        let first = server.mcp_presence.begin(&server, &sid, &doc_id);
+        let state = agent_state(&server, &doc_id, &sid).unwrap();
+        assert_eq!(state["user"]["name"], DEFAULT_NAME);
+        assert!(state["user"]["color"].as_str().unwrap().starts_with('#'));
+        assert!(state.get("cursor").is_none());
+
+        // A second call before the first one's state expires keeps it shown
+        let second = server.mcp_presence.begin(&server, &sid, &doc_id);
+        drop(first);
+        tokio::time::sleep(LINGER + Duration::from_secs(1)).await;
+        assert!(agent_state(&server, &doc_id, &sid).is_some());
+
+        drop(second);
+        tokio::time::sleep(LINGER + Duration::from_secs(1)).await;
+        assert!(agent_state(&server, &doc_id, &sid).is_none());
+    }
+
+    #[test]
+    fn sessions_get_distinct_client_ids() {
+        assert_eq!(client_id_for("a"), client_id_for("a"));
+        assert_ne!(client_id_for("a"), client_id_for("b"));
+        assert!(client_id_for("a") <= u32::MAX as ClientID);
+    }
+}
@@ -0,0 +1,248 @@
+//! MCP resources: every document the session's credential can see is exposed
+//! as a `relay:///<folder>/<path>` resource that reads as markdown.
+
+use super::auth::McpCredential;
+use crate::acl::AclDecision;
+use crate::server::Server;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use yrs::{GetString, ReadTxn, Transact};
+
+pub const URI_PREFIX: &str = "relay:///";
+
+/// Build the resource URI for a document path like "Lens Edu/Notes.md".
+pub fn uri_for_path(path: &str) -> String {
+    let mut uri = String::from(URI_PREFIX);
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Inverse of `uri_for_path`. Returns None for foreign URIs or bad escapes.
+pub fn path_for_uri(uri: &str) -> Option<String> {
+    let encoded = uri.strip_prefix(URI_PREFIX)?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// `resources/list`: all documents visible to the credential, sorted by path.
+pub fn list(server: &Arc<Server>, credential: &McpCredential) -> Value {
+    let resolver = server.doc_resolver();
+    let mut paths: Vec<String> = resolver
+        .all_paths()
+        .into_iter()
+        .filter(|p| credential.can_see(resolver, &server.acl, p))
+        .collect();
+    paths.sort();
+
+    let resources: Vec<Value> = paths
+        .iter()
+        .map(|path| {
+            json!({
+                "uri": uri_for_path(path),
+                "name": path,
+                "mimeType": "text/markdown"
+            })
+        })
+        .collect();
+    json!({ "resources": resources })
+}
+
+/// `resources/templates/list`: URI patterns clients can fill in themselves.
+pub fn templates() -> Value {
+    json!({
+        "resourceTemplates": [
+            {
+                "uriTemplate": format!("{}{{+path}}", URI_PREFIX),
+                "name": "Document by path",
+                "description": "A document in the knowledge base, addressed by folder and path (e.g. 'Lens/Photosynthesis.md'). Spaces are percent-encoded.",
+                "mimeType": "text/markdown"
+            },
+            {
+                "uriTemplate": format!("{}{{folder}}/{{+name}}.md", URI_PREFIX),
+                "name": "Document in folder",
+                "description": "A markdown document inside a named folder (e.g. folder 'Lens Edu', name 'Biology/Cells').",
+                "mimeType": "text/markdown"
+            }
+        ]
+    })
+}
+
+/// `resources/read`: the document's accepted view (pending CriticMarkup
+/// suggestions applied as if accepted), as markdown.
+pub async fn read(
+    server: &Arc<Server>,
+    credential: &McpCredential,
+    params: Option<&Value>,
+) -> Result<Value, String> {
+    let (uri, path, doc_id) = resolve_params(server, credential, params)?;
+
+    server
+        .ensure_doc_loaded(&doc_id)
+        .await
+        .map_err(|e| format!("Failed to load document {}: {}", path, e))?;
+
+    let content = {
+        let doc_ref = server
+            .docs()
+            .get(&doc_id)
+            .ok_or_else(|| format!("Document data not loaded: {}", path))?;
+        let awareness = doc_ref.awareness();
+        let guard = awareness.read().unwrap_or_else(|e| e.into_inner());
+        let txn = guard.doc.transact();
+        match txn.get_text("contents") {
+            Some(text) => text.get_string(&txn),
+            None => String::new(),
+        }
+    };
+
+    let spans = super::tools::critic_markup::parse(&content);
+    let accepted = super::tools::critic_markup::accepted_view(&spans);
+
+    Ok(json!({
+        "contents": [
+            {
+                "uri": uri,
+                "mimeType": "text/markdown",
+                "text": accepted
+            }
+        ]
+    }))
+}
+
+/// `resources/subscribe`: queue `notifications/resources/updated` for this
+/// session whenever the document's Y.Doc changes.
+pub fn subscribe(
+    server: &Arc<Server>,
+    session_id: &str,
+    credential: &McpCredential,
+    params: Option<&Value>,
+) -> Result<Value, String> {
+    let (uri, _, doc_id) = resolve_params(server, credential, params)?;
+    server.mcp_sessions.subscribe(session_id, &doc_id, &uri);
+    Ok(json!({}))
+}
+
+/// `resources/unsubscribe`. Unknown URIs are not an error.
+pub fn unsubscribe(
+    server: &Arc<Server>,
+    session_id: &str,
+    params: Option<&Value>,
+) -> Result<Value, String> {
+    let uri = params
+        .and_then(|p| p.get("uri"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Missing required parameter: uri".to_string())?;
+    server.mcp_sessions.unsubscribe(session_id, uri);
+    Ok(json!({}))
+}
+
+/// Extract `uri` from params and resolve it to (uri, path, doc_id), applying
+/// the credential's scope.
+fn resolve_params(
+    server: &Arc<Server>,
+    credential: &McpCredential,
+    params: Option<&Value>,
+) -> Result<(String, String, String), String> {
+    let uri = params
+        .and_then(|p| p.get("uri"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Missing required parameter: uri".to_string())?;
+    let path = path_for_uri(uri).ok_or_else(|| format!("Invalid resource URI: {}", uri))?;
+    let doc_info = server
+        .doc_resolver()
+        .resolve_path(&path)
+        .ok_or_else(|| format!("Resource not found: {}", uri))?;
+    if !credential.covers(&path, Some(&doc_info.doc_id))
+        || credential.acl_decision(&server.acl, &path) == AclDecision::Denied
+    {
+        // Indistinguishable from a missing document on purpose
+        return Err(format!("Resource not found: {}", uri));
+    }
+    Ok((uri.to_string(), path, doc_info.doc_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::auth::{McpPrincipal, McpScope};
+    use crate::mcp::tools::test_helpers::*;
+    use y_sweet_core::config::McpMode;
+
+    #[test]
+    fn uri_round_trips_paths_with_spaces() {
+        let uri = uri_for_path("Lens Edu/Cells & Tissues.md");
+        assert_eq!(uri, "relay:///Lens%20Edu/Cells%20%26%20Tissues.md");
+        assert_eq!(path_for_uri(&uri).unwrap(), "Lens Edu/Cells & Tissues.md");
+        assert!(path_for_uri("file:///etc/passwd").is_none());
+        assert!(path_for_uri("relay:///bad%2").is_none());
+        assert!(path_for_uri("relay:///bad%+1").is_none());
+    }
+
+    #[tokio::test]
+    async fn list_and_read_return_accepted_markdown() {
+        let server =
+            build_test_server(&[("/Notes.md", "uuid-notes", "Hello {--old--}{++new++} world")])
+                .await;
+        let credential = McpCredential::shared_key();
+
+        let listed = list(&server, &credential);
+        assert_eq!(listed["resources"][0]["uri"], "relay:///Lens/Notes.md");
+        assert_eq!(listed["resources"][0]["mimeType"], "text/markdown");
+
+        let params = json!({"uri": "relay:///Lens/Notes.md"});
+        let result = read(&server, &credential, Some(&params)).await.unwrap();
+        assert_eq!(result["contents"][0]["text"], "Hello new world");
+    }
+
+    #[tokio::test]
+    async fn out_of_scope_resources_are_hidden() {
+        let server = build_test_server(&[("/Notes.md", "uuid-notes", "text")]).await;
+        let credential = McpCredential {
+            principal: McpPrincipal::Key("other".into()),
+            scope: McpScope::Folders(vec!["Lens Edu".into()]),
+            mode: McpMode::ReadOnly,
+        };
+
+        assert_eq!(list(&server, &credential)["resources"], json!([]));
+        let params = json!({"uri": "relay:///Lens/Notes.md"});
+        let err = read(&server, &credential, Some(&params)).await.unwrap_err();
+        assert!(err.contains("not found"));
+    }
+
+    #[tokio::test]
+    async fn subscribe_registers_doc_for_updates() {
+        let server = build_test_server(&[("/Notes.md", "uuid-notes", "text")]).await;
+        let sid = setup_session_no_reads(&server);
+        let params = json!({"uri": "relay:///Lens/Notes.md"});
+        subscribe(&server, &sid, &McpCredential::shared_key(), Some(&params)).unwrap();
+
+        server
+            .mcp_sessions
+            .notify_doc_updated(&format!("{}-uuid-notes", RELAY_ID));
+        let pending = server.mcp_sessions.drain_notifications(&sid);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0]["params"]["uri"], "relay:///Lens/Notes.md");
+    }
+}
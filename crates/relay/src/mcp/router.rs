@@ -2,12 +2,15 @@ use serde_json::{json, Value};
 use std::sync::Arc;
 use tracing::debug;
 
+use super::audit::{AuditEntry, AuditOutcome};
 use super::jsonrpc::{
-    error_response, success_response, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse,
-    INTERNAL_ERROR, METHOD_NOT_FOUND,
+    error_response, error_response_with_data, success_response, JsonRpcNotification,
+    JsonRpcRequest, JsonRpcResponse, INTERNAL_ERROR, INVALID_PARAMS, METHOD_NOT_FOUND,
+    RATE_LIMITED, REQUEST_CANCELLED, REQUEST_TIMEOUT,
 };
-use super::session::SessionManager;
-use super::tools;
+use super::resources;
+use super::session::{ProgressReporter, SessionManager};
+use super::tools::{self, CallContext};
 use crate::server::Server;
 
 /// Dispatch a JSON-RPC request to the appropriate handler.
@@ -42,6 +45,19 @@ pub async fn dispatch_request(
                 None,
             )
         }
+        "resources/list"
+        | "resources/templates/list"
+        | "resources/read"
+        | "resources/subscribe"
+        | "resources/unsubscribe" => {
+            if let Err(err_resp) = validate_session(sessions, session_id, &request.id) {
+                return (err_resp, None);
+            }
+            (
+                handle_resources(server, session_id.unwrap(), request).await,
+                None,
+            )
+        }
         _ => (
             error_response(
                 request.id.clone(),
@@ -73,10 +89,19 @@ pub fn handle_notification(
             }
         }
         "notifications/cancelled" => {
-            debug!(
-                method = "notifications/cancelled",
-                "Cancellation notification received (no-op)"
-            );
+            let request_id = notification
+                .params
+                .as_ref()
+                .and_then(|p| p.get("requestId"));
+            if let (Some(sid), Some(request_id)) = (session_id, request_id) {
+                let cancelled = sessions.cancel_call(sid, request_id);
+                debug!(
+                    session_id = sid,
+                    request_id = %request_id,
+                    cancelled,
+                    "Cancellation notification received"
+                );
+            }
         }
         other => {
             debug!(method = other, "Unknown notification received");
@@ -113,7 +138,11 @@ fn handle_initialize(
         json!({
             "protocolVersion": negotiated_version,
             "capabilities": {
-                "tools": {}
+                "tools": {},
+                "resources": {
+                    "subscribe": true,
+                    "listChanged": false
+                }
             },
             "serverInfo": {
                 "name": "lens-relay",
@@ -149,13 +178,204 @@ async fn handle_tools_call(
         None => {
             return success_response(
                 id,
-                tools::dispatch_tool(server, session_id, "", &json!({})).await,
+                tools::dispatch_tool(server, session_id, "", &json!({}), &CallContext::detached())
+                    .await,
             );
         }
     };
 
-    let result = tools::dispatch_tool(server, session_id, &name, &arguments).await;
-    success_response(id, result)
+    // Unknown names would otherwise become unbounded metric labels
+    let metric_tool = if tools::is_known_tool(&name) {
+        name.as_str()
+    } else {
+        "unknown"
+    };
+    let principal = server
+        .mcp_sessions
+        .get_session(session_id)
+        .map(|session| session.credential.principal.limit_id())
+        .unwrap_or_default();
+
+    if let Err(limited) = server.mcp_limits.check(&principal, session_id, &name) {
+        let message = limited.message();
+        server
+            .metrics
+            .record_mcp_rate_limited(limited.scope.as_str(), limited.limit.as_str());
+        server
+            .metrics
+            .record_mcp_tool_call(metric_tool, "rate_limited");
+        let response = error_response_with_data(
+            id,
+            RATE_LIMITED,
+            message.clone(),
+            json!({
+                "tool": name,
+                "scope": limited.scope.as_str(),
+                "limit": limited.limit.as_str(),
+                "max": limited.max,
+                "retryAfterMs": limited.retry_after.as_millis() as u64,
+            }),
+        );
+        record_audit(
+            server,
+            session_id,
+            &name,
+            arguments,
+            now_millis(),
+            std::time::Duration::ZERO,
+            AuditOutcome::Error,
+            Some(message),
+        );
+        return response;
+    }
+
+    // Track the call so notifications/cancelled and the deadline can stop it
+    let sessions = &server.mcp_sessions;
+    let ctx = CallContext {
+        progress: ProgressReporter::from_params(sessions, session_id, params),
+        cancellation: sessions.begin_call(session_id, &id),
+    };
+    let started_at = now_millis();
+    let started = std::time::Instant::now();
+    let deadline = server.mcp_tool_timeout;
+    let outcome = tokio::time::timeout(
+        deadline,
+        tools::dispatch_tool(server, session_id, &name, &arguments, &ctx),
+    )
+    .await;
+    sessions.end_call(session_id, &id);
+
+    let (response, audit_outcome, error) = match outcome {
+        Err(_) => {
+            ctx.cancellation.cancel();
+            let message = format!("Tool call timed out after {}s", deadline.as_secs());
+            (
+                error_response_with_data(
+                    id,
+                    REQUEST_TIMEOUT,
+                    message.clone(),
+                    json!({ "tool": name, "timeoutMs": deadline.as_millis() as u64 }),
+                ),
+                AuditOutcome::Timeout,
+                Some(message),
+            )
+        }
+        Ok(_) if ctx.cancellation.is_cancelled() => (
+            error_response(id, REQUEST_CANCELLED, "Request cancelled"),
+            AuditOutcome::Cancelled,
+            None,
+        ),
+        Ok(result) => {
+            let (audit_outcome, error) = if result["isError"] == json!(true) {
+                let text = result["content"][0]["text"].as_str().map(str::to_string);
+                (AuditOutcome::Error, text)
+            } else {
+                (AuditOutcome::Ok, None)
+            };
+            let bytes = result.to_string().len();
+            server
+                .mcp_limits
+                .record_bytes(&principal, session_id, bytes as u64);
+            server.metrics.record_mcp_bytes_returned(metric_tool, bytes);
+            (success_response(id, result), audit_outcome, error)
+        }
+    };
+
+    server
+        .metrics
+        .record_mcp_tool_call(metric_tool, audit_outcome.as_str());
+
+    record_audit(
+        server,
+        session_id,
+        &name,
+        arguments,
+        started_at,
+        started.elapsed(),
+        audit_outcome,
+        error,
+    );
+    response
+}
+
+/// Append a tools/call to the audit log, with the session's identity and the
+/// documents its path arguments point at.
+#[allow(clippy::too_many_arguments)]
+fn record_audit(
+    server: &Arc<Server>,
+    session_id: &str,
+    tool: &str,
+    arguments: Value,
+    started_at: u64,
+    elapsed: std::time::Duration,
+    outcome: AuditOutcome,
+    error: Option<String>,
+) {
+    let (principal, client_info) = match server.mcp_sessions.get_session(session_id) {
+        Some(session) => (
+            session.credential.principal.to_string(),
+            session.client_info.clone(),
+        ),
+        None => (String::new(), None),
+    };
+    let doc_ids = arguments
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .and_then(|path| server.doc_resolver().resolve_path(path))
+        .map(|info| vec![info.doc_id])
+        .unwrap_or_default();
+
+    server.mcp_audit.record(AuditEntry {
+        timestamp: started_at,
+        session_id: session_id.to_string(),
+        principal,
+        client_info,
+        tool: tool.to_string(),
+        arguments,
+        doc_ids,
+        outcome,
+        error,
+        duration_ms: elapsed.as_millis() as u64,
+    });
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+async fn handle_resources(
+    server: &Arc<Server>,
+    session_id: &str,
+    request: &JsonRpcRequest,
+) -> JsonRpcResponse {
+    // Resources are filtered by the credential that opened the session
+    let credential = match server.mcp_sessions.get_session(session_id) {
+        Some(session) => session.credential.clone(),
+        None => {
+            return error_response(
+                request.id.clone(),
+                INTERNAL_ERROR,
+                "Session not found. Send an initialize request first.",
+            )
+        }
+    };
+    let params = request.params.as_ref();
+
+    let result = match request.method.as_str() {
+        "resources/list" => Ok(resources::list(server, &credential)),
+        "resources/templates/list" => Ok(resources::templates()),
+        "resources/read" => resources::read(server, &credential, params).await,
+        "resources/subscribe" => resources::subscribe(server, session_id, &credential, params),
+        _ => resources::unsubscribe(server, session_id, params),
+    };
+
+    match result {
+        Ok(value) => success_response(request.id.clone(), value),
+        Err(msg) => error_response(request.id.clone(), INVALID_PARAMS, msg),
+    }
 }
 
 fn validate_session(
@@ -186,7 +406,10 @@ fn validate_session(
             "Session not initialized. Send notifications/initialized first.",
         ));
     }
+    drop(session);
 
+    // Keep sessions in use from expiring
+    sessions.touch(sid);
     Ok(())
 }
 
@@ -243,6 +466,7 @@ mod tests {
         let result = resp.result.expect("should have result");
         assert_eq!(result["protocolVersion"], "2025-03-26");
         assert!(result["capabilities"]["tools"].is_object());
+        assert_eq!(result["capabilities"]["resources"]["subscribe"], true);
         assert_eq!(result["serverInfo"]["name"], "lens-relay");
         assert!(result["serverInfo"]["version"].is_string());
 
@@ -264,7 +488,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn tools_list_returns_eight_tools() {
+    async fn tools_list_returns_thirteen_tools() {
         let server = test_server();
         let req = make_request(json!(3), "tools/list", None);
 
@@ -283,7 +507,7 @@ mod tests {
         let result = resp.result.unwrap();
         assert!(result["tools"].is_array());
         let tools_arr = result["tools"].as_array().unwrap();
-        assert_eq!(tools_arr.len(), 8);
+        assert_eq!(tools_arr.len(), 13);
 
         // Verify tool names
         let names: Vec<&str> = tools_arr
@@ -292,10 +516,15 @@ mod tests {
             .collect();
         assert!(names.contains(&"create_session"));
         assert!(names.contains(&"read"));
+        assert!(names.contains(&"read_attachment"));
         assert!(names.contains(&"glob"));
         assert!(names.contains(&"get_links"));
         assert!(names.contains(&"grep"));
+        assert!(names.contains(&"history"));
+        assert!(names.contains(&"diff"));
         assert!(names.contains(&"edit"));
+        assert!(names.contains(&"multi_edit"));
+        assert!(names.contains(&"write"));
         assert!(names.contains(&"create"));
         assert!(names.contains(&"move"));
     }
@@ -480,7 +709,7 @@ mod tests {
         {
             let session = server.mcp_sessions.get_session(&sid).unwrap();
             assert!(
-                session.read_docs.contains(&content_doc_id),
+                session.read_docs.contains_key(&content_doc_id),
                 "read_docs should contain {} after read, got: {:?}",
                 content_doc_id,
                 session.read_docs
@@ -638,4 +867,183 @@ mod tests {
             "returned session_id should exist in SessionManager"
         );
     }
+
+    #[tokio::test]
+    async fn resources_require_session_and_list_documents() {
+        let server = crate::mcp::tools::test_helpers::build_test_server(&[(
+            "/Notes.md",
+            "uuid-notes",
+            "hello",
+        )])
+        .await;
+
+        let req = make_request(json!(40), "resources/list", None);
+        let (resp, _) = dispatch_request(&server, None, &req).await;
+        assert!(resp.error.is_some(), "resources/list needs a session");
+
+        let sid = server
+            .mcp_sessions
+            .create_session("2025-03-26".into(), None);
+        server.mcp_sessions.mark_initialized(&sid);
+
+        let (resp, _) = dispatch_request(&server, Some(&sid), &req).await;
+        let result = resp.result.expect("resources/list should succeed");
+        assert_eq!(result["resources"][0]["name"], "Lens/Notes.md");
+
+        let req = make_request(
+            json!(41),
+            "resources/read",
+            Some(json!({"uri": "relay:///Lens/Missing.md"})),
+        );
+        let (resp, _) = dispatch_request(&server, Some(&sid), &req).await;
+        assert_eq!(resp.error.unwrap().code, INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn tools_call_past_deadline_returns_timeout_error() {
+        let names: Vec<(String, String)> = (0..40)
+            .map(|i| (format!("/Doc{}.md", i), format!("uuid-{}", i)))
+            .collect();
+        let entries: Vec<(&str, &str, &str)> = names
+            .iter()
+            .map(|(path, uuid)| (path.as_str(), uuid.as_str(), "text"))
+            .collect();
+        let mut server = crate::mcp::tools::test_helpers::build_test_server(&entries).await;
+        Arc::get_mut(&mut server).unwrap().mcp_tool_timeout = std::time::Duration::ZERO;
+
+        let sid = server
+            .mcp_sessions
+            .create_session("2025-03-26".into(), None);
+        server.mcp_sessions.mark_initialized(&sid);
+
+        let req = make_request(
+            json!(50),
+            "tools/call",
+            Some(json!({"name": "grep", "arguments": {"pattern": "text", "session_id": &sid}})),
+        );
+        let (resp, _) = dispatch_request(&server, Some(&sid), &req).await;
+        let err = resp.error.expect("grep should time out");
+        assert_eq!(err.code, REQUEST_TIMEOUT);
+        assert_eq!(err.data.unwrap()["tool"], "grep");
+
+        // The finished call is no longer cancellable
+        assert!(!server.mcp_sessions.cancel_call(&sid, &json!(50)));
+    }
+
+    #[tokio::test]
+    async fn tools_call_is_recorded_in_audit_log() {
+        let server =
+            crate::mcp::tools::test_helpers::build_test_server(&[("/Doc.md", "uuid-doc", "hello")])
+                .await;
+        let sid = server.mcp_sessions.create_session(
+            "2025-03-26".into(),
+            Some(json!({"name": "test-client", "version": "1.0"})),
+        );
+        server.mcp_sessions.mark_initialized(&sid);
+
+        let req = make_request(
+            json!(60),
+            "tools/call",
+            Some(
+                json!({"name": "read", "arguments": {"file_path": "Lens/Doc.md", "session_id": &sid}}),
+            ),
+        );
+        dispatch_request(&server, Some(&sid), &req).await;
+        let req = make_request(
+            json!(61),
+            "tools/call",
+            Some(
+                json!({"name": "read", "arguments": {"file_path": "Lens/Missing.md", "session_id": &sid}}),
+            ),
+        );
+        dispatch_request(&server, Some(&sid), &req).await;
+
+        let entries = server
+            .mcp_audit
+            .query(0, u64::MAX / 2, Some(&sid), 10)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].tool, "read");
+        assert_eq!(entries[0].outcome, AuditOutcome::Ok);
+        assert_eq!(
+            entries[0].doc_ids,
+            vec![format!(
+                "{}-uuid-doc",
+                crate::mcp::tools::test_helpers::RELAY_ID
+            )]
+        );
+        assert_eq!(
+            entries[0].client_info.as_ref().unwrap()["name"],
+            "test-client"
+        );
+        assert_eq!(entries[1].outcome, AuditOutcome::Error);
+        assert!(entries[1].error.as_ref().unwrap().contains("not found"));
+    }
+
+    #[tokio::test]
+    async fn tools_call_over_limit_returns_rate_limited_error() {
+        let mut server =
+            crate::mcp::tools::test_helpers::build_test_server(&[("/Doc.md", "uuid-doc", "hello")])
+                .await;
+        let limits = y_sweet_core::config::McpLimitsConfig {
+            session: y_sweet_core::config::McpQuota {
+                calls_per_minute: Some(1),
+                ..Default::default()
+            },
+            key: Default::default(),
+        };
+        Arc::get_mut(&mut server).unwrap().mcp_limits =
+            crate::mcp::limits::McpLimiter::new(&limits, &[]);
+
+        let sid = server
+            .mcp_sessions
+            .create_session("2025-03-26".into(), None);
+        server.mcp_sessions.mark_initialized(&sid);
+
+        let call = |id: i64| {
+            make_request(
+                json!(id),
+                "tools/call",
+                Some(json!({"name": "glob", "arguments": {"pattern": "**", "session_id": &sid}})),
+            )
+        };
+        let (resp, _) = dispatch_request(&server, Some(&sid), &call(70)).await;
+        assert!(resp.error.is_none());
+
+        let (resp, _) = dispatch_request(&server, Some(&sid), &call(71)).await;
+        let err = resp.error.expect("second call should be rate limited");
+        assert_eq!(err.code, RATE_LIMITED);
+        let data = err.data.unwrap();
+        assert_eq!(data["scope"], "session");
+        assert_eq!(data["limit"], "calls_per_minute");
+        assert!(data["retryAfterMs"].as_u64().unwrap() > 0);
+
+        // A new session starts with a fresh allowance
+        let other = server
+            .mcp_sessions
+            .create_session("2025-03-26".into(), None);
+        server.mcp_sessions.mark_initialized(&other);
+        let req = make_request(
+            json!(72),
+            "tools/call",
+            Some(json!({"name": "glob", "arguments": {"pattern": "**", "session_id": &other}})),
+        );
+        let (resp, _) = dispatch_request(&server, Some(&other), &req).await;
+        assert!(resp.error.is_none());
+    }
+
+    #[test]
+    fn cancelled_notification_cancels_in_flight_call() {
+        let sessions = SessionManager::new();
+        let sid = sessions.create_session("2025-03-26".into(), None);
+        let token = sessions.begin_call(&sid, &json!(7));
+
+        let notif = make_notification(
+            "notifications/cancelled",
+            Some(json!({"requestId": 7, "reason": "user abandoned"})),
+        );
+        handle_notification(&sessions, Some(&sid), &notif);
+        assert!(token.is_cancelled());
+    }
 }
@@ -1,11 +1,31 @@
+use super::auth::McpCredential;
 use dashmap::DashMap;
-use serde_json::Value;
-use std::collections::HashSet;
-use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use y_sweet_core::store::Store;
 
-/// Maximum session age before cleanup. Sessions from clients that never
-/// send DELETE (e.g. Claude.ai) are purged after this duration.
-const SESSION_TTL: std::time::Duration = std::time::Duration::from_secs(3600);
+/// Idle time after which a session is purged (MCP_SESSION_TTL_SECS). Clients
+/// that never send DELETE (e.g. Claude.ai) leave their sessions to this.
+pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(3600);
+
+/// Sessions kept at most (MCP_MAX_SESSIONS). Past this, the least recently
+/// active are evicted.
+pub const DEFAULT_MAX_SESSIONS: usize = 10_000;
+
+/// Store prefix for persisted sessions, one object per session.
+pub const SESSION_PREFIX: &str = "mcp-sessions";
+
+/// How often changed sessions are written to the store.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Server-to-client messages retained per session for `Last-Event-ID` replay.
+/// The oldest are dropped first.
+const MAX_RETAINED_EVENTS: usize = 256;
 
 pub struct McpSession {
     pub session_id: String,
@@ -14,23 +34,234 @@ pub struct McpSession {
     pub initialized: bool,
     pub created_at: Instant,
     pub last_activity: Instant,
-    pub read_docs: HashSet<String>,
+    /// Documents read in this session (for read-before-edit), with the
+    /// document version each was read at.
+    pub read_docs: HashMap<String, u64>,
+    /// Who opened the session and what they may touch. Sessions start with the
+    /// shared-key credential; the HTTP transport rebinds it after initialize.
+    pub credential: McpCredential,
+    /// Subscribed resources: doc_id -> resource URI.
+    pub subscriptions: HashMap<String, String>,
+    /// Server-to-client JSON-RPC messages (notifications, progress).
+    pub events: EventLog,
+    /// Wakes the session's SSE stream when an event is pushed.
+    pub event_notify: Arc<Notify>,
+    /// Tool calls still running, keyed by JSON-RPC request ID.
+    pub in_flight: HashMap<String, CancellationToken>,
+}
+
+/// Numbered server-to-client messages for one session. Events are kept after
+/// delivery so a reconnecting SSE stream can resume from `Last-Event-ID`.
+#[derive(Default)]
+pub struct EventLog {
+    events: VecDeque<(u64, Value)>,
+    last_id: u64,
+    delivered: u64,
+}
+
+impl EventLog {
+    fn push(&mut self, message: Value) -> u64 {
+        self.last_id += 1;
+        if self.events.len() >= MAX_RETAINED_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back((self.last_id, message));
+        self.last_id
+    }
+
+    fn undelivered(&self) -> impl Iterator<Item = &Value> {
+        let delivered = self.delivered;
+        self.events
+            .iter()
+            .filter(move |(id, _)| *id > delivered)
+            .map(|(_, message)| message)
+    }
+
+    /// Events with an ID above `after`, marking everything returned as delivered.
+    fn take_after(&mut self, after: u64) -> Vec<(u64, Value)> {
+        let taken: Vec<(u64, Value)> = self
+            .events
+            .iter()
+            .filter(|(id, _)| *id > after)
+            .cloned()
+            .collect();
+        if let Some((last, _)) = taken.last() {
+            self.delivered = self.delivered.max(*last);
+        }
+        taken
+    }
+}
+
+/// SSE event IDs carry the session ID so a `Last-Event-ID` from another
+/// session is never mistaken for a position in this one.
+pub fn event_id(session_id: &str, seq: u64) -> String {
+    format!("{}:{}", session_id, seq)
+}
+
+/// Parse an SSE event ID produced by `event_id` for this session.
+pub fn parse_event_id(session_id: &str, id: &str) -> Option<u64> {
+    let (sid, seq) = id.rsplit_once(':')?;
+    if sid != session_id {
+        return None;
+    }
+    seq.parse().ok()
+}
+
+/// Emits `notifications/progress` for a request whose `_meta` carried a
+/// `progressToken`.
+#[derive(Clone)]
+pub struct ProgressReporter {
+    sessions: Arc<SessionManager>,
+    session_id: String,
+    token: Value,
+}
+
+impl ProgressReporter {
+    /// Returns None when the client did not ask for progress.
+    pub fn from_params(
+        sessions: &Arc<SessionManager>,
+        session_id: &str,
+        params: Option<&Value>,
+    ) -> Option<Self> {
+        let token = params?.get("_meta")?.get("progressToken")?;
+        if !(token.is_string() || token.is_number()) {
+            return None;
+        }
+        Some(Self {
+            sessions: sessions.clone(),
+            session_id: session_id.to_string(),
+            token: token.clone(),
+        })
+    }
+
+    pub fn report(&self, progress: usize, total: Option<usize>, message: Option<&str>) {
+        let mut params = json!({
+            "progressToken": self.token,
+            "progress": progress,
+        });
+        if let Some(total) = total {
+            params["total"] = json!(total);
+        }
+        if let Some(message) = message {
+            params["message"] = json!(message);
+        }
+        self.sessions.push_notification(
+            &self.session_id,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/progress",
+                "params": params
+            }),
+        );
+    }
+}
+
+/// The durable part of a session. Events and in-flight calls are not kept;
+/// timestamps are wall-clock milliseconds so they survive a restart.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredSession {
+    session_id: String,
+    protocol_version: String,
+    client_info: Option<Value>,
+    initialized: bool,
+    created_at: u64,
+    last_activity: u64,
+    read_docs: HashMap<String, u64>,
+    credential: McpCredential,
+    subscriptions: HashMap<String, String>,
+}
+
+impl StoredSession {
+    fn from_session(session: &McpSession) -> Self {
+        Self {
+            session_id: session.session_id.clone(),
+            protocol_version: session.protocol_version.clone(),
+            client_info: session.client_info.clone(),
+            initialized: session.initialized,
+            created_at: to_wall_clock(session.created_at),
+            last_activity: to_wall_clock(session.last_activity),
+            read_docs: session.read_docs.clone(),
+            credential: session.credential.clone(),
+            subscriptions: session.subscriptions.clone(),
+        }
+    }
+
+    fn into_session(self) -> McpSession {
+        McpSession {
+            session_id: self.session_id,
+            protocol_version: self.protocol_version,
+            client_info: self.client_info,
+            initialized: self.initialized,
+            created_at: from_wall_clock(self.created_at),
+            last_activity: from_wall_clock(self.last_activity),
+            read_docs: self.read_docs,
+            credential: self.credential,
+            subscriptions: self.subscriptions,
+            events: EventLog::default(),
+            event_notify: Arc::new(Notify::new()),
+            in_flight: HashMap::new(),
+        }
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn to_wall_clock(instant: Instant) -> u64 {
+    now_millis().saturating_sub(instant.elapsed().as_millis() as u64)
+}
+
+fn from_wall_clock(millis: u64) -> Instant {
+    let age = Duration::from_millis(now_millis().saturating_sub(millis));
+    Instant::now().checked_sub(age).unwrap_or_else(Instant::now)
+}
+
+fn session_key(session_id: &str) -> String {
+    format!("{}/{}.json", SESSION_PREFIX, session_id)
 }
 
 pub struct SessionManager {
     sessions: DashMap<String, McpSession>,
+    ttl: Duration,
+    max_sessions: usize,
+    store: Option<Arc<Box<dyn Store>>>,
+    /// Sessions created, changed or removed since the last `persist`.
+    dirty: Mutex<HashSet<String>>,
 }
 
 impl SessionManager {
+    /// In-memory sessions with the default TTL and cap.
     pub fn new() -> Self {
+        Self::with_config(None, DEFAULT_SESSION_TTL, DEFAULT_MAX_SESSIONS)
+    }
+
+    /// Sessions persisted to `store` (when given) so they survive a restart.
+    pub fn with_config(
+        store: Option<Arc<Box<dyn Store>>>,
+        ttl: Duration,
+        max_sessions: usize,
+    ) -> Self {
         Self {
             sessions: DashMap::new(),
+            ttl,
+            max_sessions,
+            store,
+            dirty: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn mark_dirty(&self, session_id: &str) {
+        if self.store.is_some() {
+            self.dirty.lock().unwrap().insert(session_id.to_string());
         }
     }
 
     /// Create a new session, returning the session ID.
     pub fn create_session(&self, protocol_version: String, client_info: Option<Value>) -> String {
-        self.cleanup_stale(SESSION_TTL);
         let session_id = nanoid::nanoid!(8);
         let now = Instant::now();
         let session = McpSession {
@@ -40,9 +271,16 @@ impl SessionManager {
             initialized: false,
             created_at: now,
             last_activity: now,
-            read_docs: HashSet::new(),
+            read_docs: HashMap::new(),
+            credential: McpCredential::shared_key(),
+            subscriptions: HashMap::new(),
+            events: EventLog::default(),
+            event_notify: Arc::new(Notify::new()),
+            in_flight: HashMap::new(),
         };
         self.sessions.insert(session_id.clone(), session);
+        self.mark_dirty(&session_id);
+        self.evict();
         session_id
     }
 
@@ -67,22 +305,310 @@ impl SessionManager {
         if let Some(mut session) = self.sessions.get_mut(session_id) {
             session.initialized = true;
             session.last_activity = Instant::now();
+            drop(session);
+            self.mark_dirty(session_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Note activity on a session so it is not evicted as idle.
+    pub fn touch(&self, session_id: &str) {
+        if let Some(mut session) = self.sessions.get_mut(session_id) {
+            session.last_activity = Instant::now();
+            drop(session);
+            self.mark_dirty(session_id);
+        }
+    }
+
+    /// Record that a session has read `doc_id` at `version`, allowing edits.
+    pub fn record_read(&self, session_id: &str, doc_id: &str, version: u64) -> bool {
+        if let Some(mut session) = self.sessions.get_mut(session_id) {
+            session.read_docs.insert(doc_id.to_string(), version);
+            drop(session);
+            self.mark_dirty(session_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Bind the credential that opened a session. Returns true if session existed.
+    pub fn bind_credential(&self, session_id: &str, credential: McpCredential) -> bool {
+        if let Some(mut session) = self.sessions.get_mut(session_id) {
+            session.credential = credential;
+            drop(session);
+            self.mark_dirty(session_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether `credential` is the one the session was opened with.
+    pub fn credential_matches(&self, session_id: &str, credential: &McpCredential) -> bool {
+        self.sessions
+            .get(session_id)
+            .is_some_and(|session| &session.credential == credential)
+    }
+
+    /// Subscribe a session to updates of a document. Returns true if session existed.
+    pub fn subscribe(&self, session_id: &str, doc_id: &str, uri: &str) -> bool {
+        if let Some(mut session) = self.sessions.get_mut(session_id) {
+            session
+                .subscriptions
+                .insert(doc_id.to_string(), uri.to_string());
+            drop(session);
+            self.mark_dirty(session_id);
             true
         } else {
             false
         }
     }
 
+    /// Drop a subscription by URI. Returns true if the session was subscribed.
+    pub fn unsubscribe(&self, session_id: &str, uri: &str) -> bool {
+        let Some(mut session) = self.sessions.get_mut(session_id) else {
+            return false;
+        };
+        let before = session.subscriptions.len();
+        session
+            .subscriptions
+            .retain(|_, subscribed| subscribed != uri);
+        let removed = session.subscriptions.len() != before;
+        drop(session);
+        if removed {
+            self.mark_dirty(session_id);
+        }
+        removed
+    }
+
+    /// Queue a server-to-client message for a session. Returns true if session existed.
+    pub fn push_notification(&self, session_id: &str, message: Value) -> bool {
+        let Some(mut session) = self.sessions.get_mut(session_id) else {
+            return false;
+        };
+        session.events.push(message);
+        session.event_notify.notify_one();
+        true
+    }
+
+    /// Queue `notifications/resources/updated` for every session subscribed to `doc_id`.
+    ///
+    /// Called from the document update callback while the doc's awareness lock is
+    /// held, so it must not touch any Y.Doc.
+    pub fn notify_doc_updated(&self, doc_id: &str) {
+        for mut session in self.sessions.iter_mut() {
+            let Some(uri) = session.subscriptions.get(doc_id).cloned() else {
+                continue;
+            };
+            // Coalesce: one undelivered update per resource is enough
+            let already_pending = session.events.undelivered().any(|n| {
+                n["method"] == "notifications/resources/updated" && n["params"]["uri"] == uri
+            });
+            if already_pending {
+                continue;
+            }
+            session.events.push(json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/resources/updated",
+                "params": { "uri": uri }
+            }));
+            session.event_notify.notify_one();
+        }
+    }
+
+    /// Take the messages not yet delivered on any stream.
+    pub fn drain_notifications(&self, session_id: &str) -> Vec<Value> {
+        self.events_after(session_id, None)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    /// Numbered messages after `after` (a replay position from `Last-Event-ID`),
+    /// or after the last delivered message when `after` is None. Returns None
+    /// if the session no longer exists.
+    pub fn events_after(&self, session_id: &str, after: Option<u64>) -> Option<Vec<(u64, Value)>> {
+        let mut session = self.sessions.get_mut(session_id)?;
+        let after = after.unwrap_or(session.events.delivered);
+        Some(session.events.take_after(after))
+    }
+
+    /// The notifier an SSE stream waits on for new events.
+    pub fn event_notifier(&self, session_id: &str) -> Option<Arc<Notify>> {
+        self.sessions
+            .get(session_id)
+            .map(|session| session.event_notify.clone())
+    }
+
+    /// Register a running request so `notifications/cancelled` can reach it.
+    /// The returned token is cancelled by `cancel_call` or session removal.
+    pub fn begin_call(&self, session_id: &str, request_id: &Value) -> CancellationToken {
+        let token = CancellationToken::new();
+        if let Some(mut session) = self.sessions.get_mut(session_id) {
+            session
+                .in_flight
+                .insert(request_id.to_string(), token.clone());
+        }
+        token
+    }
+
+    /// Forget a finished request.
+    pub fn end_call(&self, session_id: &str, request_id: &Value) {
+        if let Some(mut session) = self.sessions.get_mut(session_id) {
+            session.in_flight.remove(&request_id.to_string());
+        }
+    }
+
+    /// Cancel a running request. Returns true if it was in flight.
+    pub fn cancel_call(&self, session_id: &str, request_id: &Value) -> bool {
+        let Some(session) = self.sessions.get(session_id) else {
+            return false;
+        };
+        match session.in_flight.get(&request_id.to_string()) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Remove a session. Returns true if session existed.
     pub fn remove_session(&self, session_id: &str) -> bool {
-        self.sessions.remove(session_id).is_some()
+        match self.sessions.remove(session_id) {
+            Some((_, session)) => {
+                // Let an open SSE stream notice the session is gone, and stop
+                // any tool calls still working on its behalf
+                session.event_notify.notify_one();
+                for token in session.in_flight.values() {
+                    token.cancel();
+                }
+                self.mark_dirty(session_id);
+                true
+            }
+            None => false,
+        }
     }
 
-    /// Remove sessions older than `max_age`.
-    pub fn cleanup_stale(&self, max_age: std::time::Duration) {
-        let cutoff = Instant::now() - max_age;
-        self.sessions
-            .retain(|_, session| session.last_activity > cutoff);
+    /// Remove sessions idle for longer than `max_age`.
+    pub fn cleanup_stale(&self, max_age: Duration) {
+        let Some(cutoff) = Instant::now().checked_sub(max_age) else {
+            return;
+        };
+        let stale: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|session| session.last_activity <= cutoff)
+            .map(|session| session.key().clone())
+            .collect();
+        for session_id in stale {
+            self.remove_session(&session_id);
+        }
+    }
+
+    /// Apply the TTL, then drop the least recently active sessions beyond the cap.
+    pub fn evict(&self) {
+        self.cleanup_stale(self.ttl);
+
+        let excess = self.sessions.len().saturating_sub(self.max_sessions);
+        if excess == 0 {
+            return;
+        }
+        let mut by_activity: Vec<(Instant, String)> = self
+            .sessions
+            .iter()
+            .map(|session| (session.last_activity, session.key().clone()))
+            .collect();
+        by_activity.sort();
+        for (_, session_id) in by_activity.into_iter().take(excess) {
+            tracing::debug!(session_id = %session_id, "Evicting least recently used MCP session");
+            self.remove_session(&session_id);
+        }
+    }
+
+    /// Reload sessions saved by a previous run. Expired ones are deleted.
+    /// Returns the number restored.
+    pub async fn load(&self) -> anyhow::Result<usize> {
+        let Some(store) = &self.store else {
+            return Ok(0);
+        };
+        let cutoff = now_millis().saturating_sub(self.ttl.as_millis() as u64);
+        let mut restored = 0;
+        for object in store.list(SESSION_PREFIX).await? {
+            let key = format!("{}/{}", SESSION_PREFIX, object.key);
+            let Some(body) = store.get(&key).await? else {
+                continue;
+            };
+            let stored = match serde_json::from_slice::<StoredSession>(&body) {
+                Ok(stored) if stored.last_activity > cutoff => stored,
+                Ok(_) => {
+                    store.remove(&key).await?;
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("Discarding unreadable MCP session {}: {}", key, e);
+                    store.remove(&key).await?;
+                    continue;
+                }
+            };
+            self.sessions
+                .insert(stored.session_id.clone(), stored.into_session());
+            restored += 1;
+        }
+        self.evict();
+        Ok(restored)
+    }
+
+    /// Write changed sessions to the store and delete removed ones. Failed
+    /// writes are retried on the next call.
+    pub async fn persist(&self) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let dirty: Vec<String> = self.dirty.lock().unwrap().drain().collect();
+        for session_id in dirty {
+            // Serialize under the map guard, write after it is released
+            let body = self.sessions.get(&session_id).and_then(|session| {
+                serde_json::to_vec(&StoredSession::from_session(&session)).ok()
+            });
+            let key = session_key(&session_id);
+            let result = match body {
+                Some(body) => store.set(&key, body).await,
+                // Sessions removed before they were ever written have nothing to delete
+                None => match store.exists(&key).await {
+                    Ok(true) => store.remove(&key).await,
+                    other => other.map(|_| ()),
+                },
+            };
+            if let Err(e) = result {
+                tracing::warn!("Failed to persist MCP session {}: {}", session_id, e);
+                self.mark_dirty(&session_id);
+            }
+        }
+    }
+
+    /// Evict and persist periodically until `cancel` fires, then persist once more.
+    pub fn spawn_persistence(self: &Arc<Self>, cancel: CancellationToken) {
+        let sessions = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PERSIST_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        sessions.evict();
+                        sessions.persist().await;
+                    }
+                    _ = cancel.cancelled() => {
+                        sessions.persist().await;
+                        break;
+                    }
+                }
+            }
+        });
     }
 }
 
@@ -167,18 +693,120 @@ mod tests {
         let mgr = SessionManager::new();
         let id = mgr.create_session("2025-03-26".into(), None);
 
-        // Insert a doc_id via get_session_mut
-        {
-            let mut session = mgr.get_session_mut(&id).unwrap();
-            session.read_docs.insert("doc-123".to_string());
-        }
+        assert!(mgr.record_read(&id, "doc-123", 7));
+        assert!(!mgr.record_read("nonexistent", "doc-123", 7));
 
-        // Verify it's there
+        // Verify it's there, with the version it was read at
         let session = mgr.get_session(&id).unwrap();
-        assert!(session.read_docs.contains("doc-123"));
+        assert_eq!(session.read_docs.get("doc-123"), Some(&7));
         assert_eq!(session.read_docs.len(), 1);
     }
 
+    #[test]
+    fn bind_credential_replaces_default() {
+        let mgr = SessionManager::new();
+        let id = mgr.create_session("2025-03-26".into(), None);
+        assert!(mgr.credential_matches(&id, &McpCredential::shared_key()));
+
+        let scoped = McpCredential {
+            principal: crate::mcp::auth::McpPrincipal::Key("assistant".into()),
+            scope: crate::mcp::auth::McpScope::Folders(vec!["Lens".into()]),
+            mode: y_sweet_core::config::McpMode::ReadOnly,
+        };
+        assert!(mgr.bind_credential(&id, scoped.clone()));
+        assert!(mgr.credential_matches(&id, &scoped));
+        assert!(!mgr.credential_matches(&id, &McpCredential::shared_key()));
+        assert!(!mgr.bind_credential("nonexistent", scoped));
+    }
+
+    #[test]
+    fn subscribed_sessions_receive_coalesced_updates() {
+        let mgr = SessionManager::new();
+        let subscribed = mgr.create_session("2025-03-26".into(), None);
+        let other = mgr.create_session("2025-03-26".into(), None);
+        assert!(mgr.subscribe(&subscribed, "doc-1", "relay:///Lens/A.md"));
+
+        mgr.notify_doc_updated("doc-1");
+        mgr.notify_doc_updated("doc-1");
+        mgr.notify_doc_updated("doc-2");
+
+        let pending = mgr.drain_notifications(&subscribed);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0]["method"], "notifications/resources/updated");
+        assert_eq!(pending[0]["params"]["uri"], "relay:///Lens/A.md");
+        assert!(mgr.drain_notifications(&subscribed).is_empty());
+        assert!(mgr.drain_notifications(&other).is_empty());
+
+        assert!(mgr.unsubscribe(&subscribed, "relay:///Lens/A.md"));
+        mgr.notify_doc_updated("doc-1");
+        assert!(mgr.drain_notifications(&subscribed).is_empty());
+    }
+
+    #[test]
+    fn events_replay_from_last_event_id() {
+        let mgr = SessionManager::new();
+        let id = mgr.create_session("2025-03-26".into(), None);
+        for n in 1..=3 {
+            mgr.push_notification(&id, json!({ "n": n }));
+        }
+
+        let first = mgr.events_after(&id, None).unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0].0, 1);
+        // Delivered events are not handed out again without a replay position
+        assert!(mgr.events_after(&id, None).unwrap().is_empty());
+        assert!(mgr.drain_notifications(&id).is_empty());
+
+        let replay = mgr.events_after(&id, Some(1)).unwrap();
+        assert_eq!(replay.len(), 2);
+        assert_eq!(replay[0].1, json!({ "n": 2 }));
+
+        assert!(mgr.events_after("nonexistent", None).is_none());
+    }
+
+    #[test]
+    fn progress_reporter_requires_token() {
+        let mgr = Arc::new(SessionManager::new());
+        let id = mgr.create_session("2025-03-26".into(), None);
+        assert!(ProgressReporter::from_params(&mgr, &id, Some(&json!({"name": "grep"}))).is_none());
+
+        let params = json!({"name": "grep", "_meta": {"progressToken": "tok-1"}});
+        let reporter = ProgressReporter::from_params(&mgr, &id, Some(&params)).unwrap();
+        reporter.report(5, Some(10), None);
+
+        let pending = mgr.drain_notifications(&id);
+        assert_eq!(pending[0]["method"], "notifications/progress");
+        assert_eq!(pending[0]["params"]["progressToken"], "tok-1");
+        assert_eq!(pending[0]["params"]["progress"], 5);
+        assert_eq!(pending[0]["params"]["total"], 10);
+    }
+
+    #[test]
+    fn cancel_call_cancels_only_the_named_request() {
+        let mgr = SessionManager::new();
+        let id = mgr.create_session("2025-03-26".into(), None);
+        let first = mgr.begin_call(&id, &json!(1));
+        let second = mgr.begin_call(&id, &json!("1"));
+
+        assert!(mgr.cancel_call(&id, &json!(1)));
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+
+        mgr.end_call(&id, &json!(1));
+        assert!(!mgr.cancel_call(&id, &json!(1)));
+
+        mgr.remove_session(&id);
+        assert!(second.is_cancelled());
+    }
+
+    #[test]
+    fn event_ids_are_bound_to_their_session() {
+        let id = event_id("abc-_123", 42);
+        assert_eq!(parse_event_id("abc-_123", &id), Some(42));
+        assert_eq!(parse_event_id("other", &id), None);
+        assert_eq!(parse_event_id("abc-_123", "garbage"), None);
+    }
+
     #[test]
     fn cleanup_stale_removes_old_sessions() {
         let mgr = SessionManager::new();
@@ -203,4 +831,81 @@ mod tests {
 
         assert!(mgr.get_session(&id).is_some());
     }
+
+    fn stored_manager(dir: &std::path::Path, ttl: Duration) -> SessionManager {
+        let store = crate::stores::filesystem::FileSystemStore::new(dir.to_path_buf()).unwrap();
+        SessionManager::with_config(Some(Arc::new(Box::new(store))), ttl, DEFAULT_MAX_SESSIONS)
+    }
+
+    #[tokio::test]
+    async fn sessions_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let scoped = McpCredential {
+            principal: crate::mcp::auth::McpPrincipal::Key("assistant".into()),
+            scope: crate::mcp::auth::McpScope::Folders(vec!["Lens".into()]),
+            mode: y_sweet_core::config::McpMode::SuggestOnly,
+        };
+
+        let id = {
+            let mgr = stored_manager(dir.path(), DEFAULT_SESSION_TTL);
+            let id = mgr.create_session("2025-03-26".into(), Some(json!({"name": "agent"})));
+            mgr.mark_initialized(&id);
+            mgr.bind_credential(&id, scoped.clone());
+            mgr.record_read(&id, "doc-1", 42);
+            mgr.subscribe(&id, "doc-1", "lens://Lens/Doc.md");
+            mgr.persist().await;
+            id
+        };
+
+        let mgr = stored_manager(dir.path(), DEFAULT_SESSION_TTL);
+        assert_eq!(mgr.load().await.unwrap(), 1);
+        let session = mgr.get_session(&id).expect("session should be restored");
+        assert!(session.initialized);
+        assert_eq!(session.credential, scoped);
+        assert_eq!(session.read_docs.get("doc-1"), Some(&42));
+        assert_eq!(session.client_info.as_ref().unwrap()["name"], "agent");
+        assert_eq!(session.subscriptions.len(), 1);
+        assert!(session.last_activity.elapsed() < Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn removed_and_expired_sessions_are_not_restored() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mgr = stored_manager(dir.path(), DEFAULT_SESSION_TTL);
+            let gone = mgr.create_session("2025-03-26".into(), None);
+            mgr.persist().await;
+            mgr.remove_session(&gone);
+            mgr.create_session("2025-03-26".into(), None);
+            // Created and removed between two writes
+            let never_written = mgr.create_session("2025-03-26".into(), None);
+            mgr.remove_session(&never_written);
+            mgr.persist().await;
+            assert!(mgr.dirty.lock().unwrap().is_empty());
+        }
+
+        let mgr = stored_manager(dir.path(), DEFAULT_SESSION_TTL);
+        assert_eq!(mgr.load().await.unwrap(), 1);
+
+        // With a zero TTL the remaining session has expired and is deleted
+        let mgr = stored_manager(dir.path(), Duration::ZERO);
+        assert_eq!(mgr.load().await.unwrap(), 0);
+        assert!(std::fs::read_dir(dir.path().join(SESSION_PREFIX))
+            .unwrap()
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn evict_drops_least_recently_active_past_the_cap() {
+        let mgr = SessionManager::with_config(None, DEFAULT_SESSION_TTL, 2);
+        let oldest = mgr.create_session("2025-03-26".into(), None);
+        let second = mgr.create_session("2025-03-26".into(), None);
+        mgr.get_session_mut(&oldest).unwrap().last_activity -= Duration::from_secs(10);
+
+        let third = mgr.create_session("2025-03-26".into(), None);
+        assert!(mgr.get_session(&oldest).is_none());
+        assert!(mgr.get_session(&second).is_some());
+        assert!(mgr.get_session(&third).is_some());
+    }
 }
@@ -0,0 +1,369 @@
+//! MCP over stdin/stdout, for `relay mcp-stdio`.
+//!
+//! Messages are newline-delimited JSON-RPC as in the MCP stdio transport. In
+//! embedded mode the router runs against an in-process `Server`, with one
+//! session for the life of the process. In remote mode each message is
+//! forwarded to a relay's `/mcp` endpoint with a bearer token.
+
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::task::JoinHandle;
+use tokio_util::task::TaskTracker;
+use tracing::debug;
+
+use super::jsonrpc::{
+    error_response, parse_message, JsonRpcMessage, JsonRpcResponse, INTERNAL_ERROR, PARSE_ERROR,
+};
+use super::router;
+use super::session::SessionManager;
+use crate::server::Server;
+
+/// Serve MCP on `input`/`output` from an embedded server. Returns once `input`
+/// is closed and the calls already received have been answered.
+pub async fn serve_local<R, W>(server: Arc<Server>, input: R, output: W) -> anyhow::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (tx, writer) = spawn_writer(output);
+    let sessions = server.mcp_sessions.clone();
+    let calls = TaskTracker::new();
+    let mut session_id: Option<String> = None;
+    let mut lines = input.lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message = match parse_line(&line) {
+            Ok(message) => message,
+            Err(response) => {
+                send(&tx, &response);
+                continue;
+            }
+        };
+
+        match message {
+            JsonRpcMessage::Notification(notification) => {
+                router::handle_notification(&sessions, session_id.as_deref(), &notification);
+            }
+            JsonRpcMessage::Request(request) if request.method == "initialize" => {
+                let (response, new_session_id) =
+                    router::dispatch_request(&server, None, &request).await;
+                if let Some(sid) = new_session_id {
+                    // One client per process: a repeated initialize starts over
+                    if let Some(old) = session_id.replace(sid.clone()) {
+                        sessions.remove_session(&old);
+                    }
+                    spawn_event_forwarder(&sessions, &sid, tx.clone());
+                }
+                send(&tx, &response);
+            }
+            // Run calls concurrently so notifications/cancelled can reach them
+            JsonRpcMessage::Request(request) => {
+                let server = server.clone();
+                let session_id = session_id.clone();
+                let tx = tx.clone();
+                calls.spawn(async move {
+                    let (response, _) =
+                        router::dispatch_request(&server, session_id.as_deref(), &request).await;
+                    send(&tx, &response);
+                });
+            }
+        }
+    }
+
+    calls.close();
+    calls.wait().await;
+    // Ending the session also stops its event forwarder
+    if let Some(sid) = session_id {
+        sessions.remove_session(&sid);
+    }
+    drop(tx);
+    writer.await??;
+    Ok(())
+}
+
+/// Serve MCP on `input`/`output` by forwarding every message to the relay at
+/// `base_url` (e.g. "https://relay.example.com"), authenticating with `token`.
+///
+/// Server-initiated messages arrive only alongside responses, as the relay
+/// sends them on the response stream; the standalone GET stream is not opened.
+pub async fn serve_remote<R, W>(
+    base_url: &str,
+    token: &str,
+    input: R,
+    output: W,
+) -> anyhow::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let endpoint = format!("{}/mcp", base_url.trim_end_matches('/'));
+    let client = reqwest::Client::new();
+    let (tx, writer) = spawn_writer(output);
+    let calls = TaskTracker::new();
+    let mut session_id: Option<String> = None;
+    let mut lines = input.lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        // Malformed input is answered here rather than sent over the network
+        let (request_id, is_initialize) = match parse_line(&line) {
+            Ok(JsonRpcMessage::Request(request)) => {
+                (Some(request.id), request.method == "initialize")
+            }
+            Ok(JsonRpcMessage::Notification(_)) => (None, false),
+            Err(response) => {
+                send(&tx, &response);
+                continue;
+            }
+        };
+
+        let mut request = client
+            .post(&endpoint)
+            .bearer_auth(token)
+            .header("content-type", "application/json")
+            .header("accept", "application/json, text/event-stream")
+            .body(line);
+        if let Some(sid) = &session_id {
+            request = request.header("mcp-session-id", sid);
+        }
+
+        if is_initialize {
+            // Later messages need the session ID, so wait for this one
+            if let Some(sid) = forward(request, request_id, &tx).await {
+                session_id = Some(sid);
+            }
+        } else {
+            let tx = tx.clone();
+            calls.spawn(async move {
+                forward(request, request_id, &tx).await;
+            });
+        }
+    }
+
+    calls.close();
+    calls.wait().await;
+    if let Some(sid) = session_id {
+        let _ = client
+            .delete(&endpoint)
+            .bearer_auth(token)
+            .header("mcp-session-id", sid)
+            .send()
+            .await;
+    }
+    drop(tx);
+    writer.await??;
+    Ok(())
+}
+
+/// Send one message to the relay and write whatever comes back. Transport
+/// failures become JSON-RPC errors for requests and are logged for
+/// notifications. Returns the session ID the relay assigned, if any.
+async fn forward(
+    request: reqwest::RequestBuilder,
+    request_id: Option<Value>,
+    tx: &UnboundedSender<String>,
+) -> Option<String> {
+    let fail = |message: String| match &request_id {
+        Some(id) => send(tx, &error_response(id.clone(), INTERNAL_ERROR, message)),
+        None => tracing::warn!("{}", message),
+    };
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            fail(format!("Relay request failed: {}", e));
+            return None;
+        }
+    };
+    let status = response.status();
+    let session_id = response
+        .headers()
+        .get("mcp-session-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let is_event_stream = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    let body = match response.text().await {
+        Ok(body) => body,
+        Err(e) => {
+            fail(format!("Relay request failed: {}", e));
+            return None;
+        }
+    };
+
+    if !status.is_success() && !body.trim_start().starts_with('{') {
+        fail(format!("Relay returned HTTP {}", status));
+        return None;
+    }
+    if is_event_stream {
+        for data in body.lines().filter_map(|line| line.strip_prefix("data:")) {
+            let _ = tx.send(data.trim_start().to_string());
+        }
+    } else if !body.trim().is_empty() {
+        let _ = tx.send(body.trim().to_string());
+    }
+    session_id
+}
+
+/// Parse one input line, or build the error response to send back for it.
+fn parse_line(line: &str) -> Result<JsonRpcMessage, JsonRpcResponse> {
+    let value: Value = serde_json::from_str(line)
+        .map_err(|_| error_response(Value::Null, PARSE_ERROR, "Parse error"))?;
+    parse_message(&value).map_err(|error| JsonRpcResponse {
+        jsonrpc: "2.0".into(),
+        id: value.get("id").cloned().unwrap_or(Value::Null),
+        result: None,
+        error: Some(error),
+    })
+}
+
+fn send(tx: &UnboundedSender<String>, response: &JsonRpcResponse) {
+    if let Ok(line) = serde_json::to_string(response) {
+        let _ = tx.send(line);
+    }
+}
+
+/// Write each line sent on the returned channel to `output`, until every
+/// sender is dropped. A single writer keeps concurrent responses whole.
+fn spawn_writer<W>(mut output: W) -> (UnboundedSender<String>, JoinHandle<std::io::Result<()>>)
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let handle = tokio::spawn(async move {
+        while let Some(line) = rx.recv().await {
+            output.write_all(line.as_bytes()).await?;
+            output.write_all(b"\n").await?;
+            output.flush().await?;
+        }
+        Ok(())
+    });
+    (tx, handle)
+}
+
+/// Copy the session's server-to-client messages (progress, resource updates)
+/// to the output until the session is removed.
+fn spawn_event_forwarder(
+    sessions: &Arc<SessionManager>,
+    session_id: &str,
+    tx: UnboundedSender<String>,
+) {
+    let Some(notify) = sessions.event_notifier(session_id) else {
+        return;
+    };
+    let sessions = sessions.clone();
+    let session_id = session_id.to_string();
+    tokio::spawn(async move {
+        while let Some(events) = sessions.events_after(&session_id, None) {
+            for (_, message) in events {
+                if tx.send(message.to_string()).is_err() {
+                    return;
+                }
+            }
+            notify.notified().await;
+        }
+        debug!(session_id = %session_id, "MCP stdio event forwarder stopped");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tools::test_helpers::build_test_server;
+    use super::*;
+    use serde_json::json;
+    use tokio::io::AsyncReadExt;
+
+    async fn run_local(server: Arc<Server>, input: &[Value]) -> Vec<Value> {
+        let input: String = input.iter().map(|m| format!("{}\n", m)).collect();
+        let (output, mut reader) = tokio::io::duplex(1 << 20);
+        serve_local(server, input.as_bytes(), output).await.unwrap();
+
+        let mut out = String::new();
+        reader.read_to_string(&mut out).await.unwrap();
+        out.lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    fn by_id(responses: &[Value], id: i64) -> &Value {
+        responses
+            .iter()
+            .find(|r| r["id"] == json!(id))
+            .unwrap_or_else(|| panic!("no response with id {} in {:?}", id, responses))
+    }
+
+    #[tokio::test]
+    async fn local_session_serves_tools_over_stdio() {
+        let server = build_test_server(&[("/Doc.md", "uuid-doc", "hello stdio")]).await;
+        let responses = run_local(
+            server.clone(),
+            &[
+                json!({"jsonrpc": "2.0", "id": 1, "method": "initialize",
+                       "params": {"protocolVersion": "2025-03-26"}}),
+                json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+                json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                       "params": {"name": "create_session", "arguments": {}}}),
+                json!({"jsonrpc": "2.0", "id": 3, "method": "tools/list"}),
+            ],
+        )
+        .await;
+
+        assert_eq!(responses.len(), 3);
+        assert!(by_id(&responses, 1)["result"]["capabilities"].is_object());
+        let sid = by_id(&responses, 2)["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(!by_id(&responses, 3)["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+
+        // The session ends with stdin
+        assert!(server.mcp_sessions.get_session(&sid).is_none());
+    }
+
+    #[tokio::test]
+    async fn malformed_lines_get_json_rpc_errors() {
+        let server = build_test_server(&[]).await;
+        let (output, mut reader) = tokio::io::duplex(1 << 16);
+        let input = "not json\n{\"jsonrpc\": \"2.0\", \"id\": 9}\n";
+        serve_local(server, input.as_bytes(), output).await.unwrap();
+
+        let mut out = String::new();
+        reader.read_to_string(&mut out).await.unwrap();
+        let responses: Vec<Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses[0]["error"]["code"], PARSE_ERROR);
+        assert_eq!(responses[0]["id"], Value::Null);
+        assert_eq!(responses[1]["id"], 9);
+        assert!(responses[1]["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("method"));
+    }
+
+    #[tokio::test]
+    async fn tools_call_before_initialize_is_rejected() {
+        let server = build_test_server(&[]).await;
+        let responses = run_local(
+            server,
+            &[json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call",
+                     "params": {"name": "read", "arguments": {}}})],
+        )
+        .await;
+        assert_eq!(responses[0]["error"]["code"], INTERNAL_ERROR);
+    }
+}
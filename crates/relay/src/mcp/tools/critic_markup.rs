@@ -29,6 +29,16 @@ pub fn reject_if_contains_markup(text: &str, param_name: &str) -> Result<(), Str
     Ok(())
 }
 
+/// Build the `{"author":...,"timestamp":...}@@` prefix that opens a suggestion's content.
+/// The author is JSON-escaped, so quotes and backslashes in it cannot break the metadata.
+pub fn meta_prefix(author: &str, timestamp: u64) -> String {
+    format!(
+        r#"{{"author":{},"timestamp":{}}}@@"#,
+        Value::from(author),
+        timestamp
+    )
+}
+
 /// Extract metadata (author, timestamp) from the inner content of a critic markup block.
 /// The format is: `{"author":"AI","timestamp":1700000000000}@@actual content`
 /// Only splits on `@@` if the prefix is valid JSON containing an "author" field.
@@ -459,7 +469,7 @@ pub fn merge_edit(
     }
 
     // Word-level diff with single-space absorption on expanded strings.
-    let meta = format!(r#"{{"author":"{}","timestamp":{}}}@@"#, author, timestamp);
+    let meta = meta_prefix(author, timestamp);
     let diff = TextDiff::from_words(&expanded_old, &expanded_new);
     let changes: Vec<_> = diff.iter_all_changes().collect();
 
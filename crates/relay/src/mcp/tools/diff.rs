@@ -0,0 +1,135 @@
+use crate::server::Server;
+use serde_json::Value;
+use similar::TextDiff;
+use std::sync::Arc;
+use yrs::{GetString, ReadTxn, Transact};
+
+/// Names the live document text wherever a version ID is accepted.
+const CURRENT: &str = "current";
+
+/// Execute the `diff` tool: unified line diff between two stored versions of a
+/// document, or between a version and the current text.
+///
+/// Both sides are the raw document text, so pending CriticMarkup suggestions
+/// show up as changes too.
+pub async fn execute(server: &Arc<Server>, arguments: &Value) -> Result<String, String> {
+    let file_path = arguments
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Missing required parameter: file_path".to_string())?;
+
+    let from = arguments
+        .get("from")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Missing required parameter: from".to_string())?;
+
+    let to = arguments
+        .get("to")
+        .and_then(|v| v.as_str())
+        .unwrap_or(CURRENT);
+
+    let doc_info = server
+        .doc_resolver()
+        .resolve_path(file_path)
+        .ok_or_else(|| format!("Error: Document not found: {}", file_path))?;
+
+    let old = load_text(server, &doc_info.doc_id, file_path, from).await?;
+    let new = load_text(server, &doc_info.doc_id, file_path, to).await?;
+
+    Ok(render_diff(file_path, from, to, &old, &new))
+}
+
+async fn load_text(
+    server: &Arc<Server>,
+    doc_id: &str,
+    file_path: &str,
+    version: &str,
+) -> Result<String, String> {
+    if version != CURRENT {
+        return server
+            .doc_version_text(doc_id, version)
+            .await
+            .map_err(|e| {
+                format!(
+                    "Error: Failed to read version {} of {}: {}",
+                    version, file_path, e
+                )
+            })?
+            .ok_or_else(|| {
+                format!(
+                    "Error: Version {} of {} not found. Call the history tool to list versions.",
+                    version, file_path
+                )
+            });
+    }
+
+    server
+        .ensure_doc_loaded(doc_id)
+        .await
+        .map_err(|e| format!("Error: Failed to load document {}: {}", file_path, e))?;
+    let doc_ref = server
+        .docs()
+        .get(doc_id)
+        .ok_or_else(|| format!("Error: Document data not loaded: {}", file_path))?;
+    let awareness = doc_ref.awareness();
+    let guard = awareness.read().unwrap_or_else(|e| e.into_inner());
+    let txn = guard.doc.transact();
+    Ok(txn
+        .get_text("contents")
+        .map(|text| text.get_string(&txn))
+        .unwrap_or_default())
+}
+
+fn render_diff(path: &str, from: &str, to: &str, old: &str, new: &str) -> String {
+    if old == new {
+        return format!("No differences in {} between {} and {}", path, from, to);
+    }
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("{}@{}", path, from), &format!("{}@{}", path, to))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::*;
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn render_diff_is_a_unified_line_diff() {
+        let out = render_diff(
+            "Lens/Doc.md",
+            "v1",
+            "current",
+            "one\ntwo\nthree\n",
+            "one\n2\nthree\n",
+        );
+        assert!(out.starts_with("--- Lens/Doc.md@v1\n+++ Lens/Doc.md@current\n"));
+        assert!(out.contains("-two\n+2\n"), "got: {}", out);
+    }
+
+    #[test]
+    fn render_diff_reports_identical_texts() {
+        let out = render_diff("Lens/Doc.md", "v1", "v2", "same", "same");
+        assert_eq!(out, "No differences in Lens/Doc.md between v1 and v2");
+    }
+
+    #[tokio::test]
+    async fn diff_current_against_itself_needs_no_store() {
+        let server = build_test_server(&[("/Doc.md", "uuid-doc", "text")]).await;
+        let out = execute(
+            &server,
+            &json!({"file_path": "Lens/Doc.md", "from": "current"}),
+        )
+        .await
+        .unwrap();
+        assert!(out.starts_with("No differences"), "got: {}", out);
+
+        let err = execute(&server, &json!({"file_path": "Lens/Doc.md", "from": "v1"}))
+            .await
+            .unwrap_err();
+        assert!(err.contains("Failed to read version v1"), "got: {}", err);
+    }
+}
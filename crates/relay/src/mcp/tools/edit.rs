@@ -1,3 +1,4 @@
+use crate::mcp::presence::cursor_at;
 use crate::server::Server;
 use serde_json::Value;
 use std::sync::Arc;
@@ -46,7 +47,7 @@ pub async fn execute(
             .mcp_sessions
             .get_session(session_id)
             .ok_or_else(|| "Error: Session not found".to_string())?;
-        if !session.read_docs.contains(&doc_info.doc_id) {
+        if !session.read_docs.contains_key(&doc_info.doc_id) {
             return Err(format!(
                 "You must read this document before editing it. Call the read tool with file_path: \"{}\" first.",
                 file_path
@@ -60,6 +61,9 @@ pub async fn execute(
         .ensure_doc_loaded(&doc_info.doc_id)
         .await
         .map_err(|e| format!("Error: Failed to load document {}: {}", file_path, e))?;
+    let presence = server
+        .mcp_presence
+        .begin(server, session_id, &doc_info.doc_id);
 
     // 5. Read content and find old_string
     let content = {
@@ -117,7 +121,7 @@ pub async fn execute(
     }
 
     // 7. Apply targeted edit under write lock with TOCTOU re-verify
-    {
+    let cursor = {
         let doc_ref = server
             .docs()
             .get(&doc_info.doc_id)
@@ -155,7 +159,15 @@ pub async fn execute(
             final_merge.raw_offset as u32,
             &final_merge.replacement,
         );
-    }
+
+        // Anchor the new suggestion so later human edits around it can be detected
+        server
+            .suggestion_anchors()
+            .refresh(&doc_info.doc_id, &mut txn, &text);
+        let end = final_merge.raw_offset + final_merge.replacement.len();
+        cursor_at(&text, &mut txn, end as u32)
+    };
+    presence.set_cursor(cursor);
 
     // 8. Explicit persist for immediate durability
     {
@@ -184,6 +196,33 @@ mod tests {
 
     // === Edit Tests ===
 
+    #[tokio::test]
+    async fn edit_shows_agent_cursor_to_collaborators() {
+        let server = build_test_server(&[("/Hello.md", "uuid-hello", "say hello to all")]).await;
+        let doc_id = format!("{}-{}", RELAY_ID, "uuid-hello");
+        let sid = setup_session_with_read(&server, &doc_id);
+
+        execute(
+            &server,
+            &sid,
+            &json!({"file_path": "Lens/Hello.md", "old_string": "hello", "new_string": "world"}),
+        )
+        .await
+        .unwrap();
+
+        let doc_ref = server.docs().get(&doc_id).unwrap();
+        let awareness = doc_ref.awareness();
+        let awareness = awareness.read().unwrap();
+        let states: Vec<Value> = awareness
+            .clients()
+            .values()
+            .map(|state| serde_json::from_str(state).unwrap())
+            .collect();
+        assert_eq!(states.len(), 1, "{:?}", states);
+        assert!(states[0]["user"]["name"].is_string());
+        assert!(states[0]["cursor"]["anchor"].is_object(), "{:?}", states[0]);
+    }
+
     #[tokio::test]
     async fn edit_basic_replacement() {
         let server = build_test_server(&[("/Hello.md", "uuid-hello", "say hello to all")]).await;
@@ -325,6 +364,42 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn edit_anchors_suggestion_for_stale_detection() {
+        let server = build_test_server(&[("/Hello.md", "uuid-hello", "say hello to all")]).await;
+        let doc_id = format!("{}-{}", RELAY_ID, "uuid-hello");
+        let sid = setup_session_with_read(&server, &doc_id);
+
+        execute(
+            &server,
+            &sid,
+            &json!({"file_path": "Lens/Hello.md", "old_string": "hello", "new_string": "world"}),
+        )
+        .await
+        .expect("edit should succeed");
+
+        // A human appends to the same line after the AI's edit
+        let doc_ref = server.docs().get(&doc_id).unwrap();
+        let awareness = doc_ref.awareness();
+        let guard = awareness.write().unwrap();
+        let mut txn = guard.doc.transact_mut();
+        let text = txn.get_or_insert_text("contents");
+        let len = text.get_string(&txn).len() as u32;
+        text.insert(&mut txn, len, " of you");
+
+        let reports = server
+            .suggestion_anchors()
+            .refresh(&doc_id, &mut txn, &text);
+        assert!(!reports.is_empty());
+        assert!(
+            reports
+                .iter()
+                .all(|r| r.status == crate::suggestion_anchors::AnchorStatus::Conflicting),
+            "same-line edit should flag the suggestion: {:?}",
+            reports
+        );
+    }
+
     #[tokio::test]
     async fn edit_missing_parameters() {
         let server = build_test_server(&[("/Doc.md", "uuid-doc", "content")]).await;
@@ -1,3 +1,4 @@
+use crate::mcp::auth::McpCredential;
 use crate::server::Server;
 use serde_json::Value;
 use std::sync::Arc;
@@ -7,7 +8,12 @@ use y_sweet_core::link_parser;
 use yrs::{GetString, Map, ReadTxn, Transact};
 
 /// Execute the `get_links` tool: return backlinks and forward links for a document.
-pub async fn execute(server: &Arc<Server>, arguments: &Value) -> Result<String, String> {
+/// Linked documents outside the credential's scope are left out.
+pub async fn execute(
+    server: &Arc<Server>,
+    credential: &McpCredential,
+    arguments: &Value,
+) -> Result<String, String> {
     let file_path = arguments
         .get("file_path")
         .and_then(|v| v.as_str())
@@ -19,10 +25,14 @@ pub async fn execute(server: &Arc<Server>, arguments: &Value) -> Result<String,
         .ok_or_else(|| format!("Error: Document not found: {}", file_path))?;
 
     // --- Backlinks ---
-    let backlink_paths = read_backlinks(server, &doc_info.folder_doc_id, &doc_info.uuid).await;
+    let mut backlink_paths = read_backlinks(server, &doc_info.folder_doc_id, &doc_info.uuid).await;
 
     // --- Forward links ---
-    let forward_link_paths = read_forward_links(server, &doc_info.doc_id).await;
+    let mut forward_link_paths = read_forward_links(server, &doc_info.doc_id).await;
+
+    let resolver = server.doc_resolver();
+    backlink_paths.retain(|p| credential.can_see(resolver, &server.acl, p));
+    forward_link_paths.retain(|p| credential.can_see(resolver, &server.acl, p));
 
     // Format output
     let mut output = String::new();
@@ -1,10 +1,21 @@
+use super::CallContext;
+use crate::mcp::auth::McpCredential;
 use crate::server::Server;
 use glob_match::glob_match;
 use serde_json::Value;
 use std::sync::Arc;
 
+/// Check for cancellation after this many paths.
+const CANCEL_CHECK_EVERY: usize = 256;
+
 /// Execute the `glob` tool: pattern match against document paths.
-pub fn execute(server: &Arc<Server>, arguments: &Value) -> Result<String, String> {
+/// Paths outside the credential's scope are never listed.
+pub fn execute(
+    server: &Arc<Server>,
+    credential: &McpCredential,
+    arguments: &Value,
+    ctx: &CallContext,
+) -> Result<String, String> {
     let pattern = arguments
         .get("pattern")
         .and_then(|v| v.as_str())
@@ -12,25 +23,32 @@ pub fn execute(server: &Arc<Server>, arguments: &Value) -> Result<String, String
 
     let path_scope = arguments.get("path").and_then(|v| v.as_str());
 
-    let all_paths = server.doc_resolver().all_paths();
-
-    let mut matched: Vec<String> = all_paths
-        .into_iter()
-        .filter(|p| {
-            // If a path scope is given, only include paths under that folder
-            if let Some(scope) = path_scope {
-                let prefix = if scope.ends_with('/') {
-                    scope.to_string()
-                } else {
-                    format!("{}/", scope)
-                };
-                if !p.starts_with(&prefix) {
-                    return false;
-                }
+    let resolver = server.doc_resolver();
+    let all_paths = resolver.all_paths();
+
+    // If a path scope is given, only include paths under that folder
+    let scope_prefix = path_scope.map(|scope| {
+        if scope.ends_with('/') {
+            scope.to_string()
+        } else {
+            format!("{}/", scope)
+        }
+    });
+
+    let mut matched: Vec<String> = Vec::new();
+    for (i, p) in all_paths.into_iter().enumerate() {
+        if i % CANCEL_CHECK_EVERY == 0 {
+            ctx.check_cancelled()?;
+        }
+        if let Some(ref prefix) = scope_prefix {
+            if !p.starts_with(prefix) {
+                continue;
             }
-            glob_match(pattern, p)
-        })
-        .collect();
+        }
+        if glob_match(pattern, &p) && credential.can_see(resolver, &server.acl, &p) {
+            matched.push(p);
+        }
+    }
 
     matched.sort();
 
@@ -1,11 +1,25 @@
+use super::CallContext;
+use crate::mcp::auth::McpCredential;
 use crate::server::Server;
 use regex::RegexBuilder;
 use serde_json::Value;
 use std::sync::Arc;
 use yrs::{GetString, ReadTxn, Transact};
 
+/// Emit `notifications/progress` after this many documents have been scanned.
+const PROGRESS_EVERY: usize = 50;
+
+/// Yield to the runtime after this many documents.
+const YIELD_EVERY: usize = 16;
+
 /// Execute the `grep` tool: regex content search across Y.Docs.
-pub async fn execute(server: &Arc<Server>, arguments: &Value) -> Result<String, String> {
+/// Only documents inside the credential's scope are searched.
+pub async fn execute(
+    server: &Arc<Server>,
+    credential: &McpCredential,
+    arguments: &Value,
+    ctx: &CallContext,
+) -> Result<String, String> {
     let pattern = arguments
         .get("pattern")
         .and_then(|v| v.as_str())
@@ -60,11 +74,26 @@ pub async fn execute(server: &Arc<Server>, arguments: &Value) -> Result<String,
         };
         all_paths.retain(|p| p.starts_with(&prefix) || p == scope);
     }
+    all_paths.retain(|p| credential.can_see(server.doc_resolver(), &server.acl, p));
 
     let mut output_lines: Vec<String> = Vec::new();
     let mut file_count = 0;
 
-    for path in &all_paths {
+    let total = all_paths.len();
+    for (scanned, path) in all_paths.iter().enumerate() {
+        // Stop promptly if the client cancelled or the call deadline passed.
+        // Yield now and then so a regex over many in-memory docs can't pin
+        // the worker thread past the deadline.
+        ctx.check_cancelled()?;
+        if scanned > 0 && scanned % YIELD_EVERY == 0 {
+            tokio::task::yield_now().await;
+        }
+        if let Some(ref progress) = ctx.progress {
+            if scanned > 0 && scanned % PROGRESS_EVERY == 0 {
+                progress.report(scanned, Some(total), None);
+            }
+        }
+
         // Apply head_limit for files_with_matches and count modes
         if head_limit > 0 && file_count >= head_limit && output_mode != "content" {
             break;
@@ -300,7 +329,9 @@ mod tests {
 
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "sunlight", "output_mode": "content"}),
+            &CallContext::detached(),
         )
         .await
         .unwrap();
@@ -323,7 +354,9 @@ mod tests {
 
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "hello", "-i": true, "output_mode": "content"}),
+            &CallContext::detached(),
         )
         .await
         .unwrap();
@@ -356,7 +389,9 @@ mod tests {
 
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "apple", "output_mode": "files_with_matches"}),
+            &CallContext::detached(),
         )
         .await
         .unwrap();
@@ -375,7 +410,9 @@ mod tests {
 
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "apple", "output_mode": "count"}),
+            &CallContext::detached(),
         )
         .await
         .unwrap();
@@ -395,7 +432,9 @@ mod tests {
 
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "MATCH", "output_mode": "content", "-C": 1}),
+            &CallContext::detached(),
         )
         .await
         .unwrap();
@@ -428,7 +467,9 @@ mod tests {
 
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "MATCH", "output_mode": "content", "-A": 2}),
+            &CallContext::detached(),
         )
         .await
         .unwrap();
@@ -466,7 +507,9 @@ mod tests {
 
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "MATCH", "output_mode": "content", "-B": 1}),
+            &CallContext::detached(),
         )
         .await
         .unwrap();
@@ -528,7 +571,9 @@ mod tests {
         // Search scoped to Lens/ only
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "target", "path": "Lens", "output_mode": "files_with_matches"}),
+            &CallContext::detached(),
         )
         .await
         .unwrap();
@@ -551,7 +596,9 @@ mod tests {
 
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "ZZZZNOTFOUND", "output_mode": "content"}),
+            &CallContext::detached(),
         )
         .await
         .unwrap();
@@ -565,7 +612,9 @@ mod tests {
 
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "[invalid", "output_mode": "content"}),
+            &CallContext::detached(),
         )
         .await;
 
@@ -587,7 +636,9 @@ mod tests {
 
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "target", "output_mode": "files_with_matches", "head_limit": 1}),
+            &CallContext::detached(),
         )
         .await
         .unwrap();
@@ -611,7 +662,9 @@ mod tests {
 
         let result = execute(
             &server,
+            &McpCredential::shared_key(),
             &json!({"pattern": "common", "output_mode": "files_with_matches"}),
+            &CallContext::detached(),
         )
         .await
         .unwrap();
@@ -621,4 +674,62 @@ mod tests {
         // Should be sorted alphabetically
         assert!(lines[0] < lines[1], "Results should be sorted: {:?}", lines);
     }
+
+    #[tokio::test]
+    async fn grep_reports_progress_when_requested() {
+        let names: Vec<(String, String)> = (0..120)
+            .map(|i| (format!("/Doc{:03}.md", i), format!("uuid-{:03}", i)))
+            .collect();
+        let entries: Vec<(&str, &str, &str)> = names
+            .iter()
+            .map(|(path, uuid)| (path.as_str(), uuid.as_str(), "text"))
+            .collect();
+        let server = build_test_server(&entries).await;
+        let sid = server
+            .mcp_sessions
+            .create_session("2025-03-26".into(), None);
+        let params = json!({"_meta": {"progressToken": 9}});
+        let ctx = CallContext {
+            progress: crate::mcp::session::ProgressReporter::from_params(
+                &server.mcp_sessions,
+                &sid,
+                Some(&params),
+            ),
+            cancellation: tokio_util::sync::CancellationToken::new(),
+        };
+
+        execute(
+            &server,
+            &McpCredential::shared_key(),
+            &json!({"pattern": "text"}),
+            &ctx,
+        )
+        .await
+        .unwrap();
+
+        let events = server.mcp_sessions.drain_notifications(&sid);
+        let reported: Vec<u64> = events
+            .iter()
+            .map(|e| e["params"]["progress"].as_u64().unwrap())
+            .collect();
+        assert_eq!(reported, vec![50, 100]);
+        assert_eq!(events[0]["params"]["total"], 120);
+        assert_eq!(events[0]["params"]["progressToken"], 9);
+    }
+
+    #[tokio::test]
+    async fn grep_stops_when_cancelled() {
+        let server = build_test_server(&[("/A.md", "uuid-a", "text")]).await;
+        let ctx = CallContext::detached();
+        ctx.cancellation.cancel();
+
+        let result = execute(
+            &server,
+            &McpCredential::shared_key(),
+            &json!({"pattern": "text"}),
+            &ctx,
+        )
+        .await;
+        assert_eq!(result.unwrap_err(), crate::mcp::tools::CANCELLED);
+    }
 }
@@ -0,0 +1,130 @@
+use crate::server::Server;
+use serde_json::Value;
+use std::sync::Arc;
+use y_sweet_core::store::VersionInfo;
+
+const DEFAULT_LIMIT: usize = 20;
+
+/// Execute the `history` tool: list the stored versions of a document, newest first.
+pub async fn execute(server: &Arc<Server>, arguments: &Value) -> Result<String, String> {
+    let file_path = arguments
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Missing required parameter: file_path".to_string())?;
+
+    let limit = arguments
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .map(|n| n as usize)
+        .unwrap_or(DEFAULT_LIMIT);
+
+    let doc_info = server
+        .doc_resolver()
+        .resolve_path(file_path)
+        .ok_or_else(|| format!("Error: Document not found: {}", file_path))?;
+
+    let versions = server.doc_versions(&doc_info.doc_id).await.map_err(|e| {
+        format!(
+            "Error: Version history is unavailable for {}: {}",
+            file_path, e
+        )
+    })?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    Ok(render_history(file_path, &versions, now, limit))
+}
+
+/// Format versions (already sorted newest first) as one line each.
+fn render_history(path: &str, versions: &[VersionInfo], now_ms: u64, limit: usize) -> String {
+    if versions.is_empty() {
+        return format!("No stored versions of {}", path);
+    }
+
+    let mut output = format!("Versions of {} (newest first):\n", path);
+    for version in versions.iter().take(limit) {
+        output.push_str(&format!(
+            "- {}  saved {} (epoch ms {}){}\n",
+            version.version_id,
+            format_age(now_ms.saturating_sub(version.last_modified)),
+            version.last_modified,
+            if version.is_latest { "  [latest]" } else { "" }
+        ));
+    }
+    if versions.len() > limit {
+        output.push_str(&format!(
+            "({} older versions not shown; raise limit to see them)\n",
+            versions.len() - limit
+        ));
+    }
+    output.push_str("\nPass a version ID to the diff tool to see what changed.");
+    output
+}
+
+fn format_age(elapsed_ms: u64) -> String {
+    let secs = elapsed_ms / 1000;
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::*;
+    use super::*;
+    use serde_json::json;
+
+    fn version(id: &str, last_modified: u64, is_latest: bool) -> VersionInfo {
+        VersionInfo {
+            version_id: id.to_string(),
+            last_modified,
+            is_latest,
+        }
+    }
+
+    #[test]
+    fn history_lists_newest_first_with_limit() {
+        let now = 10 * 86_400_000;
+        let versions = vec![
+            version("v3", now - 30_000, true),
+            version("v2", now - 2 * 3_600_000, false),
+            version("v1", now - 3 * 86_400_000, false),
+        ];
+        let out = render_history("Lens/Doc.md", &versions, now, 2);
+
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "Versions of Lens/Doc.md (newest first):");
+        assert!(
+            lines[1].starts_with("- v3  saved just now"),
+            "got: {}",
+            lines[1]
+        );
+        assert!(lines[1].ends_with("[latest]"));
+        assert!(
+            lines[2].starts_with("- v2  saved 2h ago"),
+            "got: {}",
+            lines[2]
+        );
+        assert!(out.contains("1 older versions not shown"));
+        assert!(!out.contains("v1"));
+    }
+
+    #[tokio::test]
+    async fn history_without_versioned_store_is_an_error() {
+        let server = build_test_server(&[("/Doc.md", "uuid-doc", "text")]).await;
+        let err = execute(&server, &json!({"file_path": "Lens/Doc.md"}))
+            .await
+            .unwrap_err();
+        assert!(
+            err.contains("Version history is unavailable"),
+            "got: {}",
+            err
+        );
+    }
+}
@@ -1,18 +1,28 @@
 pub mod create_doc;
 pub mod critic_diff;
 pub mod critic_markup;
+pub mod diff;
 pub mod edit;
 pub mod get_links;
 pub mod glob;
 pub mod grep;
+pub mod history;
 pub mod move_doc;
+pub mod multi_edit;
 pub mod read;
+pub mod read_attachment;
 #[cfg(test)]
 pub(crate) mod test_helpers;
+pub mod write;
 
+use super::auth::{mode_for, McpCredential};
+use super::session::ProgressReporter;
+use crate::acl::AclDecision;
 use crate::server::Server;
 use serde_json::{json, Value};
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use y_sweet_core::config::McpMode;
 
 /// Return tool definitions for MCP tools/list response.
 pub fn tool_definitions() -> Vec<Value> {
@@ -54,6 +64,29 @@ pub fn tool_definitions() -> Vec<Value> {
                 }
             }
         }),
+        json!({
+            "name": "read_attachment",
+            "description": "Reads a file attached to or embedded in the knowledge base, such as an image, CSV or text file. Accepts a full path or an embed as written in a document (e.g. '![[diagram.png]]'). Text files are returned inline and images as image content; other files, and files over the size limits, are described instead.",
+            "inputSchema": {
+                "type": "object",
+                "required": ["path", "session_id"],
+                "additionalProperties": false,
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Path to the file (e.g. 'Lens/images/diagram.png') or an embed or link to it (e.g. '![[diagram.png]]', '![Diagram](images/diagram.png)')"
+                    },
+                    "from": {
+                        "type": "string",
+                        "description": "Path of the document containing the embed, used to resolve relative links and short names (e.g. 'Lens/Photosynthesis.md')"
+                    },
+                    "session_id": {
+                        "type": "string",
+                        "description": "Session ID from create_session. Required for all tool calls."
+                    }
+                }
+            }
+        }),
         json!({
             "name": "glob",
             "description": "Fast document pattern matching. Returns matching document paths sorted alphabetically. Use to discover documents in the knowledge base.",
@@ -144,6 +177,56 @@ pub fn tool_definitions() -> Vec<Value> {
                 }
             }
         }),
+        json!({
+            "name": "history",
+            "description": "List the stored versions of a document, newest first, with their version IDs and when each was saved. Use a version ID with the diff tool to see what changed.",
+            "inputSchema": {
+                "type": "object",
+                "required": ["file_path", "session_id"],
+                "additionalProperties": false,
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "Path to the document (e.g. 'Lens/Photosynthesis.md')"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "Maximum number of versions to list (default 20)."
+                    },
+                    "session_id": {
+                        "type": "string",
+                        "description": "Session ID from create_session. Required for all tool calls."
+                    }
+                }
+            }
+        }),
+        json!({
+            "name": "diff",
+            "description": "Show a unified line diff of a document between two stored versions, or between a version and the current text. Pending CriticMarkup suggestions appear as part of the text.",
+            "inputSchema": {
+                "type": "object",
+                "required": ["file_path", "from", "session_id"],
+                "additionalProperties": false,
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "Path to the document (e.g. 'Lens/Photosynthesis.md')"
+                    },
+                    "from": {
+                        "type": "string",
+                        "description": "Version ID from the history tool, or 'current'."
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "Version ID from the history tool, or 'current' (default)."
+                    },
+                    "session_id": {
+                        "type": "string",
+                        "description": "Session ID from create_session. Required for all tool calls."
+                    }
+                }
+            }
+        }),
         json!({
             "name": "edit",
             "description": "Edit a document by replacing old_string with new_string. The change is wrapped in CriticMarkup ({--old--}{++new++}) for human review. You must read the document first.",
@@ -171,6 +254,68 @@ pub fn tool_definitions() -> Vec<Value> {
                 }
             }
         }),
+        json!({
+            "name": "multi_edit",
+            "description": "Apply several replacements to one document at once. Edits run in order, each matched against the text as left by the edits before it. Every edit must match exactly once; if any fails, none are applied. Changes are wrapped in CriticMarkup for human review. You must read the document first.",
+            "inputSchema": {
+                "type": "object",
+                "required": ["file_path", "edits", "session_id"],
+                "additionalProperties": false,
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "Path to the document (e.g. 'Lens/Photosynthesis.md')"
+                    },
+                    "edits": {
+                        "type": "array",
+                        "minItems": 1,
+                        "description": "Ordered list of replacements to apply.",
+                        "items": {
+                            "type": "object",
+                            "required": ["old_string", "new_string"],
+                            "additionalProperties": false,
+                            "properties": {
+                                "old_string": {
+                                    "type": "string",
+                                    "description": "The exact text to find and replace. Must match exactly and be unique in the document."
+                                },
+                                "new_string": {
+                                    "type": "string",
+                                    "description": "The replacement text. Empty string for deletion."
+                                }
+                            }
+                        }
+                    },
+                    "session_id": {
+                        "type": "string",
+                        "description": "Session ID from create_session. Required for all tool calls."
+                    }
+                }
+            }
+        }),
+        json!({
+            "name": "write",
+            "description": "Replace the full content of an existing document. The difference from the current text is recorded word by word as CriticMarkup suggestions for human review, so prefer this over many edit calls for large rewrites. You must read the document first.",
+            "inputSchema": {
+                "type": "object",
+                "required": ["file_path", "content", "session_id"],
+                "additionalProperties": false,
+                "properties": {
+                    "file_path": {
+                        "type": "string",
+                        "description": "Path to the document (e.g. 'Lens/Photosynthesis.md')"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "The complete new content of the document, without CriticMarkup."
+                    },
+                    "session_id": {
+                        "type": "string",
+                        "description": "Session ID from create_session. Required for all tool calls."
+                    }
+                }
+            }
+        }),
         json!({
             "name": "create",
             "description": "Create a new document at the specified path. The document is created immediately and syncs to all connected clients (including Obsidian).",
@@ -224,12 +369,47 @@ pub fn tool_definitions() -> Vec<Value> {
     ]
 }
 
+/// Per-call plumbing handed to long-running tools.
+pub struct CallContext {
+    /// Present when the client asked for `notifications/progress`.
+    pub progress: Option<ProgressReporter>,
+    /// Cancelled by `notifications/cancelled`, the call deadline or session removal.
+    /// Loops over many documents check it and bail out with `CANCELLED`.
+    pub cancellation: CancellationToken,
+}
+
+impl CallContext {
+    /// A context nobody can cancel and that reports no progress.
+    pub fn detached() -> Self {
+        Self {
+            progress: None,
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    pub fn check_cancelled(&self) -> Result<(), String> {
+        if self.cancellation.is_cancelled() {
+            Err(CANCELLED.to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Whether `name` is one of the tools in `tool_definitions`.
+pub fn is_known_tool(name: &str) -> bool {
+    tool_definitions().iter().any(|tool| tool["name"] == name)
+}
+
+pub const CANCELLED: &str = "Error: Request cancelled";
+
 /// Dispatch a tool call to the correct handler and wrap result in MCP CallToolResult format.
 pub async fn dispatch_tool(
     server: &Arc<Server>,
     transport_session_id: &str,
     name: &str,
     arguments: &Value,
+    ctx: &CallContext,
 ) -> Value {
     // create_session returns the transport session_id — no argument validation needed
     if name == "create_session" {
@@ -242,7 +422,18 @@ pub async fn dispatch_tool(
         None => return tool_error("Missing required parameter: session_id. Call create_session first and pass the returned session_id."),
     };
 
-    if server.mcp_sessions.get_session(session_id).is_none() {
+    // Scopes come from the credential that opened the transport session; the
+    // session_id argument must belong to the same credential.
+    let credential = match server.mcp_sessions.get_session(transport_session_id) {
+        Some(session) => session.credential.clone(),
+        None => {
+            return tool_error("Invalid session_id. Call create_session to get a valid session.")
+        }
+    };
+    if !server
+        .mcp_sessions
+        .credential_matches(session_id, &credential)
+    {
         return tool_error("Invalid session_id. Call create_session to get a valid session.");
     }
 
@@ -252,20 +443,36 @@ pub async fn dispatch_tool(
         server.doc_resolver().rebuild(server.docs());
     }
 
+    if let Err(msg) = authorize(server, &credential, name, arguments) {
+        return tool_error(&msg);
+    }
+
     match name {
         "read" => match read::execute(server, session_id, arguments).await {
             Ok(text) => tool_success(&text),
             Err(msg) => tool_error(&msg),
         },
-        "glob" => match glob::execute(server, arguments) {
+        "read_attachment" => match read_attachment::execute(server, &credential, arguments).await {
+            Ok(content) => tool_content(content),
+            Err(msg) => tool_error(&msg),
+        },
+        "glob" => match glob::execute(server, &credential, arguments, ctx) {
+            Ok(text) => tool_success(&text),
+            Err(msg) => tool_error(&msg),
+        },
+        "get_links" => match get_links::execute(server, &credential, arguments).await {
             Ok(text) => tool_success(&text),
             Err(msg) => tool_error(&msg),
         },
-        "get_links" => match get_links::execute(server, arguments).await {
+        "grep" => match grep::execute(server, &credential, arguments, ctx).await {
             Ok(text) => tool_success(&text),
             Err(msg) => tool_error(&msg),
         },
-        "grep" => match grep::execute(server, arguments).await {
+        "history" => match history::execute(server, arguments).await {
+            Ok(text) => tool_success(&text),
+            Err(msg) => tool_error(&msg),
+        },
+        "diff" => match diff::execute(server, arguments).await {
             Ok(text) => tool_success(&text),
             Err(msg) => tool_error(&msg),
         },
@@ -273,6 +480,14 @@ pub async fn dispatch_tool(
             Ok(text) => tool_success(&text),
             Err(msg) => tool_error(&msg),
         },
+        "multi_edit" => match multi_edit::execute(server, session_id, arguments).await {
+            Ok(text) => tool_success(&text),
+            Err(msg) => tool_error(&msg),
+        },
+        "write" => match write::execute(server, session_id, arguments).await {
+            Ok(text) => tool_success(&text),
+            Err(msg) => tool_error(&msg),
+        },
         "create" => match create_doc::execute(server, arguments).await {
             Ok(text) => tool_success(&text),
             Err(msg) => tool_error(&msg),
@@ -285,6 +500,81 @@ pub async fn dispatch_tool(
     }
 }
 
+/// Check the credential's mode and scope for tools that name a document path.
+/// `glob`, `grep` and `get_links` filter their results by scope themselves, and
+/// `read_attachment` checks the path its `path` argument resolves to.
+fn authorize(
+    server: &Arc<Server>,
+    credential: &McpCredential,
+    name: &str,
+    arguments: &Value,
+) -> Result<(), String> {
+    let required = match name {
+        "read" | "read_attachment" | "get_links" | "history" | "diff" => McpMode::ReadOnly,
+        "edit" | "multi_edit" | "write" => McpMode::SuggestOnly,
+        "create" | "move" => McpMode::Full,
+        _ => return Ok(()),
+    };
+    credential.require_mode(required, name)?;
+
+    // Missing parameters are reported by the tools themselves
+    let Some(file_path) = arguments.get("file_path").and_then(|v| v.as_str()) else {
+        return Ok(());
+    };
+    let doc_id = server
+        .doc_resolver()
+        .resolve_path(file_path)
+        .map(|info| info.doc_id);
+    if !credential.covers(file_path, doc_id.as_deref()) {
+        return Err(out_of_scope(file_path));
+    }
+    acl_permits(server, credential, file_path, required, name)?;
+
+    // A move must also land inside the scope. The doc keeps its ID when moved.
+    if name == "move" {
+        if let Some(new_path) = arguments.get("new_path").and_then(|v| v.as_str()) {
+            let folder = match arguments.get("target_folder").and_then(|v| v.as_str()) {
+                Some(target) => target,
+                None => file_path.split('/').next().unwrap_or(""),
+            };
+            let destination = format!("{}/{}", folder, new_path.trim_start_matches('/'));
+            if !credential.covers(&destination, doc_id.as_deref()) {
+                return Err(out_of_scope(&destination));
+            }
+            acl_permits(server, credential, &destination, required, name)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the access control list allows `tool` at `path`: the path must not be
+/// denied, and the level the list grants must reach the tool's mode.
+fn acl_permits(
+    server: &Arc<Server>,
+    credential: &McpCredential,
+    path: &str,
+    required: McpMode,
+    tool: &str,
+) -> Result<(), String> {
+    match credential.acl_decision(&server.acl, path) {
+        AclDecision::Unrestricted => Ok(()),
+        AclDecision::Denied => Err(out_of_scope(path)),
+        AclDecision::Granted(authorization) if mode_for(authorization) >= required => Ok(()),
+        AclDecision::Granted(_) => Err(format!(
+            "Error: The {} tool is not permitted on {} by the access control list",
+            tool, path
+        )),
+    }
+}
+
+fn out_of_scope(path: &str) -> String {
+    format!(
+        "Error: Access denied: {} is outside the folders this credential may access",
+        path
+    )
+}
+
 /// Wrap successful tool output in MCP CallToolResult format.
 fn tool_success(text: &str) -> Value {
     json!({
@@ -298,6 +588,14 @@ fn tool_success(text: &str) -> Value {
     })
 }
 
+/// Wrap content blocks (text, image) in MCP CallToolResult format.
+fn tool_content(content: Vec<Value>) -> Value {
+    json!({
+        "content": content,
+        "isError": false
+    })
+}
+
 /// Wrap tool error in MCP CallToolResult format (tool-level error, not protocol error).
 fn tool_error(msg: &str) -> Value {
     json!({
@@ -310,3 +608,224 @@ fn tool_error(msg: &str) -> Value {
         "isError": true
     })
 }
+
+#[cfg(test)]
+mod scope_tests {
+    use super::test_helpers::*;
+    use super::*;
+    use crate::mcp::auth::{McpPrincipal, McpScope};
+
+    const DOC_UUID: &str = "uuid-lens-doc";
+    const EDU_FOLDER_UUID: &str = "bbbb0000-0000-0000-0000-000000000000";
+
+    /// A server with "Lens/Notes.md" (loaded) and "Lens Edu/Secret.md" (resolver only).
+    async fn two_folder_server() -> Arc<Server> {
+        let server = build_test_server(&[("/Notes.md", DOC_UUID, "hello world")]).await;
+        let edu = create_folder_doc(&[("/Secret.md", "uuid-edu-doc")]);
+        set_folder_name(&edu, "Lens Edu");
+        server
+            .doc_resolver()
+            .update_folder_from_doc(&format!("{}-{}", RELAY_ID, EDU_FOLDER_UUID), &edu);
+        server
+    }
+
+    fn session_for(server: &Arc<Server>, scope: McpScope, mode: McpMode) -> String {
+        let sid = setup_session_no_reads(server);
+        server.mcp_sessions.bind_credential(
+            &sid,
+            McpCredential {
+                principal: McpPrincipal::Key("test".into()),
+                scope,
+                mode,
+            },
+        );
+        sid
+    }
+
+    async fn call(server: &Arc<Server>, sid: &str, name: &str, mut args: Value) -> (bool, String) {
+        args["session_id"] = json!(sid);
+        let result = dispatch_tool(server, sid, name, &args, &CallContext::detached()).await;
+        let text = result["content"][0]["text"].as_str().unwrap().to_string();
+        (result["isError"].as_bool().unwrap(), text)
+    }
+
+    #[tokio::test]
+    async fn glob_and_grep_only_see_scoped_folders() {
+        let server = two_folder_server().await;
+        let sid = session_for(
+            &server,
+            McpScope::Folders(vec!["Lens".into()]),
+            McpMode::ReadOnly,
+        );
+
+        let (is_error, text) = call(&server, &sid, "glob", json!({"pattern": "**"})).await;
+        assert!(!is_error);
+        assert_eq!(text, "Lens/Notes.md");
+
+        let (_, text) = call(&server, &sid, "grep", json!({"pattern": "."})).await;
+        assert!(!text.contains("Secret"), "got: {}", text);
+    }
+
+    #[tokio::test]
+    async fn read_outside_scope_is_denied() {
+        let server = two_folder_server().await;
+        let sid = session_for(
+            &server,
+            McpScope::Folders(vec!["Lens".into()]),
+            McpMode::Full,
+        );
+
+        let (is_error, text) = call(
+            &server,
+            &sid,
+            "read",
+            json!({"file_path": "Lens Edu/Secret.md"}),
+        )
+        .await;
+        assert!(is_error);
+        assert!(text.contains("Access denied"), "got: {}", text);
+
+        let (is_error, _) =
+            call(&server, &sid, "read", json!({"file_path": "Lens/Notes.md"})).await;
+        assert!(!is_error);
+    }
+
+    #[tokio::test]
+    async fn read_only_credential_cannot_edit() {
+        let server = two_folder_server().await;
+        let sid = session_for(&server, McpScope::All, McpMode::ReadOnly);
+
+        call(&server, &sid, "read", json!({"file_path": "Lens/Notes.md"})).await;
+        let (is_error, text) = call(
+            &server,
+            &sid,
+            "edit",
+            json!({"file_path": "Lens/Notes.md", "old_string": "hello", "new_string": "goodbye"}),
+        )
+        .await;
+        assert!(is_error);
+        assert!(text.contains("read-only"), "got: {}", text);
+
+        let doc_id = format!("{}-{}", RELAY_ID, DOC_UUID);
+        assert_eq!(read_doc_content(&server, &doc_id), "hello world");
+    }
+
+    #[tokio::test]
+    async fn suggest_only_credential_cannot_create_or_move() {
+        let server = two_folder_server().await;
+        let sid = session_for(&server, McpScope::All, McpMode::SuggestOnly);
+
+        let (is_error, text) =
+            call(&server, &sid, "create", json!({"file_path": "Lens/New.md"})).await;
+        assert!(is_error);
+        assert!(text.contains("suggest-only"), "got: {}", text);
+
+        let (is_error, _) = call(
+            &server,
+            &sid,
+            "move",
+            json!({"file_path": "Lens/Notes.md", "new_path": "/Renamed.md"}),
+        )
+        .await;
+        assert!(is_error);
+    }
+
+    #[tokio::test]
+    async fn move_into_folder_outside_scope_is_denied() {
+        let server = two_folder_server().await;
+        let sid = session_for(
+            &server,
+            McpScope::Folders(vec!["Lens".into()]),
+            McpMode::Full,
+        );
+
+        let (is_error, text) = call(
+            &server,
+            &sid,
+            "move",
+            json!({"file_path": "Lens/Notes.md", "new_path": "/Notes.md", "target_folder": "Lens Edu"}),
+        )
+        .await;
+        assert!(is_error);
+        assert!(text.contains("Lens Edu/Notes.md"), "got: {}", text);
+    }
+
+    #[tokio::test]
+    async fn doc_scoped_credential_cannot_create() {
+        let server = two_folder_server().await;
+        let doc_id = format!("{}-{}", RELAY_ID, DOC_UUID);
+        let sid = session_for(&server, McpScope::Doc(doc_id), McpMode::Full);
+
+        let (is_error, text) =
+            call(&server, &sid, "create", json!({"file_path": "Lens/New.md"})).await;
+        assert!(is_error);
+        assert!(text.contains("Access denied"), "got: {}", text);
+
+        let (is_error, _) =
+            call(&server, &sid, "read", json!({"file_path": "Lens/Notes.md"})).await;
+        assert!(!is_error);
+    }
+
+    #[tokio::test]
+    async fn session_id_of_another_credential_is_rejected() {
+        let server = two_folder_server().await;
+        let restricted = session_for(
+            &server,
+            McpScope::Folders(vec!["Lens".into()]),
+            McpMode::ReadOnly,
+        );
+        let unrestricted = setup_session_no_reads(&server);
+
+        let args = json!({"pattern": "**", "session_id": unrestricted});
+        let result = dispatch_tool(
+            &server,
+            &restricted,
+            "glob",
+            &args,
+            &CallContext::detached(),
+        )
+        .await;
+        assert_eq!(result["isError"], json!(true));
+    }
+
+    #[tokio::test]
+    async fn access_control_list_narrows_full_credential() {
+        let server = two_folder_server().await;
+        server.acl.replace(
+            serde_json::from_value(json!({
+                "rules": [
+                    { "subject": "key:test", "path": "Lens/**", "permission": "read-only" },
+                    { "subject": "someone-else", "path": "Lens Edu/**", "permission": "full" },
+                ]
+            }))
+            .unwrap(),
+        );
+        let sid = session_for(&server, McpScope::All, McpMode::Full);
+
+        let (_, text) = call(&server, &sid, "glob", json!({"pattern": "**"})).await;
+        assert_eq!(text, "Lens/Notes.md");
+
+        let (is_error, _) =
+            call(&server, &sid, "read", json!({"file_path": "Lens/Notes.md"})).await;
+        assert!(!is_error);
+        let (is_error, text) = call(
+            &server,
+            &sid,
+            "edit",
+            json!({"file_path": "Lens/Notes.md", "old_string": "hello", "new_string": "goodbye"}),
+        )
+        .await;
+        assert!(is_error);
+        assert!(text.contains("access control list"), "got: {}", text);
+
+        let (is_error, text) = call(
+            &server,
+            &sid,
+            "read",
+            json!({"file_path": "Lens Edu/Secret.md"}),
+        )
+        .await;
+        assert!(is_error);
+        assert!(text.contains("Access denied"), "got: {}", text);
+    }
+}
@@ -0,0 +1,315 @@
+use crate::mcp::presence::cursor_at;
+use crate::server::Server;
+use serde_json::Value;
+use std::sync::Arc;
+use yrs::{GetString, ReadTxn, Text, Transact, WriteTxn};
+
+use super::critic_markup::{self, MergeResult};
+
+/// Execute the `multi_edit` tool: apply an ordered list of replacements to one
+/// document as CriticMarkup suggestions, all in one Yrs transaction or not at all.
+///
+/// Each edit is matched against the accepted view as it stands after the edits
+/// before it, so later edits may refer to text introduced by earlier ones.
+pub async fn execute(
+    server: &Arc<Server>,
+    session_id: &str,
+    arguments: &Value,
+) -> Result<String, String> {
+    let file_path = arguments
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Missing required parameter: file_path".to_string())?;
+
+    let edits = parse_edits(arguments)?;
+
+    let doc_info = server
+        .doc_resolver()
+        .resolve_path(file_path)
+        .ok_or_else(|| format!("Error: Document not found: {}", file_path))?;
+
+    // Same read-before-edit rule as `edit`
+    {
+        let session = server
+            .mcp_sessions
+            .get_session(session_id)
+            .ok_or_else(|| "Error: Session not found".to_string())?;
+        if !session.read_docs.contains_key(&doc_info.doc_id) {
+            return Err(format!(
+                "You must read this document before editing it. Call the read tool with file_path: \"{}\" first.",
+                file_path
+            ));
+        }
+    }
+
+    server
+        .ensure_doc_loaded(&doc_info.doc_id)
+        .await
+        .map_err(|e| format!("Error: Failed to load document {}: {}", file_path, e))?;
+    let presence = server
+        .mcp_presence
+        .begin(server, session_id, &doc_info.doc_id);
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    // Validate every edit up front so a bad one is reported before taking the write lock
+    let content = {
+        let doc_ref = server
+            .docs()
+            .get(&doc_info.doc_id)
+            .ok_or_else(|| format!("Error: Document data not loaded: {}", file_path))?;
+        let awareness = doc_ref.awareness();
+        let guard = awareness.read().unwrap_or_else(|e| e.into_inner());
+        let txn = guard.doc.transact();
+        match txn.get_text("contents") {
+            Some(text) => text.get_string(&txn),
+            None => return Err("Document has no content".to_string()),
+        }
+    };
+    plan_edits(&content, &edits, file_path, timestamp)?;
+
+    let (applied, cursor) = {
+        let doc_ref = server
+            .docs()
+            .get(&doc_info.doc_id)
+            .ok_or_else(|| format!("Error: Document data not loaded: {}", file_path))?;
+        let awareness = doc_ref.awareness();
+        let mut guard = awareness.write().unwrap_or_else(|e| e.into_inner());
+        let mut txn = guard.doc.transact_mut();
+        let text = txn.get_or_insert_text("contents");
+
+        // Re-plan against the text under the lock; if someone edited in the
+        // meantime the plan must still hold in full.
+        let current_raw = text.get_string(&txn);
+        let merges = plan_edits(&current_raw, &edits, file_path, timestamp).map_err(|e| {
+            format!(
+                "Document changed since last read and the edits no longer apply ({}). Please re-read and try again.",
+                e.trim_start_matches("Error: ")
+            )
+        })?;
+
+        for merge in &merges {
+            text.remove_range(&mut txn, merge.raw_offset as u32, merge.raw_len as u32);
+            text.insert(&mut txn, merge.raw_offset as u32, &merge.replacement);
+        }
+
+        server
+            .suggestion_anchors()
+            .refresh(&doc_info.doc_id, &mut txn, &text);
+        let cursor = merges.last().and_then(|merge| {
+            let end = merge.raw_offset + merge.replacement.len();
+            cursor_at(&text, &mut txn, end as u32)
+        });
+        (merges.len(), cursor)
+    };
+
+    if applied == 0 {
+        return Ok(format!("No changes needed for {}", file_path));
+    }
+    presence.set_cursor(cursor);
+
+    {
+        let doc_ref = server
+            .docs()
+            .get(&doc_info.doc_id)
+            .ok_or_else(|| format!("Error: Document data not loaded: {}", file_path))?;
+        if let Err(e) = doc_ref.sync_kv().persist().await {
+            tracing::error!("Failed to persist edit for {}: {:?}", doc_info.doc_id, e);
+        }
+    }
+
+    Ok(format!(
+        "Edited {}: applied {} of {} edits as CriticMarkup suggestions for human review.",
+        file_path,
+        applied,
+        edits.len()
+    ))
+}
+
+/// Read the `edits` array of {old_string, new_string} objects.
+fn parse_edits(arguments: &Value) -> Result<Vec<(String, String)>, String> {
+    let edits = arguments
+        .get("edits")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| "Missing required parameter: edits".to_string())?;
+    if edits.is_empty() {
+        return Err("edits must contain at least one edit".to_string());
+    }
+
+    edits
+        .iter()
+        .enumerate()
+        .map(|(i, edit)| {
+            let field = |name: &str| {
+                edit.get(name)
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+                    .ok_or_else(|| format!("Edit {}: missing required field {}", i + 1, name))
+            };
+            let old_string = field("old_string")?;
+            let new_string = field("new_string")?;
+            critic_markup::reject_if_contains_markup(&old_string, "old_string")
+                .map_err(|e| format!("Edit {}: {}", i + 1, e))?;
+            critic_markup::reject_if_contains_markup(&new_string, "new_string")
+                .map_err(|e| format!("Edit {}: {}", i + 1, e))?;
+            Ok((old_string, new_string))
+        })
+        .collect()
+}
+
+/// Run the edits in order against `raw`, returning the merges to apply in
+/// sequence. Fails on the first edit that does not match exactly once.
+/// No-op edits produce no merge.
+fn plan_edits(
+    raw: &str,
+    edits: &[(String, String)],
+    file_path: &str,
+    timestamp: u64,
+) -> Result<Vec<MergeResult>, String> {
+    let mut current = raw.to_string();
+    let mut merges = Vec::new();
+
+    for (i, (old_string, new_string)) in edits.iter().enumerate() {
+        let accepted = critic_markup::accepted_view(&critic_markup::parse(&current));
+        match accepted.matches(old_string.as_str()).count() {
+            0 => {
+                return Err(format!(
+                    "Error: Edit {}: old_string not found in {}. Make sure it matches exactly. No edits were applied.",
+                    i + 1,
+                    file_path
+                ))
+            }
+            1 => {}
+            n => {
+                return Err(format!(
+                    "Error: Edit {}: old_string is not unique in {} ({} occurrences found). Include more surrounding context to make it unique. No edits were applied.",
+                    i + 1,
+                    file_path,
+                    n
+                ))
+            }
+        }
+
+        let merge = critic_markup::merge_edit(&current, old_string, new_string, "AI", timestamp)
+            .map_err(|e| format!("Error: Edit {}: {}", i + 1, e))?;
+        if merge.raw_len == 0 && merge.replacement.is_empty() {
+            continue;
+        }
+        current.replace_range(
+            merge.raw_offset..merge.raw_offset + merge.raw_len,
+            &merge.replacement,
+        );
+        merges.push(merge);
+    }
+
+    Ok(merges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::*;
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn plan_applies_edits_in_order() {
+        let edits = vec![
+            ("alpha".to_string(), "one".to_string()),
+            ("beta".to_string(), "two".to_string()),
+        ];
+        let merges = plan_edits("alpha and beta", &edits, "Lens/A.md", 1).unwrap();
+        assert_eq!(merges.len(), 2);
+
+        let mut raw = "alpha and beta".to_string();
+        for m in &merges {
+            raw.replace_range(m.raw_offset..m.raw_offset + m.raw_len, &m.replacement);
+        }
+        let accepted = critic_markup::accepted_view(&critic_markup::parse(&raw));
+        assert_eq!(accepted, "one and two");
+    }
+
+    #[test]
+    fn plan_fails_on_ambiguous_edit() {
+        let edits = vec![
+            ("alpha".to_string(), "one".to_string()),
+            ("x".to_string(), "y".to_string()),
+        ];
+        let err = plan_edits("alpha x x", &edits, "Lens/A.md", 1).unwrap_err();
+        assert!(err.contains("Edit 2"), "got: {}", err);
+        assert!(err.contains("2 occurrences"), "got: {}", err);
+    }
+
+    #[tokio::test]
+    async fn multi_edit_applies_all_in_one_transaction() {
+        let server = build_test_server(&[("/Doc.md", "uuid-doc", "The cat sat on the mat.")]).await;
+        let doc_id = format!("{}-{}", RELAY_ID, "uuid-doc");
+        let sid = setup_session_with_read(&server, &doc_id);
+
+        let result = execute(
+            &server,
+            &sid,
+            &json!({
+                "file_path": "Lens/Doc.md",
+                "edits": [
+                    {"old_string": "cat", "new_string": "dog"},
+                    {"old_string": "mat", "new_string": "rug"}
+                ]
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(result.contains("applied 2 of 2"), "got: {}", result);
+
+        let raw = read_doc_content(&server, &doc_id);
+        let accepted = critic_markup::accepted_view(&critic_markup::parse(&raw));
+        assert_eq!(accepted, "The dog sat on the rug.");
+        assert!(raw.contains("@@cat--}"), "got: {}", raw);
+    }
+
+    #[tokio::test]
+    async fn multi_edit_applies_nothing_when_one_edit_fails() {
+        let server = build_test_server(&[("/Doc.md", "uuid-doc", "The cat sat on the mat.")]).await;
+        let doc_id = format!("{}-{}", RELAY_ID, "uuid-doc");
+        let sid = setup_session_with_read(&server, &doc_id);
+
+        let err = execute(
+            &server,
+            &sid,
+            &json!({
+                "file_path": "Lens/Doc.md",
+                "edits": [
+                    {"old_string": "cat", "new_string": "dog"},
+                    {"old_string": "hat", "new_string": "cap"}
+                ]
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("Edit 2"), "got: {}", err);
+        assert_eq!(
+            read_doc_content(&server, &doc_id),
+            "The cat sat on the mat."
+        );
+    }
+
+    #[tokio::test]
+    async fn multi_edit_requires_read_first() {
+        let server = build_test_server(&[("/Doc.md", "uuid-doc", "The cat sat on the mat.")]).await;
+        let sid = setup_session_no_reads(&server);
+
+        let err = execute(
+            &server,
+            &sid,
+            &json!({
+                "file_path": "Lens/Doc.md",
+                "edits": [{"old_string": "cat", "new_string": "dog"}]
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("must read"), "got: {}", err);
+    }
+}
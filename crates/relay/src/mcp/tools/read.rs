@@ -38,7 +38,7 @@ pub async fn execute(
         .map_err(|e| format!("Error: Failed to load document {}: {}", file_path, e))?;
 
     // Read Y.Doc content into an owned String, then drop all guards
-    let content = {
+    let (content, version) = {
         let doc_ref = server
             .docs()
             .get(&doc_info.doc_id)
@@ -46,17 +46,18 @@ pub async fn execute(
         let awareness = doc_ref.awareness();
         let guard = awareness.read().unwrap_or_else(|e| e.into_inner());
         let txn = guard.doc.transact();
-        match txn.get_text("contents") {
+        let content = match txn.get_text("contents") {
             Some(text) => text.get_string(&txn),
             None => String::new(),
-        }
+        };
+        (content, doc_version(&txn))
         // guard, awareness, doc_ref all dropped here
     };
 
     // Record this doc as read in the session (for read-before-edit enforcement)
-    if let Some(mut session) = server.mcp_sessions.get_session_mut(session_id) {
-        session.read_docs.insert(doc_info.doc_id.clone());
-    }
+    server
+        .mcp_sessions
+        .record_read(session_id, &doc_info.doc_id, version);
 
     // Parse CriticMarkup and return accepted view
     let spans = super::critic_markup::parse(&content);
@@ -72,6 +73,15 @@ pub async fn execute(
     Ok(output)
 }
 
+/// A number that grows with every change to the doc: the sum of the clocks in
+/// its state vector.
+fn doc_version<T: ReadTxn>(txn: &T) -> u64 {
+    txn.state_vector()
+        .iter()
+        .map(|(_, clock)| *clock as u64)
+        .sum()
+}
+
 /// Format content as cat -n output with 6-char right-aligned line numbers.
 fn format_cat_n(content: &str, offset: usize, limit: usize) -> String {
     // offset is 1-indexed (line number to start from), 0 means start from beginning
@@ -0,0 +1,511 @@
+use crate::mcp::auth::McpCredential;
+use crate::server::Server;
+use data_encoding::BASE64;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use y_sweet_core::doc_resolver::{DocInfo, DocumentResolver};
+use yrs::{Any, Map, Out, ReadTxn, Transact};
+
+/// Text attachments larger than this are described instead of returned.
+const MAX_TEXT_BYTES: u64 = 256 * 1024;
+
+/// Images larger than this are described instead of returned.
+const MAX_IMAGE_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Image formats MCP clients accept in image content blocks, by extension.
+const IMAGE_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+];
+
+/// Other extensions with a known MIME type.
+const OTHER_TYPES: &[(&str, &str)] = &[
+    ("txt", "text/plain"),
+    ("csv", "text/csv"),
+    ("tsv", "text/tab-separated-values"),
+    ("json", "application/json"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("xml", "application/xml"),
+    ("svg", "image/svg+xml"),
+    ("html", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("py", "text/x-python"),
+    ("pdf", "application/pdf"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Text,
+    Image,
+    Other,
+}
+
+/// Execute the `read_attachment` tool: return an embedded or attached file.
+///
+/// Text comes back inline, supported images as image content blocks, and
+/// anything else (or anything over the size limits) as a description.
+pub async fn execute(
+    server: &Arc<Server>,
+    credential: &McpCredential,
+    arguments: &Value,
+) -> Result<Vec<Value>, String> {
+    let path = arguments
+        .get("path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Missing required parameter: path".to_string())?;
+    let from = arguments.get("from").and_then(|v| v.as_str());
+
+    let resolver = server.doc_resolver();
+    let file_path = resolve(resolver, &embed_target(path), from)
+        .ok_or_else(|| format!("Error: Attachment not found: {}", path))?;
+    if !credential.can_see(resolver, &server.acl, &file_path) {
+        return Err(super::out_of_scope(&file_path));
+    }
+    let doc_info = resolver
+        .resolve_path(&file_path)
+        .ok_or_else(|| format!("Error: Attachment not found: {}", path))?;
+
+    server
+        .ensure_doc_loaded(&doc_info.folder_doc_id)
+        .await
+        .map_err(|e| format!("Error: Failed to load folder of {}: {}", file_path, e))?;
+    let (file_type, hash, mimetype) = read_filemeta(server, &doc_info, &file_path)?;
+    if file_type == "markdown" {
+        return Err(format!(
+            "Error: {} is a document, not an attachment. Use the read tool instead.",
+            file_path
+        ));
+    }
+    let hash = hash.ok_or_else(|| format!("Error: {} has no uploaded content", file_path))?;
+    let mime = mimetype.unwrap_or_else(|| mime_for(&file_path).to_string());
+
+    let unavailable = |e: anyhow::Error| format!("Error: Attachments are unavailable: {}", e);
+    let not_uploaded = || format!("Error: The content of {} has not been uploaded", file_path);
+    let size = server
+        .file_size(&doc_info.doc_id, &hash)
+        .await
+        .map_err(unavailable)?
+        .ok_or_else(not_uploaded)?;
+
+    let bytes = if max_size(classify(&mime)).is_some_and(|max| size <= max) {
+        let bytes = server
+            .file_bytes(&doc_info.doc_id, &hash)
+            .await
+            .map_err(unavailable)?
+            .ok_or_else(not_uploaded)?;
+        Some(bytes)
+    } else {
+        None
+    };
+
+    Ok(content_blocks(&file_path, &file_type, &mime, size, bytes))
+}
+
+/// The link target inside an embed or link (`![[a.png|300]]`,
+/// `![alt](images/a%20b.png)`), or the input itself if it is a plain path.
+fn embed_target(input: &str) -> String {
+    let input = input.trim();
+    let link = input.strip_prefix('!').unwrap_or(input);
+
+    if let Some(inner) = link.strip_prefix("[[").and_then(|s| s.strip_suffix("]]")) {
+        let name = inner.split(|c| c == '|' || c == '#').next().unwrap_or("");
+        return name.trim().trim_start_matches('/').to_string();
+    }
+
+    if let Some((_, url)) = link.strip_prefix('[').and_then(|s| s.split_once("](")) {
+        let url = url.strip_suffix(')').unwrap_or(url).trim();
+        // `<a b.png>` allows spaces; otherwise anything after a space is a title
+        let url = match url.strip_prefix('<') {
+            Some(rest) => rest.split('>').next().unwrap_or(rest),
+            None => url.split(' ').next().unwrap_or(url),
+        };
+        let decoded = urlencoding::decode(url)
+            .map(|s| s.into_owned())
+            .unwrap_or_else(|_| url.to_string());
+        return decoded
+            .trim_start_matches("./")
+            .trim_start_matches('/')
+            .to_string();
+    }
+
+    input.trim_start_matches('/').to_string()
+}
+
+/// Find the full path of `target`: relative to the embedding document `from`,
+/// then as a full path, then as the shortest unique form Obsidian writes
+/// (a file name or trailing part of a path), preferring `from`'s folder.
+/// Matching ignores case.
+fn resolve(resolver: &DocumentResolver, target: &str, from: Option<&str>) -> Option<String> {
+    if target.is_empty() {
+        return None;
+    }
+    let paths = resolver.all_paths();
+    let exact = |candidate: &str| {
+        paths
+            .iter()
+            .find(|p| p.eq_ignore_ascii_case(candidate))
+            .cloned()
+    };
+
+    if let Some(from) = from {
+        let dir = &from[..from.rfind('/').unwrap_or(0)];
+        if let Some(found) = exact(&join(dir, target)) {
+            return Some(found);
+        }
+    }
+    if let Some(found) = exact(target) {
+        return Some(found);
+    }
+
+    let suffix = format!("/{}", target.to_lowercase());
+    let folder = from
+        .and_then(|f| f.split('/').next())
+        .map(|f| format!("{}/", f));
+    paths
+        .iter()
+        .filter(|p| p.to_lowercase().ends_with(&suffix))
+        .min_by_key(|p| {
+            let elsewhere = folder
+                .as_ref()
+                .map_or(false, |f| !p.starts_with(f.as_str()));
+            (elsewhere, p.len(), (*p).clone())
+        })
+        .cloned()
+}
+
+/// Join a relative link onto a directory, resolving `.` and `..`.
+fn join(dir: &str, relative: &str) -> String {
+    let mut segments: Vec<&str> = dir.split('/').filter(|s| !s.is_empty()).collect();
+    for part in relative.split('/') {
+        match part {
+            ".." => {
+                segments.pop();
+            }
+            "." | "" => {}
+            _ => segments.push(part),
+        }
+    }
+    segments.join("/")
+}
+
+/// The attachment's `type`, `hash` and `mimetype` from its folder's filemeta_v0.
+fn read_filemeta(
+    server: &Server,
+    doc_info: &DocInfo,
+    file_path: &str,
+) -> Result<(String, Option<String>, Option<String>), String> {
+    let key = format!(
+        "/{}",
+        file_path
+            .strip_prefix(&format!("{}/", doc_info.folder_name))
+            .unwrap_or(file_path)
+    );
+    let awareness = server
+        .docs()
+        .get(&doc_info.folder_doc_id)
+        .ok_or_else(|| format!("Error: Folder data not loaded for {}", file_path))?
+        .awareness();
+    let guard = awareness.read().unwrap_or_else(|e| e.into_inner());
+    let txn = guard.doc.transact();
+    let entry = txn
+        .get_map("filemeta_v0")
+        .and_then(|filemeta| filemeta.get(&txn, &key))
+        .ok_or_else(|| format!("Error: Attachment not found: {}", file_path))?;
+
+    let file_type = filemeta_field(&entry, &txn, "type").unwrap_or_else(|| "file".to_string());
+    let hash = filemeta_field(&entry, &txn, "hash");
+    let mimetype = filemeta_field(&entry, &txn, "mimetype");
+    Ok((file_type, hash, mimetype))
+}
+
+/// A string field of a filemeta_v0 entry, which may be a Y.Map (server
+/// writes) or a plain object (Y.js clients).
+fn filemeta_field(entry: &Out, txn: &impl ReadTxn, field: &str) -> Option<String> {
+    match entry {
+        Out::YMap(map) => match map.get(txn, field) {
+            Some(Out::Any(Any::String(value))) => Some(value.to_string()),
+            _ => None,
+        },
+        Out::Any(Any::Map(map)) => match map.get(field) {
+            Some(Any::String(value)) => Some(value.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn mime_for(path: &str) -> &'static str {
+    let extension = path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+    IMAGE_TYPES
+        .iter()
+        .chain(OTHER_TYPES)
+        .find(|(ext, _)| extension.as_deref() == Some(*ext))
+        .map(|(_, mime)| *mime)
+        .unwrap_or("application/octet-stream")
+}
+
+fn classify(mime: &str) -> Kind {
+    if IMAGE_TYPES.iter().any(|(_, image)| *image == mime) {
+        Kind::Image
+    } else if mime.starts_with("text/")
+        || mime.ends_with("+xml")
+        || ["application/json", "application/yaml", "application/xml"].contains(&mime)
+    {
+        Kind::Text
+    } else {
+        Kind::Other
+    }
+}
+
+fn max_size(kind: Kind) -> Option<u64> {
+    match kind {
+        Kind::Text => Some(MAX_TEXT_BYTES),
+        Kind::Image => Some(MAX_IMAGE_BYTES),
+        Kind::Other => None,
+    }
+}
+
+/// Build the tool result. `bytes` is None when the content was not fetched.
+fn content_blocks(
+    path: &str,
+    file_type: &str,
+    mime: &str,
+    size: u64,
+    bytes: Option<Vec<u8>>,
+) -> Vec<Value> {
+    let kind = classify(mime);
+    let heading = format!("{} ({}, {})", path, mime, format_size(size));
+    let describe = |note: String| {
+        vec![text_block(format!(
+            "{}\nType: {}\nMIME type: {}\nSize: {}\n\n{}",
+            path,
+            file_type,
+            mime,
+            format_size(size),
+            note
+        ))]
+    };
+
+    let Some(bytes) = bytes else {
+        return describe(match max_size(kind) {
+            Some(max) => format!(
+                "Content not returned: larger than the {} limit for {}.",
+                format_size(max),
+                if kind == Kind::Image {
+                    "images"
+                } else {
+                    "text"
+                }
+            ),
+            None => "Content is not returned for this file type.".to_string(),
+        });
+    };
+
+    match kind {
+        Kind::Image => vec![
+            text_block(heading),
+            json!({
+                "type": "image",
+                "data": BASE64.encode(&bytes),
+                "mimeType": mime
+            }),
+        ],
+        Kind::Text => match String::from_utf8(bytes) {
+            Ok(text) => vec![text_block(format!("{}:\n\n{}", heading, text))],
+            Err(_) => describe("Content not returned: it is not valid UTF-8 text.".to_string()),
+        },
+        Kind::Other => describe("Content is not returned for this file type.".to_string()),
+    }
+}
+
+fn text_block(text: String) -> Value {
+    json!({ "type": "text", "text": text })
+}
+
+fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = 1024 * KB;
+    if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.1} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::*;
+    use super::*;
+    use std::collections::HashMap;
+    use y_sweet_core::doc_sync::DocWithSyncKv;
+    use yrs::WriteTxn;
+
+    /// Load the "Lens" folder doc with the given (path, uuid, type) entries,
+    /// each with an uploaded file hash, the way clients register attachments.
+    async fn load_folder(server: &Arc<Server>, entries: &[(&str, &str, &str)]) {
+        let folder_id = folder0_id();
+        let folder = DocWithSyncKv::new(&folder_id, None, || (), None)
+            .await
+            .unwrap();
+        {
+            let awareness = folder.awareness();
+            let guard = awareness.write().unwrap();
+            let mut txn = guard.doc.transact_mut();
+            let config = txn.get_or_insert_map("folder_config");
+            config.insert(&mut txn, "name", Any::String("Lens".into()));
+            let filemeta = txn.get_or_insert_map("filemeta_v0");
+            for (path, uuid, file_type) in entries {
+                let mut map = HashMap::new();
+                map.insert("id".to_string(), Any::String((*uuid).into()));
+                map.insert("type".to_string(), Any::String((*file_type).into()));
+                map.insert("hash".to_string(), Any::String("abc123".into()));
+                filemeta.insert(&mut txn, *path, Any::Map(map.into()));
+            }
+        }
+        server.docs().insert(folder_id.clone(), folder);
+        server
+            .doc_resolver()
+            .update_folder(&folder_id, server.docs());
+    }
+
+    #[test]
+    fn embed_target_strips_link_syntax() {
+        assert_eq!(embed_target("![[diagram.png|300]]"), "diagram.png");
+        assert_eq!(embed_target("[[Data/table.csv#Results]]"), "Data/table.csv");
+        assert_eq!(
+            embed_target("![Figure 1](images/cell%20wall.png \"Cell\")"),
+            "images/cell wall.png"
+        );
+        assert_eq!(embed_target("![x](<./a b.gif>)"), "a b.gif");
+        assert_eq!(
+            embed_target("Lens/images/diagram.png"),
+            "Lens/images/diagram.png"
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_prefers_relative_then_same_folder() {
+        let server = build_test_server(&[
+            ("/Notes/Page.md", "uuid-page", "![[diagram.png]]"),
+            ("/Notes/diagram.png", "uuid-near", ""),
+            ("/images/diagram.png", "uuid-far", ""),
+            ("/images/chart.png", "uuid-chart", ""),
+        ])
+        .await;
+        let resolver = server.doc_resolver();
+
+        let from = Some("Lens/Notes/Page.md");
+        assert_eq!(
+            resolve(resolver, "diagram.png", from).as_deref(),
+            Some("Lens/Notes/diagram.png")
+        );
+        assert_eq!(
+            resolve(resolver, "../images/diagram.png", from).as_deref(),
+            Some("Lens/images/diagram.png")
+        );
+        assert_eq!(
+            resolve(resolver, "Chart.PNG", from).as_deref(),
+            Some("Lens/images/chart.png")
+        );
+        assert_eq!(
+            resolve(resolver, "Lens/images/diagram.png", None).as_deref(),
+            Some("Lens/images/diagram.png")
+        );
+        assert_eq!(resolve(resolver, "missing.png", from), None);
+    }
+
+    #[tokio::test]
+    async fn documents_are_sent_to_the_read_tool() {
+        let server = build_test_server(&[("/Doc.md", "uuid-doc", "text")]).await;
+        load_folder(&server, &[("/Doc.md", "uuid-doc", "markdown")]).await;
+        let err = execute(
+            &server,
+            &McpCredential::shared_key(),
+            &json!({"path": "[[Doc.md]]"}),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("Use the read tool"), "got: {}", err);
+    }
+
+    #[tokio::test]
+    async fn attachments_need_a_store() {
+        let server = build_test_server(&[]).await;
+        load_folder(&server, &[("/slides.pdf", "uuid-pdf", "pdf")]).await;
+        let err = execute(
+            &server,
+            &McpCredential::shared_key(),
+            &json!({"path": "![[slides.pdf]]"}),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("Attachments are unavailable"), "got: {}", err);
+    }
+
+    #[test]
+    fn text_and_images_are_returned_inline() {
+        let blocks = content_blocks(
+            "Lens/data.csv",
+            "file",
+            "text/csv",
+            7,
+            Some(b"a,b\n1,2".to_vec()),
+        );
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(
+            blocks[0]["text"],
+            "Lens/data.csv (text/csv, 7 bytes):\n\na,b\n1,2"
+        );
+
+        let blocks = content_blocks("Lens/a.png", "image", "image/png", 3, Some(vec![1, 2, 3]));
+        assert_eq!(blocks[1]["type"], "image");
+        assert_eq!(blocks[1]["data"], "AQID");
+        assert_eq!(blocks[1]["mimeType"], "image/png");
+    }
+
+    #[test]
+    fn other_files_and_large_files_are_described() {
+        let blocks = content_blocks("Lens/slides.pdf", "pdf", "application/pdf", 2048, None);
+        let text = blocks[0]["text"].as_str().unwrap();
+        assert!(text.contains("Size: 2.0 KB"), "{}", text);
+        assert!(text.contains("not returned for this file type"), "{}", text);
+
+        let blocks = content_blocks("Lens/big.csv", "file", "text/csv", MAX_TEXT_BYTES + 1, None);
+        let text = blocks[0]["text"].as_str().unwrap();
+        assert!(
+            text.contains("larger than the 256.0 KB limit for text"),
+            "{}",
+            text
+        );
+
+        let blocks = content_blocks(
+            "Lens/odd.txt",
+            "file",
+            "text/plain",
+            2,
+            Some(vec![0xff, 0xfe]),
+        );
+        assert!(blocks[0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("not valid UTF-8"));
+    }
+
+    #[test]
+    fn mime_types_come_from_extensions() {
+        assert_eq!(mime_for("Lens/a.JPG"), "image/jpeg");
+        assert_eq!(mime_for("Lens/data.csv"), "text/csv");
+        assert_eq!(mime_for("Lens/blob"), "application/octet-stream");
+        assert_eq!(classify("image/svg+xml"), Kind::Text);
+        assert_eq!(classify("application/pdf"), Kind::Other);
+    }
+}
@@ -75,9 +75,7 @@ pub(crate) fn setup_session_with_read(server: &Arc<Server>, doc_id: &str) -> Str
         .mcp_sessions
         .create_session("2025-03-26".into(), None);
     server.mcp_sessions.mark_initialized(&sid);
-    if let Some(mut session) = server.mcp_sessions.get_session_mut(&sid) {
-        session.read_docs.insert(doc_id.to_string());
-    }
+    server.mcp_sessions.record_read(&sid, doc_id, 0);
     sid
 }
 
@@ -0,0 +1,306 @@
+use crate::mcp::presence::cursor_at;
+use crate::server::Server;
+use serde_json::Value;
+use similar::{ChangeTag, TextDiff};
+use std::sync::Arc;
+use yrs::{GetString, Text, Transact, WriteTxn};
+
+use super::critic_diff::smart_critic_markup;
+use super::critic_markup::{self, MergeResult, Span};
+
+/// Execute the `write` tool: replace a document's whole content, recorded as
+/// word-level CriticMarkup suggestions against the accepted view.
+///
+/// Only the changed stretches of the Y.Text are touched, so collaborators'
+/// cursors and concurrent edits elsewhere in the document survive.
+pub async fn execute(
+    server: &Arc<Server>,
+    session_id: &str,
+    arguments: &Value,
+) -> Result<String, String> {
+    let file_path = arguments
+        .get("file_path")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Missing required parameter: file_path".to_string())?;
+
+    let content = arguments
+        .get("content")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Missing required parameter: content".to_string())?;
+
+    critic_markup::reject_if_contains_markup(content, "content")?;
+
+    let doc_info = server
+        .doc_resolver()
+        .resolve_path(file_path)
+        .ok_or_else(|| format!("Error: Document not found: {}", file_path))?;
+
+    let author = {
+        let session = server
+            .mcp_sessions
+            .get_session(session_id)
+            .ok_or_else(|| "Error: Session not found".to_string())?;
+        if !session.read_docs.contains_key(&doc_info.doc_id) {
+            return Err(format!(
+                "You must read this document before editing it. Call the read tool with file_path: \"{}\" first.",
+                file_path
+            ));
+        }
+        session.credential.suggestion_author()
+    };
+
+    server
+        .ensure_doc_loaded(&doc_info.doc_id)
+        .await
+        .map_err(|e| format!("Error: Failed to load document {}: {}", file_path, e))?;
+    let presence = server
+        .mcp_presence
+        .begin(server, session_id, &doc_info.doc_id);
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    let (changed, cursor) = {
+        let doc_ref = server
+            .docs()
+            .get(&doc_info.doc_id)
+            .ok_or_else(|| format!("Error: Document data not loaded: {}", file_path))?;
+        let awareness = doc_ref.awareness();
+        let mut guard = awareness.write().unwrap_or_else(|e| e.into_inner());
+        let mut txn = guard.doc.transact_mut();
+        let text = txn.get_or_insert_text("contents");
+
+        let raw = text.get_string(&txn);
+        let ops = plan_rewrite(&raw, content, &author, timestamp)?;
+        for op in &ops {
+            text.remove_range(&mut txn, op.raw_offset as u32, op.raw_len as u32);
+            text.insert(&mut txn, op.raw_offset as u32, &op.replacement);
+        }
+
+        server
+            .suggestion_anchors()
+            .refresh(&doc_info.doc_id, &mut txn, &text);
+        let cursor = ops.last().and_then(|op| {
+            let end = op.raw_offset + op.replacement.len();
+            cursor_at(&text, &mut txn, end as u32)
+        });
+        (!ops.is_empty(), cursor)
+    };
+
+    if !changed {
+        return Ok(format!("No changes needed for {}", file_path));
+    }
+    presence.set_cursor(cursor);
+
+    {
+        let doc_ref = server
+            .docs()
+            .get(&doc_info.doc_id)
+            .ok_or_else(|| format!("Error: Document data not loaded: {}", file_path))?;
+        if let Err(e) = doc_ref.sync_kv().persist().await {
+            tracing::error!("Failed to persist write for {}: {:?}", doc_info.doc_id, e);
+        }
+    }
+
+    Ok(format!(
+        "Wrote {}: changes recorded as CriticMarkup suggestions for human review.",
+        file_path
+    ))
+}
+
+/// Work out the Y.Text operations that turn `raw` into a document whose
+/// accepted view is `new_content`, with every change wrapped in CriticMarkup.
+///
+/// Without pending suggestions the accepted view is the raw text, and the
+/// result is `smart_critic_markup` over the whole document. Otherwise the
+/// rewrite goes through `merge_edit` so existing suggestions are kept intact
+/// where the new content leaves them alone.
+///
+/// The returned operations are ordered from the end of the document to the
+/// start, so each offset is still valid once the ones before it are applied.
+fn plan_rewrite(
+    raw: &str,
+    new_content: &str,
+    author: &str,
+    timestamp: u64,
+) -> Result<Vec<MergeResult>, String> {
+    let spans = critic_markup::parse(raw);
+    let accepted = critic_markup::accepted_view(&spans);
+    if accepted == new_content {
+        return Ok(Vec::new());
+    }
+
+    let has_suggestions = spans.iter().any(|s| matches!(s, Span::Suggestion { .. }));
+    let rewritten = if !has_suggestions || accepted.is_empty() {
+        let meta = critic_markup::meta_prefix(author, timestamp);
+        let markup = smart_critic_markup(&accepted, new_content, Some(&meta));
+        if has_suggestions {
+            // merge_edit needs accepted text to anchor on. With none left, every
+            // pending suggestion is a deletion, so the new text goes after them.
+            format!("{}{}", raw, markup)
+        } else {
+            markup
+        }
+    } else {
+        let merge = critic_markup::merge_edit(raw, &accepted, new_content, author, timestamp)
+            .map_err(|e| format!("Error: {}", e))?;
+        let mut out = raw.to_string();
+        out.replace_range(
+            merge.raw_offset..merge.raw_offset + merge.raw_len,
+            &merge.replacement,
+        );
+        out
+    };
+
+    Ok(minimal_ops(raw, &rewritten))
+}
+
+/// Byte-level replacements taking `old` to `new`, last one first.
+fn minimal_ops(old: &str, new: &str) -> Vec<MergeResult> {
+    let diff = TextDiff::from_words(old, new);
+    let mut ops: Vec<MergeResult> = Vec::new();
+    let mut pos = 0;
+    let mut pending: Option<MergeResult> = None;
+
+    for change in diff.iter_all_changes() {
+        let value = change.value();
+        match change.tag() {
+            ChangeTag::Equal => {
+                ops.extend(pending.take());
+                pos += value.len();
+            }
+            ChangeTag::Delete => {
+                pending
+                    .get_or_insert_with(|| MergeResult {
+                        raw_offset: pos,
+                        raw_len: 0,
+                        replacement: String::new(),
+                    })
+                    .raw_len += value.len();
+                pos += value.len();
+            }
+            ChangeTag::Insert => {
+                pending
+                    .get_or_insert_with(|| MergeResult {
+                        raw_offset: pos,
+                        raw_len: 0,
+                        replacement: String::new(),
+                    })
+                    .replacement
+                    .push_str(value);
+            }
+        }
+    }
+    ops.extend(pending);
+    ops.reverse();
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_helpers::*;
+    use super::*;
+    use serde_json::json;
+
+    fn apply(raw: &str, ops: &[MergeResult]) -> String {
+        let mut out = raw.to_string();
+        for op in ops {
+            out.replace_range(op.raw_offset..op.raw_offset + op.raw_len, &op.replacement);
+        }
+        out
+    }
+
+    #[test]
+    fn rewrite_wraps_only_changed_words() {
+        let raw = "The quick brown fox jumps.\nSecond line stays.";
+        let new = "The slow brown fox jumps.\nSecond line stays.";
+        let ops = plan_rewrite(raw, new, "AI", 1).unwrap();
+        let out = apply(raw, &ops);
+
+        let spans = critic_markup::parse(&out);
+        assert_eq!(critic_markup::accepted_view(&spans), new);
+        assert_eq!(critic_markup::base_view(&spans), raw);
+        assert!(
+            out.ends_with("brown fox jumps.\nSecond line stays."),
+            "got: {}",
+            out
+        );
+        assert!(ops.iter().all(|op| op.raw_offset >= 4), "ops: {:?}", ops);
+    }
+
+    #[test]
+    fn rewrite_keeps_existing_suggestions_elsewhere() {
+        let raw = r#"Alpha {--{"author":"Human","timestamp":1}@@beta--}{++{"author":"Human","timestamp":1}@@gamma++} delta."#;
+        let ops = plan_rewrite(raw, "Alpha gamma epsilon.", "AI", 2).unwrap();
+        let out = apply(raw, &ops);
+
+        let spans = critic_markup::parse(&out);
+        assert_eq!(critic_markup::accepted_view(&spans), "Alpha gamma epsilon.");
+        assert!(out.contains(r#""author":"Human""#), "got: {}", out);
+        assert!(out.contains(r#""author":"AI""#), "got: {}", out);
+    }
+
+    #[test]
+    fn rewrite_to_same_content_is_a_no_op() {
+        assert!(plan_rewrite("same text", "same text", "AI", 1)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn write_records_suggestions_with_session_author() {
+        let server = build_test_server(&[("/Doc.md", "uuid-doc", "The cat sat on the mat.")]).await;
+        let doc_id = format!("{}-{}", RELAY_ID, "uuid-doc");
+        let sid = setup_session_with_read(&server, &doc_id);
+
+        execute(
+            &server,
+            &sid,
+            &json!({"file_path": "Lens/Doc.md", "content": "The dog sat on the mat."}),
+        )
+        .await
+        .unwrap();
+
+        let raw = read_doc_content(&server, &doc_id);
+        let spans = critic_markup::parse(&raw);
+        assert_eq!(
+            critic_markup::accepted_view(&spans),
+            "The dog sat on the mat."
+        );
+        assert_eq!(critic_markup::base_view(&spans), "The cat sat on the mat.");
+        assert!(raw.contains(r#""author":"AI""#), "got: {}", raw);
+    }
+
+    #[tokio::test]
+    async fn write_requires_read_first() {
+        let server = build_test_server(&[("/Doc.md", "uuid-doc", "text")]).await;
+        let sid = setup_session_no_reads(&server);
+
+        let err = execute(
+            &server,
+            &sid,
+            &json!({"file_path": "Lens/Doc.md", "content": "new text"}),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("must read"), "got: {}", err);
+    }
+
+    #[tokio::test]
+    async fn write_rejects_markup_in_content() {
+        let server = build_test_server(&[("/Doc.md", "uuid-doc", "text")]).await;
+        let doc_id = format!("{}-{}", RELAY_ID, "uuid-doc");
+        let sid = setup_session_with_read(&server, &doc_id);
+
+        let result = execute(
+            &server,
+            &sid,
+            &json!({"file_path": "Lens/Doc.md", "content": "{++sneaky++}"}),
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(read_doc_content(&server, &doc_id), "text");
+    }
+}
@@ -2,21 +2,31 @@ use axum::{
     extract::{Path, State},
     http::{HeaderMap, HeaderValue, StatusCode},
     middleware::Next,
-    response::{IntoResponse, Response},
-    Json,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Extension, Json,
 };
+use futures::Stream;
 use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::convert::Infallible;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::debug;
 
+use super::auth::McpCredential;
 use super::jsonrpc::{self, parse_message, JsonRpcMessage, JsonRpcResponse, PARSE_ERROR};
 use super::router;
+use super::session::{self, SessionManager};
 use crate::server::Server;
 
-/// Middleware that validates Bearer token auth for MCP endpoints.
+/// Middleware that resolves the Bearer token to an MCP credential and stores it
+/// in the request extensions for the handlers.
 pub async fn mcp_auth_middleware(
-    State(expected_key): State<String>,
-    req: axum::extract::Request,
+    State(server): State<Arc<Server>>,
+    mut req: axum::extract::Request,
     next: Next,
 ) -> Response {
     let auth_header = req
@@ -24,22 +34,26 @@ pub async fn mcp_auth_middleware(
         .get("authorization")
         .and_then(|v| v.to_str().ok());
 
-    match auth_header {
+    let credential = match auth_header {
         Some(value) if value.starts_with("Bearer ") => {
-            let token = &value["Bearer ".len()..];
-            if token == expected_key {
-                next.run(req).await
-            } else {
-                StatusCode::UNAUTHORIZED.into_response()
-            }
+            server.resolve_mcp_credential(&value["Bearer ".len()..])
+        }
+        _ => None,
+    };
+
+    match credential {
+        Some(credential) => {
+            req.extensions_mut().insert(credential);
+            next.run(req).await
         }
-        _ => StatusCode::UNAUTHORIZED.into_response(),
+        None => StatusCode::UNAUTHORIZED.into_response(),
     }
 }
 
 /// Handle POST /mcp — JSON-RPC messages (requests and notifications).
 pub async fn handle_mcp_post(
     State(server): State<Arc<Server>>,
+    Extension(credential): Extension<McpCredential>,
     headers: HeaderMap,
     body: String,
 ) -> Response {
@@ -78,6 +92,18 @@ pub async fn handle_mcp_post(
     let session_id = extract_session_id(&headers);
     let sessions = &server.mcp_sessions;
 
+    // A session may only be used with the credential that opened it
+    if let Some(ref sid) = session_id {
+        if sessions.get_session(sid).is_some() && !sessions.credential_matches(sid, &credential) {
+            debug!(
+                session_id = %sid,
+                principal = %credential.principal,
+                "MCP credential does not match session"
+            );
+            return session_not_found(value.get("id").cloned().unwrap_or(Value::Null));
+        }
+    }
+
     match message {
         JsonRpcMessage::Notification(notif) => {
             debug!(method = %notif.method, "MCP notification received");
@@ -91,6 +117,10 @@ pub async fn handle_mcp_post(
                 // Initialize does not require an existing session
                 let (resp, new_session_id) = router::dispatch_request(&server, None, &req).await;
 
+                if let Some(ref sid) = new_session_id {
+                    sessions.bind_credential(sid, credential);
+                }
+
                 let mut response = (StatusCode::OK, Json(resp)).into_response();
 
                 if let Some(sid) = new_session_id {
@@ -122,35 +152,121 @@ pub async fn handle_mcp_post(
 
                 // Verify session exists
                 if sessions.get_session(&sid).is_none() {
-                    return (
-                        StatusCode::NOT_FOUND,
-                        Json(json!({
-                            "jsonrpc": "2.0",
-                            "id": req.id,
-                            "error": {
-                                "code": -32600,
-                                "message": "Session not found"
-                            }
-                        })),
-                    )
-                        .into_response();
+                    return session_not_found(req.id.clone());
                 }
 
                 let (resp, _) = router::dispatch_request(&server, Some(&sid), &req).await;
 
+                // Clients that accept SSE get queued notifications (e.g. resource
+                // updates) ahead of the response on the same stream.
+                if accepts_event_stream(&headers) {
+                    let pending = sessions.drain_notifications(&sid);
+                    if !pending.is_empty() {
+                        return event_stream_response(&pending, &resp);
+                    }
+                }
+
                 (StatusCode::OK, Json(resp)).into_response()
             }
         }
     }
 }
 
-/// Handle GET /mcp — SSE transport (not yet implemented).
-pub async fn handle_mcp_get() -> impl IntoResponse {
-    (StatusCode::METHOD_NOT_ALLOWED, "SSE not supported yet")
+/// How long an idle SSE stream waits before re-checking that its session
+/// still exists. Keep-alive comments are sent independently by axum.
+const STREAM_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Handle GET /mcp — SSE stream for server-initiated messages (resource
+/// updates, progress). Resumes after `Last-Event-ID` when the client sends one.
+pub async fn handle_mcp_get(
+    State(server): State<Arc<Server>>,
+    Extension(credential): Extension<McpCredential>,
+    headers: HeaderMap,
+) -> Response {
+    if !accepts_event_stream(&headers) {
+        return (
+            StatusCode::NOT_ACCEPTABLE,
+            "GET /mcp requires Accept: text/event-stream",
+        )
+            .into_response();
+    }
+
+    let session_id = match extract_session_id(&headers) {
+        Some(sid) => sid,
+        None => return (StatusCode::BAD_REQUEST, "Missing mcp-session-id header").into_response(),
+    };
+    if !server
+        .mcp_sessions
+        .credential_matches(&session_id, &credential)
+    {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    // IDs from another session (or garbage) are ignored rather than rejected
+    let resume_after = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|id| session::parse_event_id(&session_id, id));
+
+    debug!(session_id = %session_id, ?resume_after, "MCP SSE stream opened");
+
+    let stream = event_stream(server.mcp_sessions.clone(), session_id, resume_after);
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+struct StreamState {
+    sessions: Arc<SessionManager>,
+    session_id: String,
+    /// Replay position for the first fetch only; afterwards the session's
+    /// delivered cursor is used so events are not sent twice.
+    resume_after: Option<u64>,
+    buffered: VecDeque<(u64, Value)>,
+}
+
+/// Stream the session's events as SSE until the session goes away.
+fn event_stream(
+    sessions: Arc<SessionManager>,
+    session_id: String,
+    resume_after: Option<u64>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let state = StreamState {
+        sessions,
+        session_id,
+        resume_after,
+        buffered: VecDeque::new(),
+    };
+
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some((seq, message)) = state.buffered.pop_front() {
+                let event = Event::default()
+                    .id(session::event_id(&state.session_id, seq))
+                    .event("message")
+                    .data(message.to_string());
+                return Some((Ok(event), state));
+            }
+
+            let notify = state.sessions.event_notifier(&state.session_id)?;
+            let events = state
+                .sessions
+                .events_after(&state.session_id, state.resume_after.take())?;
+            if events.is_empty() {
+                let _ = tokio::time::timeout(STREAM_POLL_INTERVAL, notify.notified()).await;
+            } else {
+                state.buffered.extend(events);
+            }
+        }
+    })
 }
 
 /// Handle DELETE /mcp — session termination.
-pub async fn handle_mcp_delete(State(server): State<Arc<Server>>, headers: HeaderMap) -> Response {
+pub async fn handle_mcp_delete(
+    State(server): State<Arc<Server>>,
+    Extension(credential): Extension<McpCredential>,
+    headers: HeaderMap,
+) -> Response {
     let session_id = match extract_session_id(&headers) {
         Some(sid) => sid,
         None => {
@@ -159,6 +275,9 @@ pub async fn handle_mcp_delete(State(server): State<Arc<Server>>, headers: Heade
     };
 
     let sessions = &server.mcp_sessions;
+    if !sessions.credential_matches(&session_id, &credential) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
     if sessions.remove_session(&session_id) {
         debug!(session_id = %session_id, "MCP session deleted");
         StatusCode::OK.into_response()
@@ -169,12 +288,12 @@ pub async fn handle_mcp_delete(State(server): State<Arc<Server>>, headers: Heade
 
 // --- Path-key variants: /mcp/:key validates key from URL path ---
 
-/// Validate the API key from the URL path. Returns 401 on mismatch.
-fn validate_path_key(server: &Server, key: &str) -> Option<Response> {
-    match &server.mcp_api_key {
-        Some(expected) if key == expected => None,
-        _ => Some(StatusCode::UNAUTHORIZED.into_response()),
-    }
+/// Resolve the key from the URL path to a credential, or a 401 response.
+/// Only MCP keys are taken in the path, never relay tokens or API keys.
+fn validate_path_key(server: &Server, key: &str) -> Result<McpCredential, Response> {
+    server
+        .resolve_mcp_path_key(key)
+        .ok_or_else(|| StatusCode::UNAUTHORIZED.into_response())
 }
 
 /// Handle POST /mcp/:key — same as handle_mcp_post but auth via URL path.
@@ -184,21 +303,24 @@ pub async fn handle_mcp_post_with_key(
     headers: HeaderMap,
     body: String,
 ) -> Response {
-    if let Some(err) = validate_path_key(&server, &key) {
-        return err;
-    }
-    handle_mcp_post(State(server), headers, body).await
+    let credential = match validate_path_key(&server, &key) {
+        Ok(credential) => credential,
+        Err(err) => return err,
+    };
+    handle_mcp_post(State(server), Extension(credential), headers, body).await
 }
 
 /// Handle GET /mcp/:key
 pub async fn handle_mcp_get_with_key(
     State(server): State<Arc<Server>>,
     Path(key): Path<String>,
+    headers: HeaderMap,
 ) -> Response {
-    if let Some(err) = validate_path_key(&server, &key) {
-        return err;
-    }
-    handle_mcp_get().await.into_response()
+    let credential = match validate_path_key(&server, &key) {
+        Ok(credential) => credential,
+        Err(err) => return err,
+    };
+    handle_mcp_get(State(server), Extension(credential), headers).await
 }
 
 /// Handle DELETE /mcp/:key
@@ -207,10 +329,50 @@ pub async fn handle_mcp_delete_with_key(
     Path(key): Path<String>,
     headers: HeaderMap,
 ) -> Response {
-    if let Some(err) = validate_path_key(&server, &key) {
-        return err;
+    let credential = match validate_path_key(&server, &key) {
+        Ok(credential) => credential,
+        Err(err) => return err,
+    };
+    handle_mcp_delete(State(server), Extension(credential), headers).await
+}
+
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get("accept")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"))
+}
+
+/// Render notifications followed by the response as a complete SSE body.
+fn event_stream_response(notifications: &[Value], resp: &JsonRpcResponse) -> Response {
+    let mut body = String::new();
+    for notification in notifications {
+        body.push_str(&format!("event: message\ndata: {}\n\n", notification));
     }
-    handle_mcp_delete(State(server), headers).await
+    let resp = serde_json::to_string(resp).unwrap_or_default();
+    body.push_str(&format!("event: message\ndata: {}\n\n", resp));
+
+    (
+        StatusCode::OK,
+        [(axum::http::header::CONTENT_TYPE, "text/event-stream")],
+        body,
+    )
+        .into_response()
+}
+
+fn session_not_found(id: Value) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {
+                "code": -32600,
+                "message": "Session not found"
+            }
+        })),
+    )
+        .into_response()
 }
 
 /// Extract the mcp-session-id from request headers.
@@ -220,3 +382,68 @@ fn extract_session_id(headers: &HeaderMap) -> Option<String> {
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn event_stream_puts_notifications_before_response() {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/resources/updated",
+            "params": {"uri": "relay:///Lens/A.md"}
+        });
+        let resp = jsonrpc::success_response(json!(7), json!({}));
+        let response = event_stream_response(&[notification], &resp);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "text/event-stream"
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let events: Vec<&str> = body.split("\n\n").filter(|e| !e.is_empty()).collect();
+        assert_eq!(events.len(), 2);
+        assert!(events[0].contains("notifications/resources/updated"));
+        assert!(events[1].contains("\"id\":7"));
+    }
+
+    #[test]
+    fn accept_header_detection() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepts_event_stream(&headers));
+        headers.insert(
+            "accept",
+            HeaderValue::from_static("application/json, text/event-stream"),
+        );
+        assert!(accepts_event_stream(&headers));
+    }
+
+    #[tokio::test]
+    async fn event_stream_resumes_after_last_event_id() {
+        use futures::StreamExt;
+
+        let sessions = Arc::new(SessionManager::new());
+        let sid = sessions.create_session("2025-03-26".into(), None);
+        for n in 1..=3 {
+            sessions.push_notification(&sid, json!({ "n": n }));
+        }
+
+        // Replay everything after event 1, then pick up a new event live
+        let stream = event_stream(sessions.clone(), sid.clone(), Some(1));
+        tokio::pin!(stream);
+        assert!(stream.next().await.is_some());
+        assert!(stream.next().await.is_some());
+        sessions.push_notification(&sid, json!({ "n": 4 }));
+        assert!(stream.next().await.is_some());
+        assert!(sessions.drain_notifications(&sid).is_empty());
+
+        // Removing the session ends the stream
+        sessions.remove_session(&sid);
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -0,0 +1,659 @@
+//! Browser login against an OIDC or OAuth2 provider.
+//!
+//! The relay runs the authorization-code flow (with PKCE) itself:
+//!
+//! 1. `GET /auth/login?return_to=/path` redirects to the provider.
+//! 2. The provider sends the browser back to `GET /auth/callback`, where the
+//!    code is exchanged for an access token and the user's claims are read
+//!    from the userinfo endpoint.
+//! 3. The user gets a login session, held in memory and identified by the
+//!    `relay_session` cookie (or as a bearer token for non-browser clients).
+//! 4. `POST /auth/token` turns the session into a short-lived CWT doc or
+//!    prefix token, at the highest level the configured grants give the
+//!    user's groups. Tokens name the user, so the access control list applies.
+//!
+//! Groups come from the provider's groups claim, if configured, and from the
+//! groups in the access control list.
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use dashmap::DashMap;
+use data_encoding::BASE64URL_NOPAD;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+use y_sweet_core::api_types::Authorization;
+use y_sweet_core::auth::{Authenticator, ExpirationTimeEpochMillis};
+use y_sweet_core::config::OidcConfig;
+
+use crate::acl::{rank, Acl};
+use crate::server::Server;
+
+pub const SESSION_COOKIE: &str = "relay_session";
+
+/// How long the user has to finish signing in at the provider.
+const LOGIN_TIMEOUT_MILLIS: u64 = 10 * 60 * 1000;
+
+/// Timeout for calls to the provider's token and userinfo endpoints.
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginSession {
+    pub user: String,
+    pub groups: Vec<String>,
+    pub expires_at: u64,
+}
+
+/// A login started by `begin` and waiting for the provider's callback.
+struct PendingLogin {
+    code_verifier: String,
+    return_to: Option<String>,
+    expires_at: u64,
+}
+
+/// What a minted token is for.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenTarget {
+    DocId(String),
+    Prefix(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MintedToken {
+    pub token: String,
+    pub authorization: Authorization,
+    pub expires_at: u64,
+}
+
+pub struct OidcLogin {
+    config: OidcConfig,
+    client_secret: Option<String>,
+    http: reqwest::Client,
+    pending: DashMap<String, PendingLogin>,
+    sessions: DashMap<String, LoginSession>,
+}
+
+impl OidcLogin {
+    pub fn new(config: OidcConfig) -> anyhow::Result<Self> {
+        let client_secret = config
+            .client_secret
+            .clone()
+            .or_else(|| std::env::var("RELAY_OIDC_CLIENT_SECRET").ok());
+        let http = reqwest::Client::builder()
+            .timeout(PROVIDER_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            config,
+            client_secret,
+            http,
+            pending: DashMap::new(),
+            sessions: DashMap::new(),
+        })
+    }
+
+    /// Start a login. Returns the provider URL to send the browser to.
+    /// `return_to` must be a path on this server; anything else is dropped.
+    pub fn begin(&self, return_to: Option<String>) -> anyhow::Result<String> {
+        let now = now_millis();
+        self.pending.retain(|_, login| login.expires_at > now);
+
+        let state = nanoid::nanoid!(32);
+        let code_verifier = nanoid::nanoid!(64);
+        let code_challenge = BASE64URL_NOPAD.encode(&Sha256::digest(code_verifier.as_bytes()));
+
+        let mut url = Url::parse(&self.config.authorization_endpoint)?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_url)
+            .append_pair("scope", &self.config.scopes.join(" "))
+            .append_pair("state", &state)
+            .append_pair("code_challenge", &code_challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        self.pending.insert(
+            state,
+            PendingLogin {
+                code_verifier,
+                return_to: return_to.filter(|path| is_local_path(path)),
+                expires_at: now + LOGIN_TIMEOUT_MILLIS,
+            },
+        );
+        Ok(url.into())
+    }
+
+    /// Finish a login from the provider's callback. Returns the new session's
+    /// ID, the session, and where to send the browser.
+    pub async fn complete(
+        &self,
+        code: &str,
+        state: &str,
+        acl: &Acl,
+    ) -> anyhow::Result<(String, LoginSession, Option<String>)> {
+        let (_, pending) = self
+            .pending
+            .remove(state)
+            .ok_or_else(|| anyhow::anyhow!("Unknown or expired login"))?;
+        if pending.expires_at <= now_millis() {
+            anyhow::bail!("Unknown or expired login");
+        }
+
+        let access_token = self.exchange_code(code, &pending.code_verifier).await?;
+        let claims = self.userinfo(&access_token).await?;
+
+        let user = match claims.get(&self.config.user_claim) {
+            Some(Value::String(user)) if !user.is_empty() => user.clone(),
+            Some(Value::Number(user)) => user.to_string(),
+            _ => anyhow::bail!(
+                "Provider did not return the '{}' claim",
+                self.config.user_claim
+            ),
+        };
+        let mut groups = match self
+            .config
+            .groups_claim
+            .as_ref()
+            .and_then(|claim| claims.get(claim))
+        {
+            Some(Value::Array(values)) => values
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            Some(Value::String(group)) => vec![group.clone()],
+            _ => Vec::new(),
+        };
+        for group in acl.groups_of(&user) {
+            if !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+
+        let now = now_millis();
+        self.sessions.retain(|_, session| session.expires_at > now);
+        let session = LoginSession {
+            user,
+            groups,
+            expires_at: now + self.config.session_seconds * 1000,
+        };
+        let session_id = nanoid::nanoid!(43);
+        self.sessions.insert(session_id.clone(), session.clone());
+        Ok((session_id, session, pending.return_to))
+    }
+
+    async fn exchange_code(&self, code: &str, code_verifier: &str) -> anyhow::Result<String> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_url.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &self.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        let response = self
+            .http
+            .post(&self.config.token_endpoint)
+            .header(header::ACCEPT, "application/json")
+            .form(&form)
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            anyhow::bail!(
+                "Token endpoint returned {}: {}",
+                status,
+                String::from_utf8_lossy(&body)
+            );
+        }
+        let body: Value = serde_json::from_slice(&body)?;
+        body.get("access_token")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("Token endpoint returned no access_token"))
+    }
+
+    async fn userinfo(&self, access_token: &str) -> anyhow::Result<Value> {
+        let response = self
+            .http
+            .get(&self.config.userinfo_endpoint)
+            .bearer_auth(access_token)
+            .header(header::ACCEPT, "application/json")
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            anyhow::bail!("Userinfo endpoint returned {}", status);
+        }
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    pub fn session(&self, session_id: &str) -> Option<LoginSession> {
+        let session = self.sessions.get(session_id)?.clone();
+        if session.expires_at <= now_millis() {
+            self.sessions.remove(session_id);
+            return None;
+        }
+        Some(session)
+    }
+
+    pub fn end(&self, session_id: &str) {
+        self.sessions.remove(session_id);
+    }
+
+    /// The highest level the grants give `session` on a doc ID or prefix.
+    pub fn authorization_for(&self, session: &LoginSession, target: &str) -> Option<Authorization> {
+        self.config
+            .grants
+            .iter()
+            .filter(|grant| grant.group == "*" || session.groups.contains(&grant.group))
+            .filter(|grant| target.starts_with(&grant.prefix))
+            .map(|grant| grant.authorization)
+            .max_by_key(|authorization| rank(*authorization))
+    }
+
+    pub fn mint(
+        &self,
+        authenticator: &Authenticator,
+        session: &LoginSession,
+        target: &TokenTarget,
+    ) -> anyhow::Result<Option<MintedToken>> {
+        let resource = match target {
+            TokenTarget::DocId(doc_id) => doc_id,
+            TokenTarget::Prefix(prefix) => prefix,
+        };
+        let Some(authorization) = self.authorization_for(session, resource) else {
+            return Ok(None);
+        };
+        // Never outlive the session the token was minted from
+        let expires_at = (now_millis() + self.config.token_seconds * 1000).min(session.expires_at);
+        let expiration = ExpirationTimeEpochMillis(expires_at);
+        let token = match target {
+            TokenTarget::DocId(doc_id) => authenticator.gen_doc_token_cwt(
+                doc_id,
+                authorization,
+                expiration,
+                Some(&session.user),
+                None,
+            )?,
+            TokenTarget::Prefix(prefix) => authenticator.gen_prefix_token_cwt(
+                prefix,
+                authorization,
+                expiration,
+                Some(&session.user),
+            )?,
+        };
+        Ok(Some(MintedToken {
+            token,
+            authorization,
+            expires_at,
+        }))
+    }
+
+    fn cookie(&self, session_id: &str, max_age_seconds: u64) -> String {
+        let secure = if self.config.redirect_url.starts_with("https:") {
+            "; Secure"
+        } else {
+            ""
+        };
+        format!(
+            "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}{}",
+            SESSION_COOKIE, session_id, max_age_seconds, secure
+        )
+    }
+}
+
+/// Paths only, so a login link can't be used to bounce users to another site.
+fn is_local_path(path: &str) -> bool {
+    path.starts_with('/') && !path.starts_with("//") && !path.contains('\\')
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// The login session ID from the `relay_session` cookie or a bearer token.
+fn session_id(headers: &HeaderMap) -> Option<String> {
+    let from_cookie = headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| {
+            let (name, value) = pair.trim().split_once('=')?;
+            (name == SESSION_COOKIE).then(|| value.to_string())
+        });
+    from_cookie.or_else(|| {
+        headers
+            .get(header::AUTHORIZATION)?
+            .to_str()
+            .ok()?
+            .strip_prefix("Bearer ")
+            .map(str::to_string)
+    })
+}
+
+fn error(status: StatusCode, message: impl std::fmt::Display) -> Response {
+    (status, Json(json!({ "error": message.to_string() }))).into_response()
+}
+
+/// Routes served under `/auth` when login is configured.
+pub fn routes() -> Router<Arc<Server>> {
+    Router::new()
+        .route("/login", get(handle_login))
+        .route("/callback", get(handle_callback))
+        .route("/session", get(handle_session))
+        .route("/token", post(handle_token))
+        .route("/logout", post(handle_logout))
+}
+
+#[derive(Deserialize)]
+struct LoginParams {
+    return_to: Option<String>,
+}
+
+async fn handle_login(
+    State(server): State<Arc<Server>>,
+    Query(params): Query<LoginParams>,
+) -> Response {
+    let Some(oidc) = &server.oidc else {
+        return error(StatusCode::NOT_FOUND, "Login is not configured");
+    };
+    match oidc.begin(params.return_to) {
+        Ok(url) => Redirect::to(&url).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+#[derive(Deserialize)]
+struct CallbackParams {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+async fn handle_callback(
+    State(server): State<Arc<Server>>,
+    Query(params): Query<CallbackParams>,
+) -> Response {
+    let Some(oidc) = &server.oidc else {
+        return error(StatusCode::NOT_FOUND, "Login is not configured");
+    };
+    if let Some(reason) = params.error {
+        return error(
+            StatusCode::UNAUTHORIZED,
+            format!("Login failed: {}", reason),
+        );
+    }
+    let (Some(code), Some(state)) = (params.code, params.state) else {
+        return error(StatusCode::BAD_REQUEST, "Missing code or state");
+    };
+
+    let (session_id, session, return_to) = match oidc.complete(&code, &state, &server.acl).await {
+        Ok(login) => login,
+        Err(e) => {
+            tracing::warn!("Login failed: {}", e);
+            return error(StatusCode::UNAUTHORIZED, format!("Login failed: {}", e));
+        }
+    };
+    tracing::info!(user = %session.user, "User signed in");
+
+    let mut response = match return_to {
+        Some(path) => Redirect::to(&path).into_response(),
+        None => Json(json!({ "sessionId": session_id, "session": session })).into_response(),
+    };
+    let cookie = oidc.cookie(&session_id, oidc.config.session_seconds);
+    if let Ok(value) = HeaderValue::from_str(&cookie) {
+        response.headers_mut().insert(header::SET_COOKIE, value);
+    }
+    response
+}
+
+async fn handle_session(State(server): State<Arc<Server>>, headers: HeaderMap) -> Response {
+    let Some(oidc) = &server.oidc else {
+        return error(StatusCode::NOT_FOUND, "Login is not configured");
+    };
+    match session_id(&headers).and_then(|id| oidc.session(&id)) {
+        Some(session) => Json(session).into_response(),
+        None => error(StatusCode::UNAUTHORIZED, "Not signed in"),
+    }
+}
+
+async fn handle_token(
+    State(server): State<Arc<Server>>,
+    headers: HeaderMap,
+    Json(target): Json<TokenTarget>,
+) -> Response {
+    let Some(oidc) = &server.oidc else {
+        return error(StatusCode::NOT_FOUND, "Login is not configured");
+    };
+    let Some(session) = session_id(&headers).and_then(|id| oidc.session(&id)) else {
+        return error(StatusCode::UNAUTHORIZED, "Not signed in");
+    };
+    let Some(authenticator) = server.authenticator() else {
+        return error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "No signing key configured",
+        );
+    };
+    match oidc.mint(authenticator, &session, &target) {
+        Ok(Some(minted)) => Json(minted).into_response(),
+        Ok(None) => error(
+            StatusCode::FORBIDDEN,
+            format!("{} has no access to that resource", session.user),
+        ),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+async fn handle_logout(State(server): State<Arc<Server>>, headers: HeaderMap) -> Response {
+    let Some(oidc) = &server.oidc else {
+        return error(StatusCode::NOT_FOUND, "Login is not configured");
+    };
+    if let Some(id) = session_id(&headers) {
+        oidc.end(&id);
+    }
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    if let Ok(value) = HeaderValue::from_str(&oidc.cookie("", 0)) {
+        response.headers_mut().insert(header::SET_COOKIE, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Form;
+    use std::collections::HashMap;
+    use y_sweet_core::auth::Permission;
+    use y_sweet_core::config::OidcGrant;
+
+    /// A provider on a local port that accepts the code "good-code" and
+    /// reports the user "alice" in the group "Editors".
+    async fn mock_provider() -> String {
+        async fn token(Form(form): Form<HashMap<String, String>>) -> Response {
+            if form.get("code").map(String::as_str) != Some("good-code")
+                || form.get("code_verifier").map_or(true, |v| v.len() < 43)
+            {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({"error": "invalid_grant"})),
+                )
+                    .into_response();
+            }
+            Json(json!({"access_token": "provider-token", "token_type": "Bearer"})).into_response()
+        }
+        async fn userinfo(headers: HeaderMap) -> Response {
+            if headers
+                .get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                != Some("Bearer provider-token")
+            {
+                return StatusCode::UNAUTHORIZED.into_response();
+            }
+            Json(json!({"sub": "alice", "groups": ["Editors"]})).into_response()
+        }
+
+        let app = Router::new()
+            .route("/token", post(token))
+            .route("/userinfo", get(userinfo));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn login(provider: &str) -> OidcLogin {
+        OidcLogin::new(OidcConfig {
+            authorization_endpoint: format!("{}/authorize", provider),
+            token_endpoint: format!("{}/token", provider),
+            userinfo_endpoint: format!("{}/userinfo", provider),
+            client_id: "relay".to_string(),
+            client_secret: Some("secret".to_string()),
+            redirect_url: "http://localhost:8080/auth/callback".to_string(),
+            scopes: vec!["openid".to_string()],
+            user_claim: "sub".to_string(),
+            groups_claim: Some("groups".to_string()),
+            session_seconds: 3600,
+            token_seconds: 600,
+            grants: vec![
+                OidcGrant {
+                    group: "*".to_string(),
+                    prefix: "org-".to_string(),
+                    authorization: Authorization::ReadOnly,
+                },
+                OidcGrant {
+                    group: "Editors".to_string(),
+                    prefix: "org-course".to_string(),
+                    authorization: Authorization::Full,
+                },
+            ],
+        })
+        .unwrap()
+    }
+
+    fn state_of(url: &str) -> String {
+        Url::parse(url)
+            .unwrap()
+            .query_pairs()
+            .find(|(k, _)| k == "state")
+            .unwrap()
+            .1
+            .into_owned()
+    }
+
+    #[tokio::test]
+    async fn login_mints_tokens_from_grants() {
+        let provider = mock_provider().await;
+        let oidc = login(&provider);
+
+        let url = oidc.begin(Some("/notes".to_string())).unwrap();
+        assert!(url.contains("code_challenge_method=S256"));
+        let (session_id, session, return_to) = oidc
+            .complete("good-code", &state_of(&url), &Acl::default())
+            .await
+            .unwrap();
+        assert_eq!(session.user, "alice");
+        assert_eq!(session.groups, vec!["Editors".to_string()]);
+        assert_eq!(return_to.as_deref(), Some("/notes"));
+        assert_eq!(oidc.session(&session_id), Some(session.clone()));
+
+        let authenticator = Authenticator::gen_key_hmac().unwrap();
+        let minted = oidc
+            .mint(
+                &authenticator,
+                &session,
+                &TokenTarget::Prefix("org-course-1".to_string()),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(minted.authorization, Authorization::Full);
+        match authenticator
+            .verify_token_auto(&minted.token, now_millis())
+            .unwrap()
+        {
+            Permission::Prefix(prefix) => {
+                assert_eq!(prefix.prefix, "org-course-1");
+                assert_eq!(prefix.user.as_deref(), Some("alice"));
+            }
+            other => panic!("expected a prefix token, got {:?}", other),
+        }
+
+        let minted = oidc
+            .mint(
+                &authenticator,
+                &session,
+                &TokenTarget::DocId("org-other-doc".to_string()),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(minted.authorization, Authorization::ReadOnly);
+
+        let none = oidc
+            .mint(
+                &authenticator,
+                &session,
+                &TokenTarget::DocId("elsewhere".to_string()),
+            )
+            .unwrap();
+        assert!(none.is_none());
+    }
+
+    #[tokio::test]
+    async fn bad_code_and_replayed_state_are_rejected() {
+        let provider = mock_provider().await;
+        let oidc = login(&provider);
+
+        let url = oidc.begin(None).unwrap();
+        let state = state_of(&url);
+        assert!(oidc
+            .complete("bad-code", &state, &Acl::default())
+            .await
+            .is_err());
+        // The state is single-use even when the exchange failed
+        assert!(oidc
+            .complete("good-code", &state, &Acl::default())
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn return_to_must_be_a_local_path() {
+        assert!(is_local_path("/notes"));
+        assert!(!is_local_path("//evil.example.com"));
+        assert!(!is_local_path("https://evil.example.com"));
+    }
+
+    #[test]
+    fn session_id_from_cookie_or_bearer() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; relay_session=abc"),
+        );
+        assert_eq!(session_id(&headers).as_deref(), Some("abc"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer xyz"),
+        );
+        assert_eq!(session_id(&headers).as_deref(), Some("xyz"));
+    }
+}
@@ -0,0 +1,191 @@
+//! Append-only logs kept in the store.
+//!
+//! Entries are batched and written as JSON lines under
+//! `{prefix}/{YYYY-MM-DD}/{first-timestamp}-{id}.jsonl`, one object per
+//! flush, so a log rotates by date without ever rewriting an object.
+//! Without a store the most recent entries are kept in memory only.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use y_sweet_core::store::Store;
+
+/// Entries are gathered for this long before being written out together.
+const FLUSH_DELAY: Duration = Duration::from_secs(2);
+
+/// Entries kept when there is no store to write to.
+const MAX_IN_MEMORY: usize = 1000;
+
+/// Longest time range a single query will scan.
+const MAX_QUERY_DAYS: i64 = 92;
+
+pub trait LogEntry: Serialize + DeserializeOwned + Clone + Send + 'static {
+    /// Milliseconds since the epoch.
+    fn timestamp(&self) -> u64;
+}
+
+pub struct RotatingLog<E> {
+    prefix: &'static str,
+    store: Option<Arc<Box<dyn Store>>>,
+    /// Recorded but not yet written (with a store), or the whole log (without).
+    pending: Mutex<VecDeque<E>>,
+    flush_lock: tokio::sync::Mutex<()>,
+}
+
+impl<E: LogEntry> RotatingLog<E> {
+    pub fn new(prefix: &'static str, store: Option<Arc<Box<dyn Store>>>) -> Self {
+        Self {
+            prefix,
+            store,
+            pending: Mutex::new(VecDeque::new()),
+            flush_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Queue an entry. The first entry of a batch schedules the flush.
+    pub fn record(self: &Arc<Self>, entry: E) {
+        let schedule_flush = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.push_back(entry);
+            if self.store.is_none() {
+                while pending.len() > MAX_IN_MEMORY {
+                    pending.pop_front();
+                }
+                false
+            } else {
+                pending.len() == 1
+            }
+        };
+
+        if schedule_flush {
+            let log = self.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(FLUSH_DELAY).await;
+                log.flush().await;
+            });
+        }
+    }
+
+    /// Write queued entries to the store, one object per day touched.
+    /// Entries that fail to write stay queued for the next flush.
+    pub async fn flush(&self) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let _guard = self.flush_lock.lock().await;
+
+        let batch: Vec<E> = self.pending.lock().unwrap().drain(..).collect();
+        let mut failed = Vec::new();
+        for (date, entries) in group_by_date(batch) {
+            let key = format!(
+                "{}/{}/{:013}-{}.jsonl",
+                self.prefix,
+                date,
+                entries[0].timestamp(),
+                nanoid::nanoid!(8)
+            );
+            let body = entries
+                .iter()
+                .filter_map(|e| serde_json::to_string(e).ok())
+                .collect::<Vec<_>>()
+                .join("\n");
+            if let Err(e) = store.set(&key, body.into_bytes()).await {
+                tracing::error!("Failed to write log {}: {}", key, e);
+                failed.extend(entries);
+            }
+        }
+
+        if !failed.is_empty() {
+            let mut pending = self.pending.lock().unwrap();
+            for entry in failed.into_iter().rev() {
+                pending.push_front(entry);
+            }
+        }
+    }
+
+    /// Entries with `from_ms <= timestamp <= to_ms` that pass `filter`,
+    /// oldest first. Unflushed entries are included.
+    pub async fn query(
+        &self,
+        from_ms: u64,
+        to_ms: u64,
+        filter: impl Fn(&E) -> bool,
+        limit: usize,
+    ) -> anyhow::Result<Vec<E>> {
+        let matches = |e: &E| e.timestamp() >= from_ms && e.timestamp() <= to_ms && filter(e);
+
+        let mut entries: Vec<E> = self
+            .pending
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| matches(*e))
+            .cloned()
+            .collect();
+
+        if let Some(store) = &self.store {
+            let (first, last) = (date_of(from_ms), date_of(to_ms));
+            if (last - first).num_days() > MAX_QUERY_DAYS {
+                anyhow::bail!("Time range is too long (at most {} days)", MAX_QUERY_DAYS);
+            }
+
+            let mut day = first;
+            while day <= last {
+                let prefix = format!("{}/{}", self.prefix, day.format("%Y-%m-%d"));
+                for object in store.list(&prefix).await? {
+                    // Objects are named after their first entry; later ones can be skipped
+                    let starts = object
+                        .key
+                        .split('-')
+                        .next()
+                        .and_then(|ts| ts.parse::<u64>().ok());
+                    if starts.is_some_and(|ts| ts > to_ms) {
+                        continue;
+                    }
+                    let Some(body) = store.get(&format!("{}/{}", prefix, object.key)).await? else {
+                        continue;
+                    };
+                    for line in String::from_utf8_lossy(&body).lines() {
+                        match serde_json::from_str::<E>(line) {
+                            Ok(entry) if matches(&entry) => entries.push(entry),
+                            Ok(_) => {}
+                            Err(e) => {
+                                tracing::warn!("Skipping bad log line in {}: {}", prefix, e)
+                            }
+                        }
+                    }
+                }
+                day = match day.succ_opt() {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+        }
+
+        entries.sort_by_key(|e| e.timestamp());
+        entries.truncate(limit);
+        Ok(entries)
+    }
+}
+
+fn date_of(timestamp_ms: u64) -> NaiveDate {
+    DateTime::<Utc>::from_timestamp_millis(timestamp_ms as i64)
+        .unwrap_or_default()
+        .date_naive()
+}
+
+/// Split a batch into per-day runs, keeping order within each day.
+fn group_by_date<E: LogEntry>(batch: Vec<E>) -> Vec<(String, Vec<E>)> {
+    let mut groups: Vec<(String, Vec<E>)> = Vec::new();
+    for entry in batch {
+        let date = date_of(entry.timestamp()).format("%Y-%m-%d").to_string();
+        match groups.iter_mut().find(|(d, _)| *d == date) {
+            Some((_, entries)) => entries.push(entry),
+            None => groups.push((date, vec![entry])),
+        }
+    }
+    groups
+}
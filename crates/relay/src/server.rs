@@ -1,3 +1,4 @@
+use crate::acl::AclPrincipal;
 use anyhow::{anyhow, Result};
 use axum::{
     body::Bytes,
@@ -35,25 +36,34 @@ use tokio_util::{sync::CancellationToken, task::TaskTracker};
 use tracing::{span, Instrument, Level};
 use url::Url;
 use y_sweet_core::{
+    api_key::{load_api_keys_from_store, save_api_keys_to_store, ApiKey},
     api_types::{
         validate_doc_name, validate_file_hash, AuthDocRequest, Authorization, ClientToken,
         DocCreationRequest, DocumentVersionEntry, DocumentVersionResponse, FileDownloadUrlResponse,
         FileHistoryEntry, FileHistoryResponse, FileUploadUrlResponse, NewDocResponse,
     },
-    auth::{Authenticator, ExpirationTimeEpochMillis, Permission, DEFAULT_EXPIRATION_SECONDS},
+    auth::{
+        AuthError, Authenticator, ExpirationTimeEpochMillis, Permission, DEFAULT_EXPIRATION_SECONDS,
+    },
+    auth_audit::{AuthDecision, Decision},
     critic_scanner,
-    doc_connection::DocConnection,
+    doc_connection::{DocConnection, TokenRefresh},
     doc_resolver::DocumentResolver,
     doc_sync::DocWithSyncKv,
     event::{
         DebouncedSyncProtocolEventSender, DocumentUpdatedEvent, EventDispatcher, EventEnvelope,
         EventSender, SyncProtocolEventSender, UnifiedEventDispatcher, WebhookSender,
     },
+    key_ring::{load_key_ring_from_store, save_key_ring_to_store},
     link_indexer::{self, LinkIndexer},
     metrics::RelayMetrics,
+    revocation::{
+        load_revocations_from_store, save_revocations_to_store, Revocation, RevocationKind,
+        TokenIdentity,
+    },
     search_index::SearchIndex,
     store::Store,
-    sync::awareness::Awareness,
+    sync::{awareness::Awareness, Error as SyncError},
     sync_kv::SyncKv,
     webhook::WebhookConfig,
 };
@@ -85,19 +95,17 @@ fn validate_file_token(
         )
     })?;
 
-    let permission = authenticator
-        .verify_token_auto(token, current_time_epoch_millis())
-        .map_err(|auth_error| {
-            // Record auth failure metric
-            server_state.metrics.record_auth_failure(
-                auth_error.to_metric_label(),
-                "file_access",
-                "POST",
-            );
-            AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token"))
-        })?;
+    let mut permission = verify_token(authenticator, token).map_err(|auth_error| {
+        // Record auth failure metric
+        server_state.metrics.record_auth_failure(
+            auth_error.to_metric_label(),
+            "file_access",
+            "POST",
+        );
+        AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token"))
+    })?;
 
-    match &permission {
+    match &mut permission {
         Permission::File(file_permission) => {
             if file_permission.doc_id != doc_id {
                 server_state.metrics.record_permission_denied(
@@ -110,6 +118,14 @@ fn validate_file_token(
                     anyhow!("Token not valid for this document"),
                 ));
             }
+            file_permission.authorization = server_state.acl_authorization(
+                doc_id,
+                file_permission
+                    .user
+                    .as_deref()
+                    .map(|user| AclPrincipal::of_token(token, user)),
+                file_permission.authorization,
+            )?;
         }
         _ => {
             server_state.metrics.record_permission_denied(
@@ -132,7 +148,11 @@ pub struct AppError(StatusCode, anyhow::Error);
 impl std::error::Error for AppError {}
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (self.0, format!("Something went wrong: {}", self.1)).into_response()
+        let mut response = (self.0, format!("Something went wrong: {}", self.1)).into_response();
+        response
+            .extensions_mut()
+            .insert(crate::auth_audit::DenialReason(self.1.to_string()));
+        response
     }
 }
 impl<E> From<(StatusCode, E)> for AppError
@@ -542,6 +562,8 @@ async fn search_handle_folder_update(
     }
 }
 
+const DEFAULT_MCP_TOOL_TIMEOUT: Duration = Duration::from_secs(60);
+
 pub struct Server {
     docs: Arc<DashMap<String, DocWithSyncKv>>,
     doc_worker_tracker: TaskTracker,
@@ -557,15 +579,49 @@ pub struct Server {
     doc_gc: std::sync::atomic::AtomicBool,
     event_dispatcher: Option<Arc<dyn EventDispatcher>>,
     sync_protocol_event_sender: Arc<SyncProtocolEventSender>,
-    metrics: Arc<RelayMetrics>,
+    pub(crate) metrics: Arc<RelayMetrics>,
     link_indexer: Option<Arc<LinkIndexer>>,
     search_index: Option<Arc<SearchIndex>>,
     search_ready: Arc<std::sync::atomic::AtomicBool>,
     search_tx: Option<tokio::sync::mpsc::Sender<String>>,
     search_pending: Option<Arc<DashMap<String, link_indexer::PendingEntry>>>,
     doc_resolver: Arc<DocumentResolver>,
+    suggestion_anchors: Arc<crate::suggestion_anchors::SuggestionAnchors>,
     pub(crate) mcp_sessions: Arc<crate::mcp::session::SessionManager>,
     pub(crate) mcp_api_key: Option<String>,
+    mcp_keys: crate::mcp::auth::McpKeyring,
+    /// Deadline for a single MCP tools/call (MCP_TOOL_TIMEOUT_SECS, default 60).
+    pub(crate) mcp_tool_timeout: Duration,
+    /// Record of every MCP tools/call, served by GET /mcp-audit.
+    pub(crate) mcp_audit: Arc<crate::mcp::audit::AuditLog>,
+    /// Record of authorization decisions, served by GET /auth-audit.
+    auth_audit: Arc<crate::auth_audit::AuthAuditLog>,
+    /// Tool call quotas from `[mcp_limits]` and per-key overrides.
+    pub(crate) mcp_limits: crate::mcp::limits::McpLimiter,
+    /// Awareness states announcing MCP agents in the documents they edit.
+    pub(crate) mcp_presence: crate::mcp::presence::AgentPresence,
+    /// Folder-level access rules from `.config/acl.json`.
+    pub(crate) acl: crate::acl::Acl,
+    /// Browser login from the `[oidc]` config section, when configured.
+    pub(crate) oidc: Option<crate::oidc::OidcLogin>,
+    /// Websocket connections opened with a token, so revoking the token can
+    /// close them.
+    live_tokens: Arc<DashMap<u64, (TokenIdentity, CancellationToken)>>,
+    next_live_token: std::sync::atomic::AtomicU64,
+}
+
+/// Registers a websocket connection's token for as long as the connection
+/// lives. `revoked` fires if a revocation covering the token is made.
+struct LiveToken {
+    id: u64,
+    revoked: CancellationToken,
+    live_tokens: Arc<DashMap<u64, (TokenIdentity, CancellationToken)>>,
+}
+
+impl Drop for LiveToken {
+    fn drop(&mut self) {
+        self.live_tokens.remove(&self.id);
+    }
 }
 
 /// Holds channel receivers for background workers.
@@ -672,10 +728,42 @@ impl Server {
             tracing::info!("MCP endpoint disabled (MCP_API_KEY not set)");
         }
 
+        let mcp_tool_timeout = match std::env::var("MCP_TOOL_TIMEOUT_SECS") {
+            Ok(value) => Duration::from_secs(value.parse().map_err(|_| {
+                anyhow!("MCP_TOOL_TIMEOUT_SECS must be a whole number of seconds")
+            })?),
+            Err(_) => DEFAULT_MCP_TOOL_TIMEOUT,
+        };
+
+        let mcp_session_ttl = match std::env::var("MCP_SESSION_TTL_SECS") {
+            Ok(value) => Duration::from_secs(value.parse().map_err(|_| {
+                anyhow!("MCP_SESSION_TTL_SECS must be a whole number of seconds")
+            })?),
+            Err(_) => crate::mcp::session::DEFAULT_SESSION_TTL,
+        };
+        let mcp_max_sessions = match std::env::var("MCP_MAX_SESSIONS") {
+            Ok(value) => value
+                .parse()
+                .map_err(|_| anyhow!("MCP_MAX_SESSIONS must be a whole number"))?,
+            Err(_) => crate::mcp::session::DEFAULT_MAX_SESSIONS,
+        };
+
+        let store = store.map(Arc::new);
+        let mcp_sessions = Arc::new(crate::mcp::session::SessionManager::with_config(
+            store.clone(),
+            mcp_session_ttl,
+            mcp_max_sessions,
+        ));
+        let mcp_audit = Arc::new(crate::mcp::audit::AuditLog::new(store.clone()));
+        let auth_audit = Arc::new(crate::auth_audit::AuthAuditLog::new(
+            crate::auth_audit::AUTH_AUDIT_PREFIX,
+            store.clone(),
+        ));
+
         let server = Self {
             docs,
             doc_worker_tracker: TaskTracker::new(),
-            store: store.map(Arc::new),
+            store,
             checkpoint_freq,
             authenticator,
             url,
@@ -691,8 +779,19 @@ impl Server {
             search_tx: search_tx_final,
             search_pending: search_pending_final,
             doc_resolver,
-            mcp_sessions: Arc::new(crate::mcp::session::SessionManager::new()),
+            suggestion_anchors: Arc::new(crate::suggestion_anchors::SuggestionAnchors::new()),
+            mcp_sessions,
             mcp_api_key,
+            mcp_keys: crate::mcp::auth::McpKeyring::default(),
+            mcp_tool_timeout,
+            mcp_audit,
+            auth_audit,
+            mcp_limits: crate::mcp::limits::McpLimiter::default(),
+            mcp_presence: crate::mcp::presence::AgentPresence::default(),
+            acl: crate::acl::Acl::default(),
+            oidc: None,
+            live_tokens: Arc::new(DashMap::new()),
+            next_live_token: std::sync::atomic::AtomicU64::new(0),
         };
 
         let receivers = WorkerReceivers {
@@ -703,6 +802,84 @@ impl Server {
         Ok((server, receivers))
     }
 
+    /// Register separately issued MCP keys from the `[[mcp_keys]]` config section.
+    pub fn with_mcp_keys(mut self, keys: &[y_sweet_core::config::McpKeyConfig]) -> Self {
+        self.mcp_keys = crate::mcp::auth::McpKeyring::new(keys);
+        if !self.mcp_keys.is_empty() {
+            tracing::info!("Loaded {} MCP keys", self.mcp_keys.len());
+        }
+        self
+    }
+
+    /// Apply `[mcp_limits]` and the per-key `limits` overrides to MCP tool calls.
+    pub fn with_mcp_limits(
+        mut self,
+        defaults: &y_sweet_core::config::McpLimitsConfig,
+        keys: &[y_sweet_core::config::McpKeyConfig],
+    ) -> Self {
+        self.mcp_limits = crate::mcp::limits::McpLimiter::new(defaults, keys);
+        if !self.mcp_limits.is_empty() {
+            tracing::info!("MCP usage limits enabled");
+        }
+        self
+    }
+
+    /// Enable login through the OIDC/OAuth2 provider in the `[oidc]` config section.
+    pub fn with_oidc(mut self, config: Option<&y_sweet_core::config::OidcConfig>) -> Result<Self> {
+        if let Some(config) = config {
+            if self.authenticator.is_none() {
+                return Err(anyhow!("OIDC login requires an auth key to sign tokens"));
+            }
+            self.oidc = Some(crate::oidc::OidcLogin::new(config.clone())?);
+            tracing::info!("OIDC login enabled");
+        }
+        Ok(self)
+    }
+
+    pub(crate) fn authenticator(&self) -> Option<&Authenticator> {
+        self.authenticator.as_ref()
+    }
+
+    /// Resolve an MCP bearer token to a credential. Accepts the shared
+    /// MCP_API_KEY, configured MCP keys, and the server's own CWT/legacy tokens.
+    pub(crate) fn resolve_mcp_credential(
+        &self,
+        token: &str,
+    ) -> Option<crate::mcp::auth::McpCredential> {
+        if let Some(credential) = self.resolve_mcp_path_key(token) {
+            return Some(credential);
+        }
+        let authenticator = self.authenticator.as_ref()?;
+        let verified = verify_token(authenticator, token);
+        self.record_api_key_request(token, &verified);
+        match verified {
+            Ok(permission) => match y_sweet_core::api_key::is_api_key(token)
+                .then(|| authenticator.api_keys.find(token))
+                .flatten()
+            {
+                Some(key) => Some(crate::mcp::auth::McpCredential::from_api_key(&key)),
+                None => crate::mcp::auth::McpCredential::from_permission(permission),
+            },
+            Err(e) => {
+                self.metrics.record_auth_failure(e.to_metric_label(), "mcp", "POST");
+                None
+            }
+        }
+    }
+
+    /// Resolve a key given in an `/mcp/:key` path to a credential. Only the
+    /// shared MCP_API_KEY and configured MCP keys are taken there; relay tokens
+    /// and API keys must be sent as a bearer token.
+    pub(crate) fn resolve_mcp_path_key(
+        &self,
+        key: &str,
+    ) -> Option<crate::mcp::auth::McpCredential> {
+        if self.mcp_api_key.as_deref() == Some(key) {
+            return Some(crate::mcp::auth::McpCredential::shared_key());
+        }
+        self.mcp_keys.lookup(key)
+    }
+
     /// Spawn background workers for link indexing and search index updates.
     /// Must be called after `startup_reindex` to avoid race conditions where
     /// workers process notifications while startup is still writing to Y.Docs.
@@ -796,14 +973,583 @@ impl Server {
             }
         }
 
+        // Evict idle MCP sessions and write the rest to the store
+        self.mcp_sessions
+            .spawn_persistence(self.cancellation_token.clone());
+
         tracing::info!("Background workers started (link indexer, search index)");
     }
 
+    /// Wait for the doc workers to make their final saves. Call after the
+    /// cancellation token has fired, when not running `serve`.
+    pub async fn wait_for_doc_workers(&self) {
+        self.doc_worker_tracker.close();
+        self.doc_worker_tracker.wait().await;
+    }
+
+    /// Reload MCP sessions persisted by the previous run, so clients keep their
+    /// sessions (and read-before-edit history) across a restart.
+    pub async fn restore_mcp_sessions(&self) -> Result<()> {
+        let restored = self.mcp_sessions.load().await?;
+        if restored > 0 {
+            tracing::info!("Restored {} MCP sessions", restored);
+        }
+        Ok(())
+    }
+
+    /// Re-read the access control list from the store and put it in force.
+    /// Returns the number of rules loaded; without a store there are none.
+    pub async fn reload_acl(&self) -> Result<usize> {
+        let Some(store) = &self.store else {
+            return Ok(0);
+        };
+        let document = crate::acl::load_acl_from_store(store.clone())
+            .await
+            .map_err(|e| anyhow!("{}", e))?
+            .unwrap_or_default();
+        let rules = document.rules.len();
+        self.acl.replace(document);
+        Ok(rules)
+    }
+
+    /// Save a new access control list to the store and put it in force.
+    pub async fn set_acl(&self, document: crate::acl::AclDocument) -> Result<()> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| anyhow!("No store configured for the access control list"))?;
+        crate::acl::set_acl_in_store(store.clone(), &document)
+            .await
+            .map_err(|e| anyhow!("{}", e))?;
+        self.acl.replace(document);
+        Ok(())
+    }
+
+    /// The user-facing path of a document, e.g. `Lens/Photosynthesis.md`.
+    pub fn doc_path(&self, doc_id: &str) -> Option<String> {
+        let (_, uuid) = link_indexer::parse_doc_id(doc_id)?;
+        self.doc_resolver.path_for_uuid(uuid)
+    }
+
+    /// Cap `authorization`, granted to `principal` for `doc_id` by a token, by
+    /// the access control list. Tokens without a user and documents without a
+    /// known path are not subject to the list. API keys are also held to their
+    /// folder scopes; a folder document counts as the folder itself, and a key
+    /// with scopes is refused any document it can't place.
+    pub(crate) fn acl_authorization(
+        &self,
+        doc_id: &str,
+        principal: Option<AclPrincipal>,
+        authorization: Authorization,
+    ) -> Result<Authorization, AppError> {
+        if let Some(path) = self.doc_path(doc_id) {
+            return self.acl_authorization_at(&path, principal, authorization);
+        }
+        let Some(key) = principal.and_then(|principal| self.api_key_for(principal)) else {
+            return Ok(authorization);
+        };
+        if key.scopes.is_empty()
+            || self
+                .folder_name(doc_id)
+                .is_some_and(|folder| key.covers(&folder))
+        {
+            Ok(authorization)
+        } else {
+            Err(AppError(
+                StatusCode::FORBIDDEN,
+                anyhow!("API key '{}' does not cover document {}", key.name, doc_id),
+            ))
+        }
+    }
+
+    /// Like `acl_authorization`, for the document at `path`, which need not
+    /// exist yet.
+    fn acl_authorization_at(
+        &self,
+        path: &str,
+        principal: Option<AclPrincipal>,
+        authorization: Authorization,
+    ) -> Result<Authorization, AppError> {
+        let Some(principal) = principal else {
+            return Ok(authorization);
+        };
+        if let Some(key) = self.api_key_for(principal) {
+            if !key.covers(path) {
+                return Err(AppError(
+                    StatusCode::FORBIDDEN,
+                    anyhow!("API key '{}' does not cover {}", key.name, path),
+                ));
+            }
+        }
+        crate::acl::narrow(authorization, self.acl.decide_for(principal, path)).ok_or_else(|| {
+            AppError(
+                StatusCode::FORBIDDEN,
+                anyhow!("Access to {} is not permitted", path),
+            )
+        })
+    }
+
+    /// The API key a request was authenticated with, if any.
+    fn api_key_for(&self, principal: AclPrincipal) -> Option<ApiKey> {
+        let AclPrincipal::Key(name) = principal else {
+            return None;
+        };
+        self.authenticator.as_ref()?.api_keys.get(name)
+    }
+
+    /// Re-read API keys from the store and put them in force. Returns the
+    /// number of keys.
+    pub async fn reload_api_keys(&self) -> Result<usize> {
+        let (Some(store), Some(authenticator)) = (&self.store, &self.authenticator) else {
+            return Ok(0);
+        };
+        let keys = load_api_keys_from_store(store.clone())
+            .await
+            .map_err(|e| anyhow!("{}", e))?;
+        let count = keys.len();
+        authenticator.api_keys.replace(keys);
+        Ok(count)
+    }
+
+    /// Create an API key and save it to the store. Returns the key's secret,
+    /// which is not kept anywhere, along with the key.
+    pub async fn create_api_key(
+        &self,
+        name: &str,
+        scopes: Vec<String>,
+        permission: Authorization,
+        expires_at: Option<u64>,
+    ) -> Result<(String, ApiKey)> {
+        let authenticator = self
+            .authenticator
+            .as_ref()
+            .ok_or_else(|| anyhow!("No auth key configured, so API keys are not needed"))?;
+        let (secret, key) = ApiKey::generate(
+            name,
+            scopes,
+            permission,
+            current_time_epoch_millis(),
+            expires_at,
+        );
+        authenticator
+            .api_keys
+            .add(key.clone())
+            .map_err(|e| anyhow!(e))?;
+        self.save_api_keys(authenticator).await?;
+        Ok((secret, key))
+    }
+
+    /// Delete an API key and close the websocket connections opened with it.
+    /// Returns whether there was one.
+    pub async fn delete_api_key(&self, name: &str) -> Result<bool> {
+        let Some(authenticator) = &self.authenticator else {
+            return Ok(false);
+        };
+        let Some(key) = authenticator.api_keys.get(name) else {
+            return Ok(false);
+        };
+        authenticator.api_keys.remove(name);
+        self.save_api_keys(authenticator).await?;
+        let subject = key.subject();
+        for entry in self.live_tokens.iter() {
+            let (identity, revoked) = entry.value();
+            if identity.subject.as_deref() == Some(subject.as_str()) {
+                revoked.cancel();
+            }
+        }
+        Ok(true)
+    }
+
+    async fn save_api_keys(&self, authenticator: &Authenticator) -> Result<()> {
+        let Some(store) = &self.store else {
+            tracing::warn!("No store configured; API keys will not survive a restart");
+            return Ok(());
+        };
+        save_api_keys_to_store(store.clone(), &authenticator.api_keys.keys())
+            .await
+            .map_err(|e| anyhow!("{}", e))
+    }
+
+    /// Re-read rotated signing keys from the store and put them in force.
+    /// Returns the number of rotated keys.
+    pub async fn reload_key_ring(&self) -> Result<usize> {
+        let (Some(store), Some(authenticator)) = (&self.store, &self.authenticator) else {
+            return Ok(0);
+        };
+        let document = load_key_ring_from_store(store.clone())
+            .await
+            .map_err(|e| anyhow!("{}", e))?;
+        let count = document.keys.len();
+        authenticator
+            .key_ring
+            .replace(document)
+            .map_err(|e| anyhow!("Invalid rotated key: {}", e))?;
+        Ok(count)
+    }
+
+    /// Start signing with a fresh key. The keys it replaces keep verifying
+    /// tokens for `grace_period`. Returns the new key's ID.
+    pub async fn rotate_keys(&self, grace_period: Duration) -> Result<String> {
+        let authenticator = self
+            .authenticator
+            .as_ref()
+            .ok_or_else(|| anyhow!("No auth key configured, so there are no keys to rotate"))?;
+        // Tokens signed with a key that is not saved stop verifying on restart
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| anyhow!("Rotating keys requires a store to keep them in"))?;
+        let now = current_time_epoch_millis();
+        let key_id = nanoid::nanoid!(12);
+        let key = authenticator
+            .new_rotated_key(&key_id, now)
+            .map_err(|e| anyhow::Error::new(e).context("Cannot rotate keys"))?;
+        let mut document = authenticator.key_ring.document();
+        document.rotate(
+            key,
+            now,
+            grace_period.as_millis() as u64,
+            authenticator.signing_key_id().as_deref(),
+        );
+        save_key_ring_to_store(store.clone(), &document)
+            .await
+            .map_err(|e| anyhow!("{}", e))?;
+        authenticator.key_ring.replace(document)?;
+        Ok(key_id)
+    }
+
+    /// Re-read revoked tokens from the store, put them in force and close
+    /// connections they cover. Returns the number of revocations.
+    pub async fn reload_revocations(&self) -> Result<usize> {
+        let (Some(store), Some(authenticator)) = (&self.store, &self.authenticator) else {
+            return Ok(0);
+        };
+        let revocations = load_revocations_from_store(store.clone())
+            .await
+            .map_err(|e| anyhow!("{}", e))?;
+        let count = revocations.len();
+        authenticator.revocations.replace(revocations);
+        self.close_revoked_connections();
+        Ok(count)
+    }
+
+    /// Revoke tokens, saving the revocation to the store, and close the
+    /// websocket connections using them.
+    pub async fn revoke(&self, revocation: Revocation) -> Result<()> {
+        let authenticator = self
+            .authenticator
+            .as_ref()
+            .ok_or_else(|| anyhow!("No auth key configured, so there are no tokens to revoke"))?;
+        authenticator.revocations.add(revocation);
+        self.save_revocations(authenticator).await?;
+        self.close_revoked_connections();
+        Ok(())
+    }
+
+    /// Lift a revocation. Returns whether there was one.
+    pub async fn unrevoke(&self, kind: RevocationKind, value: &str) -> Result<bool> {
+        let Some(authenticator) = &self.authenticator else {
+            return Ok(false);
+        };
+        if !authenticator.revocations.remove(kind, value) {
+            return Ok(false);
+        }
+        self.save_revocations(authenticator).await?;
+        Ok(true)
+    }
+
+    async fn save_revocations(&self, authenticator: &Authenticator) -> Result<()> {
+        let Some(store) = &self.store else {
+            tracing::warn!("No store configured; revocations will not survive a restart");
+            return Ok(());
+        };
+        save_revocations_to_store(store.clone(), &authenticator.revocations.entries())
+            .await
+            .map_err(|e| anyhow!("{}", e))
+    }
+
+    fn close_revoked_connections(&self) {
+        let Some(authenticator) = &self.authenticator else {
+            return;
+        };
+        for entry in self.live_tokens.iter() {
+            let (identity, revoked) = entry.value();
+            if let Some(revocation) = authenticator.revocations.find(identity) {
+                tracing::info!(
+                    kind = %revocation.kind,
+                    value = %revocation.value,
+                    "Closing connection using a revoked token"
+                );
+                revoked.cancel();
+            }
+        }
+    }
+
+    /// Track a websocket connection opened with `token`, issued to `user`.
+    fn watch_token(&self, token: Option<&str>, user: Option<&str>) -> Option<LiveToken> {
+        let identity = self.authenticator.as_ref()?.token_identity(token?, user);
+        let id = self
+            .next_live_token
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let revoked = CancellationToken::new();
+        self.live_tokens.insert(id, (identity, revoked.clone()));
+        Some(LiveToken {
+            id,
+            revoked,
+            live_tokens: self.live_tokens.clone(),
+        })
+    }
+
+    /// Check a token presented on an open websocket connection to `doc_id`
+    /// and add the decision to the audit trail.
+    fn refresh_socket_token(
+        &self,
+        token: &str,
+        doc_id: &str,
+        user: Option<&str>,
+        live_token: Option<u64>,
+    ) -> TokenRefresh {
+        let Some(authenticator) = &self.authenticator else {
+            return TokenRefresh::Granted {
+                authorization: Authorization::Full,
+                expiration_time: None,
+            };
+        };
+        let now = current_time_epoch_millis();
+        let verified = authenticator.verify_token_auto(token, now);
+        self.record_api_key_request(token, &verified);
+        let refresh = match &verified {
+            Ok(permission) => self.verify_refreshed_token(
+                authenticator,
+                token,
+                permission,
+                doc_id,
+                user,
+                live_token,
+            ),
+            Err(e) => TokenRefresh::Rejected(e.to_string()),
+        };
+        let denial = match &refresh {
+            TokenRefresh::Granted { .. } => None,
+            TokenRefresh::Rejected(reason) => Some(reason.clone()),
+        };
+        self.auth_audit.record(authenticator.audit_decision(
+            Some((token, Some(&verified))),
+            doc_id,
+            "WS token refresh",
+            denial,
+            now,
+        ));
+        refresh
+    }
+
+    /// A token presented on an open websocket connection, verified to
+    /// `permission`, must be valid for the same document and user the
+    /// connection was opened with; the connection then takes on its
+    /// authorization, capped by the access control list, and its expiration
+    /// time.
+    fn verify_refreshed_token(
+        &self,
+        authenticator: &Authenticator,
+        token: &str,
+        permission: &Permission,
+        doc_id: &str,
+        user: Option<&str>,
+        live_token: Option<u64>,
+    ) -> TokenRefresh {
+        let authorization = match permission.doc_authorization(doc_id) {
+            Ok(authorization) => authorization,
+            Err(e) => return TokenRefresh::Rejected(e.to_string()),
+        };
+        if permission.user() != user {
+            return TokenRefresh::Rejected("Token was issued to a different user".to_string());
+        }
+        let principal = acl_principal(token, permission);
+        let authorization = match self.acl_authorization(doc_id, principal, authorization) {
+            Ok(authorization) => authorization,
+            Err(AppError(_, e)) => return TokenRefresh::Rejected(e.to_string()),
+        };
+        if let Some(mut entry) = live_token.and_then(|id| self.live_tokens.get_mut(&id)) {
+            entry.0 = authenticator.token_identity(token, user);
+        }
+        TokenRefresh::Granted {
+            authorization,
+            expiration_time: self.token_expiration(token),
+        }
+    }
+
+    /// Add an authorization decision about a request carrying `token` to the
+    /// audit trail, describing the token as its handler `verified` it.
+    /// Nothing is recorded when auth is not configured.
+    pub(crate) fn record_auth_decision(
+        &self,
+        token: Option<&str>,
+        verified: Option<Result<Permission, AuthError>>,
+        resource: &str,
+        action: &str,
+        denial: Option<String>,
+    ) {
+        let Some(authenticator) = &self.authenticator else {
+            return;
+        };
+        let now = current_time_epoch_millis();
+        // A token refused before its handler got to it is verified here for
+        // the record alone
+        let verified =
+            verified.or_else(|| token.map(|token| authenticator.verify_token_auto(token, now)));
+        if let (Some(token), Some(verified)) = (token, &verified) {
+            self.record_api_key_request(token, verified);
+        }
+        self.auth_audit.record(authenticator.audit_decision(
+            token.map(|token| (token, verified.as_ref())),
+            resource,
+            action,
+            denial,
+            now,
+        ));
+    }
+
+    /// Count a request made with the API key `token` under the outcome of its
+    /// verification. Other tokens, and keys the relay doesn't know, are not
+    /// counted.
+    fn record_api_key_request(&self, token: &str, verified: &Result<Permission, AuthError>) {
+        if !y_sweet_core::api_key::is_api_key(token) {
+            return;
+        }
+        let outcome = match verified {
+            Ok(_) => "accepted",
+            Err(AuthError::Expired) => "expired",
+            Err(AuthError::Revoked) => "revoked",
+            Err(_) => return,
+        };
+        if let Some(key) = self
+            .authenticator
+            .as_ref()
+            .and_then(|authenticator| authenticator.api_keys.find(token))
+        {
+            self.metrics.record_api_key_request(&key.name, outcome);
+        }
+    }
+
+    /// When a token expires, in milliseconds since the epoch.
+    fn token_expiration(&self, token: &str) -> Option<u64> {
+        let authenticator = self.authenticator.as_ref()?;
+        if let Some(key) = authenticator.api_keys.find(token) {
+            return key.expires_at;
+        }
+        authenticator
+            .decode_token(token)
+            .ok()
+            .and_then(|payload| payload.expiration_millis)
+            .map(|exp| exp.0)
+    }
+
+    /// Check that `token` may move the document `uuid` to `new_path` in
+    /// `target_folder` (by default, its own folder). Server tokens may move
+    /// anything; other tokens need Full access to the document and to both
+    /// folders, and the access control list must allow Full access at the
+    /// destination.
+    pub(crate) fn authorize_move(
+        &self,
+        token: Option<&str>,
+        uuid: &str,
+        new_path: &str,
+        target_folder: Option<&str>,
+    ) -> Result<(), AppError> {
+        let Some(authenticator) = &self.authenticator else {
+            return Ok(());
+        };
+        let token = token
+            .ok_or_else(|| AppError(StatusCode::UNAUTHORIZED, anyhow!("No token provided.")))?;
+        let permission =
+            verify_token(authenticator, token).map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+        if permission == Permission::Server {
+            return Ok(());
+        }
+
+        let source = self
+            .doc_resolver
+            .path_for_uuid(uuid)
+            .and_then(|path| self.doc_resolver.resolve_path(&path))
+            .ok_or_else(|| {
+                AppError(
+                    StatusCode::NOT_FOUND,
+                    anyhow!("UUID {} not found in any folder document", uuid),
+                )
+            })?;
+        let (target_folder_doc_id, target_folder_name) = match target_folder {
+            Some(name) if name != source.folder_name => {
+                let folder_doc_id = self.folder_doc_id_by_name(name).ok_or_else(|| {
+                    AppError(
+                        StatusCode::BAD_REQUEST,
+                        anyhow!("Target folder '{}' not found", name),
+                    )
+                })?;
+                (folder_doc_id, name)
+            }
+            _ => (source.folder_doc_id.clone(), source.folder_name.as_str()),
+        };
+
+        let forbidden = |what: &str| {
+            AppError(
+                StatusCode::FORBIDDEN,
+                anyhow!("Moving documents requires Full access to {}", what),
+            )
+        };
+        let principal = acl_principal(token, &permission);
+        for doc_id in [&source.doc_id, &source.folder_doc_id, &target_folder_doc_id] {
+            let authorization = permission
+                .doc_authorization(doc_id)
+                .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+            if self.acl_authorization(doc_id, principal, authorization)? != Authorization::Full {
+                return Err(forbidden(doc_id));
+            }
+        }
+
+        let destination = format!("{}{}", target_folder_name, new_path);
+        if self.acl_authorization_at(&destination, principal, Authorization::Full)?
+            != Authorization::Full
+        {
+            return Err(forbidden(&destination));
+        }
+        Ok(())
+    }
+
+    /// The name of the folder whose folder document is `doc_id`, if it is a
+    /// loaded folder document.
+    fn folder_name(&self, doc_id: &str) -> Option<String> {
+        link_indexer::is_folder_doc(doc_id, &self.docs)?;
+        let awareness = self.docs.get(doc_id)?.awareness();
+        let guard = awareness.read().unwrap_or_else(|e| e.into_inner());
+        Some(y_sweet_core::doc_resolver::read_folder_name(
+            &guard.doc, doc_id,
+        ))
+    }
+
+    /// The ID of the loaded folder document named `name`.
+    fn folder_doc_id_by_name(&self, name: &str) -> Option<String> {
+        link_indexer::find_all_folder_docs(&self.docs)
+            .into_iter()
+            .find(|folder_doc_id| {
+                let Some(awareness) = self.docs.get(folder_doc_id).map(|doc| doc.awareness())
+                else {
+                    return false;
+                };
+                let guard = awareness.read().unwrap_or_else(|e| e.into_inner());
+                y_sweet_core::doc_resolver::read_folder_name(&guard.doc, folder_doc_id) == name
+            })
+    }
+
     /// Get the DocumentResolver for path-to-UUID resolution.
     pub fn doc_resolver(&self) -> &Arc<DocumentResolver> {
         &self.doc_resolver
     }
 
+    /// Get the tracker for pending suggestion anchors.
+    pub fn suggestion_anchors(&self) -> &Arc<crate::suggestion_anchors::SuggestionAnchors> {
+        &self.suggestion_anchors
+    }
+
     /// Get the DashMap of all loaded documents.
     pub fn docs(&self) -> &Arc<DashMap<String, DocWithSyncKv>> {
         &self.docs
@@ -1369,8 +2115,22 @@ impl Server {
             search_tx: None,
             search_pending: None,
             doc_resolver: Arc::new(DocumentResolver::new()),
+            suggestion_anchors: Arc::new(crate::suggestion_anchors::SuggestionAnchors::new()),
             mcp_sessions: Arc::new(crate::mcp::session::SessionManager::new()),
             mcp_api_key: None,
+            mcp_keys: crate::mcp::auth::McpKeyring::default(),
+            mcp_tool_timeout: DEFAULT_MCP_TOOL_TIMEOUT,
+            mcp_audit: Arc::new(crate::mcp::audit::AuditLog::new(None)),
+            auth_audit: Arc::new(crate::auth_audit::AuthAuditLog::new(
+                crate::auth_audit::AUTH_AUDIT_PREFIX,
+                None,
+            )),
+            mcp_limits: crate::mcp::limits::McpLimiter::default(),
+            mcp_presence: crate::mcp::presence::AgentPresence::default(),
+            acl: crate::acl::Acl::default(),
+            oidc: None,
+            live_tokens: Arc::new(DashMap::new()),
+            next_live_token: std::sync::atomic::AtomicU64::new(0),
         })
     }
 
@@ -1458,16 +2218,87 @@ impl Server {
         Ok(())
     }
 
-    pub async fn load_doc_with_user(
+    /// Stored versions of a document, newest first. Errors if the store keeps
+    /// no version history.
+    pub async fn doc_versions(
         &self,
         doc_id: &str,
-        routing_channel: Option<String>,
-        user: Option<String>,
-    ) -> Result<()> {
-        Self::validate_doc_id(doc_id)?;
-        let (send, recv) = channel(1024);
-
-        // Determine routing channel: use provided channel or fallback to doc_id
+    ) -> Result<Vec<y_sweet_core::store::VersionInfo>> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| anyhow!("No store configured"))?;
+        let mut versions = store
+            .list_versions(&format!("{}/data.ysweet", doc_id))
+            .await?;
+        versions.sort_by(|a, b| b.last_modified.cmp(&a.last_modified));
+        Ok(versions)
+    }
+
+    /// The text of a stored version of a document, or None if the store has
+    /// no such version.
+    pub async fn doc_version_text(&self, doc_id: &str, version_id: &str) -> Result<Option<String>> {
+        use yrs_kvstore::DocOps;
+
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| anyhow!("No store configured"))?;
+        let Some(snapshot) = store
+            .get_version(&format!("{}/data.ysweet", doc_id), version_id)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let sync_kv = SyncKv::from_snapshot(doc_id, &snapshot)?;
+        let doc = yrs::Doc::new();
+        {
+            let mut txn = doc.transact_mut();
+            sync_kv
+                .load_doc(y_sweet_core::doc_connection::DOC_NAME, &mut txn)
+                .map_err(|_| anyhow!("Failed to load version {} of {}", version_id, doc_id))?;
+        }
+        let txn = doc.transact();
+        Ok(Some(
+            txn.get_text("contents")
+                .map(|text| text.get_string(&txn))
+                .unwrap_or_default(),
+        ))
+    }
+
+    /// Size in bytes of an uploaded file, or None if the store does not have it.
+    pub async fn file_size(&self, doc_id: &str, file_hash: &str) -> Result<Option<u64>> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| anyhow!("No store configured"))?;
+        let files = store.list(&format!("files/{}/", doc_id)).await?;
+        Ok(files
+            .into_iter()
+            .find(|info| info.key == file_hash)
+            .map(|info| info.size))
+    }
+
+    /// Contents of an uploaded file, or None if the store does not have it.
+    pub async fn file_bytes(&self, doc_id: &str, file_hash: &str) -> Result<Option<Vec<u8>>> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| anyhow!("No store configured"))?;
+        Ok(store.get(&format!("files/{}/{}", doc_id, file_hash)).await?)
+    }
+
+    pub async fn load_doc_with_user(
+        &self,
+        doc_id: &str,
+        routing_channel: Option<String>,
+        user: Option<String>,
+    ) -> Result<()> {
+        Self::validate_doc_id(doc_id)?;
+        let (send, recv) = channel(1024);
+
+        // Determine routing channel: use provided channel or fallback to doc_id
         let routing_channel_name = routing_channel
             .clone()
             .unwrap_or_else(|| doc_id.to_string());
@@ -1480,6 +2311,7 @@ impl Server {
             let link_indexer_for_callback = self.link_indexer.clone();
             let search_tx_for_callback = self.search_tx.clone();
             let search_pending_for_callback = self.search_pending.clone();
+            let mcp_sessions_for_callback = self.mcp_sessions.clone();
             let doc_key_for_indexer = doc_id.to_string();
 
             if let Some(dispatcher) = event_dispatcher {
@@ -1510,6 +2342,9 @@ impl Server {
                         // Step 2: Send via dispatcher
                         dispatcher.send_event(envelope);
 
+                        // Queue resource update notifications for subscribed MCP sessions
+                        mcp_sessions_for_callback.notify_doc_updated(&doc_key_for_indexer);
+
                         // Notify link indexer (if this update is not from the indexer itself)
                         if !is_indexer {
                             if let Some(ref indexer) = link_indexer_for_callback {
@@ -1558,9 +2393,11 @@ impl Server {
                     let indexer = link_indexer_for_callback.clone();
                     let search_tx = search_tx_for_callback.clone();
                     let search_pending = search_pending_for_callback.clone();
+                    let mcp_sessions = mcp_sessions_for_callback.clone();
                     let doc_key = doc_key_for_indexer.clone();
                     Some(
                         Arc::new(move |_event: DocumentUpdatedEvent, is_indexer: bool| {
+                            mcp_sessions.notify_doc_updated(&doc_key);
                             if !is_indexer {
                                 if let Some(ref indexer) = indexer {
                                     let indexer = indexer.clone();
@@ -2092,9 +2929,7 @@ impl Server {
     ) -> Result<(), AppError> {
         if let Some(auth) = &self.authenticator {
             if let Some(TypedHeader(headers::Authorization(bearer))) = auth_header {
-                if let Ok(()) =
-                    auth.verify_server_token(bearer.token(), current_time_epoch_millis())
-                {
+                if let Ok(Permission::Server) = verify_token(auth, bearer.token()) {
                     return Ok(());
                 }
             }
@@ -2142,39 +2977,35 @@ impl Server {
                 get(handle_socket_upgrade_full_path),
             )
             .route("/webhook/reload", post(reload_webhook_config_endpoint))
+            .route("/acl", get(handle_get_acl).put(handle_put_acl))
+            .route("/acl/reload", post(handle_reload_acl))
+            .route(
+                "/revocations",
+                get(handle_get_revocations)
+                    .post(handle_add_revocation)
+                    .delete(handle_remove_revocation),
+            )
+            .route("/revocations/reload", post(handle_reload_revocations))
+            .route(
+                "/api-keys",
+                get(handle_list_api_keys).post(handle_create_api_key),
+            )
+            .route("/api-keys/reload", post(handle_reload_api_keys))
+            .route("/api-keys/:name", delete(handle_delete_api_key))
+            .route("/.well-known/relay-keys", get(handle_relay_keys))
+            .route("/keys/rotate", post(handle_rotate_keys))
+            .route("/keys/reload", post(handle_reload_keys))
             .route("/search", get(handle_search))
             .route("/doc/move", post(handle_move_document))
             .route("/open/*path", get(handle_open_by_path))
-            .route("/suggestions", get(handle_suggestions));
-
-        // Only register /mcp if MCP_API_KEY is set
-        if let Some(ref key) = self.mcp_api_key {
-            // Bearer auth: POST/GET/DELETE /mcp (for Claude Code / .mcp.json)
-            let bearer_routes = Router::new()
-                .route(
-                    "/",
-                    post(crate::mcp::transport::handle_mcp_post)
-                        .get(crate::mcp::transport::handle_mcp_get)
-                        .delete(crate::mcp::transport::handle_mcp_delete),
-                )
-                .layer(middleware::from_fn_with_state(
-                    key.clone(),
-                    crate::mcp::transport::mcp_auth_middleware,
-                ));
-
-            // Path-key auth: POST/GET/DELETE /mcp/:key (for claude.ai connectors)
-            let path_key_routes = Router::new().route(
-                "/:key",
-                post(crate::mcp::transport::handle_mcp_post_with_key)
-                    .get(crate::mcp::transport::handle_mcp_get_with_key)
-                    .delete(crate::mcp::transport::handle_mcp_delete_with_key),
-            );
-
-            let mcp_routes = bearer_routes
-                .merge(path_key_routes)
-                .with_state(self.clone());
-            router = router.nest("/mcp", mcp_routes);
-        }
+            .route("/suggestions", get(handle_suggestions))
+            .route("/d/:doc_id/suggestions/rebase", post(handle_rebase_suggestions))
+            .route(
+                "/d/:doc_id/suggestions.patch",
+                get(handle_export_suggestions_patch).post(handle_import_suggestions_patch),
+            )
+            .route("/mcp-audit", get(handle_mcp_audit))
+            .route("/auth-audit", get(handle_auth_audit));
 
         // Only add file endpoints if a store is configured
         if let Some(store) = &self.store {
@@ -2203,6 +3034,47 @@ impl Server {
             }
         }
 
+        // MCP and login routes are left out: MCP keeps its own audit log, and
+        // the path-key routes carry a secret in the path
+        router = router.layer(middleware::from_fn_with_state(
+            self.clone(),
+            crate::auth_audit::record_auth_decisions,
+        ));
+
+        // Only register /mcp if some kind of MCP credential can be presented
+        if self.mcp_api_key.is_some() || !self.mcp_keys.is_empty() || self.authenticator.is_some() {
+            // Bearer auth: POST/GET/DELETE /mcp (for Claude Code / .mcp.json)
+            let bearer_routes = Router::new()
+                .route(
+                    "/",
+                    post(crate::mcp::transport::handle_mcp_post)
+                        .get(crate::mcp::transport::handle_mcp_get)
+                        .delete(crate::mcp::transport::handle_mcp_delete),
+                )
+                .layer(middleware::from_fn_with_state(
+                    self.clone(),
+                    crate::mcp::transport::mcp_auth_middleware,
+                ));
+
+            // Path-key auth: POST/GET/DELETE /mcp/:key (for claude.ai connectors),
+            // only when there are MCP keys to take there
+            let mut mcp_routes = bearer_routes;
+            if self.mcp_api_key.is_some() || !self.mcp_keys.is_empty() {
+                let path_key_routes = Router::new().route(
+                    "/:key",
+                    post(crate::mcp::transport::handle_mcp_post_with_key)
+                        .get(crate::mcp::transport::handle_mcp_get_with_key)
+                        .delete(crate::mcp::transport::handle_mcp_delete_with_key),
+                );
+                mcp_routes = mcp_routes.merge(path_key_routes);
+            }
+            router = router.nest("/mcp", mcp_routes.with_state(self.clone()));
+        }
+
+        if self.oidc.is_some() {
+            router = router.nest("/auth", crate::oidc::routes().with_state(self.clone()));
+        }
+
         router
             .layer(DefaultBodyLimit::max(10 * 1024 * 1024)) // 10MB default
             .with_state(self.clone())
@@ -2282,18 +3154,29 @@ impl Server {
         s.serve_internal(listener, false, routes).await
     }
 
-    fn verify_doc_token(&self, token: Option<&str>, doc: &str) -> Result<Authorization, AppError> {
+    /// The access `token` grants to the document `doc`, capped by the access
+    /// control list, along with the permission it was verified to. There is
+    /// no permission when auth is not configured.
+    fn verify_doc_token(
+        &self,
+        token: Option<&str>,
+        doc: &str,
+    ) -> Result<(Authorization, Option<Permission>), AppError> {
         if let Some(authenticator) = &self.authenticator {
             if let Some(token) = token {
-                let authorization = authenticator
-                    .verify_doc_token(token, doc, current_time_epoch_millis())
+                let permission = verify_token(authenticator, token)
                     .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
-                Ok(authorization)
+                let authorization = permission
+                    .doc_authorization(doc)
+                    .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+                let authorization =
+                    self.acl_authorization(doc, acl_principal(token, &permission), authorization)?;
+                Ok((authorization, Some(permission)))
             } else {
                 Err((StatusCode::UNAUTHORIZED, anyhow!("No token provided.")))?
             }
         } else {
-            Ok(Authorization::Full)
+            Ok((Authorization::Full, None))
         }
     }
 
@@ -2318,7 +3201,7 @@ async fn get_doc_as_update(
 ) -> Result<Response, AppError> {
     // All authorization types allow reading the document.
     let token = get_token_from_header(auth_header);
-    let _ = server_state.verify_doc_token(token.as_deref(), &doc_id)?;
+    server_state.verify_doc_token(token.as_deref(), &doc_id)?;
 
     let dwskv = server_state
         .get_or_create_doc(&doc_id)
@@ -2364,7 +3247,7 @@ async fn update_doc(
     body: Bytes,
 ) -> Result<Response, AppError> {
     let token = get_token_from_header(auth_header);
-    let authorization = server_state.verify_doc_token(token.as_deref(), &doc_id)?;
+    let (authorization, _) = server_state.verify_doc_token(token.as_deref(), &doc_id)?;
     update_doc_inner(doc_id, server_state, authorization, body).await
 }
 
@@ -2398,39 +3281,10 @@ async fn update_doc_single(
 ) -> Result<Response, AppError> {
     let doc_id = server_state.get_single_doc_id()?;
     let token = get_token_from_header(auth_header);
-    let authorization = server_state.verify_doc_token(token.as_deref(), &doc_id)?;
+    let (authorization, _) = server_state.verify_doc_token(token.as_deref(), &doc_id)?;
     update_doc_inner(doc_id, server_state, authorization, body).await
 }
 
-async fn handle_socket_upgrade(
-    ws: WebSocketUpgrade,
-    Path(doc_id): Path<String>,
-    authorization: Authorization,
-    State(server_state): State<Arc<Server>>,
-) -> Result<Response, AppError> {
-    handle_socket_upgrade_with_channel(ws, Path(doc_id), authorization, None, State(server_state))
-        .await
-}
-
-async fn handle_socket_upgrade_with_channel(
-    ws: WebSocketUpgrade,
-    Path(doc_id): Path<String>,
-    authorization: Authorization,
-    routing_channel: Option<String>,
-    State(server_state): State<Arc<Server>>,
-) -> Result<Response, AppError> {
-    handle_socket_upgrade_with_channel_and_user(
-        ws,
-        Path(doc_id),
-        authorization,
-        routing_channel,
-        None,
-        None, // No token available at this level
-        State(server_state),
-    )
-    .await
-}
-
 async fn handle_socket_upgrade_with_channel_and_user(
     ws: WebSocketUpgrade,
     Path(doc_id): Path<String>,
@@ -2440,6 +3294,12 @@ async fn handle_socket_upgrade_with_channel_and_user(
     token: Option<String>,
     State(server_state): State<Arc<Server>>,
 ) -> Result<Response, AppError> {
+    let principal = user
+        .as_deref()
+        .zip(token.as_deref())
+        .map(|(user, token)| AclPrincipal::of_token(token, user));
+    let authorization = server_state.acl_authorization(&doc_id, principal, authorization)?;
+
     if !matches!(authorization, Authorization::Full) && !server_state.docs.contains_key(&doc_id) {
         return Err(AppError(
             StatusCode::NOT_FOUND,
@@ -2448,22 +3308,25 @@ async fn handle_socket_upgrade_with_channel_and_user(
     }
 
     // Extract expiration time from token
-    let expiration_time = if let Some(authenticator) = &server_state.authenticator {
-        if let Some(token_str) = token.as_deref() {
-            authenticator
-                .decode_token(token_str)
-                .ok()
-                .and_then(|payload| payload.expiration_millis)
-                .map(|exp| exp.0)
-        } else {
-            None
+    let expiration_time = token
+        .as_deref()
+        .and_then(|token| server_state.token_expiration(token));
+
+    let live_token = server_state.watch_token(token.as_deref(), user.as_deref());
+
+    // Clients may present a fresh token on the open connection
+    let token_refresher = {
+        let server_state = server_state.clone();
+        let doc_id = doc_id.clone();
+        let user = user.clone();
+        let live_token_id = live_token.as_ref().map(|live_token| live_token.id);
+        move |token: &str| {
+            server_state.refresh_socket_token(token, &doc_id, user.as_deref(), live_token_id)
         }
-    } else {
-        None
     };
 
     let dwskv = server_state
-        .get_or_create_doc_with_channel_and_user(&doc_id, routing_channel, user)
+        .get_or_create_doc_with_channel_and_user(&doc_id, routing_channel, user.clone())
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
     let awareness = dwskv.awareness();
@@ -2477,7 +3340,10 @@ async fn handle_socket_upgrade_with_channel_and_user(
             socket,
             awareness,
             authorization,
+            user,
             expiration_time,
+            token_refresher,
+            live_token,
             cancellation_token,
             sync_protocol_event_sender,
             doc_id_clone,
@@ -2497,17 +3363,15 @@ async fn handle_socket_upgrade_deprecated(
     );
     let (permission, channel) = if let Some(authenticator) = &server_state.authenticator {
         if let Some(token) = params.token.as_deref() {
-            authenticator
-                .verify_token_with_channel(token, current_time_epoch_millis())
-                .map_err(|e| {
-                    // Record authentication failure metric
-                    server_state.metrics.record_auth_failure(
-                        e.to_metric_label(),
-                        "websocket_upgrade_deprecated",
-                        "GET",
-                    );
-                    (StatusCode::UNAUTHORIZED, e)
-                })?
+            verify_token_with_channel(authenticator, token).map_err(|e| {
+                // Record authentication failure metric
+                server_state.metrics.record_auth_failure(
+                    e.to_metric_label(),
+                    "websocket_upgrade_deprecated",
+                    "GET",
+                );
+                (StatusCode::UNAUTHORIZED, e)
+            })?
         } else {
             // Record missing token when authenticator is present but no token provided
             server_state
@@ -2577,14 +3441,10 @@ async fn handle_socket_upgrade_full_path(
 
     let (permission, channel) = if let Some(authenticator) = &server_state.authenticator {
         if let Some(token) = params.token.as_deref() {
-            let current_time = current_time_epoch_millis();
-
-            authenticator
-                .verify_token_with_channel(token, current_time)
-                .map_err(|e| {
-                    tracing::debug!("Token verification failed: {:?}", e);
-                    (StatusCode::UNAUTHORIZED, e)
-                })?
+            verify_token_with_channel(authenticator, token).map_err(|e| {
+                tracing::debug!("Token verification failed: {:?}", e);
+                (StatusCode::UNAUTHORIZED, e)
+            })?
         } else {
             (y_sweet_core::auth::Permission::Server, None)
         }
@@ -2647,20 +3507,37 @@ async fn handle_socket_upgrade_single(
     }
 
     let token = get_token_from_header(auth_header);
-    let authorization = server_state.verify_doc_token(token.as_deref(), &doc_id)?;
-    handle_socket_upgrade(ws, Path(single_doc_id), authorization, State(server_state)).await
+    let (authorization, _) = server_state.verify_doc_token(token.as_deref(), &doc_id)?;
+    handle_socket_upgrade_with_channel_and_user(
+        ws,
+        Path(single_doc_id),
+        authorization,
+        None,
+        None,
+        token,
+        State(server_state),
+    )
+    .await
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_socket(
     socket: WebSocket,
     awareness: Arc<RwLock<Awareness>>,
     authorization: Authorization,
+    user: Option<String>,
     expiration_time: Option<u64>,
+    token_refresher: impl Fn(&str) -> TokenRefresh + Send + Sync + 'static,
+    live_token: Option<LiveToken>,
     cancellation_token: CancellationToken,
     sync_protocol_event_sender: Arc<SyncProtocolEventSender>,
     doc_id: String,
     metrics: Arc<RelayMetrics>,
 ) {
+    let revoked = live_token
+        .as_ref()
+        .map(|live_token| live_token.revoked.clone())
+        .unwrap_or_default();
     let (mut sink, mut stream) = socket.split();
     let (send, mut recv) = channel(1024);
 
@@ -2671,16 +3548,20 @@ async fn handle_socket(
     });
 
     let send_clone = send.clone();
-    let connection = Arc::new(DocConnection::new_with_expiration(
-        awareness,
-        authorization,
-        expiration_time,
-        move |bytes| {
-            if let Err(e) = send_clone.try_send(Message::Binary(bytes.to_vec())) {
-                tracing::warn!(?e, "Error sending message");
-            }
-        },
-    ));
+    let connection = Arc::new(
+        DocConnection::new_with_expiration(
+            awareness,
+            authorization,
+            expiration_time,
+            move |bytes| {
+                if let Err(e) = send_clone.try_send(Message::Binary(bytes.to_vec())) {
+                    tracing::warn!(?e, "Error sending message");
+                }
+            },
+        )
+        .with_token_refresh(token_refresher)
+        .with_user(user),
+    );
 
     // Register the connection with the sync protocol event sender
     sync_protocol_event_sender.register_doc_connection(doc_id.clone(), Arc::downgrade(&connection));
@@ -2720,11 +3601,54 @@ async fn handle_socket(
                         })));
                         break;
                     }
+                    Err(e) if matches!(
+                        e.downcast_ref::<SyncError>(),
+                        Some(SyncError::TokenRefreshRejected { .. })
+                    ) => {
+                        tracing::warn!(
+                            doc_id = %doc_id,
+                            error = %e,
+                            "Closing connection after a rejected token refresh"
+                        );
+                        let _ = send.try_send(Message::Close(Some(CloseFrame {
+                            code: 1008,
+                            reason: "Token refresh rejected".into(),
+                        })));
+                        break;
+                    }
+                    Err(e) if matches!(
+                        e.downcast_ref::<SyncError>(),
+                        Some(SyncError::UpdateRejected { .. })
+                    ) => {
+                        // The client's later updates build on the rejected one, so
+                        // it has to reconnect and resynchronize
+                        tracing::warn!(
+                            doc_id = %doc_id,
+                            error = %e,
+                            "Closing connection after a rejected update"
+                        );
+                        let _ = send.try_send(Message::Close(Some(CloseFrame {
+                            code: 1008,
+                            reason: "Update rejected".into(),
+                        })));
+                        break;
+                    }
                     Err(e) => {
                         tracing::warn!(?e, "Error handling message");
                     }
                 }
             }
+            _ = revoked.cancelled() => {
+                tracing::warn!(
+                    doc_id = %doc_id,
+                    "Closing connection due to token revocation"
+                );
+                let _ = send.try_send(Message::Close(Some(CloseFrame {
+                    code: 1008,
+                    reason: "Token revoked".into(),
+                })));
+                break;
+            }
             _ = cancellation_token.cancelled() => {
                 tracing::debug!("Closing doc connection due to server cancel...");
                 break;
@@ -2763,27 +3687,59 @@ async fn ready() -> Result<Json<Value>, AppError> {
     Ok(Json(json!({"ok": true})))
 }
 
+/// Redirect to the document at `path`. The token is checked before the path
+/// is looked up, and a document the token can't read is reported as not
+/// found, so the file tree can't be probed through this route.
 async fn handle_open_by_path(
     State(server_state): State<Arc<Server>>,
     Path(path): Path<String>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
     request: Request,
 ) -> Result<Response, AppError> {
-    match server_state.doc_resolver().resolve_path(&path) {
-        Some(info) => {
-            let short_uuid = &info.uuid[..8.min(info.uuid.len())];
-            let encoded_path = path.replace(' ', "-");
-            let mut redirect_url = format!("/{}/{}", short_uuid, encoded_path);
-            if let Some(query) = request.uri().query() {
-                redirect_url.push('?');
-                redirect_url.push_str(query);
-            }
-            Ok(axum::response::Redirect::temporary(&redirect_url).into_response())
+    let token = get_token_from_header(auth_header);
+    let verified = match &server_state.authenticator {
+        Some(authenticator) => {
+            let token = token
+                .as_deref()
+                .ok_or_else(|| AppError(StatusCode::UNAUTHORIZED, anyhow!("No token provided.")))?;
+            let permission =
+                verify_token(authenticator, token).map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+            Some((token, permission))
         }
-        None => Err(AppError(
+        None => None,
+    };
+
+    let not_found = || {
+        AppError(
             StatusCode::NOT_FOUND,
             anyhow!("No document found at path '{}'", path),
-        )),
+        )
+    };
+    let info = server_state
+        .doc_resolver()
+        .resolve_path(&path)
+        .ok_or_else(not_found)?;
+    if let Some((token, permission)) = &verified {
+        let authorization = permission
+            .doc_authorization(&info.doc_id)
+            .map_err(|_| not_found())?;
+        server_state
+            .acl_authorization(
+                &info.doc_id,
+                acl_principal(token, permission),
+                authorization,
+            )
+            .map_err(|_| not_found())?;
+    }
+
+    let short_uuid = &info.uuid[..8.min(info.uuid.len())];
+    let encoded_path = path.replace(' ', "-");
+    let mut redirect_url = format!("/{}/{}", short_uuid, encoded_path);
+    if let Some(query) = request.uri().query() {
+        redirect_url.push('?');
+        redirect_url.push_str(query);
     }
+    Ok(axum::response::Redirect::temporary(&redirect_url).into_response())
 }
 
 async fn handle_search(
@@ -2838,15 +3794,22 @@ async fn handle_search(
 /// Scan all documents in a folder for CriticMarkup suggestions.
 ///
 /// GET /suggestions?folder_id=...
-/// Response: { "files": [{ "path": "...", "doc_id": "...", "suggestions": [...] }] }
+/// Response: { "files": [{ "path": "...", "doc_id": "...", "suggestions": [...], "stale": [...] }] }
+///
+/// `stale` lists the suggestions not known to still apply: conflicting,
+/// orphaned, or unknown because they haven't been anchored since the server
+/// started. Rebasing a document anchors its unknown suggestions.
+///
+/// Takes any token that can read the folder document; documents the token
+/// cannot read are left out.
 async fn handle_suggestions(
     auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
     State(server_state): State<Arc<Server>>,
     Query(params): Query<SuggestionsQuery>,
 ) -> Result<Json<Value>, AppError> {
-    server_state.check_auth(auth_header)?;
-
+    let token = get_token_from_header(auth_header);
     let folder_id = &params.folder_id;
+    server_state.verify_doc_token(token.as_deref(), folder_id)?;
 
     // Load the folder doc and get content UUIDs from filemeta_v0
     server_state
@@ -2896,11 +3859,18 @@ async fn handle_suggestions(
             .cloned()
             .unwrap_or_else(|| content_uuid.clone());
 
+        if server_state
+            .verify_doc_token(token.as_deref(), &doc_id)
+            .is_err()
+        {
+            continue;
+        }
+
         // Load doc content
         if server_state.ensure_doc_loaded(&doc_id).await.is_err() {
             continue;
         }
-        let content = {
+        let (content, anchors) = {
             let awareness = {
                 let Some(doc_ref) = server_state.docs.get(&doc_id) else {
                     continue;
@@ -2909,14 +3879,19 @@ async fn handle_suggestions(
             }; // DashMap shard lock released
             let guard = awareness.read().unwrap_or_else(|e| e.into_inner());
             let txn = guard.doc.transact();
-            match txn.get_text("contents") {
-                Some(text) => text.get_string(&txn),
-                None => continue,
-            }
+            let Some(text) = txn.get_text("contents") else {
+                continue;
+            };
+            let anchors = server_state.suggestion_anchors.report(&doc_id, &txn, &text);
+            (text.get_string(&txn), anchors)
         };
 
         let suggestions = critic_scanner::scan_suggestions(&content);
-        if suggestions.is_empty() {
+        let stale: Vec<_> = anchors
+            .into_iter()
+            .filter(|a| a.status != crate::suggestion_anchors::AnchorStatus::Clean)
+            .collect();
+        if suggestions.is_empty() && stale.is_empty() {
             continue;
         }
 
@@ -2924,68 +3899,357 @@ async fn handle_suggestions(
             "path": path,
             "doc_id": doc_id,
             "suggestions": suggestions,
+            "stale": stale,
         }));
     }
 
     Ok(Json(serde_json::json!({ "files": files })))
 }
 
-/// Move a document to a new path within or across folders.
+/// Rebase stale suggestions in a document against its current text.
 ///
-/// POST /doc/move
-/// Body: { "uuid": "...", "new_path": "/Biology/Photosynthesis.md", "target_folder": "Lens Edu" }
-/// Response: { "old_path", "new_path", "old_folder", "new_folder", "links_rewritten" }
-async fn handle_move_document(
+/// POST /d/:doc_id/suggestions/rebase
+/// Response: { "rebased": N, "dropped": N, "stale": [...] }
+async fn handle_rebase_suggestions(
     auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
     State(server_state): State<Arc<Server>>,
-    Json(body): Json<MoveDocRequest>,
-) -> Result<Json<MoveDocResponse>, AppError> {
+    Path(doc_id): Path<String>,
+) -> Result<Json<Value>, AppError> {
     server_state.check_auth(auth_header)?;
-    let result = server_state
-        .move_document(&body.uuid, &body.new_path, body.target_folder.as_deref())
+
+    server_state
+        .ensure_doc_loaded(&doc_id)
         .await
-        .map_err(AppError::from)?;
+        .map_err(|e| AppError(StatusCode::NOT_FOUND, anyhow!("Document not found: {}", e)))?;
 
-    Ok(Json(MoveDocResponse {
-        old_path: result.old_path,
-        new_path: result.new_path,
-        old_folder: result.old_folder_name,
-        new_folder: result.new_folder_name,
-        links_rewritten: result.links_rewritten,
-    }))
+    let (awareness, sync_kv) = {
+        let doc_ref = server_state
+            .docs
+            .get(&doc_id)
+            .ok_or_else(|| AppError(StatusCode::NOT_FOUND, anyhow!("Document not loaded")))?;
+        (doc_ref.awareness(), doc_ref.sync_kv())
+    };
+
+    let (summary, anchors) = {
+        let mut guard = awareness.write().unwrap_or_else(|e| e.into_inner());
+        let mut txn = guard.doc.transact_mut();
+        let text = txn.get_or_insert_text("contents");
+        server_state
+            .suggestion_anchors
+            .rebase(&doc_id, &mut txn, &text)
+    };
+
+    if summary.rebased > 0 || summary.dropped > 0 {
+        if let Err(e) = sync_kv.persist().await {
+            tracing::error!("Failed to persist rebase for {}: {:?}", doc_id, e);
+        }
+    }
+
+    let stale: Vec<_> = anchors
+        .into_iter()
+        .filter(|a| a.status != crate::suggestion_anchors::AnchorStatus::Clean)
+        .collect();
+
+    Ok(Json(json!({
+        "rebased": summary.rebased,
+        "dropped": summary.dropped,
+        "stale": stale,
+    })))
 }
 
-async fn new_doc(
-    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+/// Read a document's raw text and its display path (falls back to the doc_id).
+async fn doc_text_and_path(
+    server_state: &Arc<Server>,
+    doc_id: &str,
+) -> Result<(String, String), AppError> {
+    server_state
+        .ensure_doc_loaded(doc_id)
+        .await
+        .map_err(|e| AppError(StatusCode::NOT_FOUND, anyhow!("Document not found: {}", e)))?;
+
+    let awareness = {
+        let doc_ref = server_state
+            .docs
+            .get(doc_id)
+            .ok_or_else(|| AppError(StatusCode::NOT_FOUND, anyhow!("Document not loaded")))?;
+        doc_ref.awareness()
+    };
+    let content = {
+        let guard = awareness.read().unwrap_or_else(|e| e.into_inner());
+        let txn = guard.doc.transact();
+        txn.get_text("contents")
+            .map(|text| text.get_string(&txn))
+            .unwrap_or_default()
+    };
+
+    // Compound doc_id is "{relay_id}-{uuid}"; the resolver is keyed by uuid
+    let path = doc_id
+        .get(37..)
+        .and_then(|uuid| server_state.doc_resolver.path_for_uuid(uuid))
+        .unwrap_or_else(|| doc_id.to_string());
+
+    Ok((content, path))
+}
+
+/// Export pending suggestions as a unified diff (base view vs accepted view).
+///
+/// GET /d/:doc_id/suggestions.patch
+/// Response: text/x-diff, one hunk per suggestion with the author after the hunk header
+async fn handle_export_suggestions_patch(
     State(server_state): State<Arc<Server>>,
-    Json(body): Json<DocCreationRequest>,
-) -> Result<Json<NewDocResponse>, AppError> {
+    Path(doc_id): Path<String>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+) -> Result<Response, AppError> {
     let token = get_token_from_header(auth_header);
+    server_state.verify_doc_token(token.as_deref(), &doc_id)?;
 
-    if let Some(authenticator) = &server_state.authenticator {
-        if let Some(token) = token.as_deref() {
-            // First try server token
-            if authenticator
-                .verify_server_token(token, current_time_epoch_millis())
-                .is_ok()
-            {
-                // Server token allows creating any document
-            } else {
-                // Try prefix token - we need to check if the doc_id matches the prefix
-                if let Some(doc_id) = &body.doc_id {
-                    let permission = authenticator
-                        .verify_token_auto(token, current_time_epoch_millis())
-                        .map_err(|auth_error| {
-                            server_state.metrics.record_auth_failure(
-                                auth_error.to_metric_label(),
-                                "new_doc",
-                                "POST",
-                            );
-                            AppError(
-                                StatusCode::UNAUTHORIZED,
-                                anyhow!("Invalid token: {}", auth_error),
-                            )
-                        })?;
+    let (content, path) = doc_text_and_path(&server_state, &doc_id).await?;
+    let patch = crate::critic_patch::render_unified_diff(&content, &path);
+
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, "text/x-diff; charset=utf-8")],
+        patch,
+    )
+        .into_response())
+}
+
+#[derive(Deserialize)]
+struct ImportPatchQuery {
+    author: Option<String>,
+}
+
+/// Import a unified diff as CriticMarkup suggestions. Needs Suggest access or
+/// above. The suggestions are credited to the token's user; only server
+/// tokens may name the author.
+///
+/// POST /d/:doc_id/suggestions.patch?author=Name
+/// Body: unified diff against the document's accepted view
+/// Response: { "hunks_applied": N }
+async fn handle_import_suggestions_patch(
+    State(server_state): State<Arc<Server>>,
+    Path(doc_id): Path<String>,
+    Query(params): Query<ImportPatchQuery>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+    body: String,
+) -> Result<Json<Value>, AppError> {
+    let token = get_token_from_header(auth_header);
+    let (authorization, permission) = server_state.verify_doc_token(token.as_deref(), &doc_id)?;
+    if !matches!(authorization, Authorization::Suggest | Authorization::Full) {
+        return Err(AppError(StatusCode::FORBIDDEN, anyhow!("Unauthorized.")));
+    }
+
+    let author = match &permission {
+        Some(permission) if *permission != Permission::Server => {
+            permission.user().unwrap_or("Patch").to_string()
+        }
+        _ => params.author.unwrap_or_else(|| "Patch".to_string()),
+    };
+    // Loads the doc if GC evicted it; the text is re-read under the write lock below
+    let _ = doc_text_and_path(&server_state, &doc_id).await?;
+
+    let (awareness, sync_kv) = {
+        let doc_ref = server_state
+            .docs
+            .get(&doc_id)
+            .ok_or_else(|| AppError(StatusCode::NOT_FOUND, anyhow!("Document not loaded")))?;
+        (doc_ref.awareness(), doc_ref.sync_kv())
+    };
+
+    let applied = {
+        let mut guard = awareness.write().unwrap_or_else(|e| e.into_inner());
+        let mut txn = guard.doc.transact_mut();
+        let text = txn.get_or_insert_text("contents");
+        let raw = text.get_string(&txn);
+
+        let edits = crate::critic_patch::apply_unified_diff(
+            &raw,
+            &body,
+            &author,
+            current_time_epoch_millis(),
+        )
+        .map_err(|e| AppError(StatusCode::UNPROCESSABLE_ENTITY, anyhow!(e)))?;
+
+        for edit in &edits {
+            text.remove_range(&mut txn, edit.raw_offset as u32, edit.raw_len as u32);
+            text.insert(&mut txn, edit.raw_offset as u32, &edit.replacement);
+        }
+        server_state
+            .suggestion_anchors
+            .refresh(&doc_id, &mut txn, &text);
+        edits.len()
+    };
+
+    if applied > 0 {
+        if let Err(e) = sync_kv.persist().await {
+            tracing::error!("Failed to persist patch import for {}: {:?}", doc_id, e);
+        }
+    }
+
+    Ok(Json(json!({ "hunks_applied": applied })))
+}
+
+#[derive(Deserialize)]
+struct McpAuditQuery {
+    /// Start of the range in epoch milliseconds (default: 24 hours before `to`).
+    from: Option<u64>,
+    /// End of the range in epoch milliseconds (default: now).
+    to: Option<u64>,
+    session: Option<String>,
+    limit: Option<usize>,
+}
+
+const AUDIT_DEFAULT_LIMIT: usize = 1000;
+const AUDIT_MAX_LIMIT: usize = 10_000;
+
+/// Query the audit log of MCP tool calls. Requires a server token.
+///
+/// GET /mcp-audit?from=<ms>&to=<ms>&session=<id>&limit=<n>
+/// Response: { "entries": [ { timestamp, session_id, principal, client_info, tool,
+///             arguments, doc_ids, outcome, error, duration_ms }, ... ] }
+async fn handle_mcp_audit(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+    Query(params): Query<McpAuditQuery>,
+) -> Result<Json<Value>, AppError> {
+    server_state.check_auth(auth_header)?;
+
+    let to = params.to.unwrap_or_else(current_time_epoch_millis);
+    let from = params
+        .from
+        .unwrap_or_else(|| to.saturating_sub(24 * 60 * 60 * 1000));
+    if from > to {
+        return Err(AppError(
+            StatusCode::BAD_REQUEST,
+            anyhow!("`from` must not be after `to`"),
+        ));
+    }
+    let limit = params
+        .limit
+        .unwrap_or(AUDIT_DEFAULT_LIMIT)
+        .min(AUDIT_MAX_LIMIT);
+
+    let entries = server_state
+        .mcp_audit
+        .query(from, to, params.session.as_deref(), limit)
+        .await
+        .map_err(|e| AppError(StatusCode::BAD_REQUEST, e))?;
+
+    Ok(Json(json!({ "entries": entries })))
+}
+
+#[derive(Deserialize)]
+struct AuthAuditQuery {
+    /// Start of the range in epoch milliseconds (default: 24 hours before `to`).
+    from: Option<u64>,
+    /// End of the range in epoch milliseconds (default: now).
+    to: Option<u64>,
+    principal: Option<String>,
+    /// `allow` or `deny`.
+    decision: Option<Decision>,
+    limit: Option<usize>,
+}
+
+/// Query the audit trail of authorization decisions. Requires a server token.
+///
+/// GET /auth-audit?from=<ms>&to=<ms>&principal=<user>&decision=deny&limit=<n>
+/// Response: { "entries": [ { timestamp, principal, token_kind, key_id, resource,
+///             action, decision, reason }, ... ] }
+async fn handle_auth_audit(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+    Query(params): Query<AuthAuditQuery>,
+) -> Result<Json<Value>, AppError> {
+    server_state.check_auth(auth_header)?;
+
+    let to = params.to.unwrap_or_else(current_time_epoch_millis);
+    let from = params
+        .from
+        .unwrap_or_else(|| to.saturating_sub(24 * 60 * 60 * 1000));
+    if from > to {
+        return Err(AppError(
+            StatusCode::BAD_REQUEST,
+            anyhow!("`from` must not be after `to`"),
+        ));
+    }
+    let limit = params
+        .limit
+        .unwrap_or(AUDIT_DEFAULT_LIMIT)
+        .min(AUDIT_MAX_LIMIT);
+
+    let matches = |entry: &AuthDecision| {
+        let principal = params.principal.as_deref();
+        (principal.is_none() || entry.principal.as_deref() == principal)
+            && params
+                .decision
+                .map_or(true, |decision| entry.decision == decision)
+    };
+    let entries = server_state
+        .auth_audit
+        .query(from, to, matches, limit)
+        .await
+        .map_err(|e| AppError(StatusCode::BAD_REQUEST, e))?;
+
+    Ok(Json(json!({ "entries": entries })))
+}
+
+/// Move a document to a new path within or across folders.
+///
+/// POST /doc/move
+/// Body: { "uuid": "...", "new_path": "/Biology/Photosynthesis.md", "target_folder": "Lens Edu" }
+/// Response: { "old_path", "new_path", "old_folder", "new_folder", "links_rewritten" }
+async fn handle_move_document(
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+    State(server_state): State<Arc<Server>>,
+    Json(body): Json<MoveDocRequest>,
+) -> Result<Json<MoveDocResponse>, AppError> {
+    let token = get_token_from_header(auth_header);
+    server_state.authorize_move(
+        token.as_deref(),
+        &body.uuid,
+        &body.new_path,
+        body.target_folder.as_deref(),
+    )?;
+    let result = server_state
+        .move_document(&body.uuid, &body.new_path, body.target_folder.as_deref())
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(Json(MoveDocResponse {
+        old_path: result.old_path,
+        new_path: result.new_path,
+        old_folder: result.old_folder_name,
+        new_folder: result.new_folder_name,
+        links_rewritten: result.links_rewritten,
+    }))
+}
+
+async fn new_doc(
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+    State(server_state): State<Arc<Server>>,
+    Json(body): Json<DocCreationRequest>,
+) -> Result<Json<NewDocResponse>, AppError> {
+    let token = get_token_from_header(auth_header);
+
+    if let Some(authenticator) = &server_state.authenticator {
+        if let Some(token) = token.as_deref() {
+            let verified = verify_token(authenticator, token);
+            // First try server token
+            if matches!(verified, Ok(Permission::Server)) {
+                // Server token allows creating any document
+            } else {
+                // Try prefix token - we need to check if the doc_id matches the prefix
+                if let Some(doc_id) = &body.doc_id {
+                    let permission = verified.map_err(|auth_error| {
+                        server_state.metrics.record_auth_failure(
+                            auth_error.to_metric_label(),
+                            "new_doc",
+                            "POST",
+                        );
+                        AppError(
+                            StatusCode::UNAUTHORIZED,
+                            anyhow!("Invalid token: {}", auth_error),
+                        )
+                    })?;
 
                     match permission {
                         Permission::Prefix(prefix_perm) => {
@@ -3202,15 +4466,20 @@ async fn auth_doc(
     }))
 }
 
+/// Resolve a doc ID prefix to the one document it matches. Takes any token
+/// that can read that document.
 async fn resolve_doc(
     auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
     State(server_state): State<Arc<Server>>,
     Path(prefix): Path<String>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    server_state.check_auth(auth_header)?;
+    let token = get_token_from_header(auth_header);
 
     match server_state.resolve_doc_id(&prefix).await {
-        Some(doc_id) => Ok(Json(serde_json::json!({ "docId": doc_id }))),
+        Some(doc_id) => {
+            server_state.verify_doc_token(token.as_deref(), &doc_id)?;
+            Ok(Json(serde_json::json!({ "docId": doc_id })))
+        }
         None => Err(AppError(
             StatusCode::NOT_FOUND,
             anyhow!("No unique doc matching prefix '{}'", prefix),
@@ -3218,6 +4487,32 @@ async fn resolve_doc(
     }
 }
 
+/// Verify `token`, noting what it was verified to for the audit record of
+/// the request being handled.
+fn verify_token(authenticator: &Authenticator, token: &str) -> Result<Permission, AuthError> {
+    let verified = authenticator.verify_token_auto(token, current_time_epoch_millis());
+    crate::auth_audit::note_verification(verified.as_ref());
+    verified
+}
+
+/// The principal the access control list knows the holder of `token`, verified
+/// to `permission`, by.
+fn acl_principal<'a>(token: &str, permission: &'a Permission) -> Option<AclPrincipal<'a>> {
+    permission
+        .user()
+        .map(|user| AclPrincipal::of_token(token, user))
+}
+
+/// Like `verify_token`, for a token that may name a routing channel.
+fn verify_token_with_channel(
+    authenticator: &Authenticator,
+    token: &str,
+) -> Result<(Permission, Option<String>), AuthError> {
+    let verified = authenticator.verify_token_with_channel(token, current_time_epoch_millis());
+    crate::auth_audit::note_verification(verified.as_ref().map(|(permission, _)| permission));
+    verified
+}
+
 fn get_token_from_header(
     auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
 ) -> Option<String> {
@@ -3243,9 +4538,13 @@ async fn handle_file_upload_url(
     if let Some(authenticator) = &server_state.authenticator {
         if let Some(token) = token.as_deref() {
             // Verify token is for this doc_id
-            let auth = authenticator
-                .verify_file_token_for_doc(token, &doc_id, current_time_epoch_millis())
+            let permission = verify_token(authenticator, token)
+                .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
+            let auth = permission
+                .doc_authorization(&doc_id)
                 .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
+            let auth =
+                server_state.acl_authorization(&doc_id, acl_principal(token, &permission), auth)?;
 
             // Only allow Full permission to upload
             if !matches!(auth, Authorization::Full) {
@@ -3255,11 +4554,6 @@ async fn handle_file_upload_url(
                 ));
             }
 
-            // Verify the token and get the file metadata
-            let permission = authenticator
-                .verify_token_auto(token, current_time_epoch_millis())
-                .map_err(|_| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token")))?;
-
             if let Permission::File(file_permission) = permission {
                 let file_hash = file_permission.file_hash;
 
@@ -3355,10 +4649,16 @@ async fn handle_file_download_url(
             let query_hash = params.hash;
 
             // Verify the token and determine its type
-            let permission = authenticator
-                .verify_token_auto(token, current_time_epoch_millis())
+            let permission = verify_token(authenticator, token)
                 .map_err(|_| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token")))?;
 
+            // Downloading needs only read access, so only an outright ACL denial matters
+            server_state.acl_authorization(
+                &doc_id,
+                acl_principal(token, &permission),
+                Authorization::ReadOnly,
+            )?;
+
             match permission {
                 Permission::File(file_permission) => {
                     // Check if file token is for this doc_id
@@ -3369,10 +4669,13 @@ async fn handle_file_download_url(
                         ));
                     }
 
-                    // Both ReadOnly and Full can download files
+                    // Every authorization level can download files
                     if !matches!(
                         file_permission.authorization,
-                        Authorization::ReadOnly | Authorization::Full
+                        Authorization::ReadOnly
+                            | Authorization::Comment
+                            | Authorization::Suggest
+                            | Authorization::Full
                     ) {
                         return Err(AppError(
                             StatusCode::FORBIDDEN,
@@ -3476,10 +4779,13 @@ async fn handle_file_download_url(
                         ));
                     }
 
-                    // Both ReadOnly and Full can download files
+                    // Every authorization level can download files
                     if !matches!(
                         prefix_perm.authorization,
-                        Authorization::ReadOnly | Authorization::Full
+                        Authorization::ReadOnly
+                            | Authorization::Comment
+                            | Authorization::Suggest
+                            | Authorization::Full
                     ) {
                         return Err(AppError(
                             StatusCode::FORBIDDEN,
@@ -3617,9 +4923,13 @@ async fn handle_file_delete(
     if let Some(authenticator) = &server_state.authenticator {
         if let Some(token) = token.as_deref() {
             // Verify token is for this doc_id
-            let auth = authenticator
-                .verify_file_token_for_doc(token, &doc_id, current_time_epoch_millis())
+            let permission = verify_token(authenticator, token)
                 .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
+            let auth = permission
+                .doc_authorization(&doc_id)
+                .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
+            let auth =
+                server_state.acl_authorization(&doc_id, acl_principal(token, &permission), auth)?;
 
             // Only Full permission can delete files
             if !matches!(auth, Authorization::Full) {
@@ -3700,9 +5010,13 @@ async fn handle_file_delete_by_hash(
     if let Some(authenticator) = &server_state.authenticator {
         if let Some(token) = token.as_deref() {
             // Verify token is for this doc_id
-            let auth = authenticator
-                .verify_file_token_for_doc(token, &doc_id, current_time_epoch_millis())
+            let permission = verify_token(authenticator, token)
+                .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
+            let auth = permission
+                .doc_authorization(&doc_id)
                 .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
+            let auth =
+                server_state.acl_authorization(&doc_id, acl_principal(token, &permission), auth)?;
 
             // Only Full permission can delete files
             if !matches!(auth, Authorization::Full) {
@@ -3797,12 +5111,22 @@ async fn handle_file_history(
     if let Some(authenticator) = &server_state.authenticator {
         if let Some(token) = token.as_deref() {
             // Verify token is for this doc_id - this now accepts both doc and file tokens
-            let auth = authenticator
-                .verify_file_token_for_doc(token, &doc_id, current_time_epoch_millis())
+            let permission = verify_token(authenticator, token)
                 .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
-
-            // Both ReadOnly and Full can view file history
-            if !matches!(auth, Authorization::ReadOnly | Authorization::Full) {
+            let auth = permission
+                .doc_authorization(&doc_id)
+                .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
+            let auth =
+                server_state.acl_authorization(&doc_id, acl_principal(token, &permission), auth)?;
+
+            // Every authorization level can view file history
+            if !matches!(
+                auth,
+                Authorization::ReadOnly
+                    | Authorization::Comment
+                    | Authorization::Suggest
+                    | Authorization::Full
+            ) {
                 return Err(AppError(
                     StatusCode::FORBIDDEN,
                     anyhow!("Insufficient permissions to view file history"),
@@ -3855,11 +5179,21 @@ async fn handle_doc_versions(
 
     if let Some(authenticator) = &server_state.authenticator {
         if let Some(token) = token.as_deref() {
-            let auth = authenticator
-                .verify_doc_token(token, &doc_id, current_time_epoch_millis())
+            let permission = verify_token(authenticator, token)
                 .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
-
-            if !matches!(auth, Authorization::ReadOnly | Authorization::Full) {
+            let auth = permission
+                .doc_authorization(&doc_id)
+                .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
+            let auth =
+                server_state.acl_authorization(&doc_id, acl_principal(token, &permission), auth)?;
+
+            if !matches!(
+                auth,
+                Authorization::ReadOnly
+                    | Authorization::Comment
+                    | Authorization::Suggest
+                    | Authorization::Full
+            ) {
                 return Err(AppError(
                     StatusCode::FORBIDDEN,
                     anyhow!("Insufficient permissions to view document versions"),
@@ -3913,23 +5247,28 @@ async fn handle_file_head(
     if let Some(authenticator) = &server_state.authenticator {
         if let Some(token) = token.as_deref() {
             // Verify token is for this doc_id
-            let auth = authenticator
-                .verify_file_token_for_doc(token, &doc_id, current_time_epoch_millis())
+            let permission = verify_token(authenticator, token)
                 .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
-
-            // Both ReadOnly and Full can check if a file exists
-            if !matches!(auth, Authorization::ReadOnly | Authorization::Full) {
+            let auth = permission
+                .doc_authorization(&doc_id)
+                .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
+            let auth =
+                server_state.acl_authorization(&doc_id, acl_principal(token, &permission), auth)?;
+
+            // Every authorization level can check if a file exists
+            if !matches!(
+                auth,
+                Authorization::ReadOnly
+                    | Authorization::Comment
+                    | Authorization::Suggest
+                    | Authorization::Full
+            ) {
                 return Err(AppError(
                     StatusCode::FORBIDDEN,
                     anyhow!("Insufficient permissions to access file"),
                 ));
             }
 
-            // Verify the token and get the file hash
-            let permission = authenticator
-                .verify_token_auto(token, current_time_epoch_millis())
-                .map_err(|_| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token")))?;
-
             if let Permission::File(file_permission) = permission {
                 let file_hash = file_permission.file_hash;
 
@@ -4000,9 +5339,14 @@ async fn reload_webhook_config_endpoint(
     if let Some(authenticator) = &server_state.authenticator {
         if let Some(token) = token.as_deref() {
             // Verify this is a server admin token
-            authenticator
-                .verify_server_token(token, current_time_epoch_millis())
+            let permission = verify_token(authenticator, token)
                 .map_err(|e| AppError(StatusCode::UNAUTHORIZED, anyhow!("Invalid token: {}", e)))?;
+            if permission != Permission::Server {
+                return Err(AppError(
+                    StatusCode::UNAUTHORIZED,
+                    anyhow!("Invalid token: {}", AuthError::InvalidResource),
+                ));
+            }
         } else {
             return Err(AppError(
                 StatusCode::UNAUTHORIZED,
@@ -4027,85 +5371,455 @@ async fn reload_webhook_config_endpoint(
     }
 }
 
-async fn metrics_endpoint(State(_server_state): State<Arc<Server>>) -> Result<String, AppError> {
-    use prometheus::{Encoder, TextEncoder};
-
-    let encoder = TextEncoder::new();
-    let metric_families = prometheus::gather();
-    let mut buffer = Vec::new();
-
-    encoder.encode(&metric_families, &mut buffer).map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            anyhow!("Failed to encode metrics: {}", e),
-        )
-    })?;
-
-    Ok(String::from_utf8(buffer).map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            anyhow!("Failed to convert metrics to string: {}", e),
-        )
-    })?)
+/// Tokens revoked before they expire. Requires a server token.
+///
+/// GET /revocations
+/// Response: [ { kind: "token" | "subject" | "key", value, revokedAt, reason? }, ... ]
+async fn handle_get_revocations(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+) -> Result<Json<Vec<Revocation>>, AppError> {
+    server_state.check_auth(auth_header)?;
+    let revocations = server_state
+        .authenticator
+        .as_ref()
+        .map(|authenticator| authenticator.revocations.entries())
+        .unwrap_or_default();
+    Ok(Json(revocations))
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use y_sweet_core::api_types::Authorization;
-    use y_sweet_core::auth::ExpirationTimeEpochMillis;
+#[derive(Deserialize)]
+struct RevocationRequest {
+    kind: RevocationKind,
+    value: String,
+    reason: Option<String>,
+}
 
-    #[tokio::test]
-    async fn test_auth_doc() {
-        let server_state = Server::new_without_workers(
-            None,
-            Duration::from_secs(60),
-            None,
-            None,
-            vec![],
-            CancellationToken::new(),
-            true,
-            None,
-        )
+/// Revoke a token by ID, every token issued to a subject, or every token
+/// signed with a key, and close the connections using them. Requires a
+/// server token.
+///
+/// POST /revocations
+/// Body: { "kind": "token" | "subject" | "key", "value": "...", "reason": "..." }
+async fn handle_add_revocation(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+    Json(request): Json<RevocationRequest>,
+) -> Result<Json<Revocation>, AppError> {
+    server_state.check_auth(auth_header)?;
+    if request.value.is_empty() {
+        return Err(AppError(
+            StatusCode::BAD_REQUEST,
+            anyhow!("Revocation value must not be empty"),
+        ));
+    }
+    let revocation = Revocation {
+        kind: request.kind,
+        value: request.value,
+        revoked_at: current_time_epoch_millis(),
+        reason: request.reason,
+    };
+    server_state
+        .revoke(revocation.clone())
         .await
-        .unwrap();
+        .map_err(|e| AppError(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    tracing::info!(
+        kind = %revocation.kind,
+        value = %revocation.value,
+        "Tokens revoked"
+    );
+    Ok(Json(revocation))
+}
 
-        let doc_id = server_state.create_doc().await.unwrap();
+#[derive(Deserialize)]
+struct RemoveRevocationRequest {
+    kind: RevocationKind,
+    value: String,
+}
 
-        let token = auth_doc(
-            None,
-            TypedHeader(headers::Host::from(http::uri::Authority::from_static(
-                "localhost",
-            ))),
-            State(Arc::new(server_state)),
-            Path(doc_id.clone()),
-            Some(Json(AuthDocRequest {
-                authorization: Authorization::Full,
-                user_id: None,
-                valid_for_seconds: None,
-            })),
-        )
+/// Lift a revocation. Requires a server token.
+///
+/// DELETE /revocations
+/// Body: { "kind": "token" | "subject" | "key", "value": "..." }
+async fn handle_remove_revocation(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+    Json(request): Json<RemoveRevocationRequest>,
+) -> Result<Json<Value>, AppError> {
+    server_state.check_auth(auth_header)?;
+    let removed = server_state
+        .unrevoke(request.kind, &request.value)
         .await
-        .unwrap();
+        .map_err(|e| AppError(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if !removed {
+        return Err(AppError(
+            StatusCode::NOT_FOUND,
+            anyhow!("No {} revocation for '{}'", request.kind, request.value),
+        ));
+    }
+    tracing::info!(kind = %request.kind, value = %request.value, "Revocation lifted");
+    Ok(Json(json!({ "status": "success" })))
+}
 
-        let expected_url = format!("ws://localhost/d/{doc_id}/ws");
-        assert_eq!(token.url, expected_url);
-        assert_eq!(token.doc_id, doc_id);
-        assert!(token.token.is_none());
+/// Re-read revocations from the store, e.g. after another relay instance
+/// revoked tokens. Requires a server token.
+async fn handle_reload_revocations(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+) -> Result<Json<Value>, AppError> {
+    server_state.check_auth(auth_header)?;
+    match server_state.reload_revocations().await {
+        Ok(revocations) => Ok(Json(
+            json!({ "status": "success", "revocations": revocations }),
+        )),
+        Err(e) => {
+            tracing::error!("Failed to reload revocations: {}", e);
+            Err(AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow!("Failed to reload revocations: {}", e),
+            ))
+        }
     }
+}
 
-    #[tokio::test]
-    async fn test_auth_doc_with_prefix() {
-        let prefix: Url = "https://foo.bar".parse().unwrap();
-        let server_state = Server::new_without_workers(
-            None,
-            Duration::from_secs(60),
-            None,
-            Some(prefix),
-            vec![],
-            CancellationToken::new(),
-            true,
-            None,
+/// Service-account API keys, by name. Secrets are not kept, only their
+/// hashes. Requires a server token.
+///
+/// GET /api-keys
+async fn handle_list_api_keys(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+) -> Result<Json<Vec<ApiKey>>, AppError> {
+    server_state.check_auth(auth_header)?;
+    let keys = server_state
+        .authenticator
+        .as_ref()
+        .map(|authenticator| authenticator.api_keys.keys())
+        .unwrap_or_default();
+    Ok(Json(keys))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateApiKeyRequest {
+    name: String,
+    #[serde(default)]
+    scopes: Vec<String>,
+    permission: Authorization,
+    valid_for_seconds: Option<u64>,
+}
+
+/// Create a service-account API key. The secret is in the response and
+/// cannot be retrieved again. Requires a server token.
+///
+/// POST /api-keys
+/// Body: { "name": "git-sync", "scopes": ["Lens Edu"], "permission": "read-only", "validForSeconds": 86400 }
+/// Response: { "secret": "rk_...", "key": { name, hash, scopes, permission, createdAt, expiresAt } }
+async fn handle_create_api_key(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<Json<Value>, AppError> {
+    server_state.check_auth(auth_header)?;
+    let valid_name = !request.name.is_empty()
+        && request
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid_name {
+        return Err(AppError(
+            StatusCode::BAD_REQUEST,
+            anyhow!("API key names may only contain letters, digits, '-', '_' and '.'"),
+        ));
+    }
+    if server_state
+        .authenticator
+        .as_ref()
+        .is_some_and(|authenticator| authenticator.api_keys.get(&request.name).is_some())
+    {
+        return Err(AppError(
+            StatusCode::CONFLICT,
+            anyhow!("An API key named '{}' already exists", request.name),
+        ));
+    }
+    let expires_at = request
+        .valid_for_seconds
+        .map(|seconds| {
+            seconds
+                .checked_mul(1000)
+                .and_then(|millis| current_time_epoch_millis().checked_add(millis))
+                .ok_or_else(|| {
+                    AppError(
+                        StatusCode::BAD_REQUEST,
+                        anyhow!("validForSeconds is too large"),
+                    )
+                })
+        })
+        .transpose()?;
+    let (secret, key) = server_state
+        .create_api_key(
+            &request.name,
+            request.scopes,
+            request.permission,
+            expires_at,
+        )
+        .await
+        .map_err(|e| AppError(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    tracing::info!(name = %key.name, permission = ?key.permission, "API key created");
+    Ok(Json(json!({ "secret": secret, "key": key })))
+}
+
+/// Delete an API key, rejecting it from then on and closing connections
+/// made with it. Requires a server token.
+///
+/// DELETE /api-keys/:name
+async fn handle_delete_api_key(
+    State(server_state): State<Arc<Server>>,
+    Path(name): Path<String>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+) -> Result<Json<Value>, AppError> {
+    server_state.check_auth(auth_header)?;
+    let deleted = server_state
+        .delete_api_key(&name)
+        .await
+        .map_err(|e| AppError(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if !deleted {
+        return Err(AppError(
+            StatusCode::NOT_FOUND,
+            anyhow!("No API key named '{}'", name),
+        ));
+    }
+    tracing::info!(name = %name, "API key deleted");
+    Ok(Json(json!({ "status": "success" })))
+}
+
+/// Re-read API keys from the store, e.g. after another relay instance
+/// created one. Requires a server token.
+async fn handle_reload_api_keys(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+) -> Result<Json<Value>, AppError> {
+    server_state.check_auth(auth_header)?;
+    match server_state.reload_api_keys().await {
+        Ok(keys) => Ok(Json(json!({ "status": "success", "keys": keys }))),
+        Err(e) => {
+            tracing::error!("Failed to reload API keys: {}", e);
+            Err(AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow!("Failed to reload API keys: {}", e),
+            ))
+        }
+    }
+}
+
+/// The keys that verify this relay's tokens, in the style of a JSON Web Key
+/// Set. Public keys come with their key material; symmetric keys are listed
+/// by ID only. No token required.
+///
+/// GET /.well-known/relay-keys
+/// Response: { "keys": [ { kid, kty, alg, use, crv, x, y, status, exp }, ... ] }
+async fn handle_relay_keys(State(server_state): State<Arc<Server>>) -> Json<Value> {
+    let keys = server_state
+        .authenticator
+        .as_ref()
+        .map(|authenticator| authenticator.key_set(current_time_epoch_millis()))
+        .unwrap_or_default();
+    Json(json!({ "keys": keys }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RotateKeysRequest {
+    grace_period_seconds: Option<u64>,
+}
+
+/// How long replaced keys keep verifying tokens when a rotation does not say.
+const DEFAULT_KEY_GRACE_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Start signing tokens with a fresh key, retiring the current ones after a
+/// grace period. Requires a server token.
+///
+/// POST /keys/rotate
+/// Body: { "gracePeriodSeconds": 86400 }
+async fn handle_rotate_keys(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+    body: Option<Json<RotateKeysRequest>>,
+) -> Result<Json<Value>, AppError> {
+    server_state.check_auth(auth_header)?;
+    let grace_period = body
+        .and_then(|Json(request)| request.grace_period_seconds)
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_KEY_GRACE_PERIOD);
+    let key_id = server_state.rotate_keys(grace_period).await.map_err(|e| {
+        // Keys of a kind that can't be rotated are a configuration problem
+        match e.downcast_ref::<AuthError>() {
+            Some(AuthError::UnsupportedRotation(_)) => AppError(StatusCode::BAD_REQUEST, e),
+            _ => AppError(StatusCode::INTERNAL_SERVER_ERROR, e),
+        }
+    })?;
+    tracing::info!(key_id = %key_id, ?grace_period, "Signing keys rotated");
+    Ok(Json(json!({
+        "status": "success",
+        "keyId": key_id,
+        "retiresAt": current_time_epoch_millis() + grace_period.as_millis() as u64,
+    })))
+}
+
+/// Re-read rotated keys from the store, e.g. after another relay instance
+/// rotated them. Requires a server token.
+async fn handle_reload_keys(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+) -> Result<Json<Value>, AppError> {
+    server_state.check_auth(auth_header)?;
+    match server_state.reload_key_ring().await {
+        Ok(keys) => Ok(Json(json!({ "status": "success", "keys": keys }))),
+        Err(e) => {
+            tracing::error!("Failed to reload rotated keys: {}", e);
+            Err(AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow!("Failed to reload rotated keys: {}", e),
+            ))
+        }
+    }
+}
+
+/// The access control list in force. Requires a server token.
+///
+/// GET /acl
+/// Response: { "groups": { name: [user, ...] }, "rules": [ { subject, path, permission }, ... ] }
+async fn handle_get_acl(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+) -> Result<Json<crate::acl::AclDocument>, AppError> {
+    server_state.check_auth(auth_header)?;
+    Ok(Json(server_state.acl.document()))
+}
+
+/// Replace the access control list, saving it to the store. Requires a
+/// server token.
+///
+/// PUT /acl with the same body GET /acl returns.
+async fn handle_put_acl(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+    Json(document): Json<crate::acl::AclDocument>,
+) -> Result<Json<Value>, AppError> {
+    server_state.check_auth(auth_header)?;
+    if let Err(e) = document.validate() {
+        return Err(AppError(StatusCode::BAD_REQUEST, anyhow!("{}", e)));
+    }
+    let rules = document.rules.len();
+    server_state
+        .set_acl(document)
+        .await
+        .map_err(|e| AppError(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    tracing::info!("Access control list replaced ({} rules)", rules);
+    Ok(Json(json!({ "status": "success", "rules": rules })))
+}
+
+/// Re-read the access control list from the store, e.g. after editing
+/// `.config/acl.json` directly. Requires a server token.
+async fn handle_reload_acl(
+    State(server_state): State<Arc<Server>>,
+    auth_header: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+) -> Result<Json<Value>, AppError> {
+    server_state.check_auth(auth_header)?;
+    match server_state.reload_acl().await {
+        Ok(rules) => {
+            tracing::info!("Access control list reloaded ({} rules)", rules);
+            Ok(Json(json!({ "status": "success", "rules": rules })))
+        }
+        Err(e) => {
+            tracing::error!("Failed to reload access control list: {}", e);
+            Err(AppError(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                anyhow!("Failed to reload access control list: {}", e),
+            ))
+        }
+    }
+}
+
+async fn metrics_endpoint(State(_server_state): State<Arc<Server>>) -> Result<String, AppError> {
+    use prometheus::{Encoder, TextEncoder};
+
+    let encoder = TextEncoder::new();
+    let metric_families = prometheus::gather();
+    let mut buffer = Vec::new();
+
+    encoder.encode(&metric_families, &mut buffer).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            anyhow!("Failed to encode metrics: {}", e),
+        )
+    })?;
+
+    Ok(String::from_utf8(buffer).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            anyhow!("Failed to convert metrics to string: {}", e),
+        )
+    })?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use y_sweet_core::api_types::Authorization;
+    use y_sweet_core::auth::ExpirationTimeEpochMillis;
+
+    #[tokio::test]
+    async fn test_auth_doc() {
+        let server_state = Server::new_without_workers(
+            None,
+            Duration::from_secs(60),
+            None,
+            None,
+            vec![],
+            CancellationToken::new(),
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let doc_id = server_state.create_doc().await.unwrap();
+
+        let token = auth_doc(
+            None,
+            TypedHeader(headers::Host::from(http::uri::Authority::from_static(
+                "localhost",
+            ))),
+            State(Arc::new(server_state)),
+            Path(doc_id.clone()),
+            Some(Json(AuthDocRequest {
+                authorization: Authorization::Full,
+                user_id: None,
+                valid_for_seconds: None,
+            })),
+        )
+        .await
+        .unwrap();
+
+        let expected_url = format!("ws://localhost/d/{doc_id}/ws");
+        assert_eq!(token.url, expected_url);
+        assert_eq!(token.doc_id, doc_id);
+        assert!(token.token.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_auth_doc_with_prefix() {
+        let prefix: Url = "https://foo.bar".parse().unwrap();
+        let server_state = Server::new_without_workers(
+            None,
+            Duration::from_secs(60),
+            None,
+            Some(prefix),
+            vec![],
+            CancellationToken::new(),
+            true,
+            None,
         )
         .await
         .unwrap();
@@ -4429,6 +6143,699 @@ mod test {
         );
     }
 
+    #[tokio::test]
+    async fn test_revocation_closes_live_connections_and_persists() {
+        use crate::stores::filesystem::FileSystemStore;
+        use tempfile::TempDir;
+        use y_sweet_core::auth::{token_id, AuthError};
+
+        let mut authenticator = Authenticator::gen_key().unwrap();
+        authenticator.set_expected_audience(Some("https://api.example.com".to_string()));
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileSystemStore::new(temp_dir.path().to_path_buf()).unwrap();
+        let server_state = Server::new_without_workers(
+            Some(Box::new(store)),
+            Duration::from_secs(60),
+            Some(authenticator.clone()),
+            None,
+            Vec::new(),
+            CancellationToken::new(),
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let token = authenticator
+            .gen_doc_token_cwt(
+                "doc123",
+                Authorization::Full,
+                ExpirationTimeEpochMillis(u64::MAX),
+                Some("alice"),
+                None,
+            )
+            .unwrap();
+        let live = server_state
+            .watch_token(Some(&token), Some("alice"))
+            .unwrap();
+        assert!(!live.revoked.is_cancelled());
+
+        server_state
+            .revoke(Revocation {
+                kind: RevocationKind::Token,
+                value: token_id(&token),
+                revoked_at: current_time_epoch_millis(),
+                reason: Some("leaked".to_string()),
+            })
+            .await
+            .unwrap();
+        assert!(live.revoked.is_cancelled());
+        assert_eq!(
+            authenticator.verify_doc_token(&token, "doc123", 0),
+            Err(AuthError::Revoked)
+        );
+
+        // Survives a reload from the store
+        authenticator.revocations.replace(Vec::new());
+        assert_eq!(server_state.reload_revocations().await.unwrap(), 1);
+
+        assert!(server_state
+            .unrevoke(RevocationKind::Token, &token_id(&token))
+            .await
+            .unwrap());
+        assert!(authenticator.verify_doc_token(&token, "doc123", 0).is_ok());
+
+        drop(live);
+        assert!(server_state.live_tokens.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_socket_token() {
+        let authenticator = Authenticator::gen_key().unwrap();
+        let server_state = Server::new_without_workers(
+            None,
+            Duration::from_secs(60),
+            Some(authenticator.clone()),
+            None,
+            Vec::new(),
+            CancellationToken::new(),
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let token = |doc_id: &str, authorization, user: Option<&str>| {
+            authenticator
+                .gen_doc_token(
+                    doc_id,
+                    authorization,
+                    ExpirationTimeEpochMillis(4_000_000_000_000),
+                    user,
+                )
+                .unwrap()
+        };
+        let old = token("doc123", Authorization::Full, Some("alice"));
+        let live = server_state.watch_token(Some(&old), Some("alice")).unwrap();
+
+        let fresh = token("doc123", Authorization::ReadOnly, Some("alice"));
+        assert_eq!(
+            server_state.refresh_socket_token(&fresh, "doc123", Some("alice"), Some(live.id)),
+            TokenRefresh::Granted {
+                authorization: Authorization::ReadOnly,
+                expiration_time: Some(4_000_000_000_000),
+            }
+        );
+        // Revocations now apply to the fresh token
+        assert_eq!(
+            server_state.live_tokens.get(&live.id).unwrap().0,
+            authenticator.token_identity(&fresh, Some("alice"))
+        );
+
+        let other_doc = token("doc456", Authorization::Full, Some("alice"));
+        assert!(matches!(
+            server_state.refresh_socket_token(&other_doc, "doc123", Some("alice"), None),
+            TokenRefresh::Rejected(_)
+        ));
+        let other_user = token("doc123", Authorization::Full, Some("bob"));
+        assert!(matches!(
+            server_state.refresh_socket_token(&other_user, "doc123", Some("alice"), None),
+            TokenRefresh::Rejected(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_auth_decisions_are_audited() {
+        use y_sweet_core::auth_audit::TokenKind;
+
+        let authenticator = Authenticator::gen_key().unwrap();
+        let server_state = Arc::new(
+            Server::new_without_workers(
+                None,
+                Duration::from_secs(60),
+                Some(authenticator.clone()),
+                None,
+                Vec::new(),
+                CancellationToken::new(),
+                true,
+                None,
+            )
+            .await
+            .unwrap(),
+        );
+        let expiration = ExpirationTimeEpochMillis(current_time_epoch_millis() + 60_000);
+        let alice = authenticator
+            .gen_doc_token("doc123", Authorization::Full, expiration, Some("alice"))
+            .unwrap();
+        let bob = authenticator
+            .gen_doc_token("doc456", Authorization::Full, expiration, Some("bob"))
+            .unwrap();
+
+        server_state.refresh_socket_token(&alice, "doc123", Some("alice"), None);
+        server_state.refresh_socket_token(&bob, "doc123", Some("bob"), None);
+        // A request is recorded as its handler verified the token
+        let (denied, verified) = crate::auth_audit::noting_verification(async {
+            server_state.verify_doc_token(Some(&bob), "doc123")
+        })
+        .await;
+        assert_eq!(denied.unwrap_err().0, StatusCode::UNAUTHORIZED);
+        server_state.record_auth_decision(
+            Some(&bob),
+            verified,
+            "/d/doc123/as-update",
+            "GET /d/:doc_id/as-update",
+            Some("Invalid token".to_string()),
+        );
+        server_state.record_auth_decision(
+            None,
+            None,
+            "/d/doc123/as-update",
+            "GET /d/:doc_id/as-update",
+            Some("No token provided.".to_string()),
+        );
+
+        let server_token = authenticator.server_token().unwrap();
+        let auth_header = || {
+            Some(TypedHeader(
+                headers::Authorization::bearer(&server_token).unwrap(),
+            ))
+        };
+        let Json(all) = handle_auth_audit(
+            State(server_state.clone()),
+            auth_header(),
+            Query(AuthAuditQuery {
+                from: None,
+                to: None,
+                principal: None,
+                decision: None,
+                limit: None,
+            }),
+        )
+        .await
+        .unwrap();
+        let entries = all["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0]["principal"], "alice");
+        assert_eq!(entries[0]["token_kind"], "doc");
+        assert_eq!(entries[0]["decision"], "allow");
+
+        let Json(denied) = handle_auth_audit(
+            State(server_state.clone()),
+            auth_header(),
+            Query(AuthAuditQuery {
+                from: None,
+                to: None,
+                principal: Some("bob".to_string()),
+                decision: Some(Decision::Deny),
+                limit: None,
+            }),
+        )
+        .await
+        .unwrap();
+        let denied: Vec<AuthDecision> = serde_json::from_value(denied["entries"].clone()).unwrap();
+        assert_eq!(denied.len(), 2);
+        assert_eq!(denied[0].resource, "doc123");
+        assert!(denied[0].reason.is_some());
+        assert_eq!(denied[1].resource, "/d/doc123/as-update");
+        assert_eq!(denied[1].token_kind, TokenKind::Doc);
+
+        let anonymous = server_state
+            .auth_audit
+            .query(
+                0,
+                u64::MAX / 2,
+                |entry| entry.token_kind == TokenKind::None,
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(anonymous.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_api_keys_are_scoped_to_folders_and_persisted() {
+        use crate::stores::filesystem::FileSystemStore;
+        use tempfile::TempDir;
+        use y_sweet_core::doc_resolver::DocInfo;
+
+        let authenticator = Authenticator::gen_key().unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileSystemStore::new(temp_dir.path().to_path_buf()).unwrap();
+        let server_state = Server::new_without_workers(
+            Some(Box::new(store)),
+            Duration::from_secs(60),
+            Some(authenticator.clone()),
+            None,
+            Vec::new(),
+            CancellationToken::new(),
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let relay_id = "11111111-1111-1111-1111-111111111111";
+        let add_doc = |uuid: &str, path: &str| {
+            let doc_id = format!("{}-{}", relay_id, uuid);
+            server_state.doc_resolver().upsert_doc(
+                uuid,
+                path,
+                DocInfo {
+                    uuid: uuid.to_string(),
+                    relay_id: relay_id.to_string(),
+                    folder_doc_id: format!("{}-folder", relay_id),
+                    folder_name: path.split('/').next().unwrap().to_string(),
+                    doc_id: doc_id.clone(),
+                },
+            );
+            doc_id
+        };
+        let inside = add_doc("22222222-2222-2222-2222-222222222222", "Lens Edu/Intro.md");
+        let outside = add_doc("33333333-3333-3333-3333-333333333333", "Private/Notes.md");
+
+        let (secret, key) = server_state
+            .create_api_key(
+                "git-sync",
+                vec!["Lens Edu".to_string()],
+                Authorization::ReadOnly,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(server_state
+            .create_api_key("git-sync", Vec::new(), Authorization::Full, None)
+            .await
+            .is_err());
+
+        assert_eq!(
+            server_state
+                .verify_doc_token(Some(&secret), &inside)
+                .unwrap()
+                .0,
+            Authorization::ReadOnly
+        );
+
+        // A request is counted once, however often its handler looks at the key
+        let accepted = || {
+            server_state
+                .metrics
+                .api_key_requests_total
+                .with_label_values(&["git-sync", "accepted"])
+                .get()
+        };
+        let before = accepted();
+        let (_, verified) = crate::auth_audit::noting_verification(async {
+            server_state.verify_doc_token(Some(&secret), &inside)
+        })
+        .await;
+        server_state.record_auth_decision(
+            Some(&secret),
+            verified,
+            "/d/doc/as-update",
+            "GET /d/:doc_id/as-update",
+            None,
+        );
+        assert_eq!(accepted(), before + 1.0);
+        let err = server_state
+            .verify_doc_token(Some(&secret), &outside)
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        // A document the key can't place is refused rather than let through
+        let unknown = format!("{}-44444444-4444-4444-4444-444444444444", relay_id);
+        let err = server_state
+            .verify_doc_token(Some(&secret), &unknown)
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let credential = server_state.resolve_mcp_credential(&secret).unwrap();
+        assert_eq!(
+            credential.scope,
+            crate::mcp::auth::McpScope::Folders(vec!["Lens Edu".to_string()])
+        );
+        assert_eq!(credential.mode, y_sweet_core::config::McpMode::ReadOnly);
+        // Secrets in a URL path end up in logs, so only MCP keys go there
+        assert!(server_state.resolve_mcp_path_key(&secret).is_none());
+        assert!(server_state
+            .resolve_mcp_path_key(&authenticator.server_token().unwrap())
+            .is_none());
+
+        // The access control list holds the key to its `key:` rules over HTTP
+        // and MCP alike
+        let acl_rule = |subject: &str| crate::acl::AclRule {
+            subject: subject.to_string(),
+            path: "Lens Edu/**".to_string(),
+            permission: Authorization::ReadOnly,
+        };
+        server_state.acl.replace(crate::acl::AclDocument {
+            groups: Default::default(),
+            rules: vec![acl_rule("alice")],
+        });
+        let err = server_state
+            .verify_doc_token(Some(&secret), &inside)
+            .unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+        assert!(!credential.can_see(
+            server_state.doc_resolver(),
+            &server_state.acl,
+            "Lens Edu/Intro.md"
+        ));
+        server_state.acl.replace(crate::acl::AclDocument {
+            groups: Default::default(),
+            rules: vec![acl_rule("key:git-sync")],
+        });
+        assert!(server_state
+            .verify_doc_token(Some(&secret), &inside)
+            .is_ok());
+        assert!(credential.can_see(
+            server_state.doc_resolver(),
+            &server_state.acl,
+            "Lens Edu/Intro.md"
+        ));
+
+        // Only the hash is stored, and it survives a reload
+        authenticator.api_keys.replace(Vec::new());
+        assert_eq!(server_state.reload_api_keys().await.unwrap(), 1);
+        assert_eq!(authenticator.api_keys.get("git-sync"), Some(key.clone()));
+        let saved = std::fs::read_to_string(temp_dir.path().join(".config/api-keys.json")).unwrap();
+        assert!(!saved.contains(&secret));
+
+        let live = server_state
+            .watch_token(Some(&secret), Some(&key.subject()))
+            .unwrap();
+        assert!(server_state.delete_api_key("git-sync").await.unwrap());
+        assert!(live.revoked.is_cancelled());
+        assert!(server_state
+            .verify_doc_token(Some(&secret), &inside)
+            .is_err());
+        assert!(!server_state.delete_api_key("git-sync").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_imported_suggestions_are_credited_to_the_token_user() {
+        let authenticator = Authenticator::gen_key().unwrap();
+        let server_state = Arc::new(
+            Server::new_without_workers(
+                None,
+                Duration::from_secs(60),
+                Some(authenticator.clone()),
+                None,
+                Vec::new(),
+                CancellationToken::new(),
+                true,
+                None,
+            )
+            .await
+            .unwrap(),
+        );
+        let doc_id = server_state.create_doc().await.unwrap();
+        let awareness = server_state.docs.get(&doc_id).unwrap().awareness();
+        {
+            let guard = awareness.write().unwrap();
+            let mut txn = guard.doc.transact_mut();
+            let text = txn.get_or_insert_text("contents");
+            text.insert(&mut txn, 0, "one\ntwo\n");
+        }
+
+        let expiration = ExpirationTimeEpochMillis(current_time_epoch_millis() + 60_000);
+        let import = |authorization: Authorization| {
+            let token = authenticator
+                .gen_doc_token(&doc_id, authorization, expiration, Some("alice"))
+                .unwrap();
+            handle_import_suggestions_patch(
+                State(server_state.clone()),
+                Path(doc_id.clone()),
+                Query(ImportPatchQuery {
+                    author: Some("Mallory".to_string()),
+                }),
+                Some(TypedHeader(headers::Authorization::bearer(&token).unwrap())),
+                "@@ -2,1 +2,1 @@\n-two\n+2\n".to_string(),
+            )
+        };
+        let err = import(Authorization::Comment).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+        import(Authorization::Suggest).await.unwrap();
+
+        let guard = awareness.read().unwrap();
+        let txn = guard.doc.transact();
+        let content = txn.get_text("contents").unwrap().get_string(&txn);
+        assert!(content.contains(r#""author":"alice""#), "{}", content);
+        assert!(!content.contains("Mallory"), "{}", content);
+    }
+
+    #[tokio::test]
+    async fn test_open_by_path_does_not_reveal_documents() {
+        use y_sweet_core::doc_resolver::DocInfo;
+
+        let authenticator = Authenticator::gen_key().unwrap();
+        let server_state = Arc::new(
+            Server::new_without_workers(
+                None,
+                Duration::from_secs(60),
+                Some(authenticator.clone()),
+                None,
+                Vec::new(),
+                CancellationToken::new(),
+                true,
+                None,
+            )
+            .await
+            .unwrap(),
+        );
+        let relay_id = "11111111-1111-1111-1111-111111111111";
+        let uuid = "22222222-2222-2222-2222-222222222222";
+        server_state.doc_resolver().upsert_doc(
+            uuid,
+            "Private/Plans.md",
+            DocInfo {
+                uuid: uuid.to_string(),
+                relay_id: relay_id.to_string(),
+                folder_doc_id: format!("{}-folder", relay_id),
+                folder_name: "Private".to_string(),
+                doc_id: format!("{}-{}", relay_id, uuid),
+            },
+        );
+
+        let open = |path: &str, token: Option<String>| {
+            handle_open_by_path(
+                State(server_state.clone()),
+                Path(path.to_string()),
+                token.map(|token| TypedHeader(headers::Authorization::bearer(&token).unwrap())),
+                Request::new(axum::body::Body::empty()),
+            )
+        };
+        let status = |result: Result<Response, AppError>| match result {
+            Ok(response) => response.status(),
+            Err(AppError(status, _)) => status,
+        };
+
+        // Without a valid token nothing is looked up
+        assert_eq!(
+            status(open("Private/Plans.md", None).await),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(open("Private/Missing.md", None).await),
+            StatusCode::UNAUTHORIZED
+        );
+
+        // A token that can't read the document can't tell it from a missing one
+        let expiration = ExpirationTimeEpochMillis(current_time_epoch_millis() + 60_000);
+        let other = authenticator
+            .gen_doc_token("other", Authorization::Full, expiration, Some("alice"))
+            .unwrap();
+        assert_eq!(
+            status(open("Private/Plans.md", Some(other.clone())).await),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(open("Private/Missing.md", Some(other)).await),
+            StatusCode::NOT_FOUND
+        );
+
+        let server_token = authenticator.server_token().unwrap();
+        assert_eq!(
+            status(open("Private/Plans.md", Some(server_token)).await),
+            StatusCode::TEMPORARY_REDIRECT
+        );
+    }
+
+    #[tokio::test]
+    async fn test_api_key_lifetimes_that_overflow_are_rejected() {
+        let authenticator = Authenticator::gen_key().unwrap();
+        let server_state = Arc::new(
+            Server::new_without_workers(
+                None,
+                Duration::from_secs(60),
+                Some(authenticator.clone()),
+                None,
+                Vec::new(),
+                CancellationToken::new(),
+                true,
+                None,
+            )
+            .await
+            .unwrap(),
+        );
+        let server_token = authenticator.server_token().unwrap();
+        let err = handle_create_api_key(
+            State(server_state.clone()),
+            Some(TypedHeader(
+                headers::Authorization::bearer(&server_token).unwrap(),
+            )),
+            Json(CreateApiKeyRequest {
+                name: "git-sync".to_string(),
+                scopes: Vec::new(),
+                permission: Authorization::Full,
+                valid_for_seconds: Some(u64::MAX / 1000),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+        assert!(authenticator.api_keys.get("git-sync").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_prefix_and_user_tokens_can_move_within_their_reach() {
+        use crate::acl::{AclDocument, AclRule};
+        use y_sweet_core::auth::ExpirationTimeEpochMillis;
+        use y_sweet_core::doc_resolver::DocInfo;
+
+        let authenticator = Authenticator::gen_key().unwrap();
+        let server_state = Server::new_without_workers(
+            None,
+            Duration::from_secs(60),
+            Some(authenticator.clone()),
+            None,
+            Vec::new(),
+            CancellationToken::new(),
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let relay_id = "11111111-1111-1111-1111-111111111111";
+        let uuid = "22222222-2222-2222-2222-222222222222";
+        server_state.doc_resolver().upsert_doc(
+            uuid,
+            "Lens Edu/Intro.md",
+            DocInfo {
+                uuid: uuid.to_string(),
+                relay_id: relay_id.to_string(),
+                folder_doc_id: format!("{}-44444444-4444-4444-4444-444444444444", relay_id),
+                folder_name: "Lens Edu".to_string(),
+                doc_id: format!("{}-{}", relay_id, uuid),
+            },
+        );
+        let expiration = ExpirationTimeEpochMillis(current_time_epoch_millis() + 60_000);
+        let prefix_token = |prefix: &str, authorization: Authorization, user: Option<&str>| {
+            authenticator
+                .gen_prefix_token(prefix, authorization, expiration, user)
+                .unwrap()
+        };
+        let move_with = |token: &str, new_path: &str| {
+            server_state.authorize_move(Some(token), uuid, new_path, None)
+        };
+
+        let full = prefix_token(relay_id, Authorization::Full, None);
+        assert!(move_with(&full, "/Renamed.md").is_ok());
+
+        let read_only = prefix_token(relay_id, Authorization::ReadOnly, None);
+        let err = move_with(&read_only, "/Renamed.md").unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let elsewhere = prefix_token("99999999", Authorization::Full, None);
+        let err = move_with(&elsewhere, "/Renamed.md").unwrap_err();
+        assert_eq!(err.0, StatusCode::UNAUTHORIZED);
+
+        // A per-user token is held to the access control list at both ends
+        server_state.acl.replace(AclDocument {
+            groups: Default::default(),
+            rules: vec![
+                AclRule {
+                    subject: "alice".to_string(),
+                    path: "Lens Edu/*.md".to_string(),
+                    permission: Authorization::Full,
+                },
+                AclRule {
+                    subject: "*".to_string(),
+                    path: "Lens Edu/Archive/**".to_string(),
+                    permission: Authorization::ReadOnly,
+                },
+            ],
+        });
+        let alice = prefix_token(relay_id, Authorization::Full, Some("alice"));
+        assert!(move_with(&alice, "/Renamed.md").is_ok());
+        let err = move_with(&alice, "/Archive/Intro.md").unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_rotated_keys_are_published_and_persisted() {
+        use crate::stores::filesystem::FileSystemStore;
+        use tempfile::TempDir;
+        use y_sweet_core::auth::ExpirationTimeEpochMillis;
+        use y_sweet_core::key_ring::KeyRingDocument;
+
+        let authenticator = Authenticator::gen_key().unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileSystemStore::new(temp_dir.path().to_path_buf()).unwrap();
+        let server_state = Server::new_without_workers(
+            Some(Box::new(store)),
+            Duration::from_secs(60),
+            Some(authenticator.clone()),
+            None,
+            Vec::new(),
+            CancellationToken::new(),
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let expiration = ExpirationTimeEpochMillis(current_time_epoch_millis() + 60_000);
+        let old_token = authenticator
+            .gen_doc_token("doc", Authorization::Full, expiration, None)
+            .unwrap();
+
+        let key_id = server_state
+            .rotate_keys(Duration::from_secs(3600))
+            .await
+            .unwrap();
+        let new_token = authenticator
+            .gen_doc_token("doc", Authorization::Full, expiration, None)
+            .unwrap();
+        assert_ne!(old_token, new_token);
+
+        // Both keys verify during the grace period
+        for token in [&old_token, &new_token] {
+            assert_eq!(
+                server_state.verify_doc_token(Some(token), "doc").unwrap().0,
+                Authorization::Full
+            );
+        }
+        let published = authenticator.key_set(current_time_epoch_millis());
+        let statuses: Vec<_> = published.iter().map(|k| k.status).collect();
+        assert_eq!(statuses, vec!["retiring", "signing"]);
+        assert_eq!(published[1].kid.as_deref(), Some(key_id.as_str()));
+
+        // The new key survives a restart
+        authenticator
+            .key_ring
+            .replace(KeyRingDocument::default())
+            .unwrap();
+        assert!(server_state
+            .verify_doc_token(Some(&new_token), "doc")
+            .is_err());
+        assert_eq!(server_state.reload_key_ring().await.unwrap(), 1);
+        assert!(server_state
+            .verify_doc_token(Some(&new_token), "doc")
+            .is_ok());
+        assert!(temp_dir.path().join(".config/keys.json").exists());
+    }
+
     #[tokio::test]
     async fn test_file_upload_url_with_filesystem_store() {
         use crate::stores::filesystem::FileSystemStore;
@@ -4961,10 +7368,13 @@ async fn handle_file_download(
     let permission = validate_file_token(&server_state, &params.token, &doc_id)?;
 
     if let Permission::File(file_permission) = permission {
-        // Both ReadOnly and Full can download files
+        // Every authorization level can download files
         if !matches!(
             file_permission.authorization,
-            Authorization::ReadOnly | Authorization::Full
+            Authorization::ReadOnly
+                | Authorization::Comment
+                | Authorization::Suggest
+                | Authorization::Full
         ) {
             return Err(AppError(
                 StatusCode::FORBIDDEN,
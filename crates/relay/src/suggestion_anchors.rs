@@ -0,0 +1,606 @@
+//! Tracks where pending CriticMarkup suggestions live in a document so we can
+//! tell whether they still apply after humans edit around them.
+//!
+//! Each suggestion is anchored with a pair of Yrs sticky indices bound to the
+//! characters immediately *outside* the markup: the start index sticks to the
+//! character before `{`, the end index to the character after `}`. Because the
+//! anchors live outside the markup they survive the markup itself being
+//! rewritten or removed, and the text between them ("the window") tells us
+//! what happened:
+//!
+//! - window is exactly the original markup and the same-line context is
+//!   unchanged: **clean**
+//! - window still contains the original markup but something changed next to
+//!   it (or elsewhere on the same line): **conflicting**
+//! - window equals the accepted or rejected text: the suggestion was resolved,
+//!   so the anchor is dropped
+//! - anything else: **orphaned** (the markup was edited or removed by hand)
+//!
+//! Anchors are made by the edit paths (MCP edits, patch import, rebase) and
+//! kept in memory only, so they don't survive a restart. A suggestion that
+//! hasn't been anchored since the server started, such as one typed in the
+//! editor, is reported as **unknown** until one of those paths anchors it.
+
+use crate::mcp::tools::critic_diff::smart_critic_markup;
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::HashSet;
+use y_sweet_core::critic_scanner::{scan_suggestions, Suggestion, SuggestionType};
+use yrs::{Assoc, GetString, IndexedSequence, ReadTxn, StickyIndex, Text, TextRef, TransactionMut};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchorStatus {
+    Clean,
+    Conflicting,
+    Orphaned,
+    /// Not anchored, so whether it still applies can't be told.
+    Unknown,
+}
+
+/// Status of a tracked suggestion, as returned by `/suggestions` and the rebase endpoint.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnchorReport {
+    pub status: AnchorStatus,
+    /// The suggestion as it was when the anchor was recorded.
+    pub suggestion: Suggestion,
+    /// Current byte range of the markup, if it can still be located.
+    pub current_from: Option<usize>,
+    pub current_to: Option<usize>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RebaseSummary {
+    /// Suggestions whose markup was regenerated against the current text.
+    pub rebased: usize,
+    /// Orphaned suggestions that could not be re-located and were forgotten.
+    pub dropped: usize,
+}
+
+struct TrackedSuggestion {
+    suggestion: Suggestion,
+    start: StickyIndex,
+    end: StickyIndex,
+}
+
+/// Outcome of checking one anchor against the current text.
+enum Classification {
+    Clean { from: usize },
+    Conflicting { from: usize },
+    Orphaned,
+    Resolved,
+}
+
+/// Per-document registry of suggestion anchors.
+pub struct SuggestionAnchors {
+    docs: DashMap<String, Vec<TrackedSuggestion>>,
+}
+
+impl Default for SuggestionAnchors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SuggestionAnchors {
+    pub fn new() -> Self {
+        Self {
+            docs: DashMap::new(),
+        }
+    }
+
+    /// Re-check every tracked suggestion in `doc_id` and start tracking any new ones.
+    ///
+    /// Requires a write transaction because creating a sticky index may split a
+    /// block; no document content is changed.
+    pub fn refresh(
+        &self,
+        doc_id: &str,
+        txn: &mut TransactionMut,
+        text: &TextRef,
+    ) -> Vec<AnchorReport> {
+        let current = text.get_string(txn);
+        let scanned = scan_suggestions(&current);
+        let scanned_starts: HashSet<usize> = scanned.iter().map(|s| s.from).collect();
+
+        let mut tracked = self
+            .docs
+            .remove(doc_id)
+            .map(|(_, anchors)| anchors)
+            .unwrap_or_default();
+
+        let mut claimed = HashSet::new();
+        let mut reports = Vec::new();
+
+        tracked.retain(
+            |anchor| match report_anchor(anchor, &*txn, &current, &scanned_starts) {
+                Some(report) => {
+                    if let Some(from) = report.current_from {
+                        claimed.insert(from);
+                    }
+                    reports.push(report);
+                    true
+                }
+                None => false,
+            },
+        );
+
+        for suggestion in scanned {
+            if claimed.contains(&suggestion.from) {
+                continue;
+            }
+            let Some(anchor) = track(txn, text, suggestion) else {
+                continue;
+            };
+            reports.push(AnchorReport {
+                status: AnchorStatus::Clean,
+                current_from: Some(anchor.suggestion.from),
+                current_to: Some(anchor.suggestion.to),
+                suggestion: anchor.suggestion.clone(),
+            });
+            tracked.push(anchor);
+        }
+
+        if !tracked.is_empty() {
+            self.docs.insert(doc_id.to_string(), tracked);
+        }
+
+        reports.sort_by_key(|r| r.current_from.unwrap_or(usize::MAX));
+        reports
+    }
+
+    /// Report on every suggestion in `doc_id` without changing the document.
+    ///
+    /// Tracked suggestions are checked against their anchors. Suggestions that
+    /// have not been anchored yet are reported unknown where they now sit;
+    /// only [`Self::refresh`], called from the edit paths, creates anchors.
+    pub fn report<T: ReadTxn>(&self, doc_id: &str, txn: &T, text: &TextRef) -> Vec<AnchorReport> {
+        let current = text.get_string(txn);
+        let scanned = scan_suggestions(&current);
+        let scanned_starts: HashSet<usize> = scanned.iter().map(|s| s.from).collect();
+
+        let mut claimed = HashSet::new();
+        let mut reports = Vec::new();
+        if let Some(tracked) = self.docs.get(doc_id) {
+            for anchor in tracked.iter() {
+                let Some(report) = report_anchor(anchor, txn, &current, &scanned_starts) else {
+                    continue;
+                };
+                if let Some(from) = report.current_from {
+                    claimed.insert(from);
+                }
+                reports.push(report);
+            }
+        }
+
+        for suggestion in scanned {
+            if claimed.contains(&suggestion.from) {
+                continue;
+            }
+            reports.push(AnchorReport {
+                status: AnchorStatus::Unknown,
+                current_from: Some(suggestion.from),
+                current_to: Some(suggestion.to),
+                suggestion,
+            });
+        }
+
+        reports.sort_by_key(|r| r.current_from.unwrap_or(usize::MAX));
+        reports
+    }
+
+    /// Regenerate conflicting and orphaned suggestions against the current text.
+    ///
+    /// Conflicting suggestions are re-diffed together with whatever text now
+    /// sits between their anchors. Orphaned suggestions are re-created if their
+    /// original text still appears exactly once between the anchors; otherwise
+    /// they are forgotten. Returns the summary and a fresh status report.
+    pub fn rebase(
+        &self,
+        doc_id: &str,
+        txn: &mut TransactionMut,
+        text: &TextRef,
+    ) -> (RebaseSummary, Vec<AnchorReport>) {
+        self.refresh(doc_id, txn, text);
+
+        let tracked = self
+            .docs
+            .remove(doc_id)
+            .map(|(_, anchors)| anchors)
+            .unwrap_or_default();
+
+        let mut summary = RebaseSummary::default();
+        let mut kept = Vec::new();
+
+        for anchor in tracked {
+            // Re-read per anchor: earlier rebases in this transaction shift offsets.
+            let current = text.get_string(txn);
+            let Some((start, end)) = resolve_window(&anchor, &*txn) else {
+                summary.dropped += 1;
+                continue;
+            };
+            let Some(window) = current.get(start..end) else {
+                summary.dropped += 1;
+                continue;
+            };
+            let raw = anchor.suggestion.raw_markup.as_str();
+            let (original, proposed) = sides(&anchor.suggestion);
+            let meta = meta_prefix(&anchor.suggestion);
+
+            if window == raw && same_line_context_matches(&anchor.suggestion, &current, start) {
+                kept.push(anchor);
+                continue;
+            }
+
+            if let Some(pos) = window.find(raw) {
+                let before = &window[..pos];
+                let after = &window[pos + raw.len()..];
+                let markup = smart_critic_markup(
+                    &format!("{}{}{}", before, original, after),
+                    &format!("{}{}{}", before, proposed, after),
+                    meta.as_deref(),
+                );
+                replace(txn, text, start, end - start, &markup);
+                summary.rebased += 1;
+            } else if !original.is_empty() && window.matches(original).count() == 1 {
+                let pos = start + window.find(original).unwrap_or_default();
+                let markup = smart_critic_markup(original, proposed, meta.as_deref());
+                replace(txn, text, pos, original.len(), &markup);
+                summary.rebased += 1;
+            } else if window == original || window == proposed {
+                // Resolved since the refresh above; nothing to do.
+            } else {
+                summary.dropped += 1;
+            }
+        }
+
+        if !kept.is_empty() {
+            self.docs.insert(doc_id.to_string(), kept);
+        }
+
+        // Pick up the regenerated markup as fresh anchors.
+        let reports = self.refresh(doc_id, txn, text);
+        (summary, reports)
+    }
+
+    /// Stop tracking a document (e.g. after it was deleted).
+    pub fn forget(&self, doc_id: &str) {
+        self.docs.remove(doc_id);
+    }
+}
+
+fn track(
+    txn: &mut TransactionMut,
+    text: &TextRef,
+    suggestion: Suggestion,
+) -> Option<TrackedSuggestion> {
+    let start = text.sticky_index(txn, suggestion.from as u32, Assoc::Before)?;
+    let end = text.sticky_index(txn, suggestion.to as u32, Assoc::After)?;
+    Some(TrackedSuggestion {
+        suggestion,
+        start,
+        end,
+    })
+}
+
+fn resolve_window<T: ReadTxn>(anchor: &TrackedSuggestion, txn: &T) -> Option<(usize, usize)> {
+    let start = anchor.start.get_offset(txn)?.index as usize;
+    let end = anchor.end.get_offset(txn)?.index as usize;
+    (start <= end).then_some((start, end))
+}
+
+/// Check one anchor against the current text. `None` if its suggestion has
+/// been accepted or rejected.
+fn report_anchor<T: ReadTxn>(
+    anchor: &TrackedSuggestion,
+    txn: &T,
+    current: &str,
+    scanned_starts: &HashSet<usize>,
+) -> Option<AnchorReport> {
+    let (status, from) = match classify(anchor, txn, current, scanned_starts) {
+        Classification::Resolved => return None,
+        Classification::Clean { from } => (AnchorStatus::Clean, Some(from)),
+        Classification::Conflicting { from } => (AnchorStatus::Conflicting, Some(from)),
+        Classification::Orphaned => (AnchorStatus::Orphaned, None),
+    };
+    Some(AnchorReport {
+        status,
+        suggestion: anchor.suggestion.clone(),
+        current_from: from,
+        current_to: from.map(|f| f + anchor.suggestion.raw_markup.len()),
+    })
+}
+
+fn classify<T: ReadTxn>(
+    anchor: &TrackedSuggestion,
+    txn: &T,
+    current: &str,
+    scanned_starts: &HashSet<usize>,
+) -> Classification {
+    let Some((start, end)) = resolve_window(anchor, txn) else {
+        return Classification::Orphaned;
+    };
+    let Some(window) = current.get(start..end) else {
+        return Classification::Orphaned;
+    };
+    let raw = anchor.suggestion.raw_markup.as_str();
+
+    if window == raw && scanned_starts.contains(&start) {
+        if same_line_context_matches(&anchor.suggestion, current, start) {
+            return Classification::Clean { from: start };
+        }
+        return Classification::Conflicting { from: start };
+    }
+
+    if let Some(pos) = window.find(raw) {
+        return Classification::Conflicting { from: start + pos };
+    }
+
+    let (original, proposed) = sides(&anchor.suggestion);
+    if window == original || window == proposed {
+        return Classification::Resolved;
+    }
+
+    Classification::Orphaned
+}
+
+/// Compare the recorded context with the current one, limited to the line the
+/// suggestion sits on. Edits in other paragraphs don't make a suggestion stale.
+fn same_line_context_matches(recorded: &Suggestion, current: &str, from: usize) -> bool {
+    let now = scan_suggestions(current);
+    let Some(live) = now.iter().find(|s| s.from == from) else {
+        return false;
+    };
+    line_tail(&recorded.context_before) == line_tail(&live.context_before)
+        && line_head(&recorded.context_after) == line_head(&live.context_after)
+}
+
+fn line_tail(context: &str) -> &str {
+    context.rsplit('\n').next().unwrap_or_default()
+}
+
+fn line_head(context: &str) -> &str {
+    context.split('\n').next().unwrap_or_default()
+}
+
+/// The (rejected, accepted) text of a suggestion.
+fn sides(suggestion: &Suggestion) -> (&str, &str) {
+    match suggestion.suggestion_type {
+        SuggestionType::Addition => ("", suggestion.content.as_str()),
+        SuggestionType::Deletion => (suggestion.content.as_str(), ""),
+        SuggestionType::Substitution => (
+            suggestion.old_content.as_deref().unwrap_or_default(),
+            suggestion.new_content.as_deref().unwrap_or_default(),
+        ),
+    }
+}
+
+/// Rebuild the `{"author":...,"timestamp":...}@@` prefix so rebased markup keeps its attribution.
+fn meta_prefix(suggestion: &Suggestion) -> Option<String> {
+    let author = serde_json::to_string(suggestion.author.as_ref()?).ok()?;
+    Some(match suggestion.timestamp {
+        Some(ts) => format!(r#"{{"author":{},"timestamp":{}}}@@"#, author, ts),
+        None => format!(r#"{{"author":{}}}@@"#, author),
+    })
+}
+
+fn replace(txn: &mut TransactionMut, text: &TextRef, index: usize, len: usize, with: &str) {
+    text.remove_range(txn, index as u32, len as u32);
+    text.insert(txn, index as u32, with);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yrs::{Doc, Transact, WriteTxn};
+
+    const SUB: &str = r#"{~~{"author":"AI","timestamp":1000}@@cat~>dog~~}"#;
+
+    fn doc_with(content: &str) -> Doc {
+        let doc = Doc::new();
+        {
+            let mut txn = doc.transact_mut();
+            let text = txn.get_or_insert_text("contents");
+            text.insert(&mut txn, 0, content);
+        }
+        doc
+    }
+
+    fn refresh(anchors: &SuggestionAnchors, doc: &Doc) -> Vec<AnchorReport> {
+        let mut txn = doc.transact_mut();
+        let text = txn.get_or_insert_text("contents");
+        anchors.refresh("doc", &mut txn, &text)
+    }
+
+    fn edit(doc: &Doc, f: impl FnOnce(&mut TransactionMut, &TextRef)) {
+        let mut txn = doc.transact_mut();
+        let text = txn.get_or_insert_text("contents");
+        f(&mut txn, &text);
+    }
+
+    fn content(doc: &Doc) -> String {
+        let txn = doc.transact();
+        txn.get_text("contents")
+            .map(|t| t.get_string(&txn))
+            .unwrap_or_default()
+    }
+
+    fn statuses(reports: &[AnchorReport]) -> Vec<AnchorStatus> {
+        reports.iter().map(|r| r.status).collect()
+    }
+
+    #[test]
+    fn new_suggestion_is_clean() {
+        let doc = doc_with(&format!("The {} sat.", SUB));
+        let anchors = SuggestionAnchors::new();
+        let reports = refresh(&anchors, &doc);
+        assert_eq!(statuses(&reports), vec![AnchorStatus::Clean]);
+        assert_eq!(reports[0].current_from, Some(4));
+    }
+
+    #[test]
+    fn edit_before_suggestion_shifts_anchor_and_stays_clean_across_paragraphs() {
+        let doc = doc_with(&format!("Intro\n\nThe {} sat.", SUB));
+        let anchors = SuggestionAnchors::new();
+        refresh(&anchors, &doc);
+
+        edit(&doc, |txn, text| text.insert(txn, 0, "Longer "));
+
+        let reports = refresh(&anchors, &doc);
+        assert_eq!(statuses(&reports), vec![AnchorStatus::Clean]);
+        assert_eq!(reports[0].current_from, Some(7 + 11));
+    }
+
+    #[test]
+    fn edit_on_same_line_is_conflicting() {
+        let doc = doc_with(&format!("The {} sat.", SUB));
+        let anchors = SuggestionAnchors::new();
+        refresh(&anchors, &doc);
+
+        let end = content(&doc).len() as u32;
+        edit(&doc, |txn, text| text.insert(txn, end - 1, " down"));
+
+        let reports = refresh(&anchors, &doc);
+        assert_eq!(statuses(&reports), vec![AnchorStatus::Conflicting]);
+    }
+
+    #[test]
+    fn text_typed_against_markup_is_conflicting() {
+        let doc = doc_with(&format!("The {} sat.", SUB));
+        let anchors = SuggestionAnchors::new();
+        refresh(&anchors, &doc);
+
+        edit(&doc, |txn, text| text.insert(txn, 4, "big "));
+
+        let reports = refresh(&anchors, &doc);
+        assert_eq!(statuses(&reports), vec![AnchorStatus::Conflicting]);
+        assert_eq!(reports[0].current_from, Some(8));
+    }
+
+    #[test]
+    fn accepted_suggestion_is_forgotten() {
+        let doc = doc_with(&format!("The {} sat.", SUB));
+        let anchors = SuggestionAnchors::new();
+        refresh(&anchors, &doc);
+
+        edit(&doc, |txn, text| {
+            text.remove_range(txn, 4, SUB.len() as u32);
+            text.insert(txn, 4, "dog");
+        });
+
+        assert!(refresh(&anchors, &doc).is_empty());
+    }
+
+    #[test]
+    fn hand_edited_markup_is_orphaned() {
+        let doc = doc_with(&format!("The {} sat.", SUB));
+        let anchors = SuggestionAnchors::new();
+        refresh(&anchors, &doc);
+
+        edit(&doc, |txn, text| {
+            text.remove_range(txn, 4, SUB.len() as u32);
+            text.insert(txn, 4, "black cat");
+        });
+
+        let reports = refresh(&anchors, &doc);
+        assert_eq!(statuses(&reports), vec![AnchorStatus::Orphaned]);
+        assert_eq!(reports[0].current_from, None);
+    }
+
+    #[test]
+    fn report_reads_without_anchoring() {
+        let doc = doc_with(&format!("The {} sat.", SUB));
+        let anchors = SuggestionAnchors::new();
+        let report = |anchors: &SuggestionAnchors| {
+            let txn = doc.transact();
+            let text = txn.get_text("contents").unwrap();
+            anchors.report("doc", &txn, &text)
+        };
+
+        let reports = report(&anchors);
+        assert_eq!(statuses(&reports), vec![AnchorStatus::Unknown]);
+        assert_eq!(reports[0].current_from, Some(4));
+        assert!(anchors.docs.is_empty());
+
+        refresh(&anchors, &doc);
+        assert_eq!(statuses(&report(&anchors)), vec![AnchorStatus::Clean]);
+        let end = content(&doc).len() as u32;
+        edit(&doc, |txn, text| text.insert(txn, end - 1, " down"));
+
+        assert_eq!(statuses(&report(&anchors)), vec![AnchorStatus::Conflicting]);
+    }
+
+    #[test]
+    fn rebase_recreates_orphaned_suggestion() {
+        let doc = doc_with(&format!("The {} sat.", SUB));
+        let anchors = SuggestionAnchors::new();
+        refresh(&anchors, &doc);
+
+        edit(&doc, |txn, text| {
+            text.remove_range(txn, 4, SUB.len() as u32);
+            text.insert(txn, 4, "black cat");
+        });
+
+        let (summary, reports) = {
+            let mut txn = doc.transact_mut();
+            let text = txn.get_or_insert_text("contents");
+            anchors.rebase("doc", &mut txn, &text)
+        };
+        assert_eq!(summary.rebased, 1);
+        assert_eq!(summary.dropped, 0);
+        assert!(!reports.is_empty());
+        assert!(reports.iter().all(|r| r.status == AnchorStatus::Clean));
+
+        let now = content(&doc);
+        assert!(now.starts_with("The black "), "got: {}", now);
+        assert!(now.contains(r#"{--{"author":"AI","timestamp":1000}@@cat--}"#));
+        assert!(now.contains(r#"{++{"author":"AI","timestamp":1000}@@dog++}"#));
+        assert!(now.ends_with(" sat."));
+    }
+
+    #[test]
+    fn rebase_rediffs_conflicting_suggestion() {
+        let doc = doc_with(&format!("The {} sat.", SUB));
+        let anchors = SuggestionAnchors::new();
+        refresh(&anchors, &doc);
+
+        edit(&doc, |txn, text| text.insert(txn, 4, "big "));
+
+        let (summary, reports) = {
+            let mut txn = doc.transact_mut();
+            let text = txn.get_or_insert_text("contents");
+            anchors.rebase("doc", &mut txn, &text)
+        };
+        assert_eq!(summary.rebased, 1);
+        assert!(reports.iter().all(|r| r.status == AnchorStatus::Clean));
+
+        let now = content(&doc);
+        assert!(now.starts_with("The big "), "got: {}", now);
+        assert!(
+            !now.contains("~>"),
+            "re-diffed markup uses del/ins: {}",
+            now
+        );
+    }
+
+    #[test]
+    fn rebase_drops_unrecoverable_suggestion() {
+        let doc = doc_with(&format!("The {} sat.", SUB));
+        let anchors = SuggestionAnchors::new();
+        refresh(&anchors, &doc);
+
+        edit(&doc, |txn, text| {
+            text.remove_range(txn, 4, SUB.len() as u32);
+            text.insert(txn, 4, "bird");
+        });
+
+        let (summary, reports) = {
+            let mut txn = doc.transact_mut();
+            let text = txn.get_or_insert_text("contents");
+            anchors.rebase("doc", &mut txn, &text)
+        };
+        assert_eq!(summary.dropped, 1);
+        assert!(reports.is_empty());
+        assert_eq!(content(&doc), "The bird sat.");
+    }
+}
@@ -1,10 +1,16 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::SystemTime;
 use tokio::io::AsyncReadExt;
 use y_sweet_core::{
+    api_key::load_api_keys_from_store,
     api_types::Authorization,
     auth::{AuthKeyEntry, AuthKeyMaterial, Authenticator, ExpirationTimeEpochMillis, Permission},
+    config::Config,
+    key_ring::load_key_ring_from_store,
+    revocation::load_revocations_from_store,
     store::{s3::S3Config, s3::S3Store, Store},
 };
 
@@ -39,6 +45,9 @@ fn create_authenticator_with_type(
                         key_lookup: std::collections::HashMap::new(),
                         keys_without_id: vec![0],
                         expected_audience: None,
+                        revocations: Default::default(),
+                        api_keys: Default::default(),
+                        key_ring: Default::default(),
                     })
                 }
             }
@@ -69,6 +78,9 @@ fn create_authenticator_with_type(
                         key_lookup: std::collections::HashMap::new(),
                         keys_without_id: vec![0],
                         expected_audience: None,
+                        revocations: Default::default(),
+                        api_keys: Default::default(),
+                        key_ring: Default::default(),
                     })
                 }
             }
@@ -92,6 +104,9 @@ fn create_authenticator_with_type(
                 key_lookup: std::collections::HashMap::new(),
                 keys_without_id: vec![0],
                 expected_audience: None,
+                revocations: Default::default(),
+                api_keys: Default::default(),
+                key_ring: Default::default(),
             })
         }
         "eddsa" => {
@@ -116,6 +131,9 @@ fn create_authenticator_with_type(
                 key_lookup: std::collections::HashMap::new(),
                 keys_without_id: vec![0],
                 expected_audience: None,
+                revocations: Default::default(),
+                api_keys: Default::default(),
+                key_ring: Default::default(),
             })
         }
         _ => anyhow::bail!("Invalid key type. Must be: hmac, legacy, es256, or eddsa"),
@@ -171,9 +189,11 @@ async fn sign_stdin(
 
     let authorization = match auth_str {
         "read" | "read-only" => Authorization::ReadOnly,
+        "comment" => Authorization::Comment,
+        "suggest" => Authorization::Suggest,
         "full" => Authorization::Full,
         other => anyhow::bail!(
-            "Invalid authorization: {}. Must be 'read', 'read-only', or 'full'",
+            "Invalid authorization: {}. Must be 'read', 'read-only', 'comment', 'suggest', or 'full'",
             other
         ),
     };
@@ -211,6 +231,8 @@ async fn sign_stdin(
 
             let auth_value = match authorization {
                 Authorization::ReadOnly => "read-only",
+                Authorization::Comment => "comment",
+                Authorization::Suggest => "suggest",
                 Authorization::Full => "full",
             };
             output.insert(
@@ -278,6 +300,8 @@ async fn sign_stdin(
 
             let auth_value = match authorization {
                 Authorization::ReadOnly => "read-only",
+                Authorization::Comment => "comment",
+                Authorization::Suggest => "suggest",
                 Authorization::Full => "full",
             };
             output.insert(
@@ -333,6 +357,8 @@ async fn sign_stdin(
 
             let auth_value = match authorization {
                 Authorization::ReadOnly => "read-only",
+                Authorization::Comment => "comment",
+                Authorization::Suggest => "suggest",
                 Authorization::Full => "full",
             };
             output.insert(
@@ -403,6 +429,8 @@ async fn verify_stdin(auth: &Authenticator, id: Option<&str>, key_type: &str) ->
                     // Add authorization
                     let auth_str = match doc_permission.authorization {
                         Authorization::ReadOnly => "read-only",
+                        Authorization::Comment => "comment",
+                        Authorization::Suggest => "suggest",
                         Authorization::Full => "full",
                     };
                     verification.insert(
@@ -436,6 +464,8 @@ async fn verify_stdin(auth: &Authenticator, id: Option<&str>, key_type: &str) ->
                     // Add authorization
                     let auth_str = match file_permission.authorization {
                         Authorization::ReadOnly => "read-only",
+                        Authorization::Comment => "comment",
+                        Authorization::Suggest => "suggest",
                         Authorization::Full => "full",
                     };
                     verification.insert(
@@ -478,6 +508,8 @@ async fn verify_stdin(auth: &Authenticator, id: Option<&str>, key_type: &str) ->
                     // Add authorization
                     let auth_str = match prefix_permission.authorization {
                         Authorization::ReadOnly => "read-only",
+                        Authorization::Comment => "comment",
+                        Authorization::Suggest => "suggest",
                         Authorization::Full => "full",
                     };
                     verification.insert(
@@ -601,6 +633,8 @@ async fn verify_stdin(auth: &Authenticator, id: Option<&str>, key_type: &str) ->
                     Ok(authorization) => {
                         let auth_str = match authorization {
                             Authorization::ReadOnly => "read-only",
+                            Authorization::Comment => "comment",
+                            Authorization::Suggest => "suggest",
                             Authorization::Full => "full",
                         };
 
@@ -696,6 +730,8 @@ async fn verify_stdin(auth: &Authenticator, id: Option<&str>, key_type: &str) ->
                     if let Ok(authorization) = auth_result {
                         let auth_str = match authorization {
                             Authorization::ReadOnly => "read-only",
+                            Authorization::Comment => "comment",
+                            Authorization::Suggest => "suggest",
                             Authorization::Full => "full",
                         };
 
@@ -986,6 +1022,93 @@ async fn decode_token(token: Option<&str>) -> Result<()> {
     Ok(())
 }
 
+/// Check a token against the keys and `server.url` in a relay.toml, the way
+/// a relay started with that file would. Given the relay's store, the API
+/// keys, rotated keys and revocations kept there are checked as well; the
+/// output lists whatever was not.
+async fn explain_token(
+    config_path: &Path,
+    token: Option<&str>,
+    s3_config: Option<S3Config>,
+) -> Result<()> {
+    let config = Config::load(Some(config_path))?;
+    if config.auth.is_empty() {
+        anyhow::bail!("{} configures no auth keys", config_path.display());
+    }
+    let mut authenticator = Authenticator::from_multi_key_config(&config.auth)?;
+    authenticator.set_expected_audience(config.server.url.clone());
+
+    // Folder ACLs live with the relay and are never checked here
+    let not_checked = match s3_config {
+        Some(s3_config) => {
+            load_store_auth(&authenticator, s3_config).await?;
+            vec!["folder-acls"]
+        }
+        None => vec!["api-keys", "rotated-keys", "revocations", "folder-acls"],
+    };
+
+    let token = if let Some(token) = token {
+        token.to_string()
+    } else {
+        let mut stdin = tokio::io::stdin();
+        let mut buffer = String::new();
+        stdin.read_to_string(&mut buffer).await?;
+        buffer.trim().to_string()
+    };
+
+    let current_time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_millis() as u64;
+
+    let explanation = authenticator.explain_token(&token, current_time);
+    let mut output = serde_json::to_value(&explanation)?;
+    output["notChecked"] = serde_json::json!(not_checked);
+    println!("{}", output);
+    Ok(())
+}
+
+/// Put the API keys, rotated keys and revocations saved in a relay's store
+/// in force on `authenticator`.
+async fn load_store_auth(authenticator: &Authenticator, s3_config: S3Config) -> Result<()> {
+    let store: Arc<Box<dyn Store>> = Arc::new(Box::new(S3Store::new(s3_config)));
+    store.init().await?;
+    let from_store = |e: Box<dyn std::error::Error>| anyhow::anyhow!("{}", e);
+    let api_keys = load_api_keys_from_store(store.clone())
+        .await
+        .map_err(from_store)?;
+    let key_ring = load_key_ring_from_store(store.clone())
+        .await
+        .map_err(from_store)?;
+    let revocations = load_revocations_from_store(store)
+        .await
+        .map_err(from_store)?;
+    authenticator.api_keys.replace(api_keys);
+    authenticator.key_ring.replace(key_ring)?;
+    authenticator.revocations.replace(revocations);
+    Ok(())
+}
+
+/// The S3 store given with `--store` (or RELAY_SERVER_STORAGE), with the
+/// `--endpoint` and `--path-style` overrides applied.
+fn s3_config_from_flags(
+    store: Option<&str>,
+    endpoint: Option<&str>,
+    path_style: bool,
+) -> Result<S3Config> {
+    // S3Config reads the store URL from the environment
+    if let Some(store_url) = store {
+        std::env::set_var("RELAY_SERVER_STORAGE", store_url);
+    }
+    let mut s3_config = S3Config::from_env(None, None)?;
+    if let Some(endpoint) = endpoint {
+        s3_config.endpoint = endpoint.to_string();
+    }
+    if path_style {
+        s3_config.path_style = true;
+    }
+    Ok(s3_config)
+}
+
 fn format_cbor_value(value: &ciborium::Value) -> serde_json::Value {
     match value {
         ciborium::Value::Integer(i) => {
@@ -1084,6 +1207,29 @@ enum SignSubcommand {
         token: Option<String>,
     },
 
+    /// Explain why a relay would accept or refuse a token
+    Explain {
+        /// Path to the relay's configuration file
+        #[clap(short = 'c', long = "config", default_value = "relay.toml")]
+        config: PathBuf,
+
+        /// Token to explain (if not provided, reads from stdin)
+        token: Option<String>,
+
+        /// The relay's S3 store (s3://bucket/path format), to check API keys,
+        /// rotated keys and revocations kept there
+        #[clap(long, env = "RELAY_SERVER_STORAGE")]
+        store: Option<String>,
+
+        /// Optional AWS endpoint URL override
+        #[clap(long, env = "AWS_ENDPOINT_URL_S3")]
+        endpoint: Option<String>,
+
+        /// Optional path style flag
+        #[clap(long, env = "AWS_S3_USE_PATH_STYLE")]
+        path_style: bool,
+    },
+
     /// Generate a presigned URL for a file using a token
     Presign {
         /// Action to perform (upload-url or download-url)
@@ -1141,6 +1287,19 @@ async fn main() -> Result<()> {
         SignSubcommand::Decode { token } => {
             decode_token(token.as_deref()).await?;
         }
+        SignSubcommand::Explain {
+            config,
+            token,
+            store,
+            endpoint,
+            path_style,
+        } => {
+            let s3_config = store
+                .as_deref()
+                .map(|store| s3_config_from_flags(Some(store), endpoint.as_deref(), *path_style))
+                .transpose()?;
+            explain_token(config, token.as_deref(), s3_config).await?;
+        }
         SignSubcommand::Presign {
             action,
             store,
@@ -1148,23 +1307,8 @@ async fn main() -> Result<()> {
             path_style,
             auth,
         } => {
-            // If store is provided via CLI arg, set it as an environment variable
-            if let Some(store_url) = store {
-                std::env::set_var("RELAY_SERVER_STORAGE", store_url);
-            }
-
-            // Use the unified S3Config::from_env method
-            let mut s3_config = S3Config::from_env(None, None)?;
-
-            // Override endpoint if provided
-            if let Some(endpoint) = endpoint {
-                s3_config.endpoint = endpoint.clone();
-            }
-
-            // Override path style if provided
-            if *path_style {
-                s3_config.path_style = true;
-            }
+            let s3_config =
+                s3_config_from_flags(store.as_deref(), endpoint.as_deref(), *path_style)?;
 
             // Get auth key from command line argument or environment
             let auth_key = match auth {
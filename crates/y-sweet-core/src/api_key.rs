@@ -0,0 +1,219 @@
+//! Service-account API keys.
+//!
+//! An API key is a random secret handed out once, when the key is created.
+//! The store keeps only its SHA-256 hash, together with the key's name, the
+//! folders it may reach, its authorization level and an optional expiry. A key
+//! is presented like any other bearer token and verifies to a prefix
+//! permission over every document, issued to the subject `key:<name>`; the
+//! server then narrows it to the key's folders.
+
+use crate::api_types::Authorization;
+use crate::store::Store;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, RwLock};
+
+pub const API_KEYS_KEY: &str = ".config/api-keys.json";
+
+/// Every API key secret starts with this, which tells it apart from signed
+/// tokens without decoding anything.
+pub const API_KEY_PREFIX: &str = "rk_";
+
+/// Prefix of the user name a key's requests are made as.
+pub const API_KEY_SUBJECT_PREFIX: &str = "key:";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub name: String,
+    /// Hex SHA-256 of the secret.
+    pub hash: String,
+    /// Folder paths the key may reach, e.g. `Lens Edu`. Empty means every
+    /// folder.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+    pub permission: Authorization,
+    /// Milliseconds since the epoch.
+    pub created_at: u64,
+    /// Milliseconds since the epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl ApiKey {
+    /// Create a key, returning its secret alongside it. The secret cannot be
+    /// recovered later.
+    pub fn generate(
+        name: &str,
+        scopes: Vec<String>,
+        permission: Authorization,
+        created_at: u64,
+        expires_at: Option<u64>,
+    ) -> (String, ApiKey) {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let secret = format!(
+            "{}{}",
+            API_KEY_PREFIX,
+            data_encoding::BASE64URL_NOPAD.encode(&bytes)
+        );
+        let key = ApiKey {
+            name: name.to_string(),
+            hash: hash_api_key(&secret),
+            scopes,
+            permission,
+            created_at,
+            expires_at,
+        };
+        (secret, key)
+    }
+
+    /// The user name requests made with this key carry.
+    pub fn subject(&self) -> String {
+        format!("{}{}", API_KEY_SUBJECT_PREFIX, self.name)
+    }
+
+    pub fn is_expired(&self, current_time: u64) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= current_time)
+    }
+
+    /// Whether the document at `path` lies within the key's folders.
+    pub fn covers(&self, path: &str) -> bool {
+        self.scopes.is_empty()
+            || self.scopes.iter().any(|scope| {
+                let scope = scope.trim_matches('/');
+                path == scope
+                    || path
+                        .strip_prefix(scope)
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+    }
+}
+
+pub fn is_api_key(token: &str) -> bool {
+    token.starts_with(API_KEY_PREFIX)
+}
+
+pub fn hash_api_key(secret: &str) -> String {
+    data_encoding::HEXLOWER.encode(&Sha256::digest(secret.as_bytes()))
+}
+
+/// The API keys in force. Clones share the same list, like
+/// [`crate::revocation::RevocationList`].
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeyRegistry {
+    keys: Arc<RwLock<Vec<ApiKey>>>,
+}
+
+impl PartialEq for ApiKeyRegistry {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.keys, &other.keys) || self.keys() == other.keys()
+    }
+}
+
+impl ApiKeyRegistry {
+    pub fn keys(&self) -> Vec<ApiKey> {
+        self.keys.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn replace(&self, keys: Vec<ApiKey>) {
+        *self.keys.write().unwrap_or_else(|e| e.into_inner()) = keys;
+    }
+
+    /// Add a key. Fails if one with the same name exists.
+    pub fn add(&self, key: ApiKey) -> Result<(), String> {
+        let mut keys = self.keys.write().unwrap_or_else(|e| e.into_inner());
+        if keys.iter().any(|k| k.name == key.name) {
+            return Err(format!("An API key named '{}' already exists", key.name));
+        }
+        keys.push(key);
+        Ok(())
+    }
+
+    /// Remove a key by name. Returns whether there was one.
+    pub fn remove(&self, name: &str) -> bool {
+        let mut keys = self.keys.write().unwrap_or_else(|e| e.into_inner());
+        let before = keys.len();
+        keys.retain(|k| k.name != name);
+        keys.len() != before
+    }
+
+    pub fn get(&self, name: &str) -> Option<ApiKey> {
+        self.keys
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|k| k.name == name)
+            .cloned()
+    }
+
+    /// The key whose secret is `secret`, if any.
+    pub fn find(&self, secret: &str) -> Option<ApiKey> {
+        let hash = hash_api_key(secret);
+        self.keys
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|k| k.hash == hash)
+            .cloned()
+    }
+}
+
+/// Load the API keys saved in the store. Empty if none have been saved.
+pub async fn load_api_keys_from_store(
+    store: Arc<Box<dyn Store>>,
+) -> Result<Vec<ApiKey>, Box<dyn std::error::Error>> {
+    match store.get(API_KEYS_KEY).await? {
+        Some(data) => Ok(serde_json::from_slice(&data)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+pub async fn save_api_keys_to_store(
+    store: Arc<Box<dyn Store>>,
+    keys: &[ApiKey],
+) -> Result<(), Box<dyn std::error::Error>> {
+    store.set(API_KEYS_KEY, serde_json::to_vec(keys)?).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_found_by_hash() {
+        let registry = ApiKeyRegistry::default();
+        let (secret, key) = ApiKey::generate("git-sync", Vec::new(), Authorization::Full, 0, None);
+        assert!(is_api_key(&secret));
+        assert_ne!(key.hash, secret);
+        registry.add(key.clone()).unwrap();
+        assert!(registry.add(key.clone()).is_err());
+
+        assert_eq!(registry.find(&secret), Some(key));
+        assert_eq!(registry.find("rk_wrong"), None);
+        assert!(registry.remove("git-sync"));
+        assert_eq!(registry.find(&secret), None);
+    }
+
+    #[test]
+    fn scopes_cover_whole_folders() {
+        let (_, mut key) = ApiKey::generate("ci", Vec::new(), Authorization::ReadOnly, 0, None);
+        assert!(key.covers("Anything/At all.md"));
+
+        key.scopes = vec!["Lens Edu/".to_string()];
+        assert!(key.covers("Lens Edu/Course 1/Intro.md"));
+        assert!(key.covers("Lens Edu"));
+        assert!(!key.covers("Lens Educators/Notes.md"));
+        assert!(!key.covers("Lens/Notes.md"));
+    }
+
+    #[test]
+    fn expiry() {
+        let (_, key) = ApiKey::generate("ci", Vec::new(), Authorization::Full, 0, Some(1_000));
+        assert!(!key.is_expired(999));
+        assert!(key.is_expired(1_000));
+    }
+}
@@ -71,12 +71,23 @@ pub fn validate_file_hash(hash: &str) -> bool {
     hash.chars().all(|c| c.is_ascii_hexdigit())
 }
 
+/// What a token lets its holder do to a document, from viewing (`ReadOnly`)
+/// up to unrestricted editing (`Full`).
+///
+/// `Comment` and `Suggest` may change a document only through CriticMarkup:
+/// commenters add or edit `{>>comments<<}` (and `{==highlights==}`), and
+/// suggesters may also propose insertions, deletions and substitutions.
+/// New variants go at the end: legacy tokens store the variant index.
 #[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub enum Authorization {
     #[serde(rename = "read-only")]
     ReadOnly,
     #[serde(rename = "full")]
     Full,
+    #[serde(rename = "comment")]
+    Comment,
+    #[serde(rename = "suggest")]
+    Suggest,
 }
 
 impl Authorization {
@@ -1,5 +1,10 @@
+use crate::api_key::{is_api_key, ApiKey, ApiKeyRegistry};
 use crate::api_types::Authorization;
+use crate::auth_audit::{AuthDecision, Decision, TokenKind};
 use crate::config::TokenType;
+use crate::key_ring::{is_retired, KeyRing, RotatedKey};
+use crate::revocation::{RevocationList, TokenIdentity};
+use crate::token_explain::{MatchedKey, TokenExplanation, TokenGrant};
 use bincode::Options;
 use data_encoding::Encoding;
 use rand::Rng;
@@ -71,7 +76,7 @@ pub const BASE64_CUSTOM: Encoding = Encoding::internal_new(&[
     128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 30, 0,
 ]);
 
-#[derive(Error, Debug, PartialEq, Eq)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum AuthError {
     #[error("The token is not a valid format")]
     InvalidToken,
@@ -119,6 +124,10 @@ pub enum AuthError {
     UnauthorizedTokenType(String),
     #[error("Invalid token type in configuration: {0}")]
     InvalidTokenType(String),
+    #[error("The token has been revoked")]
+    Revoked,
+    #[error("{0} signing keys cannot be rotated; replace the key in the [[auth]] section of the config file instead")]
+    UnsupportedRotation(&'static str),
 }
 
 impl AuthError {
@@ -147,6 +156,8 @@ impl AuthError {
             AuthError::InsufficientPermissions(_) => "insufficient_permissions",
             AuthError::UnauthorizedTokenType(_) => "unauthorized_token_type",
             AuthError::InvalidTokenType(_) => "invalid_token_type",
+            AuthError::Revoked => "revoked",
+            AuthError::UnsupportedRotation(_) => "unsupported_rotation",
         }
     }
 }
@@ -191,22 +202,121 @@ pub struct AuthKeyEntry {
     pub allowed_token_types: Vec<TokenType>,
 }
 
+/// A verification key in the shape of a JSON Web Key. Symmetric keys are
+/// listed without their key material, so clients can still tell which key ID
+/// is current.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PublishedKey {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    pub kty: &'static str,
+    pub alg: &'static str,
+    #[serde(rename = "use")]
+    pub key_use: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+    /// `signing` for the key new tokens are signed with, `retiring` for keys
+    /// a rotation has replaced, otherwise `verifying`.
+    pub status: &'static str,
+    /// When a retiring key stops being accepted, in seconds since the epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+}
+
+impl PublishedKey {
+    fn new(key_entry: &AuthKeyEntry, status: &'static str, retires_at: Option<u64>) -> Self {
+        let b64url = |bytes: &[u8]| data_encoding::BASE64URL_NOPAD.encode(bytes);
+        let (kty, alg, crv, x, y) = match &key_entry.key_material {
+            AuthKeyMaterial::Hmac256(_) => ("oct", "HS256", None, None, None),
+            AuthKeyMaterial::Legacy(_) => ("oct", "legacy", None, None, None),
+            AuthKeyMaterial::EcdsaP256Private(bytes) | AuthKeyMaterial::EcdsaP256Public(bytes) => {
+                use p256::elliptic_curve::sec1::ToEncodedPoint;
+                let public_key = match &key_entry.key_material {
+                    AuthKeyMaterial::EcdsaP256Private(_) => p256::SecretKey::from_slice(bytes)
+                        .ok()
+                        .map(|secret_key| secret_key.public_key()),
+                    _ => p256::PublicKey::from_sec1_bytes(bytes).ok(),
+                };
+                let point = public_key.map(|public_key| public_key.to_encoded_point(false));
+                (
+                    "EC",
+                    "ES256",
+                    Some("P-256"),
+                    point
+                        .as_ref()
+                        .and_then(|p| p.x())
+                        .map(|x| b64url(x.as_slice())),
+                    point
+                        .as_ref()
+                        .and_then(|p| p.y())
+                        .map(|y| b64url(y.as_slice())),
+                )
+            }
+            AuthKeyMaterial::Ed25519Private(bytes) | AuthKeyMaterial::Ed25519Public(bytes) => {
+                let public_bytes = match &key_entry.key_material {
+                    AuthKeyMaterial::Ed25519Private(_) => {
+                        <[u8; 32]>::try_from(bytes.as_slice()).ok().map(|secret| {
+                            ed25519_dalek::SigningKey::from_bytes(&secret)
+                                .verifying_key()
+                                .to_bytes()
+                                .to_vec()
+                        })
+                    }
+                    _ => Some(bytes.clone()),
+                };
+                (
+                    "OKP",
+                    "EdDSA",
+                    Some("Ed25519"),
+                    public_bytes.map(|x| b64url(&x)),
+                    None,
+                )
+            }
+        };
+        Self {
+            kid: key_entry.key_id.clone(),
+            kty,
+            alg,
+            key_use: "sig",
+            crv,
+            x,
+            y,
+            status,
+            exp: retires_at.map(|retires_at| retires_at / 1000),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
 pub struct Authenticator {
     pub keys: Vec<AuthKeyEntry>,
     pub key_lookup: std::collections::HashMap<String, usize>,
     pub keys_without_id: Vec<usize>,
     pub expected_audience: Option<String>,
+    /// Shared with every clone; see [`RevocationList`].
+    #[serde(skip)]
+    pub revocations: RevocationList,
+    /// Service-account API keys; shared with every clone, like `revocations`.
+    #[serde(skip)]
+    pub api_keys: ApiKeyRegistry,
+    /// Signing keys added by rotation, shared with every clone. Once one
+    /// exists it signs in place of `keys`.
+    #[serde(skip)]
+    pub key_ring: KeyRing,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct DocPermission {
     pub doc_id: String,
     pub authorization: Authorization,
     pub user: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct FilePermission {
     pub file_hash: String,
     pub authorization: Authorization,
@@ -232,14 +342,14 @@ struct LegacyFilePermission {
     pub doc_id: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct PrefixPermission {
     pub prefix: String,
     pub authorization: Authorization,
     pub user: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Permission {
     Server,
     Doc(DocPermission),
@@ -247,6 +357,48 @@ pub enum Permission {
     Prefix(PrefixPermission),
 }
 
+impl Permission {
+    /// The access this permission grants to the document `doc`.
+    pub fn doc_authorization(&self, doc: &str) -> Result<Authorization, AuthError> {
+        match self {
+            Permission::Doc(doc_permission) => {
+                if doc_permission.doc_id == doc {
+                    Ok(doc_permission.authorization)
+                } else {
+                    Err(AuthError::InvalidResource)
+                }
+            }
+            Permission::File(file_permission) => {
+                // Only check for file tokens using doc_id, not file_hash
+                // This prevents document tokens from being misinterpreted
+                if file_permission.doc_id == doc {
+                    Ok(file_permission.authorization)
+                } else {
+                    Err(AuthError::InvalidResource)
+                }
+            }
+            Permission::Prefix(prefix_permission) => {
+                if doc.starts_with(&prefix_permission.prefix) {
+                    Ok(prefix_permission.authorization)
+                } else {
+                    Err(AuthError::InvalidResource)
+                }
+            }
+            Permission::Server => Ok(Authorization::Full), // Server tokens can access any doc.
+        }
+    }
+
+    /// The user the token was issued to, if it names one. Server tokens never do.
+    pub fn user(&self) -> Option<&str> {
+        match self {
+            Permission::Server => None,
+            Permission::Doc(doc) => doc.user.as_deref(),
+            Permission::File(file) => file.user.as_deref(),
+            Permission::Prefix(prefix) => prefix.user.as_deref(),
+        }
+    }
+}
+
 // Legacy enums for backward compatibility
 #[derive(Serialize, Deserialize)]
 enum LegacyPermission {
@@ -349,7 +501,7 @@ fn detect_key_type(key_bytes: &[u8]) -> &'static str {
     }
 }
 
-fn parse_private_key_format(input: &str) -> Result<AuthKeyMaterial, AuthError> {
+pub(crate) fn parse_private_key_format(input: &str) -> Result<AuthKeyMaterial, AuthError> {
     let trimmed = input.trim();
 
     // Raw base64 - for private keys, only Legacy and HMAC256 are supported
@@ -616,6 +768,142 @@ fn extract_cwt_key_id(token: &str) -> Option<String> {
     None
 }
 
+/// The ID revocations name a token by: its `cti` claim in hex, or for tokens
+/// without one, the first 16 bytes of the token's SHA-256 in hex. Does not
+/// verify the token.
+pub fn token_id(token: &str) -> String {
+    if detect_token_format(token) == TokenFormat::Cwt {
+        if let Some(cti) = extract_cwt_claims_unverified(token).and_then(|claims| claims.token_id) {
+            return data_encoding::HEXLOWER.encode(&cti);
+        }
+    }
+    data_encoding::HEXLOWER.encode(&Sha256::digest(token.as_bytes())[..16])
+}
+
+/// Read a CWT token's claims without checking its signature.
+fn extract_cwt_claims_unverified(token: &str) -> Option<crate::cwt::CwtClaims> {
+    let token_bytes = b64_decode(token).ok()?;
+    let cbor_value: ciborium::Value = ciborium::de::from_reader(&token_bytes[..]).ok()?;
+    let cose_structure = match cbor_value {
+        ciborium::Value::Tag(61, inner) => *inner,
+        other => other,
+    };
+    let ciborium::Value::Tag(17 | 18, cose_content) = cose_structure else {
+        return None;
+    };
+    let ciborium::Value::Array(cose_array) = *cose_content else {
+        return None;
+    };
+    let Some(ciborium::Value::Bytes(payload)) = cose_array.get(2) else {
+        return None;
+    };
+    let claims_map: ciborium::Value = ciborium::de::from_reader(&payload[..]).ok()?;
+    crate::cwt::parse_claims(claims_map).ok()
+}
+
+/// Read the COSE algorithm from a CWT token's protected header.
+fn extract_cwt_algorithm(token: &str) -> Option<i64> {
+    let token_bytes = b64_decode(token).ok()?;
+    let cbor_value: ciborium::Value = ciborium::de::from_reader(&token_bytes[..]).ok()?;
+    let cose_structure = match cbor_value {
+        ciborium::Value::Tag(61, inner) => *inner,
+        other => other,
+    };
+    let ciborium::Value::Tag(17 | 18, cose_content) = cose_structure else {
+        return None;
+    };
+    let ciborium::Value::Array(cose_array) = *cose_content else {
+        return None;
+    };
+    let Some(ciborium::Value::Bytes(protected)) = cose_array.first() else {
+        return None;
+    };
+    let protected: ciborium::Value = ciborium::de::from_reader(&protected[..]).ok()?;
+    let ciborium::Value::Map(headers) = protected else {
+        return None;
+    };
+    headers
+        .into_iter()
+        .find_map(|(label, value)| match (label, value) {
+            // COSE header parameter 1 is the algorithm
+            (ciborium::Value::Integer(label), ciborium::Value::Integer(alg))
+                if i64::try_from(label).ok() == Some(1) =>
+            {
+                i64::try_from(alg).ok()
+            }
+            _ => None,
+        })
+}
+
+fn cose_algorithm_name(alg: i64) -> &'static str {
+    match alg {
+        4 => "HMAC 256/64",
+        5 => "HMAC 256/256",
+        6 => "HMAC 384/384",
+        7 => "HMAC 512/512",
+        -7 => "ES256",
+        -8 => "EdDSA",
+        _ => "unknown",
+    }
+}
+
+fn key_algorithm(key_material: &AuthKeyMaterial) -> &'static str {
+    match key_material {
+        AuthKeyMaterial::Hmac256(_) => "HS256",
+        AuthKeyMaterial::Legacy(_) => "legacy",
+        AuthKeyMaterial::EcdsaP256Private(_) | AuthKeyMaterial::EcdsaP256Public(_) => "ES256",
+        AuthKeyMaterial::Ed25519Private(_) | AuthKeyMaterial::Ed25519Public(_) => "EdDSA",
+    }
+}
+
+/// Whether a key can verify a token in `format` signed with `alg`. CWT
+/// tokens without an algorithm are MACed with HMAC 256/64.
+fn key_accepts_algorithm(
+    key_material: &AuthKeyMaterial,
+    format: &TokenFormat,
+    alg: Option<i64>,
+) -> bool {
+    match (format, key_material) {
+        (TokenFormat::Custom, AuthKeyMaterial::Legacy(_)) => true,
+        (TokenFormat::Custom, _) | (TokenFormat::Cwt, AuthKeyMaterial::Legacy(_)) => false,
+        (TokenFormat::Cwt, AuthKeyMaterial::Hmac256(_)) => matches!(alg, None | Some(4..=7)),
+        (
+            TokenFormat::Cwt,
+            AuthKeyMaterial::EcdsaP256Private(_) | AuthKeyMaterial::EcdsaP256Public(_),
+        ) => alg == Some(-7),
+        (
+            TokenFormat::Cwt,
+            AuthKeyMaterial::Ed25519Private(_) | AuthKeyMaterial::Ed25519Public(_),
+        ) => alg == Some(-8),
+    }
+}
+
+/// The resources a permission reaches, as `y-sign explain` lists them.
+/// The folders an API key reaches, as `folder:<path>`, or every document if
+/// it has no scopes.
+fn api_key_resources(key: &ApiKey) -> Vec<String> {
+    if key.scopes.is_empty() {
+        return vec!["doc:*".to_string()];
+    }
+    key.scopes
+        .iter()
+        .map(|scope| format!("folder:{}", scope.trim_matches('/')))
+        .collect()
+}
+
+fn granted_resources(permission: &Permission) -> Vec<String> {
+    match permission {
+        Permission::Server => vec!["*".to_string()],
+        Permission::Doc(doc) => vec![format!("doc:{}", doc.doc_id)],
+        // File tokens also open the document the file belongs to
+        Permission::File(file) => vec![
+            format!("file:{}", file.file_hash),
+            format!("doc:{}", file.doc_id),
+        ],
+        Permission::Prefix(prefix) => vec![format!("doc:{}*", prefix.prefix)],
+    }
+}
+
 fn is_cwt_token(data: &[u8]) -> bool {
     // Try to parse as CBOR value first
     if let Ok(cbor_value) = ciborium::de::from_reader(&data[..]) {
@@ -685,6 +973,9 @@ impl Authenticator {
             key_lookup: std::collections::HashMap::new(),
             keys_without_id: vec![0],
             expected_audience: None,
+            revocations: RevocationList::default(),
+            api_keys: ApiKeyRegistry::default(),
+            key_ring: KeyRing::default(),
         })
     }
 
@@ -745,6 +1036,9 @@ impl Authenticator {
             key_lookup,
             keys_without_id,
             expected_audience: None,
+            revocations: RevocationList::default(),
+            api_keys: ApiKeyRegistry::default(),
+            key_ring: KeyRing::default(),
         })
     }
 
@@ -795,18 +1089,58 @@ impl Authenticator {
         let signing_key = self
             .get_signing_key()
             .map_err(|_| crate::cwt::CwtError::InvalidCose)?;
-        self.create_cwt_authenticator_for_key(signing_key)
+        self.create_cwt_authenticator_for_key(&signing_key)
+    }
+
+    /// Whether a key from the config file still verifies tokens at
+    /// `current_time`. The signing key a rotation replaced stops once the
+    /// grace period runs out; every other key is kept.
+    fn config_key_active(&self, key_entry: &AuthKeyEntry, current_time: u64) -> bool {
+        !self.is_replaced_config_key(key_entry)
+            || !is_retired(self.key_ring.config_keys_retire_at(), current_time)
+    }
+
+    /// Whether `key_entry` is the config file's signing key that the first
+    /// rotation replaced. Key rings saved before the replaced key was
+    /// recorded, or whose replaced key had no ID, mean the config file's
+    /// current signing key.
+    fn is_replaced_config_key(&self, key_entry: &AuthKeyEntry) -> bool {
+        if self.key_ring.config_keys_retire_at().is_none() {
+            return false;
+        }
+        match self.key_ring.replaced_config_key_id() {
+            Some(key_id) => key_entry.key_id.as_deref() == Some(key_id.as_str()),
+            None => self.keys.iter().find(|k| k.can_sign) == Some(key_entry),
+        }
+    }
+
+    /// Every key that verifies tokens at `current_time`: the config file's,
+    /// then rotated keys, newest first.
+    fn verifying_keys(&self, current_time: u64) -> Vec<AuthKeyEntry> {
+        let mut keys: Vec<AuthKeyEntry> = self
+            .keys
+            .iter()
+            .filter(|key_entry| self.config_key_active(key_entry, current_time))
+            .cloned()
+            .collect();
+        keys.extend(
+            self.key_ring
+                .verifying_keys(current_time)
+                .into_iter()
+                .map(|(entry, _)| entry),
+        );
+        keys
     }
 
     /// Find which key was used to verify a token by attempting verification with each key
-    fn find_verifying_key(&self, token: &str) -> Result<&AuthKeyEntry, AuthError> {
+    fn find_verifying_key(&self, token: &str) -> Result<AuthKeyEntry, AuthError> {
         let format = detect_token_format(token);
 
         match format {
             TokenFormat::Custom => {
                 // Try verification with each key and return the first that succeeds
-                for key_entry in &self.keys {
-                    if self.verify_with_key_entry(key_entry, token, 0).is_ok() {
+                for key_entry in self.verifying_keys(0) {
+                    if self.verify_with_key_entry(&key_entry, token, 0).is_ok() {
                         return Ok(key_entry);
                     }
                 }
@@ -817,7 +1151,10 @@ impl Authenticator {
                 if let Some(key_id_from_token) = extract_cwt_key_id(token) {
                     // Look up the key by ID
                     if let Some(&index) = self.key_lookup.get(&key_id_from_token) {
-                        return Ok(&self.keys[index]);
+                        return Ok(self.keys[index].clone());
+                    }
+                    if let Some(key_entry) = self.key_ring.get(&key_id_from_token, 0) {
+                        return Ok(key_entry);
                     }
                 }
 
@@ -829,7 +1166,7 @@ impl Authenticator {
                             .verify_cwt_with_key(key_entry, token, 0, audience)
                             .is_ok()
                         {
-                            return Ok(key_entry);
+                            return Ok(key_entry.clone());
                         }
                     }
                 }
@@ -839,14 +1176,53 @@ impl Authenticator {
         }
     }
 
-    /// Get the first signing key
-    fn get_signing_key(&self) -> Result<&AuthKeyEntry, AuthError> {
+    /// Get the newest rotated key, or else the first signing key from the config
+    fn get_signing_key(&self) -> Result<AuthKeyEntry, AuthError> {
+        if let Some(key_entry) = self.key_ring.signing_key() {
+            return Ok(key_entry);
+        }
         self.keys
             .iter()
             .find(|k| k.can_sign)
+            .cloned()
             .ok_or(AuthError::NoSigningKey)
     }
 
+    /// The ID of the key new tokens are signed with, if it has one.
+    pub fn signing_key_id(&self) -> Option<String> {
+        self.get_signing_key().ok()?.key_id
+    }
+
+    /// A fresh key of the same kind as the current signing key, to rotate to.
+    /// Only symmetric keys can be rotated: a new key pair would need its
+    /// public half handed to every verifier first.
+    pub fn new_rotated_key(&self, key_id: &str, now: u64) -> Result<RotatedKey, AuthError> {
+        for key_entry in &self.keys {
+            match key_entry.key_material {
+                AuthKeyMaterial::EcdsaP256Private(_) => {
+                    return Err(AuthError::UnsupportedRotation("ES256"))
+                }
+                AuthKeyMaterial::Ed25519Private(_) => {
+                    return Err(AuthError::UnsupportedRotation("Ed25519"))
+                }
+                _ => {}
+            }
+        }
+        let signing_key = self.get_signing_key()?;
+        let key_bytes: Vec<u8> = match signing_key.key_material {
+            AuthKeyMaterial::Hmac256(_) => rand::thread_rng().gen::<[u8; 32]>().to_vec(),
+            AuthKeyMaterial::Legacy(_) => rand::thread_rng().gen::<[u8; 30]>().to_vec(),
+            _ => return Err(AuthError::NoSigningKey),
+        };
+        Ok(RotatedKey {
+            key_id: key_id.to_string(),
+            private_key: b64_encode(&key_bytes),
+            allowed_token_types: signing_key.allowed_token_types,
+            created_at: now,
+            retires_at: None,
+        })
+    }
+
     /// Get the key material for direct access by callers (first key for compatibility)
     pub fn key_material(&self) -> &AuthKeyMaterial {
         &self.keys[0].key_material
@@ -891,6 +1267,44 @@ impl Authenticator {
         }
     }
 
+    /// The keys that verify tokens at `current_time`, for publishing.
+    pub fn key_set(&self, current_time: u64) -> Vec<PublishedKey> {
+        let signing_key = self.get_signing_key().ok();
+        let status = |key_entry: &AuthKeyEntry, retires_at: Option<u64>| {
+            if Some(key_entry) == signing_key.as_ref() {
+                "signing"
+            } else if retires_at.is_some() {
+                "retiring"
+            } else {
+                "verifying"
+            }
+        };
+
+        let mut published = Vec::new();
+        for key_entry in &self.keys {
+            if !self.config_key_active(key_entry, current_time) {
+                continue;
+            }
+            let retires_at = self
+                .is_replaced_config_key(key_entry)
+                .then(|| self.key_ring.config_keys_retire_at())
+                .flatten();
+            published.push(PublishedKey::new(
+                key_entry,
+                status(key_entry, retires_at),
+                retires_at,
+            ));
+        }
+        for (key_entry, retires_at) in self.key_ring.verifying_keys(current_time) {
+            published.push(PublishedKey::new(
+                &key_entry,
+                status(&key_entry, retires_at),
+                retires_at,
+            ));
+        }
+        published
+    }
+
     pub fn server_token(&self) -> Result<String, AuthError> {
         self.server_token_cwt()
     }
@@ -944,6 +1358,9 @@ impl Authenticator {
             key_lookup,
             keys_without_id,
             expected_audience: None,
+            revocations: self.revocations,
+            api_keys: self.api_keys,
+            key_ring: self.key_ring,
         }
     }
 
@@ -1188,7 +1605,7 @@ impl Authenticator {
         let cwt_auth = self.create_cwt_authenticator().map_err(|e| match e {
             crate::cwt::CwtError::InvalidCose => {
                 // Check if we actually have no signing keys
-                if self.keys.iter().any(|k| k.can_sign) {
+                if self.get_signing_key().is_ok() {
                     AuthError::CannotSignWithPublicKey
                 } else {
                     AuthError::NoSigningKey
@@ -1218,6 +1635,7 @@ impl Authenticator {
             ),
             scope: permission_to_scope(&permission),
             channel,
+            token_id: Some(rand::random::<[u8; 16]>().to_vec()),
         };
 
         let token_bytes = cwt_auth
@@ -1235,34 +1653,8 @@ impl Authenticator {
         doc: &str,
         current_time_epoch_millis: u64,
     ) -> Result<Authorization, AuthError> {
-        let payload = self.verify_token_auto(token, current_time_epoch_millis)?;
-
-        match payload {
-            Permission::Doc(doc_permission) => {
-                if doc_permission.doc_id == doc {
-                    Ok(doc_permission.authorization)
-                } else {
-                    Err(AuthError::InvalidResource)
-                }
-            }
-            Permission::File(file_permission) => {
-                // Only check for file tokens using doc_id, not file_hash
-                // This prevents document tokens from being misinterpreted
-                if file_permission.doc_id == doc {
-                    Ok(file_permission.authorization)
-                } else {
-                    Err(AuthError::InvalidResource)
-                }
-            }
-            Permission::Prefix(prefix_permission) => {
-                if doc.starts_with(&prefix_permission.prefix) {
-                    Ok(prefix_permission.authorization)
-                } else {
-                    Err(AuthError::InvalidResource)
-                }
-            }
-            Permission::Server => Ok(Authorization::Full), // Server tokens can access any doc.
-        }
+        self.verify_token_auto(token, current_time_epoch_millis)?
+            .doc_authorization(doc)
     }
 
     pub fn verify_file_token(
@@ -1371,6 +1763,9 @@ impl Authenticator {
             key_lookup: std::collections::HashMap::new(),
             keys_without_id: vec![0],
             expected_audience: None,
+            revocations: RevocationList::default(),
+            api_keys: ApiKeyRegistry::default(),
+            key_ring: KeyRing::default(),
         })
     }
 
@@ -1395,6 +1790,9 @@ impl Authenticator {
             key_lookup: std::collections::HashMap::new(),
             keys_without_id: vec![0],
             expected_audience: None,
+            revocations: RevocationList::default(),
+            api_keys: ApiKeyRegistry::default(),
+            key_ring: KeyRing::default(),
         })
     }
 
@@ -1416,6 +1814,9 @@ impl Authenticator {
             key_lookup: std::collections::HashMap::new(),
             keys_without_id: vec![0],
             expected_audience: None,
+            revocations: RevocationList::default(),
+            api_keys: ApiKeyRegistry::default(),
+            key_ring: KeyRing::default(),
         })
     }
 
@@ -1443,6 +1844,10 @@ impl Authenticator {
         token: &str,
         current_time: u64,
     ) -> Result<Permission, AuthError> {
+        if is_api_key(token) {
+            return self.verify_api_key(token, current_time);
+        }
+
         // First verify the token normally
         let permission = self.verify_token_internal(token, current_time)?;
 
@@ -1463,9 +1868,325 @@ impl Authenticator {
             )));
         }
 
+        self.check_revoked(token, &permission)?;
         Ok(permission)
     }
 
+    /// Look up a service-account API key. A valid key grants its permission
+    /// on every document to the key's subject; folder scopes are the server's
+    /// to enforce, since only it knows document paths.
+    fn verify_api_key(&self, token: &str, current_time: u64) -> Result<Permission, AuthError> {
+        let Some(key) = self.api_keys.find(token) else {
+            return Err(AuthError::InvalidToken);
+        };
+        if key.is_expired(current_time) {
+            return Err(AuthError::Expired);
+        }
+        let permission = Permission::Prefix(PrefixPermission {
+            prefix: String::new(),
+            authorization: key.permission,
+            user: Some(key.subject()),
+        });
+        self.check_revoked(token, &permission)?;
+        Ok(permission)
+    }
+
+    /// What revocations can match `token` on. `subject` is the user the
+    /// verified token was issued to.
+    pub fn token_identity(&self, token: &str, subject: Option<&str>) -> TokenIdentity {
+        let issued_at = match detect_token_format(token) {
+            TokenFormat::Cwt => extract_cwt_claims_unverified(token)
+                .and_then(|claims| claims.issued_at)
+                .map(|iat| iat * 1000),
+            TokenFormat::Custom => None,
+        };
+        TokenIdentity {
+            token_id: token_id(token),
+            subject: subject.map(str::to_string),
+            key_id: extract_cwt_key_id(token),
+            issued_at,
+        }
+    }
+
+    /// Describe a decision about a request for the audit trail. The
+    /// principal, token kind and key ID are read from `token`, given with
+    /// what verifying it came to; API keys are described from the key they
+    /// name, so need no verifying. An expired or revoked token still names
+    /// its principal, while one that fails to verify otherwise is recorded as
+    /// invalid. `denial` is the reason a request was refused, `None` if it
+    /// was allowed.
+    pub fn audit_decision(
+        &self,
+        token: Option<(&str, Option<&Result<Permission, AuthError>>)>,
+        resource: &str,
+        action: &str,
+        denial: Option<String>,
+        current_time: u64,
+    ) -> AuthDecision {
+        let (token_kind, principal, key_id) = match token {
+            None => (TokenKind::None, None, None),
+            Some((token, _)) if is_api_key(token) => match self.api_keys.find(token) {
+                Some(key) => (TokenKind::ApiKey, Some(key.subject()), Some(key.name)),
+                None => (TokenKind::Invalid, None, None),
+            },
+            Some((token, verified)) => {
+                let claimed;
+                let permission = match verified {
+                    Some(Ok(permission)) => Some(permission),
+                    // Both are only found once the signature checks out, so
+                    // the token's claims can be taken as they stand
+                    Some(Err(AuthError::Expired | AuthError::Revoked)) => {
+                        claimed = self.claimed_permission(token);
+                        claimed.as_ref()
+                    }
+                    _ => None,
+                };
+                match permission {
+                    Some(permission) => {
+                        let token_kind = match permission {
+                            Permission::Server => TokenKind::Server,
+                            Permission::Doc(_) => TokenKind::Doc,
+                            Permission::File(_) => TokenKind::File,
+                            Permission::Prefix(_) => TokenKind::Prefix,
+                        };
+                        let principal = permission.user().map(str::to_string);
+                        (token_kind, principal, extract_cwt_key_id(token))
+                    }
+                    None => (TokenKind::Invalid, None, extract_cwt_key_id(token)),
+                }
+            }
+        };
+        AuthDecision {
+            timestamp: current_time,
+            principal,
+            token_kind,
+            key_id,
+            resource: resource.to_string(),
+            action: action.to_string(),
+            decision: if denial.is_some() {
+                Decision::Deny
+            } else {
+                Decision::Allow
+            },
+            reason: denial,
+        }
+    }
+
+    /// The permission `token` claims, read without checking its signature.
+    /// CWT tokens carry the user in their subject.
+    fn claimed_permission(&self, token: &str) -> Option<Permission> {
+        match detect_token_format(token) {
+            TokenFormat::Custom => self.decode_token(token).ok().map(|payload| payload.payload),
+            TokenFormat::Cwt => {
+                let claims = extract_cwt_claims_unverified(token)?;
+                let mut permission = crate::cwt::scope_to_permission(&claims.scope).ok()?;
+                match &mut permission {
+                    Permission::Doc(doc_perm) => doc_perm.user = claims.subject,
+                    Permission::File(file_perm) => file_perm.user = claims.subject,
+                    Permission::Prefix(prefix_perm) => prefix_perm.user = claims.subject,
+                    Permission::Server => {}
+                }
+                Some(permission)
+            }
+        }
+    }
+
+    /// Work through each check [`Self::verify_token_auto`] makes on `token`
+    /// and report how it fares, along with what the token grants. Claims
+    /// are read whether or not the token verifies, so a refused token still
+    /// says what it was for. Only the keys, API keys and revocations this
+    /// authenticator holds are consulted; ACLs kept by a running relay are
+    /// not.
+    pub fn explain_token(&self, token: &str, current_time: u64) -> TokenExplanation {
+        let verified = self.verify_token_auto(token, current_time);
+
+        if is_api_key(token) {
+            let key = self.api_keys.find(token);
+            let expires_at = key.as_ref().and_then(|key| key.expires_at);
+            return TokenExplanation {
+                format: "api-key",
+                key_id: key.as_ref().map(|key| key.name.clone()),
+                algorithm: None,
+                matched_key: None,
+                token_type: key.as_ref().map(|_| TokenType::Prefix),
+                issuer: None,
+                audience: None,
+                expected_audience: self.expected_audience.clone(),
+                audience_valid: None,
+                expires_at,
+                expired: key.as_ref().is_some_and(|key| key.is_expired(current_time)),
+                grants: key.map(|key| TokenGrant {
+                    resources: api_key_resources(&key),
+                    authorization: key.permission,
+                    user: Some(key.subject()),
+                }),
+                valid: verified.is_ok(),
+                error: verified.err().map(|e| e.to_string()),
+            };
+        }
+
+        let format = detect_token_format(token);
+        let (claims, permission, expires_at) = match format {
+            TokenFormat::Cwt => {
+                let claims = extract_cwt_claims_unverified(token);
+                let permission = claims
+                    .as_ref()
+                    .and_then(|claims| crate::cwt::scope_to_permission(&claims.scope).ok());
+                let expires_at = claims
+                    .as_ref()
+                    .and_then(|claims| claims.expiration)
+                    .map(|exp| exp * 1000);
+                (claims, permission, expires_at)
+            }
+            TokenFormat::Custom => {
+                let payload = self.decode_token(token).ok();
+                let expires_at = payload
+                    .as_ref()
+                    .and_then(|payload| payload.expiration_millis)
+                    .map(|exp| exp.0);
+                (None, payload.map(|payload| payload.payload), expires_at)
+            }
+        };
+        let token_type = permission.as_ref().map(TokenType::from_permission);
+        let algorithm = extract_cwt_algorithm(token);
+
+        let audience = claims.as_ref().and_then(|claims| claims.audience.clone());
+        let audience_valid = (format == TokenFormat::Cwt)
+            .then(|| audience.is_some() && audience == self.expected_audience);
+
+        let grants = permission.as_ref().map(|permission| TokenGrant {
+            resources: granted_resources(permission),
+            authorization: match permission {
+                Permission::Server => Authorization::Full,
+                Permission::Doc(doc) => doc.authorization,
+                Permission::File(file) => file.authorization,
+                Permission::Prefix(prefix) => prefix.authorization,
+            },
+            // CWT tokens carry the user in their subject rather than their scope
+            user: permission
+                .user()
+                .map(str::to_string)
+                .or_else(|| claims.as_ref().and_then(|claims| claims.subject.clone())),
+        });
+
+        TokenExplanation {
+            format: match format {
+                TokenFormat::Cwt => "cwt",
+                TokenFormat::Custom => "custom",
+            },
+            key_id: extract_cwt_key_id(token),
+            algorithm: algorithm.map(cose_algorithm_name),
+            matched_key: self.explain_matched_key(
+                token,
+                &format,
+                algorithm,
+                token_type.as_ref(),
+                current_time,
+            ),
+            token_type,
+            issuer: claims.as_ref().and_then(|claims| claims.issuer.clone()),
+            audience,
+            expected_audience: self.expected_audience.clone(),
+            audience_valid,
+            expires_at,
+            expired: expires_at.is_some_and(|exp| exp < current_time),
+            grants,
+            valid: verified.is_ok(),
+            error: verified.err().map(|e| e.to_string()),
+        }
+    }
+
+    /// The key a token is checked against: the one its key ID names, or
+    /// else the first key without an ID whose signature it carries.
+    fn explain_matched_key(
+        &self,
+        token: &str,
+        format: &TokenFormat,
+        algorithm: Option<i64>,
+        token_type: Option<&TokenType>,
+        current_time: u64,
+    ) -> Option<MatchedKey> {
+        let describe = |key_entry: &AuthKeyEntry, source: &'static str, retired: bool| MatchedKey {
+            key_id: key_entry.key_id.clone(),
+            source,
+            algorithm: key_algorithm(&key_entry.key_material),
+            algorithm_allowed: key_accepts_algorithm(&key_entry.key_material, format, algorithm),
+            allowed_token_types: key_entry.allowed_token_types.clone(),
+            token_type_allowed: token_type
+                .is_some_and(|token_type| key_entry.allowed_token_types.contains(token_type)),
+            signature_valid: self.signature_matches(key_entry, token),
+            retired,
+        };
+
+        if let Some(key_id) = extract_cwt_key_id(token) {
+            if let Some(&index) = self.key_lookup.get(&key_id) {
+                let key_entry = &self.keys[index];
+                let retired = !self.config_key_active(key_entry, current_time);
+                return Some(describe(key_entry, "config", retired));
+            }
+            let key_entry = self.key_ring.get(&key_id, 0)?;
+            let retired = self.key_ring.get(&key_id, current_time).is_none();
+            return Some(describe(&key_entry, "rotated", retired));
+        }
+
+        let candidates: Vec<usize> = match format {
+            TokenFormat::Cwt => self.keys_without_id.clone(),
+            TokenFormat::Custom => (0..self.keys.len()).collect(),
+        };
+        candidates
+            .into_iter()
+            .map(|index| &self.keys[index])
+            .find(|key_entry| self.signature_matches(key_entry, token))
+            .map(|key_entry| {
+                let retired = !self.config_key_active(key_entry, current_time);
+                describe(key_entry, "config", retired)
+            })
+    }
+
+    /// Whether `token` is signed with `key_entry`, regardless of whether it
+    /// has expired or is meant for this audience.
+    fn signature_matches(&self, key_entry: &AuthKeyEntry, token: &str) -> bool {
+        match detect_token_format(token) {
+            TokenFormat::Custom => self.verify_custom_with_key(key_entry, token, 0).is_ok(),
+            TokenFormat::Cwt => {
+                let (Ok(token_bytes), Ok(cwt_auth)) = (
+                    b64_decode(token),
+                    self.create_cwt_authenticator_for_key(key_entry),
+                ) else {
+                    return false;
+                };
+                // The audience is checked after the signature, so an audience
+                // error means the signature was good
+                matches!(
+                    cwt_auth.verify_cwt(&token_bytes, ""),
+                    Ok(_)
+                        | Err(crate::cwt::CwtError::InvalidAudience { .. })
+                        | Err(crate::cwt::CwtError::MissingAudience { .. })
+                )
+            }
+        }
+    }
+
+    fn check_revoked(&self, token: &str, permission: &Permission) -> Result<(), AuthError> {
+        if self.revocations.is_empty() {
+            return Ok(());
+        }
+        match self
+            .revocations
+            .find(&self.token_identity(token, permission.user()))
+        {
+            Some(revocation) => {
+                tracing::debug!(
+                    kind = %revocation.kind,
+                    value = %revocation.value,
+                    "Rejected revoked token"
+                );
+                Err(AuthError::Revoked)
+            }
+            None => Ok(()),
+        }
+    }
+
     /// Internal method to verify token without permission checking
     fn verify_token_internal(
         &self,
@@ -1480,8 +2201,8 @@ impl Authenticator {
                 // Try all keys in configuration order
                 let mut last_error = AuthError::KeyMismatch;
 
-                for (index, _) in self.keys.iter().enumerate() {
-                    match self.verify_with_key_entry(&self.keys[index], token, current_time) {
+                for key_entry in self.verifying_keys(current_time) {
+                    match self.verify_with_key_entry(&key_entry, token, current_time) {
                         Ok(permission) => return Ok(permission),
                         Err(err) => {
                             // Prioritize specific errors over generic ones
@@ -1747,7 +2468,11 @@ impl Authenticator {
                 self.key_lookup.keys().collect::<Vec<_>>()
             );
             // Use hashmap lookup for keys with key_id (O(1) performance)
-            if let Some(&index) = self.key_lookup.get(key_id) {
+            let config_key = self
+                .key_lookup
+                .get(key_id)
+                .filter(|&&index| self.config_key_active(&self.keys[index], current_time));
+            if let Some(&index) = config_key {
                 tracing::trace!("Found matching key at index {}", index);
                 return self.verify_cwt_with_channel(
                     &self.keys[index],
@@ -1755,6 +2480,14 @@ impl Authenticator {
                     current_time,
                     expected_audience,
                 );
+            } else if let Some(key_entry) = self.key_ring.get(key_id, current_time) {
+                tracing::trace!("Found matching rotated key");
+                return self.verify_cwt_with_channel(
+                    &key_entry,
+                    token,
+                    current_time,
+                    expected_audience,
+                );
             } else {
                 tracing::debug!(
                     "CWT COSE header key ID '{}' not found in configured keys",
@@ -1769,6 +2502,9 @@ impl Authenticator {
         let mut last_error = AuthError::KeyMismatch;
 
         for &index in &self.keys_without_id {
+            if !self.config_key_active(&self.keys[index], current_time) {
+                continue;
+            }
             match self.verify_cwt_with_channel(
                 &self.keys[index],
                 token,
@@ -1910,6 +2646,9 @@ impl Authenticator {
 
     /// Extract user information from a token (works with both custom and CWT tokens)
     pub fn extract_user_from_token(&self, token: &str) -> Result<Option<String>, AuthError> {
+        if is_api_key(token) {
+            return Ok(self.api_keys.find(token).map(|key| key.subject()));
+        }
         match detect_token_format(token) {
             TokenFormat::Custom => {
                 let payload = self.decode_token(token)?;
@@ -1999,6 +2738,7 @@ impl Authenticator {
                 if let Some(ref audience) = self.expected_audience {
                     let (permission, channel) =
                         self.verify_cwt_token_with_channel(token, current_time, audience)?;
+                    self.check_revoked(token, &permission)?;
                     Ok((permission, channel))
                 } else {
                     tracing::warn!("CWT token verification without audience validation - consider configuring server.url");
@@ -2466,6 +3206,374 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_revoked_tokens_are_rejected() {
+        use crate::revocation::{Revocation, RevocationKind};
+
+        let mut authenticator = Authenticator::gen_key()
+            .unwrap()
+            .with_key_id("test_key".try_into().unwrap());
+        authenticator.set_expected_audience(Some("https://api.example.com".to_string()));
+        let token_for = |user: &str| {
+            authenticator
+                .gen_doc_token_cwt(
+                    "doc123",
+                    Authorization::Full,
+                    ExpirationTimeEpochMillis(u64::MAX),
+                    Some(user),
+                    None,
+                )
+                .unwrap()
+        };
+        let alice = token_for("alice");
+        let bob = token_for("bob");
+        assert_ne!(token_id(&alice), token_id(&bob));
+
+        let revoke = |kind, value: String| Revocation {
+            kind,
+            value,
+            revoked_at: u64::MAX,
+            reason: None,
+        };
+
+        authenticator
+            .revocations
+            .add(revoke(RevocationKind::Token, token_id(&alice)));
+        assert_eq!(
+            authenticator.verify_doc_token(&alice, "doc123", 0),
+            Err(AuthError::Revoked)
+        );
+        assert!(authenticator.verify_doc_token(&bob, "doc123", 0).is_ok());
+        assert!(matches!(
+            authenticator.verify_token_with_channel(&alice, 0),
+            Err(AuthError::Revoked)
+        ));
+
+        authenticator
+            .revocations
+            .add(revoke(RevocationKind::Subject, "bob".to_string()));
+        assert_eq!(
+            authenticator.verify_doc_token(&bob, "doc123", 0),
+            Err(AuthError::Revoked)
+        );
+
+        authenticator.revocations.replace(Vec::new());
+        authenticator
+            .revocations
+            .add(revoke(RevocationKind::Key, "test_key".to_string()));
+        assert_eq!(
+            authenticator.verify_doc_token(&bob, "doc123", 0),
+            Err(AuthError::Revoked)
+        );
+    }
+
+    #[test]
+    fn test_rotated_keys_sign_and_old_keys_retire() {
+        use crate::key_ring::KeyRingDocument;
+
+        let mut authenticator = Authenticator::gen_key_hmac()
+            .unwrap()
+            .with_key_id("original".try_into().unwrap());
+        authenticator.set_expected_audience(Some("https://api.example.com".to_string()));
+        let token = |authenticator: &Authenticator| {
+            authenticator
+                .gen_doc_token_cwt(
+                    "doc123",
+                    Authorization::Full,
+                    ExpirationTimeEpochMillis(u64::MAX),
+                    None,
+                    None,
+                )
+                .unwrap()
+        };
+        let old_token = token(&authenticator);
+        assert_eq!(authenticator.key_set(1)[0].status, "signing");
+
+        let mut document = KeyRingDocument::default();
+        document.rotate(
+            authenticator.new_rotated_key("rotated", 1_000).unwrap(),
+            1_000,
+            1_000,
+            authenticator.signing_key_id().as_deref(),
+        );
+        assert_eq!(document.replaced_config_key_id.as_deref(), Some("original"));
+        authenticator.key_ring.replace(document).unwrap();
+
+        let new_token = token(&authenticator);
+        assert_eq!(extract_cwt_key_id(&new_token).as_deref(), Some("rotated"));
+        assert!(authenticator
+            .verify_doc_token(&new_token, "doc123", 1_500)
+            .is_ok());
+        assert!(authenticator
+            .verify_doc_token(&old_token, "doc123", 1_500)
+            .is_ok());
+
+        let statuses: Vec<_> = authenticator
+            .key_set(1_500)
+            .into_iter()
+            .map(|key| (key.kid.unwrap(), key.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("original".to_string(), "retiring"),
+                ("rotated".to_string(), "signing")
+            ]
+        );
+
+        // After the grace period only the rotated key is accepted
+        assert!(authenticator
+            .verify_doc_token(&new_token, "doc123", 2_000)
+            .is_ok());
+        assert_eq!(
+            authenticator.verify_doc_token(&old_token, "doc123", 2_000),
+            Err(AuthError::KeyMismatch)
+        );
+        assert_eq!(authenticator.key_set(2_000).len(), 1);
+    }
+
+    #[test]
+    fn test_rotation_retires_only_the_replaced_signing_key() {
+        use crate::key_ring::KeyRingDocument;
+
+        let mut authenticator = Authenticator::gen_key_hmac()
+            .unwrap()
+            .with_key_id("original".try_into().unwrap());
+        // A verify-only key, for tokens another issuer signs
+        authenticator.keys.push(AuthKeyEntry {
+            key_id: Some("partner".to_string()),
+            key_material: AuthKeyMaterial::Hmac256(vec![9u8; 32]),
+            can_sign: false,
+            allowed_token_types: vec![TokenType::Document],
+        });
+        authenticator.key_lookup.insert("partner".to_string(), 1);
+
+        let mut document = KeyRingDocument::default();
+        document.rotate(
+            authenticator.new_rotated_key("rotated", 1_000).unwrap(),
+            1_000,
+            1_000,
+            authenticator.signing_key_id().as_deref(),
+        );
+        authenticator.key_ring.replace(document).unwrap();
+
+        let statuses: Vec<_> = authenticator
+            .key_set(2_000)
+            .into_iter()
+            .map(|key| (key.kid.unwrap(), key.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("partner".to_string(), "verifying"),
+                ("rotated".to_string(), "signing")
+            ]
+        );
+
+        assert_eq!(
+            Authenticator::gen_key_ecdsa()
+                .unwrap()
+                .new_rotated_key("rotated", 0),
+            Err(AuthError::UnsupportedRotation("ES256"))
+        );
+        assert_eq!(
+            Authenticator::gen_key_ed25519()
+                .unwrap()
+                .new_rotated_key("rotated", 0),
+            Err(AuthError::UnsupportedRotation("Ed25519"))
+        );
+    }
+
+    #[test]
+    fn test_api_keys_verify_as_prefix_tokens() {
+        use crate::api_key::ApiKey;
+
+        let authenticator = Authenticator::gen_key_hmac().unwrap();
+        let (secret, key) = ApiKey::generate(
+            "git-sync",
+            vec!["Lens Edu".to_string()],
+            Authorization::ReadOnly,
+            0,
+            Some(10_000),
+        );
+        assert_eq!(
+            authenticator.verify_doc_token(&secret, "doc123", 0),
+            Err(AuthError::InvalidToken)
+        );
+
+        authenticator.api_keys.add(key).unwrap();
+        assert_eq!(
+            authenticator.verify_doc_token_with_prefix(&secret, "doc123", 5_000),
+            Ok((Authorization::ReadOnly, Some("key:git-sync".to_string())))
+        );
+        assert_eq!(
+            authenticator.verify_server_token(&secret, 5_000),
+            Err(AuthError::InvalidResource)
+        );
+        assert_eq!(
+            authenticator.verify_doc_token(&secret, "doc123", 10_000),
+            Err(AuthError::Expired)
+        );
+
+        assert!(authenticator.api_keys.remove("git-sync"));
+        assert_eq!(
+            authenticator.verify_doc_token(&secret, "doc123", 5_000),
+            Err(AuthError::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn test_audit_decision_describes_the_token() {
+        use crate::auth_audit::{Decision, TokenKind};
+        use crate::revocation::{Revocation, RevocationKind};
+
+        let mut authenticator = Authenticator::gen_key_hmac().unwrap();
+        authenticator.set_expected_audience(Some("https://api.example.com".to_string()));
+        let token = authenticator
+            .gen_prefix_token_cwt(
+                "relay-",
+                Authorization::Full,
+                ExpirationTimeEpochMillis(1_000),
+                Some("alice"),
+            )
+            .unwrap();
+
+        // Expired, but still attributed to alice
+        let verified = authenticator.verify_token_auto(&token, 5_000);
+        assert_eq!(verified, Err(AuthError::Expired));
+        let decision = authenticator.audit_decision(
+            Some((&token, Some(&verified))),
+            "/d/relay-doc/as-update",
+            "GET /d/:doc_id/as-update",
+            Some("Token expired".to_string()),
+            5_000,
+        );
+        assert_eq!(decision.token_kind, TokenKind::Prefix);
+        assert_eq!(decision.principal.as_deref(), Some("alice"));
+        assert_eq!(decision.decision, Decision::Deny);
+
+        // Revoked, and likewise
+        authenticator.revocations.add(Revocation {
+            kind: RevocationKind::Subject,
+            value: "alice".to_string(),
+            revoked_at: u64::MAX,
+            reason: None,
+        });
+        let verified = authenticator.verify_token_auto(&token, 500);
+        assert_eq!(verified, Err(AuthError::Revoked));
+        let decision = authenticator.audit_decision(
+            Some((&token, Some(&verified))),
+            "/d/relay-doc/as-update",
+            "GET /d/:doc_id/as-update",
+            Some("Token revoked".to_string()),
+            500,
+        );
+        assert_eq!(decision.token_kind, TokenKind::Prefix);
+        assert_eq!(decision.principal.as_deref(), Some("alice"));
+
+        let verified = authenticator.verify_token_auto("garbage", 5_000);
+        let decision = authenticator.audit_decision(
+            Some(("garbage", Some(&verified))),
+            "/",
+            "GET /",
+            None,
+            5_000,
+        );
+        assert_eq!(decision.token_kind, TokenKind::Invalid);
+        assert_eq!(decision.principal, None);
+        assert_eq!(decision.decision, Decision::Allow);
+
+        let decision = authenticator.audit_decision(None, "/", "GET /", None, 5_000);
+        assert_eq!(decision.token_kind, TokenKind::None);
+    }
+
+    #[test]
+    fn test_explain_token_reports_each_check() {
+        let mut authenticator = Authenticator::gen_key_hmac()
+            .unwrap()
+            .with_key_id("main".try_into().unwrap());
+        authenticator.set_expected_audience(Some("https://api.example.com".to_string()));
+        authenticator.keys[0].allowed_token_types = vec![TokenType::Document];
+
+        let token = authenticator
+            .gen_prefix_token_cwt(
+                "relay-",
+                Authorization::ReadOnly,
+                ExpirationTimeEpochMillis(1_000),
+                Some("alice"),
+            )
+            .unwrap();
+        let explanation = authenticator.explain_token(&token, 5_000);
+        assert_eq!(explanation.format, "cwt");
+        assert_eq!(explanation.key_id.as_deref(), Some("main"));
+        assert_eq!(explanation.token_type, Some(TokenType::Prefix));
+        assert_eq!(explanation.audience_valid, Some(true));
+        assert!(explanation.expired);
+        assert!(!explanation.valid);
+
+        let key = explanation.matched_key.unwrap();
+        assert_eq!(key.source, "config");
+        assert_eq!(key.algorithm, "HS256");
+        assert!(key.algorithm_allowed);
+        assert!(key.signature_valid);
+        assert!(!key.token_type_allowed);
+
+        let grants = explanation.grants.unwrap();
+        assert_eq!(grants.resources, vec!["doc:relay-*".to_string()]);
+        assert_eq!(grants.authorization, Authorization::ReadOnly);
+        assert_eq!(grants.user.as_deref(), Some("alice"));
+
+        // Signed with the right key, but for another relay
+        let mut other = authenticator.clone();
+        other.set_expected_audience(Some("https://other.example.com".to_string()));
+        let token = other
+            .gen_doc_token_cwt(
+                "doc123",
+                Authorization::Full,
+                ExpirationTimeEpochMillis::max(),
+                None,
+                None,
+            )
+            .unwrap();
+        let explanation = authenticator.explain_token(&token, 5_000);
+        assert_eq!(explanation.audience_valid, Some(false));
+        assert!(!explanation.expired);
+        assert!(explanation.matched_key.unwrap().signature_valid);
+        assert!(!explanation.valid);
+
+        let token = authenticator
+            .gen_doc_token_cwt(
+                "doc123",
+                Authorization::Full,
+                ExpirationTimeEpochMillis::max(),
+                None,
+                None,
+            )
+            .unwrap();
+        let explanation = authenticator.explain_token(&token, 5_000);
+        assert!(explanation.valid);
+        assert_eq!(explanation.error, None);
+        assert_eq!(
+            explanation.grants.unwrap().resources,
+            vec!["doc:doc123".to_string()]
+        );
+
+        let (secret, key) = ApiKey::generate(
+            "git-sync",
+            vec!["Lens Edu/".to_string(), "Notes".to_string()],
+            Authorization::ReadOnly,
+            0,
+            None,
+        );
+        authenticator.api_keys.add(key).unwrap();
+        let explanation = authenticator.explain_token(&secret, 5_000);
+        assert_eq!(explanation.format, "api-key");
+        assert_eq!(
+            explanation.grants.unwrap().resources,
+            vec!["folder:Lens Edu".to_string(), "folder:Notes".to_string()]
+        );
+    }
+
     #[test]
     fn test_cwt_expiration() {
         let authenticator = create_test_authenticator_with_audience();
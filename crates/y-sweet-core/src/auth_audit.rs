@@ -0,0 +1,61 @@
+//! Authorization decisions, as recorded in the audit trail.
+//!
+//! An entry says who presented which kind of token, signed with which key,
+//! to do what to which resource, and whether that was allowed. Entries are
+//! built by [`crate::auth::Authenticator::audit_decision`]; storing and
+//! querying them is up to the server.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenKind {
+    Server,
+    Doc,
+    File,
+    Prefix,
+    ApiKey,
+    /// A token was presented but does not verify.
+    Invalid,
+    /// No token was presented.
+    None,
+}
+
+impl TokenKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TokenKind::Server => "server",
+            TokenKind::Doc => "doc",
+            TokenKind::File => "file",
+            TokenKind::Prefix => "prefix",
+            TokenKind::ApiKey => "api-key",
+            TokenKind::Invalid => "invalid",
+            TokenKind::None => "none",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthDecision {
+    /// Milliseconds since the epoch.
+    pub timestamp: u64,
+    /// The user the token was issued to, or `key:<name>` for API keys.
+    pub principal: Option<String>,
+    pub token_kind: TokenKind,
+    /// The signing key's ID for CWT tokens, the key's name for API keys.
+    pub key_id: Option<String>,
+    /// What was accessed, e.g. `/d/<doc_id>/as-update`.
+    pub resource: String,
+    /// What was attempted, e.g. `GET /d/:doc_id/as-update`.
+    pub action: String,
+    pub decision: Decision,
+    /// Why a request was denied.
+    pub reason: Option<String>,
+}
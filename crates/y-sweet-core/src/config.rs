@@ -282,6 +282,17 @@ pub struct Config {
     #[serde(default)]
     pub folders: Vec<FolderConfig>,
 
+    /// Keys issued to individual MCP clients, each limited to a set of folders.
+    #[serde(default)]
+    pub mcp_keys: Vec<McpKeyConfig>,
+
+    /// Default MCP usage limits; individual keys may override them.
+    #[serde(default)]
+    pub mcp_limits: McpLimitsConfig,
+
+    /// Browser login against an OIDC/OAuth2 provider. Disabled when unset.
+    pub oidc: Option<OidcConfig>,
+
     /// Track which fields were overridden by environment variables
     #[serde(skip)]
     pub env_overrides: HashMap<String, String>,
@@ -494,6 +505,109 @@ pub struct FolderConfig {
     pub name: String,
 }
 
+/// What an MCP client may do with the documents it can see.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum McpMode {
+    /// read, glob, grep and get_links only.
+    ReadOnly,
+    /// Additionally allows `edit`, which only ever writes CriticMarkup suggestions.
+    #[default]
+    SuggestOnly,
+    /// Additionally allows creating and moving documents.
+    Full,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct McpKeyConfig {
+    /// Name of the client the key was issued to; shows up in logs.
+    pub name: String,
+    pub key: String,
+
+    /// Folder paths the key may access (e.g. "Lens" or "Lens Edu/Biology").
+    /// Empty means every folder.
+    #[serde(default)]
+    pub folders: Vec<String>,
+
+    #[serde(default)]
+    pub mode: McpMode,
+
+    /// Limits for this key. Fields set here replace the `[mcp_limits]` defaults.
+    #[serde(default)]
+    pub limits: Option<McpLimitsConfig>,
+}
+
+/// Caps on MCP tool usage within a fixed window. Unset means unlimited.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct McpQuota {
+    pub calls_per_minute: Option<u32>,
+    /// Calls to tools that change documents (edit, multi_edit, write, create, move).
+    pub edits_per_hour: Option<u32>,
+    /// Size of tool results sent back to the client.
+    pub bytes_per_hour: Option<u64>,
+}
+
+impl McpQuota {
+    /// Fields set in `other` win over fields set in `self`.
+    pub fn overridden_by(&self, other: &McpQuota) -> McpQuota {
+        McpQuota {
+            calls_per_minute: other.calls_per_minute.or(self.calls_per_minute),
+            edits_per_hour: other.edits_per_hour.or(self.edits_per_hour),
+            bytes_per_hour: other.bytes_per_hour.or(self.bytes_per_hour),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct McpLimitsConfig {
+    /// Applies to each MCP session on its own.
+    #[serde(default)]
+    pub session: McpQuota,
+    /// Applies to all sessions opened with the same credential together.
+    #[serde(default)]
+    pub key: McpQuota,
+}
+
+/// An OIDC or plain OAuth2 provider (e.g. Discord) users sign in with. The
+/// relay runs the authorization-code flow itself and hands signed-in users
+/// short-lived doc and prefix tokens according to `grants`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OidcConfig {
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    /// Returns the signed-in user's claims as JSON, given the access token.
+    pub userinfo_endpoint: String,
+    pub client_id: String,
+    /// May be left out and given as `RELAY_OIDC_CLIENT_SECRET` instead.
+    pub client_secret: Option<String>,
+    /// This server's callback, `{server url}/auth/callback`, as registered
+    /// with the provider.
+    pub redirect_url: String,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+    /// Userinfo claim used as the relay user name.
+    #[serde(default = "default_oidc_user_claim")]
+    pub user_claim: String,
+    /// Userinfo claim listing the user's groups, if the provider has one.
+    pub groups_claim: Option<String>,
+    #[serde(default = "default_oidc_session_seconds")]
+    pub session_seconds: u64,
+    /// Lifetime of the tokens handed out to signed-in users.
+    #[serde(default = "default_oidc_token_seconds")]
+    pub token_seconds: u64,
+    #[serde(default)]
+    pub grants: Vec<OidcGrant>,
+}
+
+/// Access given to members of a group on every doc ID starting with `prefix`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OidcGrant {
+    /// Group name, or `*` for every signed-in user.
+    pub group: String,
+    pub prefix: String,
+    pub authorization: crate::api_types::Authorization,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct LoggingConfig {
     #[serde(default = "default_log_level")]
@@ -540,6 +654,22 @@ fn default_presigned_url_expiration() -> u64 {
     3600
 }
 
+fn default_oidc_scopes() -> Vec<String> {
+    vec!["openid".to_string(), "profile".to_string()]
+}
+
+fn default_oidc_user_claim() -> String {
+    "sub".to_string()
+}
+
+fn default_oidc_session_seconds() -> u64 {
+    7 * 24 * 3600
+}
+
+fn default_oidc_token_seconds() -> u64 {
+    600
+}
+
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -813,6 +943,50 @@ impl Config {
             self.validate_multi_key_auth()?;
         }
 
+        // Validate MCP keys
+        let mut mcp_keys_seen = std::collections::HashSet::new();
+        for (i, mcp_key) in self.mcp_keys.iter().enumerate() {
+            if mcp_key.name.is_empty() {
+                return Err(ConfigError::InvalidConfiguration(format!(
+                    "MCP key {} has empty name",
+                    i
+                )));
+            }
+            if mcp_key.key.len() < 16 {
+                return Err(ConfigError::InvalidConfiguration(format!(
+                    "MCP key '{}' must be at least 16 characters",
+                    mcp_key.name
+                )));
+            }
+            if !mcp_keys_seen.insert(mcp_key.key.as_str()) {
+                return Err(ConfigError::InvalidConfiguration(format!(
+                    "MCP key '{}' reuses the key of another entry",
+                    mcp_key.name
+                )));
+            }
+        }
+
+        if let Some(ref oidc) = self.oidc {
+            for (name, url) in [
+                ("authorization_endpoint", &oidc.authorization_endpoint),
+                ("token_endpoint", &oidc.token_endpoint),
+                ("userinfo_endpoint", &oidc.userinfo_endpoint),
+                ("redirect_url", &oidc.redirect_url),
+            ] {
+                Url::parse(url).map_err(|_| {
+                    ConfigError::InvalidConfiguration(format!(
+                        "oidc.{} is not a valid URL: {}",
+                        name, url
+                    ))
+                })?;
+            }
+            if oidc.client_id.is_empty() {
+                return Err(ConfigError::InvalidConfiguration(
+                    "oidc.client_id cannot be empty".to_string(),
+                ));
+            }
+        }
+
         // Validate webhook configurations
         for (i, webhook) in self.webhooks.iter().enumerate() {
             if webhook.url.is_empty() {
@@ -942,6 +1116,9 @@ impl Default for Config {
             logging: LoggingConfig::default(),
             metrics: None,
             folders: Vec::new(),
+            mcp_keys: Vec::new(),
+            mcp_limits: McpLimitsConfig::default(),
+            oidc: None,
             env_overrides: HashMap::new(),
         }
     }
@@ -1172,4 +1349,102 @@ port = 8080
         let config: Config = toml::from_str(toml_content).unwrap();
         assert!(config.folders.is_empty());
     }
+
+    #[test]
+    fn test_mcp_keys_config_deserializes() {
+        let toml_content = r#"
+[[mcp_keys]]
+name = "biology-assistant"
+key = "0123456789abcdef0123"
+folders = ["Lens Edu/Biology"]
+mode = "read-only"
+
+[[mcp_keys]]
+name = "editor"
+key = "fedcba9876543210fedc"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.mcp_keys.len(), 2);
+        assert_eq!(config.mcp_keys[0].folders, vec!["Lens Edu/Biology"]);
+        assert_eq!(config.mcp_keys[0].mode, McpMode::ReadOnly);
+        assert!(config.mcp_keys[1].folders.is_empty());
+        assert_eq!(config.mcp_keys[1].mode, McpMode::SuggestOnly);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_mcp_limits_config_deserializes() {
+        let toml_content = r#"
+[mcp_limits.session]
+calls_per_minute = 60
+bytes_per_hour = 10000000
+
+[mcp_limits.key]
+edits_per_hour = 500
+
+[[mcp_keys]]
+name = "batch-importer"
+key = "0123456789abcdef0123"
+limits = { key = { edits_per_hour = 5000 } }
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        assert_eq!(config.mcp_limits.session.calls_per_minute, Some(60));
+        assert_eq!(config.mcp_limits.session.edits_per_hour, None);
+        assert_eq!(config.mcp_limits.key.edits_per_hour, Some(500));
+
+        let key_limits = config.mcp_keys[0].limits.as_ref().unwrap();
+        let merged = config.mcp_limits.key.overridden_by(&key_limits.key);
+        assert_eq!(merged.edits_per_hour, Some(5000));
+        assert_eq!(key_limits.session, McpQuota::default());
+    }
+
+    #[test]
+    fn test_oidc_config_deserializes() {
+        let toml_content = r#"
+[oidc]
+authorization_endpoint = "https://discord.com/oauth2/authorize"
+token_endpoint = "https://discord.com/api/oauth2/token"
+userinfo_endpoint = "https://discord.com/api/users/@me"
+client_id = "1234"
+redirect_url = "https://relay.example.com/auth/callback"
+scopes = ["identify"]
+user_claim = "username"
+
+[[oidc.grants]]
+group = "*"
+prefix = "cb696037"
+authorization = "read-only"
+"#;
+        let config: Config = toml::from_str(toml_content).unwrap();
+        let oidc = config.oidc.as_ref().unwrap();
+        assert_eq!(oidc.user_claim, "username");
+        assert_eq!(oidc.token_seconds, 600);
+        assert_eq!(
+            oidc.grants[0].authorization,
+            crate::api_types::Authorization::ReadOnly
+        );
+        assert!(config.validate().is_ok());
+
+        let mut config = config;
+        config.oidc.as_mut().unwrap().token_endpoint = "not a url".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_mcp_keys_reject_short_and_duplicate_keys() {
+        let mut config = Config::default();
+        config.mcp_keys.push(McpKeyConfig {
+            name: "short".to_string(),
+            key: "abc".to_string(),
+            folders: Vec::new(),
+            mode: McpMode::Full,
+            limits: None,
+        });
+        assert!(config.validate().is_err());
+
+        config.mcp_keys[0].key = "0123456789abcdef".to_string();
+        config.mcp_keys.push(config.mcp_keys[0].clone());
+        config.mcp_keys[1].name = "copy".to_string();
+        assert!(config.validate().is_err());
+    }
 }
@@ -35,6 +35,8 @@ static ADDITION_RE: LazyLock<Regex> =
 static DELETION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)\{--(.*?)--\}").unwrap());
 static SUBSTITUTION_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(?s)\{~~(.*?)~>(.*?)~~\}").unwrap());
+static COMMENT_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)\{>>(.*?)<<\}").unwrap());
+static HIGHLIGHT_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)\{==(.*?)==\}").unwrap());
 
 /// Budget for context extraction. Newlines cost more to keep context compact.
 const CONTEXT_BUDGET: usize = 200;
@@ -80,6 +82,30 @@ fn strip_critic_markup(text: &str) -> String {
     result.into_owned()
 }
 
+/// The document as it reads with every pending suggestion rejected and all
+/// comments removed. Two versions of a document with the same base text differ
+/// only in suggestions and comments.
+pub fn base_text(text: &str) -> String {
+    let result = SUBSTITUTION_RE.replace_all(text, |caps: &regex::Captures| {
+        let (_, _, old_content) = extract_metadata(caps.get(1).unwrap().as_str());
+        old_content.to_string()
+    });
+    let result = ADDITION_RE.replace_all(&result, "");
+    let result = DELETION_RE.replace_all(&result, |caps: &regex::Captures| {
+        let (_, _, content) = extract_metadata(caps.get(1).unwrap().as_str());
+        content.to_string()
+    });
+    without_comments(&result)
+}
+
+/// The document with comments removed and highlighted spans unwrapped, so
+/// that two versions differing only in comments compare equal.
+pub fn without_comments(text: &str) -> String {
+    let result = COMMENT_RE.replace_all(text, "");
+    let result = HIGHLIGHT_RE.replace_all(&result, "$1");
+    result.into_owned()
+}
+
 /// Walk backwards from `start` spending budget (newlines cost NEWLINE_COST, other chars cost 1).
 fn budget_scan_back(text: &str, start: usize) -> usize {
     let mut budget = CONTEXT_BUDGET;
@@ -211,6 +237,148 @@ pub fn scan_suggestions(text: &str) -> Vec<Suggestion> {
     suggestions
 }
 
+/// The kind of a CriticMarkup span found by [`markup_spans`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkupKind {
+    Addition,
+    Deletion,
+    Substitution,
+    Comment,
+    Highlight,
+}
+
+impl MarkupKind {
+    pub const ALL: [MarkupKind; 5] = [
+        MarkupKind::Addition,
+        MarkupKind::Deletion,
+        MarkupKind::Substitution,
+        MarkupKind::Comment,
+        MarkupKind::Highlight,
+    ];
+
+    pub fn open(self) -> &'static str {
+        match self {
+            MarkupKind::Addition => "{++",
+            MarkupKind::Deletion => "{--",
+            MarkupKind::Substitution => "{~~",
+            MarkupKind::Comment => "{>>",
+            MarkupKind::Highlight => "{==",
+        }
+    }
+
+    pub fn close(self) -> &'static str {
+        match self {
+            MarkupKind::Addition => "++}",
+            MarkupKind::Deletion => "--}",
+            MarkupKind::Substitution => "~~}",
+            MarkupKind::Comment => "<<}",
+            MarkupKind::Highlight => "==}",
+        }
+    }
+
+    /// Whether `rest`, the text after an opening delimiter of this kind,
+    /// closes the span the way the scanner's patterns would.
+    pub fn is_closed_in(self, rest: &str) -> bool {
+        match self {
+            MarkupKind::Substitution => rest
+                .find("~>")
+                .is_some_and(|arrow| rest[arrow + 2..].contains(self.close())),
+            kind => rest.contains(kind.close()),
+        }
+    }
+}
+
+/// A CriticMarkup span, from the start of its opening delimiter to the end of
+/// its closing one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkupSpan {
+    pub kind: MarkupKind,
+    pub from: usize,
+    pub to: usize,
+    /// The author named in the span's metadata. A highlight takes the author
+    /// of the comment right after it.
+    pub author: Option<String>,
+}
+
+enum Delimiter {
+    Open(MarkupKind),
+    Close(MarkupKind),
+    Arrow,
+}
+
+fn delimiter_at(rest: &str) -> Option<Delimiter> {
+    if rest.starts_with("~>") {
+        return Some(Delimiter::Arrow);
+    }
+    MarkupKind::ALL.into_iter().find_map(|kind| {
+        if rest.starts_with(kind.open()) {
+            Some(Delimiter::Open(kind))
+        } else if rest.starts_with(kind.close()) {
+            Some(Delimiter::Close(kind))
+        } else {
+            None
+        }
+    })
+}
+
+/// Split `text` into CriticMarkup spans, or `None` unless it is strictly well
+/// formed: every span closed, no delimiters inside a span besides the single
+/// `~>` of a substitution, and no stray closing delimiters. Text that passes
+/// reads the same here as it does to the scanner's patterns.
+pub fn markup_spans(text: &str) -> Option<Vec<MarkupSpan>> {
+    let mut spans: Vec<MarkupSpan> = Vec::new();
+    let mut open: Option<(MarkupKind, usize)> = None;
+    let mut arrows = 0;
+    let mut i = 0;
+    while i < text.len() {
+        // Delimiters are ASCII, so they never match partway through a character
+        let Some(delimiter) = text.get(i..).and_then(delimiter_at) else {
+            i += 1;
+            continue;
+        };
+        match (open, delimiter) {
+            (None, Delimiter::Open(kind)) => {
+                open = Some((kind, i));
+                arrows = 0;
+                i += 3;
+            }
+            (Some((kind, from)), Delimiter::Close(closing))
+                if closing == kind && (kind != MarkupKind::Substitution || arrows == 1) =>
+            {
+                let (author, _, _) = extract_metadata(&text[from + 3..i]);
+                spans.push(MarkupSpan {
+                    kind,
+                    from,
+                    to: i + 3,
+                    author,
+                });
+                open = None;
+                i += 3;
+            }
+            (Some((MarkupKind::Substitution, _)), Delimiter::Arrow) => {
+                arrows += 1;
+                i += 2;
+            }
+            (_, Delimiter::Arrow) => i += 2,
+            _ => return None,
+        }
+    }
+    if open.is_some() {
+        return None;
+    }
+
+    for index in 0..spans.len().saturating_sub(1) {
+        let (span, next) = (&spans[index], &spans[index + 1]);
+        if span.kind == MarkupKind::Highlight
+            && next.kind == MarkupKind::Comment
+            && next.from == span.to
+        {
+            spans[index].author = spans[index + 1].author.clone();
+        }
+    }
+    Some(spans)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -285,6 +453,52 @@ mod tests {
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn test_base_text_rejects_suggestions() {
+        let text = r#"Say {~~{"author":"AI","timestamp":2000}@@hello~>goodbye~~} to {++new ++}{--old--}friends"#;
+        assert_eq!(base_text(text), "Say hello to oldfriends");
+    }
+
+    #[test]
+    fn test_base_text_drops_comments() {
+        let text = "Some {==important==}{>>why?<<} text {>>note<<}";
+        assert_eq!(base_text(text), "Some important text ");
+        assert_eq!(without_comments(text), "Some important text ");
+    }
+
+    #[test]
+    fn test_without_comments_keeps_suggestions() {
+        let text = "Hello {++world++}{>>nice<<}";
+        assert_eq!(without_comments(text), "Hello {++world++}");
+        assert_eq!(base_text(text), "Hello ");
+    }
+
+    #[test]
+    fn test_markup_spans_with_authors() {
+        let text = r#"Hi {++{"author":"bob"}@@there++} {==you==}{>>{"author":"amy"}@@who?<<}"#;
+        let spans = markup_spans(text).unwrap();
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[0].kind, MarkupKind::Addition);
+        assert_eq!(
+            &text[spans[0].from..spans[0].to],
+            r#"{++{"author":"bob"}@@there++}"#
+        );
+        assert_eq!(spans[0].author.as_deref(), Some("bob"));
+        assert_eq!(spans[1].kind, MarkupKind::Highlight);
+        assert_eq!(spans[1].author.as_deref(), Some("amy"));
+        assert_eq!(spans[2].author.as_deref(), Some("amy"));
+    }
+
+    #[test]
+    fn test_markup_spans_rejects_loose_markup() {
+        assert!(markup_spans("plain text ~> arrow").unwrap().is_empty());
+        assert!(markup_spans("{++unclosed").is_none());
+        assert!(markup_spans("stray++}").is_none());
+        assert!(markup_spans("{++a{>>b<<}++}").is_none());
+        assert!(markup_spans("{~~old~~}").is_none());
+        assert!(markup_spans("{~~old~>new~~}").is_some());
+    }
+
     #[test]
     fn test_context_truncation() {
         // Context should be truncated to ~200 chars
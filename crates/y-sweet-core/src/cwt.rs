@@ -39,6 +39,8 @@ pub struct CwtClaims {
     pub issued_at: Option<u64>,
     pub scope: String,
     pub channel: Option<String>,
+    /// The `cti` claim, naming this token for revocation.
+    pub token_id: Option<Vec<u8>>,
 }
 
 pub struct CwtAuthenticator {
@@ -852,60 +854,18 @@ impl CwtAuthenticator {
             ));
         }
 
+        if let Some(cti) = claims.token_id {
+            map.push((
+                ciborium::Value::Integer(7.into()),
+                ciborium::Value::Bytes(cti),
+            ));
+        }
+
         Ok(ciborium::Value::Map(map))
     }
 
     pub fn parse_claims_map(&self, claims_map: ciborium::Value) -> Result<CwtClaims, CwtError> {
-        let map = match claims_map {
-            ciborium::Value::Map(m) => m,
-            _ => {
-                tracing::warn!("Claims map is not a CBOR map");
-                return Err(CwtError::InvalidClaims);
-            }
-        };
-
-        let mut issuer = None;
-        let mut subject = None;
-        let mut audience = None;
-        let mut expiration = None;
-        let mut issued_at = None;
-        let mut scope = None;
-        let mut channel = None;
-
-        for (key, value) in map {
-            match (key, value) {
-                (ciborium::Value::Integer(k), ciborium::Value::Text(s)) => {
-                    match TryInto::<i64>::try_into(k) {
-                        Ok(1) => issuer = Some(s),
-                        Ok(2) => subject = Some(s),
-                        Ok(3) => audience = Some(s),
-                        Ok(-80201) => scope = Some(s),
-                        Ok(-80202) => channel = Some(s),
-                        _ => {} // Ignore unknown claims
-                    }
-                }
-                (ciborium::Value::Integer(k), ciborium::Value::Integer(i)) => {
-                    match (TryInto::<u64>::try_into(k), TryInto::<u64>::try_into(i)) {
-                        (Ok(4), Ok(exp)) => expiration = Some(exp),
-                        (Ok(6), Ok(iat)) => issued_at = Some(iat),
-                        _ => {} // Ignore unknown claims
-                    }
-                }
-                _ => {} // Ignore unknown claims
-            }
-        }
-
-        let scope = scope.unwrap_or_else(|| "unknown".to_string());
-
-        Ok(CwtClaims {
-            issuer,
-            subject,
-            audience,
-            expiration,
-            issued_at,
-            scope,
-            channel,
-        })
+        parse_claims(claims_map)
     }
 
     fn sign_with_key(&self, data: &[u8]) -> Vec<u8> {
@@ -993,32 +953,106 @@ impl CwtAuthenticator {
     }
 }
 
+/// Parse a CWT claims map. Unknown claims are ignored.
+pub fn parse_claims(claims_map: ciborium::Value) -> Result<CwtClaims, CwtError> {
+    let map = match claims_map {
+        ciborium::Value::Map(m) => m,
+        _ => {
+            tracing::warn!("Claims map is not a CBOR map");
+            return Err(CwtError::InvalidClaims);
+        }
+    };
+
+    let mut issuer = None;
+    let mut subject = None;
+    let mut audience = None;
+    let mut expiration = None;
+    let mut issued_at = None;
+    let mut scope = None;
+    let mut channel = None;
+    let mut token_id = None;
+
+    for (key, value) in map {
+        match (key, value) {
+            (ciborium::Value::Integer(k), ciborium::Value::Text(s)) => {
+                match TryInto::<i64>::try_into(k) {
+                    Ok(1) => issuer = Some(s),
+                    Ok(2) => subject = Some(s),
+                    Ok(3) => audience = Some(s),
+                    Ok(-80201) => scope = Some(s),
+                    Ok(-80202) => channel = Some(s),
+                    _ => {} // Ignore unknown claims
+                }
+            }
+            (ciborium::Value::Integer(k), ciborium::Value::Integer(i)) => {
+                match (TryInto::<u64>::try_into(k), TryInto::<u64>::try_into(i)) {
+                    (Ok(4), Ok(exp)) => expiration = Some(exp),
+                    (Ok(6), Ok(iat)) => issued_at = Some(iat),
+                    _ => {} // Ignore unknown claims
+                }
+            }
+            (ciborium::Value::Integer(k), ciborium::Value::Bytes(b)) => {
+                if TryInto::<i64>::try_into(k) == Ok(7) {
+                    token_id = Some(b);
+                }
+            }
+            _ => {} // Ignore unknown claims
+        }
+    }
+
+    let scope = scope.unwrap_or_else(|| "unknown".to_string());
+
+    Ok(CwtClaims {
+        issuer,
+        subject,
+        audience,
+        expiration,
+        issued_at,
+        scope,
+        channel,
+        token_id,
+    })
+}
+
 // Helper functions to convert between Permission and scope strings
+
+/// Scope suffix for an authorization level: `r`, `c` (comment), `s`
+/// (suggest) or `rw`.
+fn authorization_to_scope(authorization: Authorization) -> &'static str {
+    match authorization {
+        Authorization::ReadOnly => "r",
+        Authorization::Comment => "c",
+        Authorization::Suggest => "s",
+        Authorization::Full => "rw",
+    }
+}
+
+fn scope_to_authorization(auth_str: &str) -> Result<Authorization, CwtError> {
+    match auth_str {
+        "r" => Ok(Authorization::ReadOnly),
+        "c" => Ok(Authorization::Comment),
+        "s" => Ok(Authorization::Suggest),
+        "rw" => Ok(Authorization::Full),
+        _ => Err(CwtError::InvalidClaims),
+    }
+}
+
 pub fn permission_to_scope(permission: &Permission) -> String {
     match permission {
         Permission::Server => "server".to_string(),
         Permission::Doc(doc_perm) => {
-            let auth_str = match doc_perm.authorization {
-                Authorization::ReadOnly => "r",
-                Authorization::Full => "rw",
-            };
+            let auth_str = authorization_to_scope(doc_perm.authorization);
             format!("doc:{}:{}", doc_perm.doc_id, auth_str)
         }
         Permission::File(file_perm) => {
-            let auth_str = match file_perm.authorization {
-                Authorization::ReadOnly => "r",
-                Authorization::Full => "rw",
-            };
+            let auth_str = authorization_to_scope(file_perm.authorization);
             format!(
                 "file:{}:{}:{}",
                 file_perm.file_hash, file_perm.doc_id, auth_str
             )
         }
         Permission::Prefix(prefix_perm) => {
-            let auth_str = match prefix_perm.authorization {
-                Authorization::ReadOnly => "r",
-                Authorization::Full => "rw",
-            };
+            let auth_str = authorization_to_scope(prefix_perm.authorization);
             format!("prefix:{}:{}", prefix_perm.prefix, auth_str)
         }
     }
@@ -1033,11 +1067,7 @@ pub fn scope_to_permission(scope: &str) -> Result<Permission, CwtError> {
 
     match parts.as_slice() {
         ["doc", doc_id, auth_str] => {
-            let authorization = match *auth_str {
-                "r" => Authorization::ReadOnly,
-                "rw" => Authorization::Full,
-                _ => return Err(CwtError::InvalidClaims),
-            };
+            let authorization = scope_to_authorization(auth_str)?;
             Ok(Permission::Doc(DocPermission {
                 doc_id: doc_id.to_string(),
                 authorization,
@@ -1045,11 +1075,7 @@ pub fn scope_to_permission(scope: &str) -> Result<Permission, CwtError> {
             }))
         }
         ["file", file_hash, doc_id, auth_str] => {
-            let authorization = match *auth_str {
-                "r" => Authorization::ReadOnly,
-                "rw" => Authorization::Full,
-                _ => return Err(CwtError::InvalidClaims),
-            };
+            let authorization = scope_to_authorization(auth_str)?;
             Ok(Permission::File(FilePermission {
                 file_hash: file_hash.to_string(),
                 doc_id: doc_id.to_string(),
@@ -1060,11 +1086,7 @@ pub fn scope_to_permission(scope: &str) -> Result<Permission, CwtError> {
             }))
         }
         ["prefix", prefix, auth_str] => {
-            let authorization = match *auth_str {
-                "r" => Authorization::ReadOnly,
-                "rw" => Authorization::Full,
-                _ => return Err(CwtError::InvalidClaims),
-            };
+            let authorization = scope_to_authorization(auth_str)?;
             Ok(Permission::Prefix(PrefixPermission {
                 prefix: prefix.to_string(),
                 authorization,
@@ -1096,6 +1118,7 @@ mod tests {
             issued_at: Some(1443944944),
             scope: "server".to_string(),
             channel: None,
+            token_id: None,
         };
 
         let token = authenticator.create_cwt(claims).unwrap();
@@ -1124,6 +1147,7 @@ mod tests {
             issued_at: Some(1443944944),
             scope: "doc:test_doc_123:rw".to_string(),
             channel: None,
+            token_id: None,
         };
 
         let token = authenticator.create_cwt(claims).unwrap();
@@ -1147,6 +1171,7 @@ mod tests {
             issued_at: None,
             scope: "file:abcdef1234567890:doc123:r".to_string(),
             channel: None,
+            token_id: None,
         };
 
         let token = authenticator.create_cwt(claims).unwrap();
@@ -1218,6 +1243,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_comment_and_suggest_scopes() {
+        for (authorization, code) in [(Authorization::Comment, "c"), (Authorization::Suggest, "s")]
+        {
+            let perm = Permission::Doc(DocPermission {
+                doc_id: "test_doc".to_string(),
+                authorization,
+                user: None,
+            });
+            let scope = permission_to_scope(&perm);
+            assert_eq!(scope, format!("doc:test_doc:{}", code));
+            assert_eq!(scope_to_permission(&scope).unwrap(), perm);
+
+            let perm = Permission::Prefix(PrefixPermission {
+                prefix: "org123-".to_string(),
+                authorization,
+                user: None,
+            });
+            let scope = permission_to_scope(&perm);
+            assert_eq!(scope, format!("prefix:org123-:{}", code));
+            assert_eq!(scope_to_permission(&scope).unwrap(), perm);
+        }
+
+        assert!(scope_to_permission("doc:test_doc:x").is_err());
+    }
+
     #[test]
     fn test_prefix_token_roundtrip() {
         let authenticator = create_test_authenticator();
@@ -1230,6 +1281,7 @@ mod tests {
             issued_at: Some(1443944944),
             scope: "prefix:org123-:rw".to_string(),
             channel: None,
+            token_id: None,
         };
 
         let token = authenticator.create_cwt(claims).unwrap();
@@ -1267,6 +1319,7 @@ mod tests {
             issued_at: Some(1443944944),
             scope: "server".to_string(), // Using our custom scope claim
             channel: None,
+            token_id: None,
         };
 
         // Test COSE_Mac0 token creation and verification
@@ -1338,6 +1391,7 @@ mod tests {
         );
         assert_eq!(parsed_claims.expiration, Some(1444064944));
         assert_eq!(parsed_claims.issued_at, Some(1443944944));
+        assert_eq!(parsed_claims.token_id, Some(vec![0x0b, 0x71]));
         // Note: The scope will be empty/default since RFC example has cti (7) not scope (9)
 
         // Test 2: Try to verify a manually constructed COSE_Mac0 without tags
@@ -1354,6 +1408,7 @@ mod tests {
                     issued_at: Some(1443944944),
                     scope: "server".to_string(),
                     channel: None,
+                    token_id: None,
                 };
                 cwt_auth.create_cwt_mac0(test_claims).unwrap()
             });
@@ -1376,6 +1431,7 @@ mod tests {
             issued_at: Some(1000000000),
             scope: "test:scope".to_string(),
             channel: None,
+            token_id: None,
         };
 
         // Test HMAC_256_64 (8-byte MAC)
@@ -1427,6 +1483,7 @@ mod tests {
             issued_at: Some(1443944944),
             scope: "doc:test_doc:rw".to_string(),
             channel: Some("team-updates".to_string()),
+            token_id: None,
         };
 
         let token_bytes = cwt_auth.create_cwt(claims_with_channel.clone()).unwrap();
@@ -1446,6 +1503,7 @@ mod tests {
             issued_at: Some(1443944944),
             scope: "doc:test_doc:rw".to_string(),
             channel: None,
+            token_id: None,
         };
 
         let token_bytes = cwt_auth.create_cwt(claims_without_channel.clone()).unwrap();
@@ -1471,6 +1529,7 @@ mod tests {
             issued_at: None,
             scope: "server".to_string(),
             channel: None,
+            token_id: None,
         };
 
         let token = authenticator.create_cwt(claims).unwrap();
@@ -1502,6 +1561,7 @@ mod tests {
             issued_at: None,
             scope: "server".to_string(),
             channel: None,
+            token_id: None,
         };
 
         // Should create COSE_Mac0 token with symmetric key
@@ -1584,6 +1644,7 @@ mod tests {
             issued_at: Some(1000000000),
             scope: "doc:test-ed25519:rw".to_string(),
             channel: Some("ed25519-channel".to_string()),
+            token_id: None,
         };
 
         // Create token with private key
@@ -1630,6 +1691,7 @@ mod tests {
             issued_at: None,
             scope: "server".to_string(),
             channel: None,
+            token_id: None,
         };
 
         // Create tokens with different key types
@@ -1678,6 +1740,7 @@ mod tests {
             issued_at: Some(1000000000),
             scope: "test:ed25519".to_string(),
             channel: None,
+            token_id: None,
         };
 
         // Test create_cwt (wrapped in CWT tag)
@@ -1716,6 +1779,7 @@ mod tests {
             issued_at: Some(1443944944),
             scope: "server".to_string(),
             channel: None,
+            token_id: None,
         };
 
         // Test 1: Create a proper CWT (should include CWT tag 61 and COSE tag)
@@ -1,18 +1,20 @@
 use crate::api_types::Authorization;
+use crate::critic_scanner::{base_text, markup_spans, without_comments, MarkupKind, MarkupSpan};
 use crate::sync::{
     self, awareness::Awareness, DefaultProtocol, EventMessage, Message, Protocol, SyncMessage,
     MSG_SYNC, MSG_SYNC_UPDATE,
 };
 use std::collections::HashSet;
-use std::sync::{Arc, OnceLock, RwLock};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 use yrs::{
     block::ClientID,
     encoding::write::Write,
+    types::Delta,
     updates::{
         decoder::Decode,
         encoder::{Encode, Encoder, EncoderV1},
     },
-    ReadTxn, Subscription, Transact, Update,
+    Any, Doc, GetString, Observable, Out, ReadTxn, StateVector, Subscription, Transact, Update,
 };
 
 fn current_time_epoch_millis() -> u64 {
@@ -30,15 +32,41 @@ type Callback = Arc<dyn Fn(&[u8]) + 'static>;
 #[cfg(feature = "sync")]
 type Callback = Arc<dyn Fn(&[u8]) + 'static + Send + Sync>;
 
+#[cfg(not(feature = "sync"))]
+type TokenRefresher = Arc<dyn Fn(&str) -> TokenRefresh + 'static>;
+
+#[cfg(feature = "sync")]
+type TokenRefresher = Arc<dyn Fn(&str) -> TokenRefresh + 'static + Send + Sync>;
+
+/// The outcome of checking a token a client presented with
+/// [`Message::TokenRefresh`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenRefresh {
+    /// The token is valid for this connection's document and user. The
+    /// connection takes on its authorization, which may be lower (or higher)
+    /// than before, and its expiration time.
+    Granted {
+        authorization: Authorization,
+        expiration_time: Option<u64>,
+    },
+    /// The token is invalid, revoked, or for another document or user. The
+    /// connection should be closed.
+    Rejected(String),
+}
+
 const SYNC_STATUS_MESSAGE: u8 = 102;
 
+/// Bytes of other clients' updates to hold for a connection's shadow document
+/// before dropping it and copying the document afresh at the next check.
+const SHADOW_BACKLOG_LIMIT: usize = 4 * 1024 * 1024;
+
 pub struct DocConnection {
     awareness: Arc<RwLock<Awareness>>,
     #[allow(unused)] // acts as RAII guard
     doc_subscription: Subscription,
     #[allow(unused)] // acts as RAII guard
     awareness_subscription: Subscription,
-    authorization: Authorization,
+    authorization: RwLock<Authorization>,
     callback: Callback,
     closed: Arc<OnceLock<()>>,
 
@@ -50,8 +78,25 @@ pub struct DocConnection {
     event_subscriptions: Arc<RwLock<HashSet<String>>>,
 
     /// Expiration time for the authentication token in milliseconds since epoch.
-    /// If None, the token never expires.
-    expiration_time: Option<u64>,
+    /// If None, the token never expires. Extended when the client refreshes
+    /// its token.
+    expiration_time: RwLock<Option<u64>>,
+
+    /// Checks tokens presented with [`Message::TokenRefresh`]. Without one,
+    /// refreshes are refused and the connection keeps its original token.
+    token_refresher: Option<TokenRefresher>,
+
+    /// The user the connection's token was issued to. Comment and suggest
+    /// tokens may only remove or change markup this user authored.
+    user: Option<String>,
+
+    /// Copy of the document that updates from a comment or suggest token are
+    /// tried on. Made at the first such update.
+    shadow: Mutex<Option<Shadow>>,
+
+    /// Updates applied to the document since `shadow` was last brought up to
+    /// date. `None` if there is no shadow, or it fell too far behind.
+    shadow_backlog: Arc<Mutex<Option<ShadowBacklog>>>,
 }
 
 impl DocConnection {
@@ -122,6 +167,7 @@ impl DocConnection {
         callback: Callback,
     ) -> Self {
         let closed = Arc::new(OnceLock::new());
+        let shadow_backlog: Arc<Mutex<Option<ShadowBacklog>>> = Arc::new(Mutex::new(None));
 
         let (doc_subscription, awareness_subscription) = {
             let mut awareness = awareness.write().unwrap_or_else(|e| e.into_inner());
@@ -148,10 +194,21 @@ impl DocConnection {
                 let doc = awareness.doc();
                 let callback = callback.clone();
                 let closed = closed.clone();
+                let shadow_backlog = shadow_backlog.clone();
                 doc.observe_update_v1(move |_, event| {
                     if closed.get().is_some() {
                         return;
                     }
+                    {
+                        let mut backlog = shadow_backlog.lock().unwrap_or_else(|e| e.into_inner());
+                        if let Some(pending) = backlog.as_mut() {
+                            pending.bytes += event.update.len();
+                            pending.updates.push(event.update.clone());
+                            if pending.bytes > SHADOW_BACKLOG_LIMIT {
+                                *backlog = None;
+                            }
+                        }
+                    }
                     // https://github.com/y-crdt/y-sync/blob/56958e83acfd1f3c09f5dd67cf23c9c72f000707/src/net/broadcast.rs#L47-L52
                     let mut encoder = EncoderV1::new();
                     encoder.write_var(MSG_SYNC);
@@ -192,18 +249,59 @@ impl DocConnection {
             awareness,
             doc_subscription,
             awareness_subscription,
-            authorization,
+            authorization: RwLock::new(authorization),
             callback,
             client_id: OnceLock::new(),
             closed,
             event_subscriptions: Arc::new(RwLock::new(HashSet::new())),
-            expiration_time,
+            expiration_time: RwLock::new(expiration_time),
+            token_refresher: None,
+            user: None,
+            shadow: Mutex::new(None),
+            shadow_backlog,
         }
     }
 
+    /// Let the client replace its token on this connection, checking each
+    /// presented token with `refresher`.
+    #[cfg(not(feature = "sync"))]
+    pub fn with_token_refresh<F>(mut self, refresher: F) -> Self
+    where
+        F: Fn(&str) -> TokenRefresh + 'static,
+    {
+        self.token_refresher = Some(Arc::new(refresher));
+        self
+    }
+
+    /// Let the client replace its token on this connection, checking each
+    /// presented token with `refresher`.
+    #[cfg(feature = "sync")]
+    pub fn with_token_refresh<F>(mut self, refresher: F) -> Self
+    where
+        F: Fn(&str) -> TokenRefresh + 'static + Send + Sync,
+    {
+        self.token_refresher = Some(Arc::new(refresher));
+        self
+    }
+
+    /// Set the user the connection's token was issued to.
+    pub fn with_user(mut self, user: Option<String>) -> Self {
+        self.user = user;
+        self
+    }
+
+    /// The authorization currently in force on this connection.
+    pub fn authorization(&self) -> Authorization {
+        *self.authorization.read().unwrap_or_else(|e| e.into_inner())
+    }
+
     /// Check if the token associated with this connection has expired
     fn is_expired(&self) -> bool {
-        if let Some(exp) = self.expiration_time {
+        let expiration_time = *self
+            .expiration_time
+            .read()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(exp) = expiration_time {
             current_time_epoch_millis() > exp
         } else {
             false // No expiration means token never expires
@@ -211,13 +309,15 @@ impl DocConnection {
     }
 
     pub async fn send(&self, update: &[u8]) -> Result<(), anyhow::Error> {
-        // Check expiration before processing
-        if self.is_expired() {
+        let msg = Message::decode_v1(update);
+
+        // Check expiration before processing. An expired connection may still
+        // present a fresh token.
+        if self.is_expired() && !matches!(msg, Ok(Message::TokenRefresh(_))) {
             return Err(anyhow::Error::msg("Token expired"));
         }
 
-        let msg = Message::decode_v1(update)?;
-        let result = self.handle_msg(&DefaultProtocol, msg)?;
+        let result = self.handle_msg(&DefaultProtocol, msg?)?;
 
         if let Some(result) = result {
             let msg = result.encode_v1();
@@ -234,6 +334,10 @@ impl DocConnection {
         protocol: &P,
         msg: Message,
     ) -> Result<Option<Message>, sync::Error> {
+        if let Message::TokenRefresh(token) = msg {
+            return self.refresh_token(&token);
+        }
+
         // Check expiration before processing
         if self.is_expired() {
             return Err(sync::Error::PermissionDenied {
@@ -241,7 +345,8 @@ impl DocConnection {
             });
         }
 
-        let can_write = matches!(self.authorization, Authorization::Full);
+        let authorization = self.authorization();
+        let can_write = !matches!(authorization, Authorization::ReadOnly);
         let a = &self.awareness;
         match msg {
             Message::Sync(msg) => match msg {
@@ -252,6 +357,11 @@ impl DocConnection {
                 SyncMessage::SyncStep2(update) => {
                     if can_write {
                         let mut awareness = a.write().unwrap_or_else(|e| e.into_inner());
+                        if let Some(reason) =
+                            self.restricted_update_denial(authorization, awareness.doc(), &update)?
+                        {
+                            return Err(sync::Error::UpdateRejected { reason });
+                        }
                         protocol.handle_sync_step2(&mut awareness, Update::decode_v1(&update)?)
                     } else {
                         Err(sync::Error::PermissionDenied {
@@ -262,6 +372,11 @@ impl DocConnection {
                 SyncMessage::Update(update) => {
                     if can_write {
                         let mut awareness = a.write().unwrap_or_else(|e| e.into_inner());
+                        if let Some(reason) =
+                            self.restricted_update_denial(authorization, awareness.doc(), &update)?
+                        {
+                            return Err(sync::Error::UpdateRejected { reason });
+                        }
                         protocol.handle_update(&mut awareness, Update::decode_v1(&update)?)
                     } else {
                         Err(sync::Error::PermissionDenied {
@@ -327,6 +442,11 @@ impl DocConnection {
                 tracing::warn!("Client sent event message to server, ignoring");
                 Ok(None)
             }
+            Message::TokenRefresh(_) => unreachable!("handled above"),
+            Message::TokenRefreshed { .. } => {
+                tracing::warn!("Client sent token refresh confirmation to server, ignoring");
+                Ok(None)
+            }
             Message::Custom(tag, data) => {
                 let mut awareness = a.write().unwrap_or_else(|e| e.into_inner());
                 protocol.missing_handle(&mut awareness, tag, data)
@@ -334,6 +454,51 @@ impl DocConnection {
         }
     }
 
+    /// Check a token the client presented and, if it is accepted, adopt its
+    /// authorization and expiration time. A rejected token is an error, after
+    /// which the connection should be closed.
+    fn refresh_token(&self, token: &str) -> Result<Option<Message>, sync::Error> {
+        let Some(refresher) = &self.token_refresher else {
+            return Ok(Some(Message::Auth(Some(
+                "Token refresh is not supported on this connection".to_string(),
+            ))));
+        };
+        match refresher(token) {
+            TokenRefresh::Granted {
+                authorization,
+                expiration_time,
+            } => {
+                let previous = self.authorization();
+                *self
+                    .authorization
+                    .write()
+                    .unwrap_or_else(|e| e.into_inner()) = authorization;
+                *self
+                    .expiration_time
+                    .write()
+                    .unwrap_or_else(|e| e.into_inner()) = expiration_time;
+                if !matches!(
+                    authorization,
+                    Authorization::Comment | Authorization::Suggest
+                ) {
+                    self.drop_shadow();
+                }
+                if previous != authorization {
+                    tracing::info!(
+                        ?previous,
+                        ?authorization,
+                        "Connection authorization changed by token refresh"
+                    );
+                }
+                Ok(Some(Message::TokenRefreshed {
+                    authorization,
+                    expires_at: expiration_time,
+                }))
+            }
+            TokenRefresh::Rejected(reason) => Err(sync::Error::TokenRefreshRejected { reason }),
+        }
+    }
+
     /// Send an event to this connection if it's subscribed to the event type
     pub fn send_event(&self, event: &EventMessage) -> Result<(), anyhow::Error> {
         // Check if connection is subscribed to this event type
@@ -387,6 +552,334 @@ impl Drop for DocConnection {
     }
 }
 
+impl DocConnection {
+    /// Why `update` may not be applied to `doc` under `authorization`, or
+    /// `None` if it may.
+    ///
+    /// Comment and suggest tokens can write, but only CriticMarkup. The update
+    /// is tried on the connection's shadow copy of the document, which is kept
+    /// current from the updates applied since the last check, so the cost
+    /// follows the size of the updates rather than of the document. The lines
+    /// of `contents` it touches, widened to take in any markup crossing them,
+    /// must read the same before and after with comments (and, for suggest,
+    /// pending suggestions) stripped, and must be well-formed markup on both
+    /// sides. Text may only be removed from, or added inside, markup the
+    /// connection's user authored. Anything outside `contents` must be left
+    /// alone. Updates that depend on changes the server hasn't seen are
+    /// refused, since they can't be checked until they integrate.
+    fn restricted_update_denial(
+        &self,
+        authorization: Authorization,
+        doc: &Doc,
+        update: &[u8],
+    ) -> Result<Option<String>, sync::Error> {
+        let (reason, strip): (&str, fn(&str) -> String) = match authorization {
+            Authorization::Full | Authorization::ReadOnly => return Ok(None),
+            Authorization::Comment => ("Token only allows adding comments", without_comments),
+            Authorization::Suggest => (
+                "Token only allows adding suggestions and comments",
+                base_text,
+            ),
+        };
+
+        let mut shadow_slot = self.shadow.lock().unwrap_or_else(|e| e.into_inner());
+        let backlog = self
+            .shadow_backlog
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        let mut shadow = match (shadow_slot.take(), backlog) {
+            (Some(mut shadow), Some(backlog)) => {
+                for pending in &backlog.updates {
+                    shadow.apply(pending)?;
+                }
+                shadow
+            }
+            _ => Shadow::new(doc)?,
+        };
+
+        let others_before = other_roots(&shadow.doc);
+        {
+            let mut txn = shadow.doc.transact_mut();
+            txn.apply_update(Update::decode_v1(update)?);
+            if txn.has_missing_updates() {
+                return Ok(Some(
+                    "Update depends on changes the server has not seen".to_string(),
+                ));
+            }
+        }
+        let edits = shadow.take_edits();
+        if other_roots(&shadow.doc) != others_before {
+            return Ok(Some(reason.to_string()));
+        }
+        if let Some(denial) = text_edit_denial(&shadow.text, &edits, strip, self.user.as_deref()) {
+            return Ok(Some(match denial {
+                TextDenial::NotMarkup => reason.to_string(),
+                TextDenial::NotOwnMarkup => {
+                    "Token only allows changing your own comments and suggestions".to_string()
+                }
+            }));
+        }
+
+        shadow.update_text(&edits);
+        *shadow_slot = Some(shadow);
+        *self
+            .shadow_backlog
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(ShadowBacklog::default());
+        Ok(None)
+    }
+
+    fn drop_shadow(&self) {
+        *self.shadow.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        *self
+            .shadow_backlog
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+#[derive(Default)]
+struct ShadowBacklog {
+    updates: Vec<Vec<u8>>,
+    bytes: usize,
+}
+
+/// A change to `contents`: `removed` bytes at `at`, in the text as it was
+/// before the transaction, replaced with `inserted`. `inserted` is `None` for
+/// an embedded object rather than text.
+#[derive(Debug, Clone)]
+struct TextEdit {
+    at: usize,
+    removed: usize,
+    inserted: Option<String>,
+}
+
+/// A private copy of a document, with its `contents` text mirrored as a string
+/// so that edits can be checked against the text around them.
+struct Shadow {
+    doc: Doc,
+    text: String,
+    /// Edits to `contents` made by transactions on `doc` since they were last
+    /// taken.
+    edits: Arc<Mutex<Vec<TextEdit>>>,
+    #[allow(unused)] // acts as RAII guard
+    subscription: Subscription,
+}
+
+impl Shadow {
+    fn new(doc: &Doc) -> Result<Self, sync::Error> {
+        let shadow_doc = Doc::new();
+        let edits: Arc<Mutex<Vec<TextEdit>>> = Arc::new(Mutex::new(Vec::new()));
+        let subscription = {
+            let edits = edits.clone();
+            shadow_doc
+                .get_or_insert_text("contents")
+                .observe(move |txn, event| {
+                    let mut edits = edits.lock().unwrap_or_else(|e| e.into_inner());
+                    let mut at = 0;
+                    for delta in event.delta(txn) {
+                        match delta {
+                            Delta::Retain(len, _) => at += *len as usize,
+                            Delta::Deleted(len) => {
+                                edits.push(TextEdit {
+                                    at,
+                                    removed: *len as usize,
+                                    inserted: Some(String::new()),
+                                });
+                                at += *len as usize;
+                            }
+                            Delta::Inserted(value, _) => edits.push(TextEdit {
+                                at,
+                                removed: 0,
+                                inserted: match value {
+                                    Out::Any(Any::String(text)) => Some(text.to_string()),
+                                    _ => None,
+                                },
+                            }),
+                        }
+                    }
+                })
+        };
+        let mut shadow = Self {
+            doc: shadow_doc,
+            text: String::new(),
+            edits,
+            subscription,
+        };
+        let state = doc
+            .transact()
+            .encode_state_as_update_v1(&StateVector::default());
+        shadow.apply(&state)?;
+        Ok(shadow)
+    }
+
+    /// Apply an update the real document has already taken.
+    fn apply(&mut self, update: &[u8]) -> Result<(), sync::Error> {
+        self.doc
+            .transact_mut()
+            .apply_update(Update::decode_v1(update)?);
+        let edits = self.take_edits();
+        self.update_text(&edits);
+        Ok(())
+    }
+
+    fn take_edits(&self) -> Vec<TextEdit> {
+        std::mem::take(&mut *self.edits.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Bring `text` up to date with `edits`, or re-read it if they can't be
+    /// applied to a string (an embedded object, say).
+    fn update_text(&mut self, edits: &[TextEdit]) {
+        if apply_text_edits(&mut self.text, edits).is_none() {
+            let txn = self.doc.transact();
+            self.text = txn
+                .get_text("contents")
+                .map(|text| text.get_string(&txn))
+                .unwrap_or_default();
+        }
+    }
+}
+
+/// Apply `edits` (in order, each relative to the text before any of them) to
+/// `text`. `None`, leaving `text` partly edited, if one doesn't fit.
+fn apply_text_edits(text: &mut String, edits: &[TextEdit]) -> Option<()> {
+    for edit in edits.iter().rev() {
+        let end = edit.at.checked_add(edit.removed)?;
+        if end > text.len() || !text.is_char_boundary(edit.at) || !text.is_char_boundary(end) {
+            return None;
+        }
+        text.replace_range(edit.at..end, edit.inserted.as_deref()?);
+    }
+    Some(())
+}
+
+enum TextDenial {
+    NotMarkup,
+    NotOwnMarkup,
+}
+
+/// Check edits to `contents` from a comment or suggest token against the text
+/// they were made to. Only the lines they touch are read, widened until no
+/// markup crosses either end.
+fn text_edit_denial(
+    before: &str,
+    edits: &[TextEdit],
+    strip: fn(&str) -> String,
+    user: Option<&str>,
+) -> Option<TextDenial> {
+    let (Some(first), Some(last)) = (edits.first(), edits.last()) else {
+        return None;
+    };
+    let fits = |edit: &TextEdit| {
+        let end = edit.at + edit.removed;
+        edit.inserted.is_some()
+            && end <= before.len()
+            && before.is_char_boundary(edit.at)
+            && before.is_char_boundary(end)
+    };
+    if !edits.iter().all(fits) {
+        return Some(TextDenial::NotMarkup);
+    }
+
+    // Widen to whole lines, then past any markup left open before the start
+    let mut lo = line_start(before, first.at);
+    let mut hi = line_end(before, last.at + last.removed);
+    loop {
+        let open = MarkupKind::ALL.into_iter().find_map(|kind| {
+            let opener = before[..lo].rfind(kind.open())?;
+            (!kind.is_closed_in(&before[opener + 3..lo])).then_some(opener)
+        });
+        match open {
+            Some(opener) => lo = line_start(before, opener),
+            None => break,
+        }
+    }
+
+    // ...and past any markup left open at the end, on either side
+    let (before_window, after_window) = loop {
+        let before_window = &before[lo..hi];
+        let mut after_window = before_window.to_string();
+        let shifted: Vec<TextEdit> = edits
+            .iter()
+            .map(|edit| TextEdit {
+                at: edit.at - lo,
+                ..edit.clone()
+            })
+            .collect();
+        if apply_text_edits(&mut after_window, &shifted).is_none() {
+            return Some(TextDenial::NotMarkup);
+        }
+
+        let open_kind = MarkupKind::ALL.into_iter().find(|kind| {
+            [before_window, after_window.as_str()].iter().any(|window| {
+                window
+                    .rfind(kind.open())
+                    .is_some_and(|opener| !kind.is_closed_in(&window[opener + 3..]))
+            })
+        });
+        let Some(kind) = open_kind else {
+            break (before_window, after_window);
+        };
+        let Some(closer) = before[hi..].find(kind.close()) else {
+            return Some(TextDenial::NotMarkup);
+        };
+        hi = line_end(before, hi + closer + 3);
+    };
+
+    let (Some(spans), Some(_)) = (markup_spans(before_window), markup_spans(&after_window)) else {
+        return Some(TextDenial::NotMarkup);
+    };
+    let own = |span: &MarkupSpan| user.is_some() && span.author.as_deref() == user;
+    for edit in edits {
+        let (from, to) = (edit.at - lo, edit.at - lo + edit.removed);
+        if edit.removed > 0 {
+            let inside = spans.iter().find(|span| span.from <= from && to <= span.to);
+            if !inside.is_some_and(own) {
+                return Some(TextDenial::NotOwnMarkup);
+            }
+        }
+        let inserting = edit
+            .inserted
+            .as_deref()
+            .is_some_and(|text| !text.is_empty());
+        if inserting
+            && spans
+                .iter()
+                .any(|span| span.from < from && from < span.to && !own(span))
+        {
+            return Some(TextDenial::NotOwnMarkup);
+        }
+    }
+
+    if strip(before_window) != strip(&after_window) {
+        return Some(TextDenial::NotMarkup);
+    }
+    None
+}
+
+fn line_start(text: &str, at: usize) -> usize {
+    text[..at].rfind('\n').map_or(0, |newline| newline + 1)
+}
+
+fn line_end(text: &str, at: usize) -> usize {
+    text[at..]
+        .find('\n')
+        .map_or(text.len(), |newline| at + newline)
+}
+
+/// Every root type other than `contents`, as JSON sorted by name.
+fn other_roots(doc: &Doc) -> Vec<(String, Any)> {
+    let txn = doc.transact();
+    let mut others: Vec<(String, Any)> = txn
+        .root_refs()
+        .filter(|(name, _)| *name != "contents")
+        .map(|(name, value)| (name.to_string(), value.to_json(&txn)))
+        .collect();
+    others.sort_by(|a, b| a.0.cmp(&b.0));
+    others
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -657,6 +1150,61 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("Token expired"));
     }
 
+    #[tokio::test]
+    async fn test_token_refresh_revives_expired_connection() {
+        let awareness = Arc::new(RwLock::new(Awareness::new(yrs::Doc::new())));
+        let sent = Arc::new(RwLock::new(Vec::new()));
+        let sent_clone = sent.clone();
+        let far_future = current_time_epoch_millis() + 3_600_000;
+        let connection = DocConnection::new_with_expiration(
+            awareness,
+            Authorization::Full,
+            Some(current_time_epoch_millis() - 1000),
+            move |bytes| sent_clone.write().unwrap().push(bytes.to_vec()),
+        )
+        .with_token_refresh(move |token| match token {
+            "fresh" => TokenRefresh::Granted {
+                authorization: Authorization::ReadOnly,
+                expiration_time: Some(far_future),
+            },
+            _ => TokenRefresh::Rejected("Invalid token".to_string()),
+        });
+
+        let refresh = Message::TokenRefresh("fresh".to_string()).encode_v1();
+        connection.send(&refresh).await.unwrap();
+        assert_eq!(
+            Message::decode_v1(sent.read().unwrap().last().unwrap()).unwrap(),
+            Message::TokenRefreshed {
+                authorization: Authorization::ReadOnly,
+                expires_at: Some(far_future),
+            }
+        );
+
+        // No longer expired, but downgraded to read-only
+        let update = Message::Sync(SyncMessage::Update(vec![0, 0])).encode_v1();
+        let result = connection.send(&update).await;
+        assert!(result.unwrap_err().to_string().contains("write access"));
+        assert_eq!(connection.authorization(), Authorization::ReadOnly);
+
+        let refresh = Message::TokenRefresh("stale".to_string()).encode_v1();
+        let error = connection.send(&refresh).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<sync::Error>(),
+            Some(sync::Error::TokenRefreshRejected { reason }) if reason == "Invalid token"
+        ));
+    }
+
+    #[test]
+    fn test_token_refresh_unsupported_without_refresher() {
+        let awareness = Arc::new(RwLock::new(Awareness::new(yrs::Doc::new())));
+        let connection = DocConnection::new(awareness, Authorization::Full, |_| {});
+        let result = connection
+            .handle_msg(&DefaultProtocol, Message::TokenRefresh("t".to_string()))
+            .unwrap();
+        assert!(matches!(result, Some(Message::Auth(Some(_)))));
+        assert_eq!(connection.authorization(), Authorization::Full);
+    }
+
     #[tokio::test]
     async fn test_doc_connection_send_not_expired() {
         let doc = yrs::Doc::new();
@@ -683,4 +1231,171 @@ mod tests {
         let result = connection.send(&encoded).await;
         assert!(result.is_ok());
     }
+
+    /// A server doc containing `initial`, and a client with a copy of it.
+    fn doc_and_client(initial: &str) -> (yrs::Doc, yrs::Doc) {
+        use yrs::{Text, WriteTxn};
+
+        let doc = yrs::Doc::new();
+        {
+            let mut txn = doc.transact_mut();
+            let text = txn.get_or_insert_text("contents");
+            text.insert(&mut txn, 0, initial);
+        }
+        let client = yrs::Doc::new();
+        let state = doc
+            .transact()
+            .encode_state_as_update_v1(&StateVector::default());
+        client
+            .transact_mut()
+            .apply_update(Update::decode_v1(&state).unwrap());
+        (doc, client)
+    }
+
+    /// The update `client` produces by making `edit` to its `contents`.
+    fn client_edit(
+        client: &yrs::Doc,
+        edit: impl FnOnce(&yrs::TextRef, &mut yrs::TransactionMut),
+    ) -> Vec<u8> {
+        use yrs::WriteTxn;
+
+        let sv = client.transact().state_vector();
+        {
+            let mut txn = client.transact_mut();
+            let text = txn.get_or_insert_text("contents");
+            edit(&text, &mut txn);
+        }
+        client.transact().encode_state_as_update_v1(&sv)
+    }
+
+    /// A server doc containing `initial`, and the update a client produces by
+    /// inserting `insert` at `index` in its own copy.
+    fn doc_and_client_update(initial: &str, index: u32, insert: &str) -> (yrs::Doc, Vec<u8>) {
+        use yrs::Text;
+
+        let (doc, client) = doc_and_client(initial);
+        let update = client_edit(&client, |text, txn| text.insert(txn, index, insert));
+        (doc, update)
+    }
+
+    fn is_rejected(result: Result<Option<Message>, sync::Error>) -> bool {
+        matches!(result, Err(sync::Error::UpdateRejected { .. }))
+    }
+
+    fn contents(awareness: &Arc<RwLock<Awareness>>) -> String {
+        let awareness = awareness.read().unwrap();
+        let txn = awareness.doc().transact();
+        txn.get_text("contents").unwrap().get_string(&txn)
+    }
+
+    #[test]
+    fn test_suggest_connection_may_add_suggestions() {
+        let (doc, update) = doc_and_client_update("Hello world", 6, "{++big ++}");
+        let awareness = Arc::new(RwLock::new(Awareness::new(doc)));
+        let connection = DocConnection::new(awareness.clone(), Authorization::Suggest, |_| {});
+
+        let result = connection
+            .handle_msg(&DefaultProtocol, Message::Sync(SyncMessage::Update(update)))
+            .unwrap();
+        assert!(result.is_none());
+        assert_eq!(contents(&awareness), "Hello {++big ++}world");
+    }
+
+    #[test]
+    fn test_suggest_connection_may_not_edit_text() {
+        let (doc, update) = doc_and_client_update("Hello world", 6, "big ");
+        let awareness = Arc::new(RwLock::new(Awareness::new(doc)));
+        let connection = DocConnection::new(awareness.clone(), Authorization::Suggest, |_| {});
+
+        let result =
+            connection.handle_msg(&DefaultProtocol, Message::Sync(SyncMessage::Update(update)));
+        assert!(is_rejected(result));
+        assert_eq!(contents(&awareness), "Hello world");
+    }
+
+    #[test]
+    fn test_suggest_connection_may_only_remove_own_markup() {
+        use yrs::Text;
+
+        let initial = r#"Hello {++{"author":"bob"}@@big ++}world"#;
+        let span_len = initial.len() as u32 - 11;
+        for (user, allowed) in [("alice", false), ("bob", true)] {
+            let (doc, client) = doc_and_client(initial);
+            let update = client_edit(&client, |text, txn| text.remove_range(txn, 6, span_len));
+            let awareness = Arc::new(RwLock::new(Awareness::new(doc)));
+            let connection = DocConnection::new(awareness.clone(), Authorization::Suggest, |_| {})
+                .with_user(Some(user.to_string()));
+            let result =
+                connection.handle_msg(&DefaultProtocol, Message::Sync(SyncMessage::Update(update)));
+            if allowed {
+                assert!(result.unwrap().is_none());
+                assert_eq!(contents(&awareness), "Hello world");
+            } else {
+                assert!(is_rejected(result));
+                assert_eq!(contents(&awareness), initial);
+            }
+        }
+    }
+
+    #[test]
+    fn test_suggest_connection_checks_successive_updates() {
+        use yrs::{Text, WriteTxn};
+
+        let (doc, client) = doc_and_client("Hello world");
+        let awareness = Arc::new(RwLock::new(Awareness::new(doc)));
+        let connection = DocConnection::new(awareness.clone(), Authorization::Suggest, |_| {})
+            .with_user(Some("amy".to_string()));
+        let suggestion = r#"{++{"author":"amy"}@@big++}"#;
+
+        let update = client_edit(&client, |text, txn| text.insert(txn, 6, suggestion));
+        let result =
+            connection.handle_msg(&DefaultProtocol, Message::Sync(SyncMessage::Update(update)));
+        assert!(result.unwrap().is_none());
+
+        // Someone with full access edits elsewhere in the meantime
+        {
+            let awareness = awareness.write().unwrap();
+            let mut txn = awareness.doc().transact_mut();
+            let text = txn.get_or_insert_text("contents");
+            let len = text.len(&txn);
+            text.insert(&mut txn, len, "!");
+        }
+
+        let inside = 6 + suggestion.len() as u32 - 3;
+        let update = client_edit(&client, |text, txn| text.insert(txn, inside, " red"));
+        let result =
+            connection.handle_msg(&DefaultProtocol, Message::Sync(SyncMessage::Update(update)));
+        assert!(result.unwrap().is_none());
+        assert_eq!(
+            contents(&awareness),
+            r#"Hello {++{"author":"amy"}@@big red++}world!"#
+        );
+
+        let update = client_edit(&client, |text, txn| text.insert(txn, 0, "Oh, "));
+        let result =
+            connection.handle_msg(&DefaultProtocol, Message::Sync(SyncMessage::Update(update)));
+        assert!(is_rejected(result));
+    }
+
+    #[test]
+    fn test_comment_connection_may_only_comment() {
+        let (doc, update) = doc_and_client_update("Hello world", 11, "{>>Nice<<}");
+        let awareness = Arc::new(RwLock::new(Awareness::new(doc)));
+        let connection = DocConnection::new(awareness.clone(), Authorization::Comment, |_| {});
+        let result = connection
+            .handle_msg(&DefaultProtocol, Message::Sync(SyncMessage::Update(update)))
+            .unwrap();
+        assert!(result.is_none());
+        assert_eq!(contents(&awareness), "Hello world{>>Nice<<}");
+
+        let (doc, update) = doc_and_client_update("Hello world", 6, "{++big ++}");
+        let awareness = Arc::new(RwLock::new(Awareness::new(doc)));
+        let connection = DocConnection::new(awareness.clone(), Authorization::Comment, |_| {});
+        let result = connection.handle_msg(
+            &DefaultProtocol,
+            Message::Sync(SyncMessage::SyncStep2(update)),
+        );
+        assert!(is_rejected(result));
+        assert_eq!(contents(&awareness), "Hello world");
+    }
 }
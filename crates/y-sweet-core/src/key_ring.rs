@@ -0,0 +1,249 @@
+//! Signing keys added at runtime by key rotation.
+//!
+//! Rotating adds a fresh signing key without touching the config file. The
+//! key it replaces (an earlier rotated key, or the signing key from the
+//! `[[auth]]` section of the config file) goes on verifying tokens until a
+//! grace period runs out, so
+//! tokens signed shortly before the rotation stay good, and are ignored after
+//! that. Rotated keys are kept in the store at `.config/keys.json`, which,
+//! like the config file, holds secret key material.
+
+use crate::auth::{AuthError, AuthKeyEntry, AuthKeyMaterial};
+use crate::config::TokenType;
+use crate::store::Store;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+pub const KEY_RING_KEY: &str = ".config/keys.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyRingDocument {
+    /// Oldest first.
+    #[serde(default)]
+    pub keys: Vec<RotatedKey>,
+    /// When the config file's signing key stops being accepted, once a
+    /// rotation has replaced it. Milliseconds since the epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_keys_retire_at: Option<u64>,
+    /// The ID of the config file's signing key that the first rotation
+    /// replaced, if it had one. Other keys in the config file, such as
+    /// verify-only keys, are never retired.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaced_config_key_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotatedKey {
+    pub key_id: String,
+    /// Base64 key bytes, as `private_key` takes them in the config file.
+    pub private_key: String,
+    pub allowed_token_types: Vec<TokenType>,
+    /// Milliseconds since the epoch.
+    pub created_at: u64,
+    /// Milliseconds since the epoch. Set once a newer key has replaced this
+    /// one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retires_at: Option<u64>,
+}
+
+impl KeyRingDocument {
+    /// Make `key` the signing key. The key it replaces retires
+    /// `grace_period` milliseconds after `now`; keys already past their
+    /// retirement are dropped. On the first rotation that is the config
+    /// file's signing key, with ID `config_key_id`.
+    pub fn rotate(
+        &mut self,
+        key: RotatedKey,
+        now: u64,
+        grace_period: u64,
+        config_key_id: Option<&str>,
+    ) {
+        let retires_at = now + grace_period;
+        self.keys
+            .retain(|k| !is_retired(k.retires_at, now) && k.key_id != key.key_id);
+        for k in &mut self.keys {
+            k.retires_at.get_or_insert(retires_at);
+        }
+        if self.config_keys_retire_at.is_none() {
+            self.config_keys_retire_at = Some(retires_at);
+            self.replaced_config_key_id = config_key_id.map(str::to_string);
+        }
+        self.keys.push(key);
+    }
+}
+
+/// Whether a key retiring at `retires_at` is retired at `current_time`. A
+/// `current_time` of zero skips time checks, as it does for expiry.
+pub fn is_retired(retires_at: Option<u64>, current_time: u64) -> bool {
+    current_time != 0 && retires_at.is_some_and(|retires_at| retires_at <= current_time)
+}
+
+/// The rotated keys in force. Clones share the same ring, so a rotation is
+/// seen by every copy of the authenticator.
+#[derive(Debug, Clone, Default)]
+pub struct KeyRing {
+    inner: Arc<RwLock<KeyRingState>>,
+}
+
+#[derive(Debug, Default)]
+struct KeyRingState {
+    document: KeyRingDocument,
+    /// Parsed from `document.keys`, in the same order, with when each retires.
+    entries: Vec<(AuthKeyEntry, Option<u64>)>,
+}
+
+impl PartialEq for KeyRing {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner) || self.document() == other.document()
+    }
+}
+
+impl KeyRing {
+    pub fn document(&self) -> KeyRingDocument {
+        self.read().document.clone()
+    }
+
+    /// Put `document` in force. Fails, leaving the ring as it was, if any key
+    /// cannot be parsed or cannot sign.
+    pub fn replace(&self, document: KeyRingDocument) -> Result<(), AuthError> {
+        let mut entries = Vec::with_capacity(document.keys.len());
+        for key in &document.keys {
+            let key_material = crate::auth::parse_private_key_format(&key.private_key)?;
+            if !matches!(
+                key_material,
+                AuthKeyMaterial::Hmac256(_) | AuthKeyMaterial::Legacy(_)
+            ) {
+                return Err(AuthError::NoSigningKey);
+            }
+            let entry = AuthKeyEntry {
+                key_id: Some(key.key_id.clone()),
+                key_material,
+                can_sign: true,
+                allowed_token_types: key.allowed_token_types.clone(),
+            };
+            entries.push((entry, key.retires_at));
+        }
+        *self.inner.write().unwrap_or_else(|e| e.into_inner()) = KeyRingState { document, entries };
+        Ok(())
+    }
+
+    /// The newest rotated key that has not been replaced.
+    pub fn signing_key(&self) -> Option<AuthKeyEntry> {
+        self.read()
+            .entries
+            .iter()
+            .rev()
+            .find(|(_, retires_at)| retires_at.is_none())
+            .map(|(entry, _)| entry.clone())
+    }
+
+    /// The rotated key with `key_id`, unless it has retired.
+    pub fn get(&self, key_id: &str, current_time: u64) -> Option<AuthKeyEntry> {
+        self.read()
+            .entries
+            .iter()
+            .find(|(entry, retires_at)| {
+                entry.key_id.as_deref() == Some(key_id) && !is_retired(*retires_at, current_time)
+            })
+            .map(|(entry, _)| entry.clone())
+    }
+
+    /// Rotated keys that have not retired, newest first, with when each
+    /// retires.
+    pub fn verifying_keys(&self, current_time: u64) -> Vec<(AuthKeyEntry, Option<u64>)> {
+        self.read()
+            .entries
+            .iter()
+            .rev()
+            .filter(|(_, retires_at)| !is_retired(*retires_at, current_time))
+            .cloned()
+            .collect()
+    }
+
+    pub fn config_keys_retire_at(&self) -> Option<u64> {
+        self.read().document.config_keys_retire_at
+    }
+
+    pub fn replaced_config_key_id(&self) -> Option<String> {
+        self.read().document.replaced_config_key_id.clone()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, KeyRingState> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Load the rotated keys saved in the store. Empty if there have been no
+/// rotations.
+pub async fn load_key_ring_from_store(
+    store: Arc<Box<dyn Store>>,
+) -> Result<KeyRingDocument, Box<dyn std::error::Error>> {
+    match store.get(KEY_RING_KEY).await? {
+        Some(data) => Ok(serde_json::from_slice(&data)?),
+        None => Ok(KeyRingDocument::default()),
+    }
+}
+
+pub async fn save_key_ring_to_store(
+    store: Arc<Box<dyn Store>>,
+    document: &KeyRingDocument,
+) -> Result<(), Box<dyn std::error::Error>> {
+    store
+        .set(KEY_RING_KEY, serde_json::to_vec_pretty(document)?)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rotated(key_id: &str, created_at: u64) -> RotatedKey {
+        RotatedKey {
+            key_id: key_id.to_string(),
+            private_key: data_encoding::BASE64.encode(&[7u8; 32]),
+            allowed_token_types: vec![TokenType::Document],
+            created_at,
+            retires_at: None,
+        }
+    }
+
+    #[test]
+    fn rotation_retires_replaced_keys_after_the_grace_period() {
+        let mut document = KeyRingDocument::default();
+        document.rotate(rotated("a", 1_000), 1_000, 500, Some("config"));
+        assert_eq!(document.config_keys_retire_at, Some(1_500));
+        assert_eq!(document.replaced_config_key_id.as_deref(), Some("config"));
+
+        document.rotate(rotated("b", 2_000), 2_000, 500, Some("a"));
+        assert_eq!(document.keys[0].retires_at, Some(2_500));
+        assert_eq!(document.config_keys_retire_at, Some(1_500));
+        assert_eq!(document.replaced_config_key_id.as_deref(), Some("config"));
+
+        let ring = KeyRing::default();
+        ring.replace(document.clone()).unwrap();
+        assert_eq!(ring.signing_key().unwrap().key_id.as_deref(), Some("b"));
+        assert!(ring.get("a", 2_499).is_some());
+        assert!(ring.get("a", 2_500).is_none());
+        assert_eq!(ring.verifying_keys(3_000).len(), 1);
+
+        // The next rotation drops "a" for good
+        document.rotate(rotated("c", 3_000), 3_000, 500, None);
+        let key_ids: Vec<_> = document.keys.iter().map(|k| k.key_id.as_str()).collect();
+        assert_eq!(key_ids, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn unparseable_documents_leave_the_ring_alone() {
+        let ring = KeyRing::default();
+        let mut document = KeyRingDocument::default();
+        document.rotate(rotated("a", 0), 0, 0, None);
+        ring.replace(document.clone()).unwrap();
+
+        document.keys[0].private_key = "%%%%".to_string();
+        assert!(ring.replace(document).is_err());
+        assert!(ring.signing_key().is_some());
+    }
+}
@@ -1,5 +1,7 @@
+pub mod api_key;
 pub mod api_types;
 pub mod auth;
+pub mod auth_audit;
 pub mod config;
 pub mod critic_scanner;
 pub mod cwt;
@@ -7,11 +9,14 @@ pub mod doc_connection;
 pub mod doc_resolver;
 pub mod doc_sync;
 pub mod event;
+pub mod key_ring;
 pub mod link_indexer;
 pub mod link_parser;
 pub mod metrics;
+pub mod revocation;
 pub mod search_index;
 pub mod store;
 pub mod sync;
 pub mod sync_kv;
+pub mod token_explain;
 pub mod webhook;
@@ -24,6 +24,12 @@ pub struct RelayMetrics {
     pub token_expired_total: CounterVec,
     pub permission_denied_total: CounterVec,
     pub missing_token_total: CounterVec,
+    pub api_key_requests_total: CounterVec,
+
+    // MCP usage metrics
+    pub mcp_tool_calls_total: CounterVec,
+    pub mcp_rate_limited_total: CounterVec,
+    pub mcp_bytes_returned_total: CounterVec,
 }
 
 static RELAY_METRICS: OnceLock<Result<Arc<RelayMetrics>, prometheus::Error>> = OnceLock::new();
@@ -184,6 +190,43 @@ impl RelayMetrics {
         )?;
         registry.register(Box::new(missing_token_total.clone()))?;
 
+        let api_key_requests_total = CounterVec::new(
+            Opts::new(
+                "relay_server_api_key_requests_total",
+                "Total number of requests authenticated with a service-account API key",
+            ),
+            &["key", "outcome"],
+        )?;
+        registry.register(Box::new(api_key_requests_total.clone()))?;
+
+        // MCP usage metrics
+        let mcp_tool_calls_total = CounterVec::new(
+            Opts::new(
+                "relay_server_mcp_tool_calls_total",
+                "Total number of MCP tool calls",
+            ),
+            &["tool", "outcome"],
+        )?;
+        registry.register(Box::new(mcp_tool_calls_total.clone()))?;
+
+        let mcp_rate_limited_total = CounterVec::new(
+            Opts::new(
+                "relay_server_mcp_rate_limited_total",
+                "Total number of MCP tool calls rejected by a usage limit",
+            ),
+            &["scope", "limit"],
+        )?;
+        registry.register(Box::new(mcp_rate_limited_total.clone()))?;
+
+        let mcp_bytes_returned_total = CounterVec::new(
+            Opts::new(
+                "relay_server_mcp_bytes_returned_total",
+                "Total bytes of MCP tool results returned to clients",
+            ),
+            &["tool"],
+        )?;
+        registry.register(Box::new(mcp_bytes_returned_total.clone()))?;
+
         Ok(Arc::new(Self {
             webhook_requests_total,
             webhook_request_duration_seconds,
@@ -201,6 +244,10 @@ impl RelayMetrics {
             token_expired_total,
             permission_denied_total,
             missing_token_total,
+            api_key_requests_total,
+            mcp_tool_calls_total,
+            mcp_rate_limited_total,
+            mcp_bytes_returned_total,
         }))
     }
 
@@ -305,6 +352,31 @@ impl RelayMetrics {
             .with_label_values(&[endpoint, auth_required])
             .inc();
     }
+
+    pub fn record_api_key_request(&self, key: &str, outcome: &str) {
+        self.api_key_requests_total
+            .with_label_values(&[key, outcome])
+            .inc();
+    }
+
+    // MCP usage metrics methods
+    pub fn record_mcp_tool_call(&self, tool: &str, outcome: &str) {
+        self.mcp_tool_calls_total
+            .with_label_values(&[tool, outcome])
+            .inc();
+    }
+
+    pub fn record_mcp_rate_limited(&self, scope: &str, limit: &str) {
+        self.mcp_rate_limited_total
+            .with_label_values(&[scope, limit])
+            .inc();
+    }
+
+    pub fn record_mcp_bytes_returned(&self, tool: &str, bytes: usize) {
+        self.mcp_bytes_returned_total
+            .with_label_values(&[tool])
+            .inc_by(bytes as f64);
+    }
 }
 
 impl Default for RelayMetrics {
@@ -0,0 +1,197 @@
+//! Revoked tokens, checked on every verification.
+//!
+//! A revocation names a single token by its ID, every token issued to a
+//! subject, or every token signed with a key. Subject revocations only cover
+//! tokens issued before the revocation was made, so a user who is let back in
+//! can be sent fresh tokens without lifting it; tokens that carry no issue
+//! time (legacy tokens) stay revoked until it is removed.
+
+use crate::store::Store;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+pub const REVOCATIONS_KEY: &str = ".config/revocations.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RevocationKind {
+    /// A token ID: the `cti` claim, or the fingerprint of a token without one.
+    Token,
+    /// A user, as carried in the token's subject.
+    Subject,
+    /// A signing key, by its `key_id`.
+    Key,
+}
+
+impl std::fmt::Display for RevocationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RevocationKind::Token => write!(f, "token"),
+            RevocationKind::Subject => write!(f, "subject"),
+            RevocationKind::Key => write!(f, "key"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Revocation {
+    pub kind: RevocationKind,
+    pub value: String,
+    /// Milliseconds since the epoch.
+    pub revoked_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// What a revocation can match a token on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenIdentity {
+    pub token_id: String,
+    pub subject: Option<String>,
+    pub key_id: Option<String>,
+    /// Milliseconds since the epoch, if the token says when it was issued.
+    pub issued_at: Option<u64>,
+}
+
+impl Revocation {
+    pub fn matches(&self, identity: &TokenIdentity) -> bool {
+        match self.kind {
+            RevocationKind::Token => identity.token_id == self.value,
+            RevocationKind::Subject => {
+                identity.subject.as_deref() == Some(self.value.as_str())
+                    && identity
+                        .issued_at
+                        .map_or(true, |issued_at| issued_at < self.revoked_at)
+            }
+            RevocationKind::Key => identity.key_id.as_deref() == Some(self.value.as_str()),
+        }
+    }
+}
+
+/// The revocations in force. Clones share the same list, so the server can
+/// update it in place while every copy of the authenticator sees the change.
+#[derive(Debug, Clone, Default)]
+pub struct RevocationList {
+    entries: Arc<RwLock<Vec<Revocation>>>,
+}
+
+impl PartialEq for RevocationList {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.entries, &other.entries) || self.entries() == other.entries()
+    }
+}
+
+impl RevocationList {
+    pub fn entries(&self) -> Vec<Revocation> {
+        self.entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn replace(&self, entries: Vec<Revocation>) {
+        *self.entries.write().unwrap_or_else(|e| e.into_inner()) = entries;
+    }
+
+    /// Add a revocation, replacing any earlier one of the same kind and value.
+    pub fn add(&self, revocation: Revocation) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|r| !(r.kind == revocation.kind && r.value == revocation.value));
+        entries.push(revocation);
+    }
+
+    /// Remove a revocation. Returns whether there was one.
+    pub fn remove(&self, kind: RevocationKind, value: &str) -> bool {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        let before = entries.len();
+        entries.retain(|r| !(r.kind == kind && r.value == value));
+        entries.len() != before
+    }
+
+    /// The first revocation covering the token, if any.
+    pub fn find(&self, identity: &TokenIdentity) -> Option<Revocation> {
+        self.entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|r| r.matches(identity))
+            .cloned()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_empty()
+    }
+}
+
+/// Load the revocations saved in the store. Empty if none have been saved.
+pub async fn load_revocations_from_store(
+    store: Arc<Box<dyn Store>>,
+) -> Result<Vec<Revocation>, Box<dyn std::error::Error>> {
+    match store.get(REVOCATIONS_KEY).await? {
+        Some(data) => Ok(serde_json::from_slice(&data)?),
+        None => Ok(Vec::new()),
+    }
+}
+
+pub async fn save_revocations_to_store(
+    store: Arc<Box<dyn Store>>,
+    revocations: &[Revocation],
+) -> Result<(), Box<dyn std::error::Error>> {
+    store
+        .set(REVOCATIONS_KEY, serde_json::to_vec(revocations)?)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(issued_at: Option<u64>) -> TokenIdentity {
+        TokenIdentity {
+            token_id: "0b71".to_string(),
+            subject: Some("alice".to_string()),
+            key_id: Some("2024-01".to_string()),
+            issued_at,
+        }
+    }
+
+    fn revocation(kind: RevocationKind, value: &str) -> Revocation {
+        Revocation {
+            kind,
+            value: value.to_string(),
+            revoked_at: 1_000,
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn subject_revocation_covers_only_earlier_tokens() {
+        let revoked = revocation(RevocationKind::Subject, "alice");
+        assert!(revoked.matches(&identity(Some(999))));
+        assert!(!revoked.matches(&identity(Some(1_000))));
+        assert!(revoked.matches(&identity(None)));
+    }
+
+    #[test]
+    fn token_and_key_revocations_match_exactly() {
+        assert!(revocation(RevocationKind::Token, "0b71").matches(&identity(None)));
+        assert!(!revocation(RevocationKind::Token, "0b72").matches(&identity(None)));
+        assert!(revocation(RevocationKind::Key, "2024-01").matches(&identity(None)));
+        assert!(!revocation(RevocationKind::Subject, "bob").matches(&identity(None)));
+    }
+
+    #[test]
+    fn clones_share_the_list() {
+        let list = RevocationList::default();
+        let copy = list.clone();
+        list.add(revocation(RevocationKind::Token, "0b71"));
+        assert!(copy.find(&identity(None)).is_some());
+        assert!(copy.remove(RevocationKind::Token, "0b71"));
+        assert!(list.is_empty());
+    }
+}
@@ -75,6 +75,13 @@ pub trait Store: 'static {
         ))
     }
 
+    /// Fetch one version of `key`, by an ID from `list_versions`.
+    async fn get_version(&self, _key: &str, _version_id: &str) -> Result<Option<Vec<u8>>> {
+        Err(StoreError::UnsupportedOperation(
+            "This store does not support reading versions".to_string(),
+        ))
+    }
+
     /// List all document IDs in storage.
     ///
     /// Returns doc_ids extracted from storage keys of the form `{doc_id}/data.ysweet`.
@@ -132,6 +139,13 @@ pub trait Store: Send + Sync {
         ))
     }
 
+    /// Fetch one version of `key`, by an ID from `list_versions`.
+    async fn get_version(&self, _key: &str, _version_id: &str) -> Result<Option<Vec<u8>>> {
+        Err(StoreError::UnsupportedOperation(
+            "This store does not support reading versions".to_string(),
+        ))
+    }
+
     /// List all document IDs in storage.
     ///
     /// Returns doc_ids extracted from storage keys of the form `{doc_id}/data.ysweet`.
@@ -641,6 +641,25 @@ impl Store for S3Store {
         Ok(versions)
     }
 
+    async fn get_version(&self, key: &str, version_id: &str) -> Result<Option<Vec<u8>>> {
+        self.init().await?;
+        let prefixed_key = self.prefixed_key(key);
+        let mut action = self
+            .bucket
+            .get_object(Some(&self.credentials), &prefixed_key);
+        action.query_mut().insert("versionId", version_id);
+        let response = self.store_request(Method::GET, action, None).await;
+
+        match response {
+            Ok(response) => {
+                let result = Self::read_response_bytes(response).await?;
+                Ok(Some(result.to_vec()))
+            }
+            Err(StoreError::DoesNotExist(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
     async fn generate_upload_url(
         &self,
         key: &str,
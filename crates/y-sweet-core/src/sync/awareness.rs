@@ -100,6 +100,13 @@ impl Awareness {
     ///
     pub fn set_local_state<S: Into<String>>(&mut self, json: S) {
         let client_id = self.doc.client_id();
+        self.set_state(client_id, json);
+    }
+
+    /// Sets the state of a given client, the same way [Awareness::set_local_state] does for the
+    /// current one. Lets a single instance publish states for several participants, e.g. a server
+    /// announcing agents that edit the document through it under their own client ids.
+    pub fn set_state<S: Into<String>>(&mut self, client_id: ClientID, json: S) {
         self.update_meta(client_id);
         let new: String = json.into();
         match self.states.entry(client_id) {
@@ -429,4 +436,26 @@ mod test {
         assert_eq!(e_remote, e_local);
         Ok(())
     }
+
+    #[test]
+    fn states_of_other_clients() -> Result<(), Box<dyn std::error::Error>> {
+        let (s, mut o_local) = channel();
+        let mut local = Awareness::new(Doc::with_client_id(1));
+        let _sub = local.on_update(move |_, e| {
+            s.send(e.clone()).unwrap();
+        });
+        let mut remote = Awareness::new(Doc::with_client_id(2));
+
+        local.set_state(7, "{agent:1}");
+        let e = update(&mut o_local, &local, &mut remote)?;
+        assert_eq!(e.added(), &[7]);
+        assert_eq!(remote.clients()[&7], "{agent:1}");
+        assert_eq!(local.local_state(), None);
+
+        local.remove_state(7);
+        let e = update(&mut o_local, &local, &mut remote)?;
+        assert_eq!(e.removed(), &[7]);
+        assert_eq!(remote.clients().get(&7), None);
+        Ok(())
+    }
 }
@@ -2,6 +2,7 @@
 
 pub mod awareness;
 
+use crate::api_types::Authorization;
 use awareness::{Awareness, AwarenessUpdate};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -215,6 +216,10 @@ pub const MSG_EVENT: u8 = 4;
 pub const MSG_EVENT_SUBSCRIBE: u8 = 5;
 /// Tag id for [Message::EventUnsubscribe].
 pub const MSG_EVENT_UNSUBSCRIBE: u8 = 6;
+/// Tag id for [Message::TokenRefresh].
+pub const MSG_TOKEN_REFRESH: u8 = 7;
+/// Tag id for [Message::TokenRefreshed].
+pub const MSG_TOKEN_REFRESHED: u8 = 8;
 
 pub const PERMISSION_DENIED: u8 = 0;
 pub const PERMISSION_GRANTED: u8 = 1;
@@ -228,6 +233,13 @@ pub enum Message {
     Event(Vec<u8>),                // CBOR-encoded EventMessage
     EventSubscribe(Vec<String>),   // List of event types to subscribe to
     EventUnsubscribe(Vec<String>), // List of event types to unsubscribe from
+    TokenRefresh(String),          // Client->Server: a fresh token for this connection
+    /// Server->Client: the refreshed token was accepted. `expires_at` is in
+    /// milliseconds since the epoch; `None` if the token does not expire.
+    TokenRefreshed {
+        authorization: Authorization,
+        expires_at: Option<u64>,
+    },
     Custom(u8, Vec<u8>),
 }
 
@@ -272,6 +284,19 @@ impl Encode for Message {
                     encoder.write_string(event_type);
                 }
             }
+            Message::TokenRefresh(token) => {
+                encoder.write_var(MSG_TOKEN_REFRESH);
+                encoder.write_string(token);
+            }
+            Message::TokenRefreshed {
+                authorization,
+                expires_at,
+            } => {
+                encoder.write_var(MSG_TOKEN_REFRESHED);
+                encoder.write_string(authorization_name(*authorization));
+                // 0 stands for "never"
+                encoder.write_var(expires_at.unwrap_or(0));
+            }
             Message::Custom(tag, data) => {
                 encoder.write_u8(*tag);
                 encoder.write_buf(data);
@@ -280,6 +305,15 @@ impl Encode for Message {
     }
 }
 
+fn authorization_name(authorization: Authorization) -> &'static str {
+    match authorization {
+        Authorization::ReadOnly => "read-only",
+        Authorization::Full => "full",
+        Authorization::Comment => "comment",
+        Authorization::Suggest => "suggest",
+    }
+}
+
 impl Decode for Message {
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, yrs::encoding::read::Error> {
         let tag: u8 = decoder.read_var()?;
@@ -324,6 +358,21 @@ impl Decode for Message {
                 }
                 Ok(Message::EventUnsubscribe(event_types))
             }
+            MSG_TOKEN_REFRESH => Ok(Message::TokenRefresh(decoder.read_string()?.to_string())),
+            MSG_TOKEN_REFRESHED => {
+                let authorization = match decoder.read_string()? {
+                    "read-only" => Authorization::ReadOnly,
+                    "full" => Authorization::Full,
+                    "comment" => Authorization::Comment,
+                    "suggest" => Authorization::Suggest,
+                    _ => return Err(yrs::encoding::read::Error::UnexpectedValue),
+                };
+                let expires_at: u64 = decoder.read_var()?;
+                Ok(Message::TokenRefreshed {
+                    authorization,
+                    expires_at: (expires_at != 0).then_some(expires_at),
+                })
+            }
             tag => {
                 let data = decoder.read_buf()?;
                 Ok(Message::Custom(tag, data.to_vec()))
@@ -402,6 +451,17 @@ pub enum Error {
     #[error("permission denied to access: {reason}")]
     PermissionDenied { reason: String },
 
+    /// A token the client presented on an open connection was rejected. The
+    /// connection should be closed.
+    #[error("token refresh rejected: {reason}")]
+    TokenRefreshRejected { reason: String },
+
+    /// An update from a client that may only comment or suggest changed
+    /// something else. It was not applied; the connection should be closed so
+    /// the client resynchronizes.
+    #[error("update rejected: {reason}")]
+    UpdateRejected { reason: String },
+
     /// Thrown whenever an unknown message tag has been sent.
     #[error("unsupported message tag identifier: {0}")]
     Unsupported(u8),
@@ -439,7 +499,9 @@ mod test {
     use super::{
         EventMessage, Message, SyncMessage, MSG_AUTH, MSG_AWARENESS, MSG_EVENT,
         MSG_EVENT_SUBSCRIBE, MSG_EVENT_UNSUBSCRIBE, MSG_QUERY_AWARENESS, MSG_SYNC,
+        MSG_TOKEN_REFRESH, MSG_TOKEN_REFRESHED,
     };
+    use crate::api_types::Authorization;
     use crate::sync::awareness::Awareness;
     use crate::sync::{DefaultProtocol, MessageReader, Protocol};
     use std::collections::HashMap;
@@ -768,6 +830,8 @@ mod test {
         assert_eq!(MSG_EVENT, 4);
         assert_eq!(MSG_EVENT_SUBSCRIBE, 5);
         assert_eq!(MSG_EVENT_UNSUBSCRIBE, 6);
+        assert_eq!(MSG_TOKEN_REFRESH, 7);
+        assert_eq!(MSG_TOKEN_REFRESHED, 8);
     }
 
     #[test]
@@ -810,6 +874,15 @@ mod test {
                 "user.joined".to_string(),
             ]),
             Message::EventUnsubscribe(vec!["user.left".to_string()]),
+            Message::TokenRefresh("token".to_string()),
+            Message::TokenRefreshed {
+                authorization: Authorization::Suggest,
+                expires_at: Some(1640995200000),
+            },
+            Message::TokenRefreshed {
+                authorization: Authorization::Full,
+                expires_at: None,
+            },
             Message::Custom(100, vec![1, 2, 3, 4]),
         ];
 
@@ -107,6 +107,49 @@ where
     BTreeMap::from_cbor_value(cbor_value).map_err(D::Error::custom)
 }
 
+struct DecodedSnapshot {
+    data: BTreeMap<Vec<u8>, Vec<u8>>,
+    created_at: Option<u64>,
+    metadata: Option<BTreeMap<String, ciborium::value::Value>>,
+}
+
+/// Decode a `data.ysweet` snapshot, falling back to the legacy bincode format.
+fn decode_snapshot(snapshot: &[u8]) -> Result<DecodedSnapshot> {
+    // Try CBOR format first
+    match ciborium::de::from_reader::<YSweetData, _>(snapshot) {
+        Ok(y_data) => {
+            tracing::info!("Loaded CBOR format data (version {})", y_data.version);
+            Ok(DecodedSnapshot {
+                data: y_data.data,
+                created_at: Some(y_data.created_at),
+                metadata: y_data.metadata,
+            })
+        }
+        Err(cbor_err) => {
+            // Fallback to bincode for backward compatibility
+            tracing::info!(
+                "CBOR deserialization failed ({}), trying bincode format",
+                cbor_err
+            );
+            match bincode::deserialize(snapshot) {
+                Ok(data) => {
+                    tracing::info!(
+                        "Loaded bincode format data, will migrate to CBOR on next persist"
+                    );
+                    Ok(DecodedSnapshot {
+                        data,
+                        created_at: None,
+                        metadata: None,
+                    })
+                }
+                Err(bincode_err) => {
+                    anyhow::bail!("Failed to deserialize data in both CBOR and bincode formats. CBOR: {}, Bincode: {}", cbor_err, bincode_err);
+                }
+            }
+        }
+    }
+}
+
 pub struct SyncKv {
     data: Arc<Mutex<BTreeMap<Vec<u8>, Vec<u8>>>>,
     store: Option<Arc<Box<dyn Store>>>,
@@ -130,32 +173,10 @@ impl SyncKv {
         let data = if let Some(store) = &store {
             if let Some(snapshot) = store.get(&key).await.context("Failed to get from store.")? {
                 tracing::info!(size=?snapshot.len(), "Loading snapshot");
-
-                // Try CBOR format first
-                match ciborium::de::from_reader::<YSweetData, _>(&snapshot[..]) {
-                    Ok(y_data) => {
-                        created_at = Some(y_data.created_at);
-                        metadata = y_data.metadata;
-                        tracing::info!("Loaded CBOR format data (version {})", y_data.version);
-                        y_data.data
-                    }
-                    Err(cbor_err) => {
-                        // Fallback to bincode for backward compatibility
-                        tracing::info!(
-                            "CBOR deserialization failed ({}), trying bincode format",
-                            cbor_err
-                        );
-                        match bincode::deserialize(&snapshot) {
-                            Ok(data) => {
-                                tracing::info!("Loaded bincode format data, will migrate to CBOR on next persist");
-                                data
-                            }
-                            Err(bincode_err) => {
-                                anyhow::bail!("Failed to deserialize data in both CBOR and bincode formats. CBOR: {}, Bincode: {}", cbor_err, bincode_err);
-                            }
-                        }
-                    }
-                }
+                let decoded = decode_snapshot(&snapshot)?;
+                created_at = decoded.created_at;
+                metadata = decoded.metadata;
+                decoded.data
             } else {
                 BTreeMap::new()
             }
@@ -174,6 +195,21 @@ impl SyncKv {
         })
     }
 
+    /// Open a stored snapshot without a backing store, e.g. an older version
+    /// of a document. Nothing written to it is persisted.
+    pub fn from_snapshot(key: &str, snapshot: &[u8]) -> Result<Self> {
+        let decoded = decode_snapshot(snapshot)?;
+        Ok(Self {
+            data: Arc::new(Mutex::new(decoded.data)),
+            store: None,
+            key: format!("{}/data.ysweet", key),
+            dirty: AtomicBool::new(false),
+            dirty_callback: Box::new(|| {}),
+            created_at: decoded.created_at,
+            metadata: Arc::new(Mutex::new(decoded.metadata)),
+        })
+    }
+
     fn mark_dirty(&self) {
         if !self.dirty.load(Ordering::Relaxed) {
             self.dirty.store(true, Ordering::Relaxed);
@@ -454,6 +490,25 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn from_snapshot_reads_stored_bytes_without_store() {
+        let store = MemoryStore::default();
+        let sync_kv = SyncKv::new(Some(Arc::new(Box::new(store.clone()))), "foo", || ())
+            .await
+            .unwrap();
+        sync_kv.set(b"foo", b"bar");
+        sync_kv.persist().await.unwrap();
+
+        let snapshot = store.data.get("foo/data.ysweet").unwrap().clone();
+        let detached = SyncKv::from_snapshot("foo", &snapshot).unwrap();
+        assert_eq!(detached.get(b"foo"), Some(b"bar".to_vec()));
+
+        // Writes stay in memory
+        detached.set(b"abc", b"def");
+        detached.persist().await.unwrap();
+        assert_eq!(store.data.len(), 1);
+    }
+
     #[tokio::test]
     async fn test_cbor_serialization_roundtrip() {
         let store = MemoryStore::default();
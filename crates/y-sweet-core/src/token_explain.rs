@@ -0,0 +1,68 @@
+//! Why a relay would accept or refuse a token.
+//!
+//! An explanation lays out each check a token goes through against one
+//! configuration: the key it is matched to, whether that key takes the
+//! token's algorithm and type, the audience and expiry, and what the token
+//! grants once accepted. Built by
+//! [`crate::auth::Authenticator::explain_token`] and printed by
+//! `y-sign explain`.
+
+use crate::api_types::Authorization;
+use crate::config::TokenType;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenExplanation {
+    /// `cwt`, `custom` or `api-key`.
+    pub format: &'static str,
+    /// The key ID in a CWT token's protected header.
+    pub key_id: Option<String>,
+    /// The COSE algorithm in a CWT token's protected header.
+    pub algorithm: Option<&'static str>,
+    /// The key the token is checked against, if any is configured.
+    pub matched_key: Option<MatchedKey>,
+    pub token_type: Option<TokenType>,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    /// The relay's `server.url`, which CWT tokens must name as audience.
+    pub expected_audience: Option<String>,
+    /// `None` for tokens without an audience claim to check.
+    pub audience_valid: Option<bool>,
+    /// Milliseconds since the epoch.
+    pub expires_at: Option<u64>,
+    pub expired: bool,
+    /// What the token claims, whether or not it is accepted.
+    pub grants: Option<TokenGrant>,
+    /// Whether the relay accepts the token.
+    pub valid: bool,
+    /// Why the relay refuses the token.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchedKey {
+    pub key_id: Option<String>,
+    /// `config` for keys in relay.toml, `rotated` for keys added by rotation.
+    pub source: &'static str,
+    /// `HS256`, `ES256`, `EdDSA` or `legacy`.
+    pub algorithm: &'static str,
+    /// Whether the key verifies tokens signed with the token's algorithm.
+    pub algorithm_allowed: bool,
+    pub allowed_token_types: Vec<TokenType>,
+    pub token_type_allowed: bool,
+    pub signature_valid: bool,
+    /// Whether a rotation's grace period for the key has run out.
+    pub retired: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenGrant {
+    /// `*` for everything, `doc:<id>`, `doc:<prefix>*`, `file:<hash>`, or
+    /// `folder:<path>` for an API key's folders.
+    pub resources: Vec<String>,
+    pub authorization: Authorization,
+    pub user: Option<String>,
+}